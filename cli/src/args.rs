@@ -1,7 +1,15 @@
-use std::{ffi::OsStr, path::PathBuf};
-
+use std::{
+    ffi::OsStr,
+    ops::{Range, RangeInclusive},
+    path::PathBuf,
+    time::Duration,
+};
+
+use althread::checker::{CheckOptions, ExploreOrder};
+use althread::vm::int_abstraction::{IntAbstraction, IntOverflow};
+use althread::vm::RunOptions;
 use clap::builder::TypedValueParser;
-use clap::{Args, Parser, Subcommand, ValueHint};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum, ValueHint};
 
 /// An input that is either stdin or a real path.
 #[derive(Debug, Clone)]
@@ -20,10 +28,25 @@ pub struct CliArguments {
     #[arg(short, long, action = clap::ArgAction::Count)]
     pub debug: u8,
 
+    /// When to color the output
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
     #[command(subcommand)]
     pub command: Command,
 }
 
+/// When to color the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color the output when it is written to a terminal
+    Auto,
+    /// Always color the output
+    Always,
+    /// Never color the output
+    Never,
+}
+
 /// What to do.
 #[derive(Debug, Clone, Subcommand)]
 #[command()]
@@ -44,6 +67,14 @@ pub enum Command {
     #[command()]
     Check(CheckCommand),
 
+    /// Output the state graph of the input program (DOT format)
+    #[command()]
+    Graph(GraphCommand),
+
+    /// Time the parsing, compilation and check of the input program
+    #[command()]
+    Bench(BenchCommand),
+
     /// Initialize a new Althread package
     #[command()]
     Init(InitCommand),
@@ -63,6 +94,14 @@ pub enum Command {
     /// Install/fetch all dependencies
     #[command()]
     Install(InstallCommand),
+
+    /// Check alt.toml, the dependency cache and the environment
+    #[command()]
+    Doctor(DoctorCommand),
+
+    /// Evaluate declarations, statements and expressions interactively
+    #[command()]
+    Repl(ReplCommand),
 }
 
 /// Compiles an input file into a supported output format
@@ -71,6 +110,10 @@ pub struct CompileCommand {
     /// Shared arguments
     #[clap(flatten)]
     pub common: SharedArgs,
+
+    /// Only print the numbered instructions of the given program
+    #[clap(long, value_name = "PROGRAM")]
+    pub dump_instructions: Option<String>,
 }
 
 /// Compiles an input file into a supported output format
@@ -104,6 +147,10 @@ pub struct CheckCommand {
     #[clap(long)]
     pub show_trace: bool,
 
+    /// print the output of the processes along the counterexample
+    #[clap(long)]
+    pub show_output: bool,
+
     /// Output all debug information
     #[clap(long)]
     pub show_all: bool,
@@ -111,6 +158,145 @@ pub struct CheckCommand {
     /// max number of states to explore
     #[clap(long, default_value_t = 100_000)]
     pub max_states: u64,
+
+    /// keep at most this number of states in memory, evicting the least recently seen ones
+    /// (the check becomes approximate)
+    #[clap(long, value_name = "STATES")]
+    pub state_cache: Option<usize>,
+
+    /// stop the search when the state graph is estimated to use more than this amount of memory
+    /// (the check becomes partial)
+    #[clap(long, value_name = "MB")]
+    pub max_memory: Option<usize>,
+
+    /// stop the search after this number of milliseconds (the check becomes partial)
+    #[clap(long, value_name = "MS")]
+    pub timeout: Option<u64>,
+
+    /// distinguish the states that differ only by the printed output
+    #[clap(long)]
+    pub output_in_state: bool,
+
+    /// merge the transitions of a process that lead to the same state (e.g. sends of different
+    /// values with the same effect) into a single link of the state graph
+    #[clap(long)]
+    pub prune_equivalent_sends: bool,
+
+    /// write the distinct reachable valuations of the shared variables to a CSV file
+    #[clap(long, value_name = "FILE")]
+    pub csv_states: Option<PathBuf>,
+
+    /// narrate the counterexample in English sentences
+    #[clap(long)]
+    pub explain_trace: bool,
+
+    /// on a successful check, list each checked condition with its line and source text
+    #[clap(long)]
+    pub summary: bool,
+
+    /// write the schedule of the counterexample to a file, to replay it with `run --replay`
+    #[clap(long, value_name = "FILE")]
+    pub export_counterexample: Option<PathBuf>,
+
+    /// report a process that stays enabled across this number of transitions without executing
+    #[clap(long, value_name = "STEPS")]
+    pub starvation: Option<usize>,
+
+    /// report an execution ending with messages in the channel of a terminated process
+    #[clap(long)]
+    pub unhandled_messages: bool,
+
+    /// only report the violations of LTL formulas in which no process stays enabled forever
+    /// without being scheduled (weak fairness)
+    #[clap(long)]
+    pub fair: bool,
+
+    /// prove that every execution terminates, or report an execution looping forever
+    #[clap(long)]
+    pub verify_termination: bool,
+
+    /// count the transitions, states and executions going through the instructions on these
+    /// lines of the input file
+    #[clap(long, value_name = "START..END", value_parser = parse_line_range)]
+    pub count_interleavings: Option<RangeInclusive<usize>>,
+
+    /// list the shared variables by number of distinct values in the explored states, to find
+    /// the ones causing a state explosion
+    #[clap(long)]
+    pub variable_ranges: bool,
+
+    /// stop at the first violated invariant (default)
+    #[clap(long, overrides_with = "explore_all")]
+    pub fail_fast: bool,
+
+    /// explore the whole state space and report every distinct violated invariant
+    #[clap(long, overrides_with = "fail_fast", conflicts_with = "state_cache")]
+    pub explore_all: bool,
+
+    /// the order in which the states are explored: bfs (shortest counterexamples), dfs (small
+    /// frontier) or random
+    #[clap(
+        long,
+        visible_alias = "explore",
+        value_name = "ORDER",
+        default_value_t = ExploreOrder::Bfs
+    )]
+    pub explore_order: ExploreOrder,
+
+    /// do not explore the states reached after this number of steps (the check becomes partial)
+    #[clap(long, value_name = "STEPS")]
+    pub max_depth: Option<usize>,
+
+    /// max number of instructions a process may execute in a single step (e.g. an atomic
+    /// block), after which the execution fails instead of hanging
+    #[clap(long, value_name = "N", default_value_t = 1_000_000)]
+    pub instr_limit_per_step: usize,
+
+    /// Integer abstraction
+    #[clap(flatten)]
+    pub int_abstraction: IntAbstractionArgs,
+}
+
+/// Output the state graph of the input program (DOT format)
+#[derive(Debug, Clone, Parser)]
+pub struct GraphCommand {
+    /// Shared arguments
+    #[clap(flatten)]
+    pub common: SharedArgs,
+
+    /// output the DAG of the strongly connected components instead of every state, each
+    /// component being labeled with its number of states
+    #[clap(long)]
+    pub reachability: bool,
+
+    /// max number of states to explore
+    #[clap(long, default_value_t = 100_000)]
+    pub max_states: u64,
+
+    /// max number of instructions a process may execute in a single step (e.g. an atomic
+    /// block), after which the execution fails instead of hanging
+    #[clap(long, value_name = "N", default_value_t = 1_000_000)]
+    pub instr_limit_per_step: usize,
+
+    /// Integer abstraction
+    #[clap(flatten)]
+    pub int_abstraction: IntAbstractionArgs,
+}
+
+/// Time the parsing, compilation and check of the input program
+#[derive(Debug, Clone, Parser)]
+pub struct BenchCommand {
+    /// Shared arguments
+    #[clap(flatten)]
+    pub common: SharedArgs,
+
+    /// max number of states to explore during the check
+    #[clap(long, default_value_t = 100_000)]
+    pub max_states: u64,
+
+    /// Integer abstraction
+    #[clap(flatten)]
+    pub int_abstraction: IntAbstractionArgs,
 }
 
 /// Compiles an input file into a supported output format
@@ -132,13 +318,72 @@ pub struct RunCommand {
     #[clap(long)]
     pub interactive: bool,
 
+    /// in interactive mode, the number of steps that can be undone with `b` (each one keeps a
+    /// copy of the state in memory)
+    #[clap(
+        long,
+        value_name = "STEPS",
+        default_value_t = althread::vm::DEFAULT_HISTORY_DEPTH,
+        requires = "interactive"
+    )]
+    pub history_depth: usize,
+
     /// max number of steps
     #[clap(long, default_value_t = 100_000)]
     pub max_steps: u64,
 
+    /// stop the execution after this number of milliseconds
+    #[clap(long, value_name = "MS")]
+    pub timeout: Option<u64>,
+
     /// seed
     #[clap(long)]
     pub seed: Option<u64>,
+
+    /// On an invariant violation, search for the fewest spawned processes that still fail
+    #[clap(long)]
+    pub minimize_trace: bool,
+
+    /// replay the schedule of a counterexample written by `check --export-counterexample`
+    /// instead of choosing the processes at random
+    #[clap(long, value_name = "FILE", conflicts_with_all = ["interactive", "seed", "minimize_trace"])]
+    pub replay: Option<PathBuf>,
+
+    /// with `--replay`, list the steps enabled at the end of the schedule and what each blocked
+    /// process is waiting for, to investigate a deadlock or a process that never runs
+    #[clap(long, requires = "replay")]
+    pub list_enabled: bool,
+
+    /// max number of instructions a process may execute in a single step (e.g. an atomic
+    /// block), after which the execution fails instead of hanging
+    #[clap(long, value_name = "N", default_value_t = 1_000_000)]
+    pub instr_limit_per_step: usize,
+
+    /// check the invariants after every step, not only after the steps that can change them
+    #[clap(long)]
+    pub check_invariants_every_step: bool,
+
+    /// output format of the execution: the printed messages, or a JSON trace of every step
+    #[clap(long, value_enum, default_value_t = TraceFormat::Text, conflicts_with_all = ["interactive", "replay", "verbose", "debug"])]
+    pub format: TraceFormat,
+
+    /// with `--format json`, write the trace to FILE instead of stdout
+    #[clap(long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Integer abstraction
+    #[clap(flatten)]
+    pub int_abstraction: IntAbstractionArgs,
+}
+
+/// The output format of `run`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TraceFormat {
+    /// The messages printed by the program
+    Text,
+    /// A JSON array with, for each step, the process executed, its instructions and actions,
+    /// and the state reached
+    Json,
 }
 
 /// Compiles an input file into a supported output format
@@ -155,6 +400,24 @@ pub struct RandomSearchCommand {
     /// max number of seeds
     #[clap(long, default_value_t = 10_000)]
     pub max_seeds: u64,
+
+    /// the seeds to run, from START (included) to END (excluded), instead of 0..max-seeds
+    #[clap(long, value_name = "START..END", value_parser = parse_seed_range, conflicts_with = "max_seeds")]
+    pub seed_range: Option<Range<u64>>,
+
+    /// number of threads running the seeds. With several threads, the output of the executions
+    /// is not printed
+    #[clap(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    pub threads: u64,
+
+    /// check the invariants after every step, not only after the steps that can change them
+    #[clap(long)]
+    pub check_invariants_every_step: bool,
+
+    /// run every seed, even after a violation, and print a JSON summary of the search instead
+    /// of the output of the executions
+    #[clap(long)]
+    pub summary_json: bool,
 }
 
 /// Initialize a new Althread package
@@ -180,6 +443,23 @@ pub struct InitCommand {
     pub force: bool,
 }
 
+/// Check alt.toml, the dependency cache and the environment
+#[derive(Debug, Clone, Parser)]
+pub struct DoctorCommand {}
+
+/// Evaluate declarations, statements and expressions interactively
+#[derive(Debug, Clone, Parser)]
+pub struct ReplCommand {
+    /// max number of steps of each evaluation
+    #[clap(long, default_value_t = 100_000)]
+    pub max_steps: u64,
+
+    /// seed of the scheduling of the steps taken by each entry, the previous entries being
+    /// replayed as they were executed
+    #[clap(long, default_value_t = 0)]
+    pub seed: u64,
+}
+
 /// Add a dependency to the current package
 #[derive(Debug, Clone, Parser)]
 pub struct AddCommand {
@@ -213,6 +493,127 @@ pub struct InstallCommand {
     pub force: bool,
 }
 
+/// Bounds the integers of the model so that its state space is finite
+#[derive(Debug, Clone, Args)]
+pub struct IntAbstractionArgs {
+    /// Clamp integers to the range MIN..MAX (both included)
+    #[clap(long, value_name = "MIN..MAX", value_parser = parse_int_range)]
+    pub abstract_ints: Option<RangeInclusive<i64>>,
+
+    /// What to do with integers out of the abstracted range: saturate or wrap
+    #[clap(long, default_value_t = IntOverflow::Saturate, requires = "abstract_ints")]
+    pub int_overflow: IntOverflow,
+}
+
+impl IntAbstractionArgs {
+    pub fn build(&self) -> Option<IntAbstraction> {
+        self.abstract_ints.as_ref().map(|range| {
+            IntAbstraction::new(*range.start(), *range.end(), self.int_overflow)
+                .expect("the range was validated when parsed")
+        })
+    }
+}
+
+impl CheckCommand {
+    /// The options of the check, the integers being clamped to `int_abstraction`
+    pub fn check_options<'a>(
+        &self,
+        int_abstraction: Option<&'a IntAbstraction>,
+    ) -> CheckOptions<'a> {
+        CheckOptions {
+            run: RunOptions {
+                int_abstraction,
+                instr_limit_per_step: Some(self.instr_limit_per_step),
+                output_in_state: self.output_in_state,
+                ..Default::default()
+            },
+            max_states: Some(usize::try_from(self.max_states).unwrap_or(usize::MAX)),
+            state_cache: self.state_cache,
+            max_memory: self.max_memory.map(|mb| mb * 1024 * 1024),
+            timeout: self.timeout.map(Duration::from_millis),
+            explore_order: self.explore_order,
+            max_depth: self.max_depth,
+            prune_equivalent_sends: self.prune_equivalent_sends,
+            weak_fairness: self.fair,
+        }
+    }
+}
+
+impl RunCommand {
+    /// The options of the execution, the integers being clamped to `int_abstraction`
+    pub fn run_options<'a>(&self, int_abstraction: Option<&'a IntAbstraction>) -> RunOptions<'a> {
+        RunOptions {
+            int_abstraction,
+            instr_limit_per_step: Some(self.instr_limit_per_step),
+            check_invariants_every_step: self.check_invariants_every_step,
+            ..Default::default()
+        }
+    }
+
+    /// Rejects `--output` when the trace is not written as JSON, as only the JSON trace is
+    /// written to a file
+    ///
+    /// # Errors
+    /// If `--output` is given without `--format json`
+    pub fn check_output(&self) -> Result<(), clap::Error> {
+        if self.output.is_some() && self.format != TraceFormat::Json {
+            return Err(Self::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--output can only be used with --format json",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl RandomSearchCommand {
+    /// The options of the execution of each seed
+    pub fn run_options(&self) -> RunOptions<'static> {
+        RunOptions {
+            check_invariants_every_step: self.check_invariants_every_step,
+            ..Default::default()
+        }
+    }
+}
+
+/// The clap value parser used by `IntAbstractionArgs.abstract_ints`
+fn parse_int_range(value: &str) -> Result<RangeInclusive<i64>, String> {
+    let abstraction: IntAbstraction = value.parse()?;
+    Ok(abstraction.min..=abstraction.max)
+}
+
+/// The clap value parser used by `CheckCommand.count_interleavings`, accepting a single line too
+fn parse_line_range(value: &str) -> Result<RangeInclusive<usize>, String> {
+    let (start, end) = value.split_once("..").unwrap_or((value, value));
+    let parse = |line: &str| {
+        line.trim()
+            .parse::<usize>()
+            .map_err(|_| format!("invalid line number '{line}'"))
+    };
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start > end {
+        return Err(format!("empty line range {start}..{end}"));
+    }
+    Ok(start..=end)
+}
+
+/// The clap value parser used by `RandomSearchCommand.seed_range`
+fn parse_seed_range(value: &str) -> Result<Range<u64>, String> {
+    let (start, end) = value
+        .split_once("..")
+        .ok_or_else(|| format!("expected START..END, got '{value}'"))?;
+    let parse = |seed: &str| {
+        seed.trim()
+            .parse::<u64>()
+            .map_err(|_| format!("invalid seed '{seed}'"))
+    };
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start >= end {
+        return Err(format!("empty seed range {start}..{end}"));
+    }
+    Ok(start..end)
+}
+
 /// Common arguments of compile, watch, and query.
 #[derive(Debug, Clone, Args)]
 pub struct SharedArgs {
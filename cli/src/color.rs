@@ -0,0 +1,62 @@
+//! Output coloring shared by every command.
+//!
+//! The `--color` choice is resolved once at startup for stdout and stderr, and every styled
+//! text goes through `Paint` so that redirected output is not garbled by escape codes.
+
+use std::{
+    fmt::Display,
+    io::{stderr, stdout, IsTerminal},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use owo_colors::{OwoColorize, Style};
+
+use crate::args::ColorChoice;
+
+static STDOUT_COLOR: AtomicBool = AtomicBool::new(false);
+static STDERR_COLOR: AtomicBool = AtomicBool::new(false);
+
+pub fn init(choice: ColorChoice) {
+    let enabled = |is_terminal: bool| match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => is_terminal && std::env::var_os("NO_COLOR").is_none(),
+    };
+    STDOUT_COLOR.store(enabled(stdout().is_terminal()), Ordering::Relaxed);
+    STDERR_COLOR.store(enabled(stderr().is_terminal()), Ordering::Relaxed);
+}
+
+pub trait Paint: Display {
+    /// Styles the text printed to stdout
+    fn paint(&self, style: Style) -> String {
+        styled(self, style, STDOUT_COLOR.load(Ordering::Relaxed))
+    }
+
+    /// Styles the text printed to stderr
+    fn epaint(&self, style: Style) -> String {
+        styled(self, style, STDERR_COLOR.load(Ordering::Relaxed))
+    }
+}
+
+impl<T: Display + ?Sized> Paint for T {}
+
+fn styled<T: Display + ?Sized>(text: &T, style: Style, enabled: bool) -> String {
+    if enabled {
+        text.style(style).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles_only_when_enabled() {
+        let style = Style::new().red();
+        assert_eq!(styled("text", style, false), "text");
+        let colored = styled("text", style, true);
+        assert!(colored.starts_with("\x1b[") && colored.contains("text"));
+    }
+}
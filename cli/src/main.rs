@@ -4,27 +4,44 @@ use std::{
     error::Error,
     fs::{self, remove_dir_all},
     io::{stdin, stdout, Read, Write},
+    ops::RangeInclusive,
     path::{Path, PathBuf},
     process::exit,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
 };
 
 mod args;
+mod color;
 mod git;
 mod package;
+mod repl;
 mod resolver;
 use args::{
-    AddCommand, CheckCommand, CliArguments, Command, CompileCommand, InitCommand, InstallCommand,
-    RandomSearchCommand, RemoveCommand, RunCommand, UpdateCommand,
+    AddCommand, BenchCommand, CheckCommand, CliArguments, Command, CompileCommand, DoctorCommand, GraphCommand, InitCommand,
+    InstallCommand, RandomSearchCommand, RemoveCommand, RunCommand, TraceFormat, UpdateCommand,
 };
 use clap::Parser;
-use owo_colors::{OwoColorize, Style};
-
-use althread::{ast::Ast, checker, module_resolver::StandardFileSystem};
+use color::Paint;
+use owo_colors::Style;
+use serde::Serialize;
+
+use althread::{
+    analysis::{
+        atomic_writes::find_atomic_write_conflicts, wait_dependencies::find_unsatisfiable_waits,
+    },
+    ast::Ast,
+    checker,
+    error::{AlthreadError, ErrorType},
+    module_resolver::StandardFileSystem,
+    vm::{int_abstraction::IntAbstraction, RunOptions},
+};
 
 use crate::package::{DependencySpec, Package};
 
 fn main() {
     let cli_args = CliArguments::parse();
+    color::init(cli_args.color);
     let enable_debug = cli_args.debug > 0
         || matches!(&cli_args.command, Command::Check(cmd) if cmd.debug)
         || matches!(&cli_args.command, Command::Run(cmd) if cmd.debug);
@@ -39,11 +56,15 @@ fn main() {
         Command::Run(command) => run_command(&command.clone()),
         Command::RandomSearch(command) => random_search_command(&command.clone()),
         Command::Check(command) => check_command(&command.clone()),
+        Command::Graph(command) => graph_command(&command.clone()),
+        Command::Bench(command) => bench_command(&command.clone()),
         Command::Init(command) => init_command(&command.clone()),
         Command::Add(command) => add_command(&command.clone()),
         Command::Remove(command) => remove_command(&command.clone()),
         Command::Update(command) => update_command(&command.clone()),
         Command::Install(command) => install_command(&command.clone()),
+        Command::Doctor(command) => doctor_command(&command.clone()),
+        Command::Repl(command) => repl::repl_command(&command.clone()),
     }
 }
 
@@ -79,7 +100,9 @@ pub fn compile_command(cli_args: &CompileCommand) {
         exit(1);
     });
 
-    println!("{}", &ast);
+    if cli_args.dump_instructions.is_none() {
+        println!("{}", &ast);
+    }
 
     let compiled_project = ast
         .compile(&path, StandardFileSystem, &mut input_map)
@@ -88,7 +111,25 @@ pub fn compile_command(cli_args: &CompileCommand) {
             exit(1);
         });
 
-    println!("{}", compiled_project);
+    let Some(program_name) = &cli_args.dump_instructions else {
+        println!("{compiled_project}");
+        return;
+    };
+    let Some(code) = compiled_project.programs_code.get(program_name) else {
+        let mut names: Vec<&String> = compiled_project.programs_code.keys().collect();
+        names.sort();
+        eprintln!(
+            "Error: program '{}' not found. Available programs: {}",
+            program_name,
+            names
+                .iter()
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        exit(1);
+    };
+    print!("{}", code.disassemble(&input_map));
 }
 
 pub fn check_command(cli_args: &CheckCommand) {
@@ -131,6 +172,10 @@ pub fn check_command(cli_args: &CheckCommand) {
             e.report(&input_map);
             exit(1);
         });
+    let int_abstraction = cli_args.int_abstraction.build();
+    let options = cli_args.check_options(int_abstraction.as_ref());
+
+    report_analysis_warnings(&compiled_project);
 
     // LTL Debug output
     let show_all = cli_args.show_all;
@@ -171,11 +216,32 @@ pub fn check_command(cli_args: &CheckCommand) {
         }
     }
 
-    let checked = checker::check_program(&compiled_project, Some(cli_args.max_states as usize))
-        .unwrap_or_else(|e| {
-            e.report(&input_map);
+    if cli_args.explore_all {
+        check_all_violations(
+            cli_args,
+            &options,
+            &ast,
+            &compiled_project,
+            &source,
+            &path,
+            &input_map,
+        );
+        return;
+    }
+
+    let checked = checker::check_program(&compiled_project, &options).unwrap_or_else(|e| {
+        e.report(&input_map);
+        exit(1);
+    });
+    report_int_abstraction(options.run.int_abstraction);
+    report_ignored_conditions(&compiled_project);
+
+    if let Some(csv_path) = &cli_args.csv_states {
+        if let Err(e) = write_states_csv(csv_path, &checked.1) {
+            eprintln!("Error writing {}: {}", csv_path.display(), e);
             exit(1);
-        });
+        }
+    }
 
     // Show state graph if requested
     if show_all || cli_args.show_state_graph {
@@ -188,57 +254,623 @@ pub fn check_command(cli_args: &CheckCommand) {
     if !checked.1.exhaustive {
         println!(
             "{}",
-            format!(
-                "Warning: Maximum number of states ({}) reached. The search was not exhaustive.",
-                cli_args.max_states
-            )
-            .yellow()
+            search_limit_warning(cli_args, &checked.1).paint(Style::new().yellow())
         );
         if checked.0.is_empty() {
             println!(
                 "{}",
                 "Note: Liveness properties (eventually) were not checked because the state space is incomplete."
-                    .italic()
+                    .paint(Style::new().italic())
             );
         } else {
             println!(
                 "{}",
                 "Note: The reported counterexample is valid, but verification stopped early at the state limit."
-                    .italic()
+                    .paint(Style::new().italic())
+            );
+        }
+    }
+
+    if checked.1.is_approximate() {
+        println!(
+            "{}",
+            format!(
+                "Warning: {} states were evicted from the state cache. The check is approximate.",
+                checked.1.evicted_states
+            )
+            .paint(Style::new().yellow())
+        );
+        if checked.0.is_empty() {
+            println!(
+                "{}",
+                "Note: Liveness properties (eventually) were not checked because some states may have been explored several times."
+                    .paint(Style::new().italic())
             );
         }
     }
 
     if checked.0.is_empty() {
         println!("✓ No invariant violated");
+        if cli_args.summary {
+            print_summary(&ast, &compiled_project, &checked.1, &input_map, true);
+        }
     } else {
-        println!("✗ Invariant violated");
-        for link in checked.0.iter() {
-            println!(
-                "{}",
-                format!("-- {}#{} --", link.name, link.pid).style(if link.pid == 0 {
-                    MAIN_STYLE
-                } else {
-                    PROCESS_PALETTE[(link.pid.saturating_sub(1)) % PROCESS_PALETTE.len()]
-                })
+        match &checked.1.deadlock {
+            Some(_) => println!("✗ Deadlock"),
+            None => println!("✗ Invariant violated"),
+        }
+        print_trace(&source, &checked.0, cli_args.show_output);
+        if let Some(deadlock) = &checked.1.deadlock {
+            deadlock.report(&input_map);
+        }
+        if let Some(schedule_path) = &cli_args.export_counterexample {
+            export_counterexample(
+                schedule_path,
+                &compiled_project,
+                options.run,
+                &checked.1,
+                &checked.0,
+                &path,
+                &input_map,
             );
-            for line_num in &link.lines {
-                if let Some(line) = source.lines().nth(line_num.saturating_sub(1)) {
-                    println!("{:4} | {}", line_num, line);
+        }
+        if cli_args.explain_trace {
+            println!("\nExplanation:");
+            for sentence in checker::explain::explain_trace(&checked.1, &checked.0, &source) {
+                println!("  {sentence}");
+            }
+        }
+    }
+
+    let starvation = match cli_args.starvation {
+        Some(budget) if checked.0.is_empty() => {
+            let starvation = checker::starvation::find_starvation(&checked.1, budget);
+            match &starvation {
+                None => println!("✓ No process starves for {budget} steps"),
+                Some(starving) => {
+                    println!(
+                        "✗ Starvation: {}#{} stays enabled for {} steps without being scheduled",
+                        starving.name, starving.pid, budget
+                    );
+                    print_trace(&source, &starving.path, cli_args.show_output);
+                }
+            }
+            starvation
+        }
+        _ => None,
+    };
+
+    let unhandled =
+        if cli_args.unhandled_messages && checked.0.is_empty() {
+            let unhandled = checker::unhandled_messages::find_unhandled_messages(&checked.1);
+            match &unhandled {
+                None => println!("✓ No message left unhandled"),
+                Some(unhandled) => {
+                    println!(
+                    "✗ Unhandled messages: {}#{} terminated with {} message{} in channel {}: {}",
+                    unhandled.name,
+                    unhandled.pid,
+                    unhandled.messages.len(),
+                    if unhandled.messages.len() == 1 { "" } else { "s" },
+                    unhandled.channel,
+                    unhandled
+                        .messages
+                        .iter()
+                        .map(std::string::ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                    print_trace(&source, &unhandled.path, cli_args.show_output);
                 }
             }
+            unhandled
+        } else {
+            None
+        };
+
+    let non_termination = if cli_args.verify_termination && checked.0.is_empty() {
+        let non_termination = checker::termination::find_non_termination(&checked.1);
+        match &non_termination {
+            None if checked.1.exhaustive && !checked.1.is_approximate() => {
+                println!("✓ Every execution terminates");
+            }
+            None => println!("✓ No looping execution in the explored states"),
+            Some(lasso) => {
+                println!(
+                    "✗ Non-termination: an execution repeats a cycle of {} steps forever",
+                    lasso.cycle.len()
+                );
+                print_trace(&source, &lasso.path, cli_args.show_output);
+                println!("-- cycle --");
+                print_trace(&source, &lasso.cycle, cli_args.show_output);
+            }
         }
+        non_termination
+    } else {
+        None
+    };
+
+    if let Some(lines) = &cli_args.count_interleavings {
+        print_interleavings(&checked.1, lines);
+    }
+    if cli_args.variable_ranges {
+        print_variable_ranges(&checked.1);
     }
 
-    println!("\nVerification Statistics:");
-    println!("  States explored: {}", checked.1.nodes.len());
-    let max_depth = checked.1.nodes.iter().map(|n| n.level).max().unwrap_or(0);
-    println!("  Maximum depth:  {}", max_depth);
+    print_graph_stats(&checked.1);
+    if cli_args.prune_equivalent_sends {
+        println!("  Pruned links:   {}", checked.1.pruned_links);
+    }
 
     if !checked.0.is_empty() {
         println!("  Violation path: {} steps", checked.0.len());
         exit(1);
     }
+    if starvation.is_some() || unhandled.is_some() || non_termination.is_some() {
+        exit(1);
+    }
+}
+
+/// Prints the state graph of a program in the DOT format
+///
+/// # Panics
+/// If the input cannot be read
+pub fn graph_command(cli_args: &GraphCommand) {
+    // Read file
+    let (source, path) = match cli_args.common.input.clone() {
+        args::Input::Stdin => {
+            let mut buf = Vec::new();
+            let _ = std::io::stdin().read_to_end(&mut buf);
+            (
+                String::from_utf8(buf).expect("Could not read stdin"),
+                PathBuf::from("./stdin.alt"),
+            )
+        }
+        args::Input::Path(path) => (
+            fs::read_to_string(&path).expect("Could not read file"),
+            path,
+        ),
+    };
+
+    let mut input_map = HashMap::new();
+    input_map.insert(path.to_string_lossy().to_string(), source.clone());
+
+    // parse code with pest
+    let pairs =
+        althread::parser::parse(&source, path.to_string_lossy().as_ref()).unwrap_or_else(|e| {
+            e.report(&input_map);
+            exit(1);
+        });
+
+    let ast = Ast::build(pairs, path.to_string_lossy().as_ref()).unwrap_or_else(|e| {
+        e.report(&input_map);
+        exit(1);
+    });
+
+    let compiled_project = ast
+        .compile(&path, StandardFileSystem, &mut input_map)
+        .unwrap_or_else(|e| {
+            e.report(&input_map);
+            exit(1);
+        });
+    let int_abstraction = cli_args.int_abstraction.build();
+    let options = checker::CheckOptions {
+        run: RunOptions {
+            int_abstraction: int_abstraction.as_ref(),
+            instr_limit_per_step: Some(cli_args.instr_limit_per_step),
+            ..Default::default()
+        },
+        max_states: Some(usize::try_from(cli_args.max_states).unwrap_or(usize::MAX)),
+        ..Default::default()
+    };
+
+    let state_graph = checker::explore(&compiled_project, &options).unwrap_or_else(|e| {
+        e.report(&input_map);
+        exit(1);
+    });
+
+    if !state_graph.exhaustive {
+        eprintln!(
+            "{}",
+            format!(
+                "Warning: Maximum number of states ({}) reached. The graph is incomplete.",
+                cli_args.max_states
+            )
+            .paint(Style::new().yellow())
+        );
+    }
+
+    if cli_args.reachability {
+        let condensation = checker::condensation::condense(&state_graph);
+        print!("{}", condensation.to_dot(&state_graph));
+    } else {
+        print!("{}", state_graph.to_dot());
+    }
+}
+
+/// Measures the time taken by the checker on a program
+///
+/// # Panics
+/// If the input cannot be read
+pub fn bench_command(cli_args: &BenchCommand) {
+    // Read file
+    let (source, path) = match cli_args.common.input.clone() {
+        args::Input::Stdin => {
+            let mut buf = Vec::new();
+            let _ = std::io::stdin().read_to_end(&mut buf);
+            (
+                String::from_utf8(buf).expect("Could not read stdin"),
+                PathBuf::from("./stdin.alt"),
+            )
+        }
+        args::Input::Path(path) => (
+            fs::read_to_string(&path).expect("Could not read file"),
+            path,
+        ),
+    };
+
+    let mut input_map = HashMap::new();
+    input_map.insert(path.to_string_lossy().to_string(), source.clone());
+
+    let start = Instant::now();
+    let pairs =
+        althread::parser::parse(&source, path.to_string_lossy().as_ref()).unwrap_or_else(|e| {
+            e.report(&input_map);
+            exit(1);
+        });
+    let parse_time = start.elapsed();
+
+    let start = Instant::now();
+    let ast = Ast::build(pairs, path.to_string_lossy().as_ref()).unwrap_or_else(|e| {
+        e.report(&input_map);
+        exit(1);
+    });
+    let build_time = start.elapsed();
+
+    let start = Instant::now();
+    let compiled_project = ast
+        .compile(&path, StandardFileSystem, &mut input_map)
+        .unwrap_or_else(|e| {
+            e.report(&input_map);
+            exit(1);
+        });
+    let compile_time = start.elapsed();
+    let int_abstraction = cli_args.int_abstraction.build();
+    let options = checker::CheckOptions {
+        run: RunOptions {
+            int_abstraction: int_abstraction.as_ref(),
+            ..Default::default()
+        },
+        max_states: Some(usize::try_from(cli_args.max_states).unwrap_or(usize::MAX)),
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+    let (violation, graph) =
+        checker::check_program(&compiled_project, &options).unwrap_or_else(|e| {
+            e.report(&input_map);
+            exit(1);
+        });
+    let check_time = start.elapsed();
+
+    let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    println!("Phase timings:");
+    println!("  Parse:   {:>10.3} ms", millis(parse_time));
+    println!("  Build:   {:>10.3} ms", millis(build_time));
+    println!("  Compile: {:>10.3} ms", millis(compile_time));
+    println!("  Check:   {:>10.3} ms", millis(check_time));
+    println!(
+        "  Total:   {:>10.3} ms",
+        millis(parse_time + build_time + compile_time + check_time)
+    );
+
+    println!("\nStates explored: {}", graph.nodes.len());
+    if !graph.exhaustive {
+        println!(
+            "{}",
+            format!(
+                "Warning: Maximum number of states ({}) reached. The search was not exhaustive.",
+                cli_args.max_states
+            )
+            .paint(Style::new().yellow())
+        );
+    }
+    if !violation.is_empty() {
+        println!("The check stopped at a violation after {} steps", violation.len());
+    }
+}
+
+/// Writes the schedule of a counterexample, with a comment telling how to replay it
+fn export_counterexample(
+    schedule_path: &Path,
+    compiled_project: &althread::compiler::CompiledProject,
+    run_options: RunOptions,
+    graph: &checker::StateGraph,
+    path: &[checker::StateLink],
+    source_path: &Path,
+    input_map: &HashMap<String, String>,
+) {
+    let schedule =
+        checker::schedule::Schedule::from_path(compiled_project, run_options, graph, path)
+            .unwrap_or_else(|e| {
+                e.report(input_map);
+                exit(1);
+            });
+    let content = format!(
+        "# counterexample of {}, replay it with `althread-cli run {} --replay {}`\n{}",
+        source_path.display(),
+        source_path.display(),
+        schedule_path.display(),
+        schedule
+    );
+    if let Err(e) = fs::write(schedule_path, content) {
+        eprintln!("Error writing {}: {}", schedule_path.display(), e);
+        exit(1);
+    }
+    println!("Counterexample written to {}", schedule_path.display());
+}
+
+fn print_trace(source: &str, path: &[checker::StateLink], show_output: bool) {
+    for link in path {
+        println!(
+            "{}",
+            format!("-- {}#{} --", link.name, link.pid).paint(if link.pid == 0 {
+                MAIN_STYLE
+            } else {
+                PROCESS_PALETTE[(link.pid.saturating_sub(1)) % PROCESS_PALETTE.len()]
+            })
+        );
+        for line_num in &link.lines {
+            if let Some(line) = source.lines().nth(line_num.saturating_sub(1)) {
+                println!("{line_num:4} | {line}");
+            }
+        }
+        if show_output {
+            for msg in link.printed() {
+                println!("{:4} > {}", "", msg);
+            }
+        }
+    }
+}
+
+fn print_interleavings(graph: &checker::StateGraph, lines: &RangeInclusive<usize>) {
+    let region = checker::interleavings::count_interleavings(graph, lines);
+    println!("\nInterleavings of lines {}..{}:", lines.start(), lines.end());
+    println!("  Transitions: {}", region.transitions);
+    println!("  States:      {}", region.states);
+    match region.paths {
+        Some(paths) => println!("  Executions:  {paths}"),
+        None => println!("  Executions:  infinite (the state graph has cycles)"),
+    }
+}
+
+fn print_variable_ranges(graph: &checker::StateGraph) {
+    let ranges = checker::variable_ranges::variable_ranges(graph);
+    let states = graph.states.iter().flatten().count();
+    println!("\nValues of the shared variables ({states} states):");
+    let width = ranges
+        .iter()
+        .map(|range| range.variable.len())
+        .max()
+        .unwrap_or_default();
+    for range in &ranges {
+        match range.int_bounds {
+            Some((min, max)) => println!(
+                "  {:<width$}  {:>6} values  ({}..{})",
+                range.variable, range.distinct_values, min, max
+            ),
+            None => println!(
+                "  {:<width$}  {:>6} values",
+                range.variable, range.distinct_values
+            ),
+        }
+    }
+}
+
+/// Prints the size of the explored state graph
+fn print_graph_stats(graph: &checker::StateGraph) {
+    let stats = graph.stats();
+    println!("\nVerification Statistics:");
+    println!("  States explored: {}", stats.states);
+    println!("  Transitions:     {}", stats.transitions);
+    println!("  Maximum depth:  {}", stats.max_depth);
+    if stats.eventually_states > 0 {
+        println!("  Eventually states: {}", stats.eventually_states);
+    }
+}
+
+/// Lists the conditions of the project and whether the check proved them
+fn print_summary(
+    ast: &Ast,
+    compiled_project: &althread::compiler::CompiledProject,
+    graph: &checker::StateGraph,
+    input_map: &HashMap<String, String>,
+    liveness_checked: bool,
+) {
+    let complete = graph.exhaustive && !graph.is_approximate();
+    let source_text = |pos: &althread::error::Pos| {
+        input_map
+            .get(&pos.file_path)
+            .and_then(|source| source.get(pos.start..pos.end))
+            .map(|text| {
+                let text = text.trim_end().trim_end_matches(';');
+                text.split_whitespace().collect::<Vec<_>>().join(" ")
+            })
+            .unwrap_or_default()
+    };
+    let verified = "✓".paint(Style::new().green());
+    let unchecked = "-".paint(Style::new().yellow());
+
+    println!("\nChecked properties ({} states explored):", graph.nodes.len());
+    for (.., pos) in &compiled_project.always_conditions {
+        if complete {
+            println!("  {} always, line {}: {}", verified, pos.line, source_text(pos));
+        } else {
+            println!(
+                "  {} always, line {}: {} (holds in the explored states only)",
+                unchecked,
+                pos.line,
+                source_text(pos)
+            );
+        }
+    }
+    for (.., pos) in &compiled_project.never_conditions {
+        if complete {
+            println!("  {} never, line {}: {}", verified, pos.line, source_text(pos));
+        } else {
+            println!(
+                "  {} never, line {}: {} (holds in the explored states only)",
+                unchecked,
+                pos.line,
+                source_text(pos)
+            );
+        }
+    }
+    for check_block in &ast.check_blocks {
+        for pos in &check_block.value.positions {
+            if complete && liveness_checked {
+                println!("  {} check, line {}: {}", verified, pos.line, source_text(pos));
+            } else {
+                println!(
+                    "  {} check, line {}: {} (not checked)",
+                    unchecked,
+                    pos.line,
+                    source_text(pos)
+                );
+            }
+        }
+    }
+    for pos in &compiled_project.ignored_conditions {
+        println!(
+            "  {} line {}: {} (ignored)",
+            unchecked,
+            pos.line,
+            source_text(pos)
+        );
+    }
+}
+
+/// The warning printed when the search was stopped before exploring the whole state space
+fn search_limit_warning(cli_args: &CheckCommand, graph: &checker::StateGraph) -> String {
+    if graph.time_limit_reached {
+        format!(
+            "Warning: Timeout ({} ms) reached after {} states. The search was not exhaustive.",
+            cli_args.timeout.unwrap_or_default(),
+            graph.nodes.len()
+        )
+    } else if graph.memory_limit_reached {
+        format!(
+            "Warning: Memory limit ({} MB) reached after {} states. The search was not exhaustive.",
+            cli_args.max_memory.unwrap_or_default(),
+            graph.nodes.len()
+        )
+    } else if graph.depth_limit_reached
+        && graph.nodes.len() < usize::try_from(cli_args.max_states).unwrap_or(usize::MAX)
+    {
+        format!(
+            "Warning: Explored to depth {} (bound reached). The search was not exhaustive.",
+            cli_args.max_depth.unwrap_or_default()
+        )
+    } else {
+        format!(
+            "Warning: Maximum number of states ({}) reached. The search was not exhaustive.",
+            cli_args.max_states
+        )
+    }
+}
+
+/// Checks every invariant over the whole state space and reports each distinct violation
+fn check_all_violations(
+    cli_args: &CheckCommand,
+    options: &checker::CheckOptions,
+    ast: &Ast,
+    compiled_project: &althread::compiler::CompiledProject,
+    source: &str,
+    path: &Path,
+    input_map: &HashMap<String, String>,
+) {
+    let (violations, graph) = checker::check_program_all_violations(compiled_project, options)
+        .unwrap_or_else(|e| {
+            e.report(input_map);
+            exit(1);
+        });
+    report_int_abstraction(options.run.int_abstraction);
+    report_ignored_conditions(compiled_project);
+
+    if let Some(csv_path) = &cli_args.csv_states {
+        if let Err(e) = write_states_csv(csv_path, &graph) {
+            eprintln!("Error writing {}: {}", csv_path.display(), e);
+            exit(1);
+        }
+    }
+
+    if !graph.exhaustive {
+        println!(
+            "{}",
+            search_limit_warning(cli_args, &graph).paint(Style::new().yellow())
+        );
+    }
+
+    if violations.is_empty() {
+        println!("✓ No invariant violated");
+        if cli_args.summary {
+            // the liveness properties are not checked when exploring all the violations
+            print_summary(ast, compiled_project, &graph, input_map, false);
+        }
+    } else {
+        println!("✗ {} distinct invariant(s) violated", violations.len());
+        // the first violation has the shortest counterexample
+        if let Some(schedule_path) = &cli_args.export_counterexample {
+            export_counterexample(
+                schedule_path,
+                compiled_project,
+                options.run,
+                &graph,
+                &violations[0].path,
+                path,
+                input_map,
+            );
+        }
+        println!("\n  #  Invariant{:26}Steps", "");
+        for (i, violation) in violations.iter().enumerate() {
+            let location = violation.error.pos.as_ref().map_or_else(
+                || "?".to_string(),
+                |pos| format!("{}:{}:{}", pos.file_path, pos.line, pos.col),
+            );
+            println!("{:3}  {:35}{}", i + 1, location, violation.path.len());
+        }
+        for (i, violation) in violations.iter().enumerate() {
+            println!("\n=== Violation #{}: {} ===", i + 1, violation.error.message);
+            print_trace(source, &violation.path, cli_args.show_output);
+            if cli_args.explain_trace {
+                println!("\nExplanation:");
+                for sentence in checker::explain::explain_trace(&graph, &violation.path, source) {
+                    println!("  {sentence}");
+                }
+            }
+        }
+    }
+    println!(
+        "{}",
+        "Note: Liveness properties (eventually) are not checked when exploring all the violations."
+            .paint(Style::new().italic())
+    );
+
+    if let Some(lines) = &cli_args.count_interleavings {
+        print_interleavings(&graph, lines);
+    }
+    if cli_args.variable_ranges {
+        print_variable_ranges(&graph);
+    }
+
+    print_graph_stats(&graph);
+    if cli_args.prune_equivalent_sends {
+        println!("  Pruned links:   {}", graph.pruned_links);
+    }
+
+    if !violations.is_empty() {
+        exit(1);
+    }
 }
 
 const MAIN_STYLE: Style = Style::new().red().on_bright_black();
@@ -255,32 +887,46 @@ pub fn run_interactive(
     source: String,
     input_map: HashMap<String, String>,
     compiled_project: althread::compiler::CompiledProject,
+    run_options: RunOptions,
+    history_depth: usize,
 ) {
-    let mut vm = althread::vm::VM::new(&compiled_project);
+    let mut vm = althread::vm::VM::new(&compiled_project, run_options);
 
     vm.start(0);
+    vm.set_history_depth(history_depth);
+    print_interactive_state(&vm);
 
     loop {
-        let next_states = vm.next().unwrap_or_else(|e| {
+        let mut next_states = vm.next().unwrap_or_else(|e| {
             e.report(&input_map);
             exit(1);
         });
         if next_states.is_empty() {
             println!("No next state");
+            if vm.can_step_back() {
+                println!("Enter b to step back, or anything else to exit:");
+                let mut input = String::new();
+                if stdin().read_line(&mut input).unwrap() > 0 && input.trim() == "b" {
+                    vm.step_back();
+                    println!("Stepped back");
+                    print_interactive_state(&vm);
+                    continue;
+                }
+            }
             return;
         }
 
+        // only a compact preview of each option is shown, the full state is printed once chosen
         for (idx, (name, pid, insts, actions, nvm)) in next_states.iter().enumerate() {
-            println!("======= VM next ({}) =======", idx);
             let preview_line: String = if let Some(first) = insts.first() {
-                if first.pos.is_some() {
-                    source
+                match &first.pos {
+                    Some(pos) => source
                         .lines()
-                        .nth(first.pos.as_ref().unwrap().line)
+                        .nth(pos.line.saturating_sub(1))
                         .unwrap_or_default()
-                        .to_string()
-                } else {
-                    "?".to_string()
+                        .trim()
+                        .to_string(),
+                    None => "?".to_string(),
                 }
             } else {
                 // Delivery steps are schedulable transitions with no executed instruction.
@@ -296,49 +942,51 @@ pub fn run_interactive(
                 }
                 preview.unwrap_or_else(|| "<no instruction>".to_string())
             };
-            println!("{}:{}:{}", name, pid, preview_line);
-
-            let s = nvm.current_state();
-            println!("global: {:?}", s.0);
-            for ((pid, cname), state) in s.1.iter() {
-                println!("channel {},{}", pid, cname);
-                for v in state.iter() {
-                    println!("  * {}", v);
-                }
-            }
-            for (pid, local_state) in s.2.iter().enumerate() {
-                println!(
-                    "{} ({}): {:?}",
-                    pid,
-                    local_state.1,
-                    local_state
-                        .0
-                        .iter()
-                        .map(|v| format!("{}", v))
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                );
+            println!("({idx}) {name}#{pid}: {preview_line}");
+
+            let changed: Vec<String> = nvm
+                .globals
+                .iter()
+                .filter(|(global, value)| vm.globals.get(*global) != Some(*value))
+                .map(|(global, value)| format!("{global} = {value}"))
+                .collect();
+            if !changed.is_empty() {
+                println!("      {}", changed.join(", "));
             }
         }
-        //read an integer from the user
-        let mut selected: i32 = -1;
-        while selected < 0 || selected >= next_states.len() as i32 {
-            println!("Enter an integer between 0 and {}:", next_states.len() - 1);
+        //read an integer from the user, or `b` to undo the last step
+        let mut selected: Option<usize> = None;
+        let mut back = false;
+        while !back && selected.is_none() {
+            println!(
+                "Enter an integer between 0 and {} (or b to step back):",
+                next_states.len() - 1
+            );
             let mut input = String::new();
             let bytes_read = stdin().read_line(&mut input).unwrap();
             if bytes_read == 0 {
                 println!("EOF on stdin, exiting interactive mode");
                 return;
             }
-            match input.trim().parse() {
-                Ok(v) => selected = v,
-                Err(_) => {
-                    selected = -1;
-                    continue;
+            if input.trim() == "b" {
+                back = vm.step_back();
+                if !back {
+                    println!("No step to undo");
                 }
+                continue;
             }
+            selected = input
+                .trim()
+                .parse()
+                .ok()
+                .filter(|index| *index < next_states.len());
         }
-        let (_name, _pid, _insts, actions, nvm) = next_states.get(selected as usize).unwrap();
+        let Some(selected) = selected else {
+            println!("Stepped back");
+            print_interactive_state(&vm);
+            continue;
+        };
+        let (_name, _pid, _insts, actions, nvm) = next_states.swap_remove(selected);
 
         for action in actions {
             if let althread::vm::GlobalAction::Print(msg) = action {
@@ -346,11 +994,24 @@ pub fn run_interactive(
             }
         }
 
-        vm = nvm.clone();
+        vm.step_forward_recorded(nvm);
+        print_interactive_state(&vm);
+    }
+}
+
+/// Prints the shared variables, the channels and the local memory of each process
+fn print_interactive_state(vm: &althread::vm::VM) {
+    println!("======= VM state =======");
+    for line in vm.state_lines() {
+        println!("{line}");
     }
 }
 
 pub fn run_command(cli_args: &RunCommand) {
+    if let Err(e) = cli_args.check_output() {
+        e.exit();
+    }
+
     // Read file
     let (source, path) = match cli_args.common.input.clone() {
         args::Input::Stdin => {
@@ -388,31 +1049,88 @@ pub fn run_command(cli_args: &RunCommand) {
             e.report(&input_map);
             exit(1);
         });
+    let int_abstraction = cli_args.int_abstraction.build();
+    let run_options = cli_args.run_options(int_abstraction.as_ref());
+
+    report_analysis_warnings(&compiled_project);
 
     if cli_args.interactive {
-        run_interactive(source, input_map, compiled_project);
+        run_interactive(
+            source,
+            input_map,
+            compiled_project,
+            run_options,
+            cli_args.history_depth,
+        );
+        return;
+    }
+
+    if let Some(schedule_path) = &cli_args.replay {
+        replay_schedule(
+            schedule_path,
+            &compiled_project,
+            run_options,
+            &source,
+            &input_map,
+            cli_args.list_enabled,
+        );
         return;
     }
 
     let mut vm_execution: Vec<althread::vm::VM> = Vec::new();
     let mut vm_set: HashSet<althread::vm::VM> = HashSet::new();
-    let mut vm = althread::vm::VM::new(&compiled_project);
+    let mut vm = althread::vm::VM::new(&compiled_project, run_options);
 
-    vm.start(cli_args.seed.unwrap_or(fastrand::u64(0..(1 << 63))));
+    let seed = cli_args.seed.unwrap_or(fastrand::u64(0..(1 << 63)));
+    vm.start(seed);
+    let json = cli_args.format == TraceFormat::Json;
+    let mut trace: Vec<serde_json::Value> = Vec::new();
     let mut step_count = 0;
+    let mut spawned = Vec::new();
+    let mut invariant_violated = false;
+    let started = Instant::now();
+    let mut time_limit_reached = false;
     while step_count < cli_args.max_steps {
         if vm.is_finished() {
             break;
         }
+        if cli_args
+            .timeout
+            .is_some_and(|timeout| started.elapsed() >= Duration::from_millis(timeout))
+        {
+            time_limit_reached = true;
+            break;
+        }
         step_count += 1;
-        let info = vm.next_random().unwrap_or_else(|err| {
-            err.report(&input_map);
-            exit(1);
-        });
+        let info = match vm.next_random() {
+            Ok(info) => info,
+            // the execution leaves the scenarios the program is assumed to run in
+            Err(err) if matches!(err.error_type, ErrorType::AssumptionViolated) => {
+                println!(
+                    "{}",
+                    "Note: an assumption does not hold, the execution stops here.".paint(Style::new().italic())
+                );
+                break;
+            }
+            Err(err) => {
+                if json {
+                    write_trace(&trace, cli_args.output.as_deref());
+                }
+                err.report(&input_map);
+                exit(1);
+            }
+        };
 
+        if json {
+            trace.push(trace_step(&info, &vm));
+        }
         for action in info.actions.iter() {
-            if let althread::vm::GlobalAction::Print(msg) = action {
-                println!("{}", msg);
+            match action {
+                althread::vm::GlobalAction::Print(msg) if !json => println!("{msg}"),
+                althread::vm::GlobalAction::StartProgram(name, pid, ..) => {
+                    spawned.push((name.clone(), *pid));
+                }
+                _ => {}
             }
         }
 
@@ -430,7 +1148,7 @@ pub fn run_command(cli_args: &RunCommand) {
                             .lines()
                             .nth(inst.pos.clone().unwrap_or_default().line - 1)
                             .unwrap_or_default()
-                            .style(if info.prog_id == 0 {
+                            .paint(if info.prog_id == 0 {
                                 MAIN_STYLE
                             } else {
                                 PROCESS_PALETTE
@@ -453,6 +1171,7 @@ pub fn run_command(cli_args: &RunCommand) {
         }
         if info.invariant_error.is_err() {
             info.invariant_error.unwrap_err().report(&input_map);
+            invariant_violated = true;
             break;
         }
 
@@ -465,43 +1184,302 @@ pub fn run_command(cli_args: &RunCommand) {
         vm_set.insert(vm.clone());
     }
 
-    if !vm.is_finished() && step_count >= cli_args.max_steps {
-        println!(
+    if json {
+        write_trace(&trace, cli_args.output.as_deref());
+    }
+
+    report_int_abstraction(run_options.int_abstraction);
+
+    if invariant_violated && cli_args.minimize_trace {
+        minimize_processes(
+            &compiled_project,
+            run_options,
+            &input_map,
+            seed,
+            &spawned,
+            cli_args.max_steps,
+        );
+    }
+
+    // the loop stopped because of the cap, not because the programs finished
+    let step_limit_reached = !vm.is_finished() && step_count >= cli_args.max_steps;
+    if time_limit_reached {
+        eprintln!(
+            "{}",
+            format!(
+                "Error: stopped after {} steps / {} ms (--timeout); simulation may be incomplete.",
+                step_count,
+                started.elapsed().as_millis()
+            )
+            .epaint(Style::new().red())
+        );
+    }
+    if step_limit_reached {
+        eprintln!(
             "{}",
             format!(
-                "Error: Maximum number of steps ({}) reached. The program might be in an infinite loop. Use --max-steps to increase the limit.",
+                "Error: step limit ({}) reached; simulation may be incomplete. The program might be in an infinite loop. Use --max-steps to increase the limit.",
                 cli_args.max_steps
             )
-            .red()
+            .epaint(Style::new().red())
         );
     }
 
     if cli_args.verbose {
         for v in vm_execution.iter() {
             println!("======= VM step =======");
-            let s = v.current_state();
-            println!("global: {:?}", s.0);
-            for ((pid, cname), state) in s.1.iter() {
-                println!("channel {},{}", pid, cname);
-                for v in state.iter() {
-                    println!("  * {}", v);
+            for line in v.state_lines() {
+                println!("{line}");
+            }
+        }
+    }
+
+    if step_limit_reached || time_limit_reached {
+        exit(1);
+    }
+}
+
+/// A step of the JSON trace of `run --format json`: the executed step, followed by the state it
+/// leads to
+fn trace_step(info: &althread::vm::ExecutionStepInfo, vm: &althread::vm::VM) -> serde_json::Value {
+    let mut step = serde_json::to_value(info).expect("a step can be serialized");
+    step["state"] = serde_json::to_value(vm).expect("a state can be serialized");
+    step
+}
+
+fn write_trace(trace: &[serde_json::Value], output: Option<&Path>) {
+    let json = serde_json::to_string_pretty(trace).expect("the trace can be serialized");
+    match output {
+        Some(path) => fs::write(path, json + "\n").unwrap_or_else(|e| {
+            eprintln!("Error: cannot write the trace to {}: {}", path.display(), e);
+            exit(1);
+        }),
+        None => println!("{json}"),
+    }
+}
+
+/// Replays the schedule of a counterexample, exiting with an error if an invariant is violated.
+/// With `list_enabled`, the steps enabled in the reached state are listed afterwards.
+fn replay_schedule(
+    schedule_path: &Path,
+    compiled_project: &althread::compiler::CompiledProject,
+    run_options: RunOptions,
+    source: &str,
+    input_map: &HashMap<String, String>,
+    list_enabled: bool,
+) {
+    let schedule: checker::schedule::Schedule = fs::read_to_string(schedule_path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| content.parse())
+        .unwrap_or_else(|e| {
+            eprintln!("Error reading {}: {}", schedule_path.display(), e);
+            exit(1);
+        });
+
+    let mut vm = schedule
+        .initial_vm(compiled_project, run_options)
+        .unwrap_or_else(|e| {
+        e.report(input_map);
+        exit(1);
+    });
+    if let Err(e) = vm.check_invariants() {
+        e.report(input_map);
+        exit(1);
+    }
+
+    let mut trace = Vec::new();
+    for step in &schedule.steps {
+        let (link, next) = step.replay(&vm).unwrap_or_else(|e| {
+            e.report(input_map);
+            exit(1);
+        });
+        for action in &link.actions {
+            if let althread::vm::GlobalAction::Print(msg) = action {
+                println!("{msg}");
+            }
+        }
+        trace.push(link);
+        vm = next;
+        if let Err(e) = vm.check_invariants() {
+            println!("✗ Invariant violated");
+            print_trace(source, &trace, false);
+            e.report(input_map);
+            exit(1);
+        }
+    }
+
+    println!(
+        "✓ The {} steps of the schedule were replayed without violating an invariant",
+        schedule.steps.len()
+    );
+
+    if list_enabled {
+        print_enabled_steps(compiled_project, &vm, input_map);
+    }
+}
+
+/// Lists the steps that can be taken from a state, and what the other processes wait for
+fn print_enabled_steps(
+    compiled_project: &althread::compiler::CompiledProject,
+    vm: &althread::vm::VM,
+    input_map: &HashMap<String, String>,
+) {
+    let line_of = |pid: usize| {
+        vm.running_programs
+            .get(pid)
+            .and_then(|program| program.current_instruction().ok())
+            .and_then(|instruction| instruction.pos.as_ref())
+            .map_or(String::new(), |pos| format!(" at line {}", pos.line))
+    };
+
+    let next_states = vm.next().unwrap_or_else(|e| {
+        e.report(input_map);
+        exit(1);
+    });
+    // a step with several outcomes (e.g. a call to `rand`) is listed once
+    let mut enabled: Vec<(String, usize)> = next_states
+        .iter()
+        .map(|(name, pid, ..)| (name.clone(), *pid))
+        .collect();
+    enabled.dedup();
+
+    println!("\nEnabled steps:");
+    if enabled.is_empty() {
+        println!("  none");
+    }
+    for (name, pid) in &enabled {
+        match name.strip_prefix("__deliver__ ") {
+            Some(channel) => println!("  delivery of a message to {channel}"),
+            None => println!("  {}#{}{}", name, pid, line_of(*pid)),
+        }
+    }
+
+    let blocked: Vec<_> = vm
+        .running_programs
+        .iter()
+        .filter(|program| !program.has_terminated())
+        .filter(|program| {
+            !enabled
+                .iter()
+                .any(|(name, pid)| *pid == program.id && *name == program.name)
+        })
+        .collect();
+    println!("\nBlocked processes:");
+    if blocked.is_empty() {
+        println!("  none");
+    }
+    for program in blocked {
+        let reason = match vm.wait_dependency(program.id) {
+            Some(dependencies) => dependencies
+                .describe(|variable| compiled_project.global_table.contains_key(variable)),
+            None => "a synchronous send to be received".to_string(),
+        };
+        println!(
+            "  {}#{}{}: waits for {}",
+            program.name,
+            program.id,
+            line_of(program.id),
+            reason
+        );
+    }
+}
+
+/// Number of seeds tried for each candidate configuration when minimizing a failing run
+const MINIMIZE_SEEDS: u64 = 100;
+
+/// Runs the project with a seed, the `disabled` programs never being scheduled. Returns the
+/// invariant violation if one occurs, with the processes that were started and not disabled.
+fn run_with_disabled_programs(
+    compiled_project: &althread::compiler::CompiledProject,
+    run_options: RunOptions,
+    seed: u64,
+    disabled: &[usize],
+    max_steps: u64,
+) -> Option<(althread::error::AlthreadError, Vec<(String, usize)>)> {
+    let mut vm = althread::vm::VM::new(compiled_project, run_options);
+    for pid in disabled {
+        vm.disable_program(*pid);
+    }
+    vm.start(seed);
+
+    let mut spawned = Vec::new();
+    for _ in 0..max_steps {
+        if vm.is_finished() {
+            break;
+        }
+        let info = vm.next_random().ok()?;
+        for action in &info.actions {
+            if let althread::vm::GlobalAction::StartProgram(name, pid, ..) = action {
+                if !disabled.contains(pid) {
+                    spawned.push((name.clone(), *pid));
                 }
             }
-            for (pid, local_state) in s.2.iter().enumerate() {
-                println!(
-                    "{} ({}): {:?}",
-                    pid,
-                    local_state.1,
-                    local_state
-                        .0
-                        .iter()
-                        .map(|v| format!("{}", v))
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                );
+        }
+        if let Err(e) = info.invariant_error {
+            return Some((e, spawned));
+        }
+    }
+    None
+}
+
+/// Greedily disables the processes spawned by a failing run, one at a time, keeping each
+/// removal for which some seed still violates an invariant, and reports the smallest
+/// configuration found. Returns its failing seed and the disabled processes.
+fn minimize_processes(
+    compiled_project: &althread::compiler::CompiledProject,
+    run_options: RunOptions,
+    input_map: &HashMap<String, String>,
+    seed: u64,
+    spawned: &[(String, usize)],
+    max_steps: u64,
+) -> (u64, Vec<usize>) {
+    println!("===== Minimizing the number of processes =====");
+    let mut disabled: Vec<usize> = Vec::new();
+    let mut failing_seed = seed;
+    for (name, pid) in spawned {
+        disabled.push(*pid);
+        let still_fails = std::iter::once(failing_seed)
+            .chain(0..MINIMIZE_SEEDS)
+            .find(|s| {
+                run_with_disabled_programs(compiled_project, run_options, *s, &disabled, max_steps)
+                    .is_some()
+            });
+        match still_fails {
+            Some(s) => {
+                println!("{name}#{pid} is not needed for the violation");
+                failing_seed = s;
+            }
+            None => {
+                disabled.pop();
             }
         }
     }
+
+    let Some((error, kept)) = run_with_disabled_programs(
+        compiled_project,
+        run_options,
+        failing_seed,
+        &disabled,
+        max_steps,
+    ) else {
+        unreachable!("the minimized configuration was checked to fail");
+    };
+    println!(
+        "{}",
+        format!(
+            "The violation still occurs with {} of the {} spawned processes (seed {}): {}",
+            kept.len(),
+            spawned.len(),
+            failing_seed,
+            kept.iter()
+                .map(|(name, pid)| format!("{name}#{pid}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+        .paint(Style::new().yellow())
+    );
+    error.report(input_map);
+    (failing_seed, disabled)
 }
 
 pub fn random_search_command(cli_args: &RandomSearchCommand) {
@@ -542,41 +1520,215 @@ pub fn random_search_command(cli_args: &RandomSearchCommand) {
             e.report(&input_map);
             exit(1);
         });
+    let (summary, elapsed) = search_seeds(cli_args, &source, &path, &input_map);
+    let json = cli_args.summary_json;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&summary).expect("the summary can be serialized")
+        );
+        if !summary.violations.is_empty() {
+            exit(1);
+        }
+        return;
+    }
+
+    println!(
+        "{} seeds run in {:.2}s ({:.0} seeds/s)",
+        summary.seeds_run,
+        elapsed.as_secs_f64(),
+        summary.seeds_per_second
+    );
+    if let Some(violation) = summary.violations.first() {
+        // the errors cannot be sent between threads, the seed is run again to report its error
+        let run = run_seed(
+            &compiled_project,
+            cli_args.run_options(),
+            violation.seed,
+            cli_args.max_steps,
+            false,
+        );
+        println!("Error with seed {}:", violation.seed);
+        if let Some(err) = run.error {
+            err.report(&input_map);
+        }
+        exit(1);
+    }
+}
 
-    for s in 0..cli_args.max_seeds {
-        println!("Seed: {}/{}", s, cli_args.max_seeds);
-        let mut vm = althread::vm::VM::new(&compiled_project);
-        vm.start(s);
-        for _ in 0..cli_args.max_steps {
-            if vm.is_finished() {
+/// Runs the seeds of a random search on several threads. Unless a JSON summary is asked for,
+/// the search stops at the first violating seed, which is the smallest one whatever the
+/// number of threads
+fn search_seeds(
+    cli_args: &RandomSearchCommand,
+    source: &str,
+    path: &Path,
+    input_map: &HashMap<String, String>,
+) -> (RandomSearchSummary, Duration) {
+    let json = cli_args.summary_json;
+    let seeds = cli_args
+        .seed_range
+        .clone()
+        .unwrap_or(0..cli_args.max_seeds);
+    // the output of the executions is only readable when the seeds run one after the other
+    let threads = usize::try_from(cli_args.threads).expect("the number of threads fits in usize");
+    let verbose = !json && threads == 1;
+    // the smallest violating seed found so far: the seeds after it are not run, except with
+    // --summary-json, so that the reported seed does not depend on the number of threads
+    let first_violation = AtomicU64::new(u64::MAX);
+
+    let search = |thread: usize| {
+        // a compiled project cannot be shared between threads, each one compiles its own
+        let pairs = althread::parser::parse(source, &path.to_string_lossy())
+            .expect("the program was parsed before the search");
+        let ast = Ast::build(pairs, &path.to_string_lossy())
+            .expect("the program was built before the search");
+        let compiled_project = ast
+            .compile(path, StandardFileSystem, &mut input_map.clone())
+            .expect("the program was compiled before the search");
+
+        let mut summary = RandomSearchSummary::default();
+        for s in seeds.clone().skip(thread).step_by(threads) {
+            if !json && s > first_violation.load(Ordering::Relaxed) {
                 break;
             }
-            let info = vm.next_random().unwrap_or_else(|err| {
-                println!("Error with seed {}:", s);
-                err.report(&input_map);
-                exit(1);
-            });
+            if verbose {
+                println!("Seed: {}/{}", s, seeds.end);
+            }
+            summary.seeds_run += 1;
+            let run = run_seed(
+                &compiled_project,
+                cli_args.run_options(),
+                s,
+                cli_args.max_steps,
+                verbose,
+            );
+            summary.max_steps_reached = summary.max_steps_reached.max(run.steps);
+            summary.step_cap_hit |= run.step_cap_hit;
+            if let Some(err) = run.error {
+                summary.violations.push(SeedViolation::new(s, &err));
+                if !json {
+                    first_violation.fetch_min(s, Ordering::Relaxed);
+                    break;
+                }
+            }
+        }
+        summary
+    };
+
+    let start = Instant::now();
+    let results: Vec<RandomSearchSummary> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|thread| scope.spawn(move || search(thread)))
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("a search thread panicked"))
+            .collect()
+    });
+    let elapsed = start.elapsed();
+
+    let mut summary = RandomSearchSummary::default();
+    for thread_summary in results {
+        summary.seeds_run += thread_summary.seeds_run;
+        summary.max_steps_reached = summary.max_steps_reached.max(thread_summary.max_steps_reached);
+        summary.step_cap_hit |= thread_summary.step_cap_hit;
+        summary.violations.extend(thread_summary.violations);
+    }
+    summary.violations.sort_by_key(|violation| violation.seed);
+    // the rate is only displayed, a rounded count of seeds is precise enough
+    #[allow(clippy::cast_precision_loss)]
+    let seeds_run = summary.seeds_run as f64;
+    summary.seeds_per_second = seeds_run / elapsed.as_secs_f64().max(f64::EPSILON);
+    (summary, elapsed)
+}
+
+/// The execution of a seed by `random-search`
+struct SeedRun {
+    steps: u64,
+    /// Whether the execution was stopped by `max_steps` before its end
+    step_cap_hit: bool,
+    error: Option<AlthreadError>,
+}
+
+/// Runs the program with the given seed, printing its output if `verbose`
+fn run_seed(
+    compiled_project: &althread::compiler::CompiledProject,
+    run_options: RunOptions,
+    seed: u64,
+    max_steps: u64,
+    verbose: bool,
+) -> SeedRun {
+    let mut vm = althread::vm::VM::new(compiled_project, run_options);
+    vm.start(seed);
+    let mut steps = 0;
+    let mut error = None;
+    while steps < max_steps && !vm.is_finished() {
+        let info = match vm.next_random() {
+            Ok(info) => info,
+            // the seed leads to an execution that is assumed not to happen
+            Err(err) if matches!(err.error_type, ErrorType::AssumptionViolated) => break,
+            Err(err) => {
+                error = Some(err);
+                break;
+            }
+        };
+        steps += 1;
 
+        if verbose {
             for action in info.actions.iter() {
                 if let althread::vm::GlobalAction::Print(msg) = action {
                     println!("{}", msg);
                 }
             }
+        }
 
-            if info.invariant_error.is_err() {
-                println!("Error with seed {}:", s);
-                info.invariant_error.unwrap_err().report(&input_map);
-                exit(1);
-            }
-            /*match vm.running_programs.iter()
-                    .find(|(id, _)| **id == info.prog_id) {
-                    Some((_, p)) => match p
-                        .current_instruction() {
-                        Some(i) => println!("{}_{}: stopped at {}", info.prog_name, info.prog_id, i),
-                        None => println!("{}_{}: stopped at ?", info.prog_name, info.prog_id),
-                    },
-                None => println!("{}_{}: prog not found", info.prog_name, info.prog_id),
-            }*/
+        if let Err(err) = info.invariant_error {
+            error = Some(err);
+            break;
+        }
+    }
+
+    SeedRun {
+        steps,
+        step_cap_hit: steps == max_steps && !vm.is_finished(),
+        error,
+    }
+}
+
+/// The outcome of `random-search` over all the seeds, printed with `--summary-json`
+#[derive(Debug, Default, Serialize)]
+struct RandomSearchSummary {
+    seeds_run: u64,
+    violations: Vec<SeedViolation>,
+    /// The largest number of steps executed by a seed
+    max_steps_reached: u64,
+    /// Whether a seed was stopped by `--max-steps` before the end of the execution
+    step_cap_hit: bool,
+    seeds_per_second: f64,
+}
+
+/// An error raised by the execution of a seed, usually a violated invariant
+#[derive(Debug, Serialize)]
+struct SeedViolation {
+    seed: u64,
+    error_type: String,
+    message: String,
+    file: Option<String>,
+    line: Option<usize>,
+    column: Option<usize>,
+}
+
+impl SeedViolation {
+    fn new(seed: u64, err: &AlthreadError) -> Self {
+        Self {
+            seed,
+            error_type: err.error_type.to_string(),
+            message: err.message.clone(),
+            file: err.pos.as_ref().map(|pos| pos.file_path.clone()),
+            line: err.pos.as_ref().map(|pos| pos.line),
+            column: err.pos.as_ref().map(|pos| pos.col),
         }
     }
 }
@@ -876,7 +2028,214 @@ pub fn install_command(cli_args: &InstallCommand) {
     }
 }
 
+/// Checks the package manifest, the dependency cache and the tools used by the resolver,
+/// exiting with an error if the dependencies cannot be resolved
+pub fn doctor_command(_cli_args: &DoctorCommand) {
+    let ok = "✓".paint(Style::new().green());
+    let warning = "!".paint(Style::new().yellow());
+    let error = "✗".paint(Style::new().red());
+    let mut healthy = true;
+
+    let alt_toml_path = Path::new("alt.toml");
+    let package = if alt_toml_path.exists() {
+        match Package::load_from_path(alt_toml_path) {
+            Ok(package) => {
+                println!(
+                    "{} alt.toml is valid (package '{}' {})",
+                    ok, package.package.name, package.package.version
+                );
+                if package.package.name.trim().is_empty() {
+                    println!("{error} The package name is empty");
+                    healthy = false;
+                }
+                if semver::Version::parse(&package.package.version).is_err() {
+                    println!(
+                        "{} The package version '{}' is not a semantic version",
+                        warning, package.package.version
+                    );
+                }
+                Some(package)
+            }
+            Err(e) => {
+                println!("{error} alt.toml is invalid: {e}");
+                healthy = false;
+                None
+            }
+        }
+    } else {
+        println!("{warning} No alt.toml in the current directory");
+        None
+    };
+
+    let context = resolver::ResolutionContext::new();
+    let cache_dir = &context.cache_dir;
+    if !cache_dir.exists() {
+        println!(
+            "{} The cache directory {} does not exist yet, 'install' will create it",
+            warning,
+            cache_dir.display()
+        );
+    } else if !cache_dir.is_dir() {
+        println!("{} {} is not a directory", error, cache_dir.display());
+        healthy = false;
+    } else {
+        let probe = cache_dir.join(".doctor");
+        match fs::write(&probe, b"") {
+            Ok(()) => {
+                let _ = fs::remove_file(&probe);
+                println!(
+                    "{} The cache directory {} is writable",
+                    ok,
+                    cache_dir.display()
+                );
+            }
+            Err(e) => {
+                println!(
+                    "{} The cache directory {} is not writable: {}",
+                    error,
+                    cache_dir.display(),
+                    e
+                );
+                healthy = false;
+            }
+        }
+    }
+
+    // the dependencies are cloned with git2, the git command is only a fallback
+    match std::process::Command::new("git").arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            println!(
+                "{} {}",
+                ok,
+                String::from_utf8_lossy(&output.stdout).trim()
+            );
+        }
+        _ => println!(
+            "{warning} git is not available, the dependencies can only be fetched with the built-in client"
+        ),
+    }
+
+    if let Some(package) = &package {
+        let mut dependencies: Vec<(&String, &DependencySpec)> = package
+            .dependencies
+            .iter()
+            .chain(package.dev_dependencies.iter())
+            .collect();
+        dependencies.sort_by(|a, b| a.0.cmp(b.0));
+        if dependencies.is_empty() {
+            println!("{ok} No dependencies");
+        }
+        for (url, spec) in dependencies {
+            let (DependencySpec::Simple(version) | DependencySpec::Detailed { version, .. }) = spec;
+            let path = context.cache_path_for(url, version);
+            if path.is_dir() {
+                println!("{ok} {url}@{version} is installed");
+                continue;
+            }
+            let mut cached: Vec<String> = path
+                .parent()
+                .and_then(|parent| fs::read_dir(parent).ok())
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|name| !name.starts_with('_'))
+                .collect();
+            cached.sort();
+            if cached.is_empty() {
+                println!("{error} {url}@{version} is missing, run 'install' to fetch it");
+                healthy = false;
+            } else {
+                println!(
+                    "{} {}@{} is not installed, but {} cached (run 'install' to fetch it)",
+                    warning,
+                    url,
+                    version,
+                    cached.join(", ")
+                );
+            }
+        }
+    }
+
+    if !healthy {
+        exit(1);
+    }
+}
+
 // Helper functions to reduce repetitive code
+/// Prints the warnings of the static analyses that do not prevent running the project
+fn report_analysis_warnings(compiled_project: &althread::compiler::CompiledProject) {
+    for wait in find_unsatisfiable_waits(compiled_project) {
+        eprintln!("Warning: {wait}");
+    }
+    for conflict in find_atomic_write_conflicts(compiled_project) {
+        eprintln!("Warning: {conflict}");
+    }
+}
+
+/// Warns when the integer abstraction had to clamp values, since the explored behaviors
+/// are then those of the abstracted model
+fn report_int_abstraction(int_abstraction: Option<&IntAbstraction>) {
+    if let Some(abstraction) = int_abstraction {
+        if abstraction.clamped_count() > 0 {
+            eprintln!(
+                "{}",
+                format!(
+                    "Warning: {} integer value(s) were clamped to {}. The results hold for the abstracted model only.",
+                    abstraction.clamped_count(),
+                    abstraction
+                )
+                .epaint(Style::new().yellow())
+            );
+        }
+    }
+}
+
+fn report_ignored_conditions(compiled_project: &althread::compiler::CompiledProject) {
+    let ignored = compiled_project.ignored_conditions.len();
+    if ignored > 0 {
+        println!(
+            "{}",
+            format!(
+                "Note: {} invariant{} ignored (@ignore)",
+                ignored,
+                if ignored == 1 { "" } else { "s" }
+            )
+            .paint(Style::new().italic())
+        );
+    }
+}
+
+/// Writes one row per distinct reachable valuation of the shared variables, with one column
+/// per shared variable
+fn write_states_csv(path: &Path, state_graph: &checker::StateGraph) -> std::io::Result<()> {
+    fn csv_field(value: String) -> String {
+        if value.contains([',', '"', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value
+        }
+    }
+
+    let valuations = state_graph.global_valuations();
+    let mut csv = String::new();
+    if let Some(first) = valuations.first() {
+        let header: Vec<String> = first.keys().cloned().map(csv_field).collect();
+        csv.push_str(&header.join(","));
+        csv.push('\n');
+    }
+    for globals in valuations {
+        let row: Vec<String> = globals
+            .values()
+            .map(|value| csv_field(value.to_string()))
+            .collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    fs::write(path, csv)
+}
+
 fn load_package_or_exit(alt_toml_path: &Path) -> Package {
     match Package::load_from_path(alt_toml_path) {
         Ok(package) => package,
@@ -1081,3 +2440,109 @@ fn is_commit_hash(s: &str) -> bool {
     // and contains only hexadecimal characters
     (s.len() == 8 || s.len() == 40) && s.chars().all(|c| c.is_ascii_hexdigit())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(source: &str) -> althread::compiler::CompiledProject {
+        let mut input_map = HashMap::from([("test.alt".to_string(), source.to_string())]);
+        let pairs = althread::parser::parse(source, "test.alt").unwrap();
+        let ast = Ast::build(pairs, "test.alt").unwrap();
+        ast.compile(Path::new("test.alt"), StandardFileSystem, &mut input_map)
+            .unwrap()
+    }
+
+    /// Two processes incrementing X without atomicity can lose an update
+    const RACE: &str = r"
+shared {
+    let X = 0;
+    let Done = 0;
+    let Y = 0;
+}
+
+program Inc() {
+    let v = X;
+    X = v + 1;
+    atomic { Done = Done + 1; }
+}
+
+program Noise() {
+    Y = 1;
+    Y = 2;
+}
+
+always {
+    Done < 2 || X == 2;
+}
+
+main {
+    run Inc();
+    run Noise();
+    run Inc();
+}
+";
+
+    #[test]
+    fn random_search_stops_at_the_first_violating_seed() {
+        let input_map = HashMap::from([("test.alt".to_string(), RACE.to_string())]);
+        let search = |extra_args: &[&str]| {
+            let mut args = vec!["random-search", "--seed-range", "10..20", "test.alt"];
+            args.extend_from_slice(extra_args);
+            let cli_args = RandomSearchCommand::parse_from(args);
+            search_seeds(&cli_args, RACE, Path::new("test.alt"), &input_map).0
+        };
+
+        // the seeds 10 to 12 do not lose an update, the seed 13 does
+        let summary = search(&[]);
+        let seeds: Vec<u64> = summary.violations.iter().map(|v| v.seed).collect();
+        assert_eq!(seeds, vec![13]);
+        assert_eq!(summary.seeds_run, 4);
+
+        // the threads do not change the reported seed
+        let summary = search(&["--threads", "3"]);
+        assert_eq!(summary.violations.first().map(|v| v.seed), Some(13));
+
+        // with a summary, every seed is run
+        let summary = search(&["--summary-json"]);
+        assert_eq!(summary.seeds_run, 10);
+        let seeds: Vec<u64> = summary.violations.iter().map(|v| v.seed).collect();
+        assert_eq!(seeds, vec![13, 14, 15, 16, 18]);
+    }
+
+    #[test]
+    fn output_requires_the_json_format() {
+        let run = |extra_args: &[&str]| {
+            let mut args = vec!["run", "test.alt"];
+            args.extend_from_slice(extra_args);
+            RunCommand::parse_from(args).check_output()
+        };
+        assert!(run(&["--format", "text", "--output", "out.json"]).is_err());
+        assert!(run(&["--output", "out.json"]).is_err());
+        assert!(run(&["--format", "json", "--output", "out.json"]).is_ok());
+        assert!(run(&["--format", "text"]).is_ok());
+    }
+
+    #[test]
+    fn minimized_processes_still_violate_the_invariant() {
+        let project = compile(RACE);
+        let input_map = HashMap::from([("test.alt".to_string(), RACE.to_string())]);
+        let options = RunOptions::default();
+        let (seed, (_, spawned)) = (0..100)
+            .find_map(|seed| {
+                run_with_disabled_programs(&project, options, seed, &[], 1000)
+                    .map(|violation| (seed, violation))
+            })
+            .expect("a seed loses an update");
+        assert_eq!(spawned.len(), 3);
+
+        let (failing_seed, disabled) =
+            minimize_processes(&project, options, &input_map, seed, &spawned, 1000);
+        let (error, kept) =
+            run_with_disabled_programs(&project, options, failing_seed, &disabled, 1000)
+                .expect("the minimized configuration violates the invariant");
+        assert!(matches!(error.error_type, ErrorType::InvariantError));
+        let names: Vec<&str> = kept.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["Inc", "Inc"]);
+    }
+}
@@ -0,0 +1,392 @@
+//! Interactive evaluator of the `repl` command.
+//!
+//! Each entry is added to a program assembled from the previous ones: the declarations of a
+//! `shared` block extend the shared memory, the other top-level blocks (programs, functions,
+//! conditions, ...) are kept as they are, and everything else is a statement of `main`. After
+//! every entry, the program is compiled again and the schedule of the previous execution is
+//! replayed, so that the new entry starts from the state the previous ones left. The entries of
+//! `main` are separated by a `yield`, which ends the step of the previous entry before the new
+//! one. Then `main` runs the new entry, and only takes its next step once the processes it started
+//! cannot go further. Only the output and the changes of the shared variables caused by these new
+//! steps are printed. An entry that does not compile or fails while running is discarded.
+
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    io::{stdin, stdout, Write},
+    path::Path,
+};
+
+use owo_colors::Style;
+
+use althread::{
+    ast::{token::literal::Literal, Ast},
+    checker::schedule::{Schedule, ScheduledStep},
+    error::{AlthreadError, ErrorType},
+    module_resolver::StandardFileSystem,
+    vm::{GlobalAction, RunOptions, VM},
+};
+
+use crate::args::ReplCommand;
+use crate::color::Paint;
+
+const REPL_FILE: &str = "repl.alt";
+
+/// The keywords starting a top-level block instead of a statement of `main`
+const TOP_LEVEL_KEYWORDS: [&str; 8] = [
+    "program", "fn", "always", "never", "check", "import", "@private", "@pure",
+];
+
+/// The entries accepted so far
+#[derive(Debug, Default, Clone)]
+struct Session {
+    shared: Vec<String>,
+    blocks: Vec<String>,
+    main: Vec<String>,
+}
+
+/// What the steps taken after the schedule of the previous entries produced
+struct Outcome {
+    prints: Vec<String>,
+    /// The shared variables these steps changed, with their new value
+    changed_globals: Vec<(String, Literal)>,
+    /// The schedule of the whole execution, replayed before the next entry
+    schedule: Schedule,
+}
+
+/// The schedule of a session without entries
+fn empty_schedule() -> Schedule {
+    Schedule {
+        initial: 0,
+        steps: Vec::new(),
+    }
+}
+
+fn sorted_globals(vm: &VM) -> Vec<(String, Literal)> {
+    let mut globals: Vec<(String, Literal)> = vm
+        .globals
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    globals.sort_by(|a, b| a.0.cmp(&b.0));
+    globals
+}
+
+impl Session {
+    fn source(&self) -> String {
+        let indent = |entries: &[String]| {
+            entries.iter().flat_map(|entry| entry.lines()).fold(
+                String::new(),
+                |mut indented, line| {
+                    writeln!(indented, "    {line}").unwrap();
+                    indented
+                },
+            )
+        };
+        let mut source = String::new();
+        if !self.shared.is_empty() {
+            writeln!(source, "shared {{\n{}}}", indent(&self.shared)).unwrap();
+        }
+        for block in &self.blocks {
+            source.push_str(block);
+            source.push('\n');
+        }
+        // the yields end the step of each entry before the next one
+        let main = self.main.join("\nyield;\n");
+        writeln!(source, "main {{\n{}}}", indent(&[main])).unwrap();
+        source
+    }
+
+    /// The sessions the entry may extend this one into, in order of preference. An entry
+    /// without a trailing `;` is first tried as an expression whose value is printed.
+    fn candidates(&self, entry: &str) -> Vec<Session> {
+        let mut candidate = self.clone();
+        let first_word = entry.split_whitespace().next().unwrap_or_default();
+        if first_word == "shared" || first_word.starts_with("shared{") {
+            let body = entry.trim_start_matches("shared").trim();
+            // a named shared block is a separate block, only the anonymous one is merged
+            match body.strip_prefix('{').and_then(|body| body.strip_suffix('}')) {
+                Some(declarations) => candidate.shared.push(declarations.trim().to_string()),
+                None => candidate.blocks.push(entry.to_string()),
+            }
+            return vec![candidate];
+        }
+        if TOP_LEVEL_KEYWORDS.contains(&first_word) {
+            candidate.blocks.push(entry.to_string());
+            return vec![candidate];
+        }
+        if entry.ends_with(';') || entry.ends_with('}') {
+            candidate.main.push(entry.to_string());
+            return vec![candidate];
+        }
+
+        let mut statement = self.clone();
+        statement.main.push(format!("{entry};"));
+        candidate.main.push(format!("print({entry});"));
+        vec![candidate, statement]
+    }
+
+    /// Compiles the session and replays `previous`, the schedule of the session before the
+    /// entry, then runs the program until it ends
+    fn compile_and_run(
+        &self,
+        cli_args: &ReplCommand,
+        previous: &Schedule,
+    ) -> Result<Outcome, (AlthreadError, HashMap<String, String>)> {
+        let source = self.source();
+        let mut input_map = HashMap::new();
+        input_map.insert(REPL_FILE.to_string(), source.clone());
+
+        let compiled_project = althread::parser::parse(&source, REPL_FILE)
+            .and_then(|pairs| Ast::build(pairs, REPL_FILE))
+            .and_then(|ast| ast.compile(Path::new(REPL_FILE), StandardFileSystem, &mut input_map))
+            .map_err(|e| (e, input_map.clone()))?;
+
+        let mut vm = previous
+            .initial_vm(&compiled_project, RunOptions::default())
+            .map_err(|e| (e, input_map.clone()))?;
+        for step in &previous.steps {
+            let (_, next) = step.replay(&vm).map_err(|e| (e, input_map.clone()))?;
+            vm = next;
+        }
+        let globals_before = sorted_globals(&vm);
+
+        let mut schedule = previous.clone();
+        let mut rng = fastrand::Rng::with_seed(cli_args.seed);
+        let mut prints = Vec::new();
+        let mut step_count = 0;
+        loop {
+            let mut steps = vm.next().map_err(|e| (e, input_map.clone()))?;
+            if steps.is_empty() {
+                // the processes either all ended or wait forever
+                if let Some(error) = vm.deadlock() {
+                    return Err((error, input_map));
+                }
+                break;
+            }
+            if step_count >= cli_args.max_steps {
+                return Err((
+                    AlthreadError::new(
+                        ErrorType::RuntimeError,
+                        None,
+                        format!(
+                            "the program did not terminate within {} steps",
+                            cli_args.max_steps
+                        ),
+                    ),
+                    input_map,
+                ));
+            }
+            step_count += 1;
+            // `main` only takes its next step once the processes cannot go further
+            let mut candidates: Vec<usize> = (0..steps.len())
+                .filter(|index| steps[*index].0 != "main")
+                .collect();
+            if candidates.is_empty() {
+                candidates = (0..steps.len()).collect();
+            }
+            let index = candidates[rng.usize(..candidates.len())];
+            let (name, pid, ..) = &steps[index];
+            let choice = steps[..index]
+                .iter()
+                .filter(|(other_name, other_pid, ..)| other_pid == pid && other_name == name)
+                .count();
+            let (name, pid, _, actions, next) = steps.swap_remove(index);
+            for action in actions {
+                if let GlobalAction::Print(msg) = action {
+                    prints.push(msg);
+                }
+            }
+            next.check_invariants()
+                .map_err(|e| (e, input_map.clone()))?;
+            schedule.steps.push(ScheduledStep { pid, name, choice });
+            vm = next;
+        }
+
+        let changed_globals = sorted_globals(&vm)
+            .into_iter()
+            .filter(|global| !globals_before.contains(global))
+            .collect();
+        Ok(Outcome {
+            prints,
+            changed_globals,
+            schedule,
+        })
+    }
+
+    /// Evaluates the entry in this session, returning the extended session and what the entry
+    /// produced, or the error of its first candidate
+    fn eval(
+        &self,
+        entry: &str,
+        cli_args: &ReplCommand,
+        previous: &Schedule,
+    ) -> Result<(Session, Outcome), (AlthreadError, HashMap<String, String>)> {
+        let mut first_error = None;
+        for candidate in self.candidates(entry) {
+            match candidate.compile_and_run(cli_args, previous) {
+                Ok(outcome) => return Ok((candidate, outcome)),
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+        Err(first_error.expect("an entry has at least one candidate"))
+    }
+}
+
+/// Whether the braces and parentheses of the entry are all closed
+fn is_complete(entry: &str) -> bool {
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in entry.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    depth <= 0 && !in_string
+}
+
+pub fn repl_command(cli_args: &ReplCommand) {
+    println!("Althread REPL. Enter declarations, statements or expressions.");
+    println!("Commands: :source (show the program), :reset, :quit");
+
+    let mut session = Session::default();
+    let mut schedule = empty_schedule();
+    let mut entry = String::new();
+
+    loop {
+        print!("{}", if entry.is_empty() { ">>> " } else { "... " });
+        let _ = stdout().flush();
+        let mut line = String::new();
+        match stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => {
+                println!();
+                return;
+            }
+            Ok(_) => {}
+        }
+        if !entry.is_empty() {
+            entry.push('\n');
+        }
+        entry.push_str(line.trim_end());
+        if !is_complete(&entry) {
+            continue;
+        }
+        let input = std::mem::take(&mut entry);
+        let input = input.trim();
+
+        match input {
+            "" => continue,
+            ":quit" | ":q" => return,
+            ":source" => {
+                print!("{}", session.source());
+                continue;
+            }
+            ":reset" => {
+                session = Session::default();
+                schedule = empty_schedule();
+                continue;
+            }
+            _ => {}
+        }
+
+        // the first candidate that runs is kept, the error of the first one is reported
+        match session.eval(input, cli_args, &schedule) {
+            Ok((extended, outcome)) => {
+                for msg in &outcome.prints {
+                    println!("{msg}");
+                }
+                let changed: Vec<String> = outcome
+                    .changed_globals
+                    .iter()
+                    .map(|(name, value)| format!("{name} = {value}"))
+                    .collect();
+                if !changed.is_empty() {
+                    println!("{}", changed.join(", ").paint(Style::new().dimmed()));
+                }
+                session = extended;
+                schedule = outcome.schedule;
+            }
+            Err((error, input_map)) => error.report(&input_map),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[test]
+    fn test_is_complete() {
+        assert!(is_complete("let x = 1;"));
+        assert!(!is_complete("program A() {"));
+        assert!(is_complete("program A() {\n    print(\"{\");\n}"));
+    }
+
+    #[test]
+    fn test_candidates() {
+        let session = Session::default();
+
+        let candidates = session.candidates("shared { let X = 0; }");
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].shared, vec!["let X = 0;".to_string()]);
+
+        let candidates = session.candidates("program A() {}");
+        assert_eq!(candidates[0].blocks, vec!["program A() {}".to_string()]);
+
+        // an expression is printed, or run as a statement missing its `;`
+        let candidates = session.candidates("x + 1");
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].main, vec!["print(x + 1);".to_string()]);
+        assert_eq!(candidates[1].main, vec!["x + 1;".to_string()]);
+    }
+
+    #[test]
+    fn test_entries_after_a_running_process() {
+        let cli_args = ReplCommand::parse_from(["repl"]);
+        let mut session = Session::default();
+        let mut schedule = empty_schedule();
+        let mut outputs = Vec::new();
+        for entry in [
+            "shared { let X = 0; }",
+            "program P() { print(\"p1\"); X = X + 1; print(\"p2\"); }",
+            "run P();",
+            "print(\"m1\");",
+            "print(\"m2\");",
+            "X = X + 10;",
+        ] {
+            let (extended, outcome) = session.eval(entry, &cli_args, &schedule).unwrap();
+            outputs.push((outcome.prints, outcome.changed_globals));
+            session = extended;
+            schedule = outcome.schedule;
+        }
+
+        // each entry only shows what it printed and changed, after the process it started ended
+        let x = |value| vec![("X".to_string(), Literal::Int(value))];
+        assert_eq!(
+            outputs[2..],
+            [
+                (vec!["p1".to_string(), "p2".to_string()], x(1)),
+                (vec!["m1".to_string()], Vec::new()),
+                (vec!["m2".to_string()], Vec::new()),
+                (Vec::new(), x(11)),
+            ]
+        );
+    }
+}
@@ -39,7 +39,7 @@
 			alias: 'system-block'
 		},
 
-		'keyword': /\b(?:let|as|const|channel|while|for|in|loop|atomic|if|else|run|send|await|seq|first|receive|private)\b/,
+		'keyword': /\b(?:let|as|const|channel|while|for|in|loop|repeat|atomic|if|else|run|send|await|seq|first|receive|join|private)\b/,
 
         'function': /\b\w+(?=\()/,
 		// Hex, oct, bin, dec numbers with visual separators and type suffix
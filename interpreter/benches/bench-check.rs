@@ -0,0 +1,70 @@
+//! Times the model checker on a model of about 10k states, in-process so that only the
+//! exploration is measured: `cargo bench -p althread --bench bench-check`
+use std::{collections::HashMap, path::Path, time::Instant};
+
+use althread::{
+    ast::Ast,
+    checker::{check_program, CheckOptions},
+    module_resolver::StandardFileSystem,
+    parser,
+};
+
+/// Three processes updating their own counter: 10649 states and 30493 transitions
+const MODEL: &str = r"
+shared {
+    let A = 0;
+    let B = 0;
+    let C = 0;
+}
+
+program Count(i: int) {
+    let n = 0;
+    repeat 10 {
+        if i == 0 { A = (A + 1) % 4; }
+        if i == 1 { B = (B + n) % 5; }
+        if i == 2 { C = (C + 2) % 3; }
+        n = n + 1;
+    }
+}
+
+always {
+    A < 4;
+}
+
+main {
+    run Count(0);
+    run Count(1);
+    run Count(2);
+}
+";
+
+const RUNS: usize = 10;
+
+fn main() {
+    let path = "counters.alt";
+    let mut input_map = HashMap::from([(path.to_string(), MODEL.to_string())]);
+    let pairs = parser::parse(MODEL, path).expect("the model parses");
+    let ast = Ast::build(pairs, path).expect("the model builds");
+    let project = ast
+        .compile(Path::new(path), StandardFileSystem, &mut input_map)
+        .expect("the model compiles");
+
+    let mut times = Vec::new();
+    let mut states = 0;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let (violation, state_graph) =
+            check_program(&project, &CheckOptions::default()).expect("the check succeeds");
+        times.push(start.elapsed());
+        assert!(violation.is_empty());
+        states = state_graph.nodes.len();
+    }
+    times.sort();
+    println!(
+        "check_program: {} states, min {:?}, median {:?} over {} runs",
+        states,
+        times[0],
+        times[RUNS / 2],
+        RUNS
+    );
+}
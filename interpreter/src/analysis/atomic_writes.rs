@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::compiler::CompiledProject;
+use crate::error::Pos;
+use crate::vm::instruction::{Instruction, InstructionType};
+
+/// A shared variable assigned several times within the same atomic block. Only the last value is
+/// visible to the other processes, so the first assignments are usually a modeling mistake.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtomicWriteConflict {
+    /// The program or function containing the atomic block
+    pub location: String,
+    pub variable: String,
+    /// The positions of the assignments, in the order of the instructions
+    pub positions: Vec<Option<Pos>>,
+}
+
+impl fmt::Display for AtomicWriteConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(Some(pos)) = self.positions.first() {
+            write!(f, "{}:{}:{}: ", pos.file_path, pos.line, pos.col)?;
+        }
+        let lines: Vec<String> = self
+            .positions
+            .iter()
+            .flatten()
+            .map(|pos| pos.line.to_string())
+            .collect();
+        write!(
+            f,
+            "the atomic block in '{}' assigns the shared variable '{}' {} times (lines {})",
+            self.location,
+            self.variable,
+            self.positions.len(),
+            lines.join(", ")
+        )
+    }
+}
+
+/// Finds the shared variables assigned more than once within an `atomic` block.
+///
+/// This is a static check over the compiled instructions between an `AtomicStart` and its
+/// `AtomicEnd`: an assignment in a loop is only counted once, and the writes made by the
+/// functions called from the block are not considered.
+#[must_use]
+pub fn find_atomic_write_conflicts(project: &CompiledProject) -> Vec<AtomicWriteConflict> {
+    let mut bodies: Vec<(&String, &[Instruction])> = project
+        .programs_code
+        .iter()
+        .map(|(name, code)| (name, code.instructions.as_slice()))
+        .chain(
+            project
+                .user_functions
+                .iter()
+                .map(|(name, func)| (name, func.body.as_slice())),
+        )
+        .collect();
+    bodies.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut conflicts = Vec::new();
+    for (name, instructions) in bodies {
+        let mut depth = 0;
+        let mut assignments: BTreeMap<&String, Vec<Option<Pos>>> = BTreeMap::new();
+        for instruction in instructions {
+            match &instruction.control {
+                InstructionType::AtomicStart => depth += 1,
+                InstructionType::AtomicEnd if depth > 0 => {
+                    depth -= 1;
+                    if depth == 0 {
+                        conflicts.extend(
+                            std::mem::take(&mut assignments)
+                                .into_iter()
+                                .filter(|(_, positions)| positions.len() > 1)
+                                .map(|(variable, positions)| AtomicWriteConflict {
+                                    location: name.clone(),
+                                    variable: variable.clone(),
+                                    positions,
+                                }),
+                        );
+                    }
+                }
+                InstructionType::GlobalAssignment { identifier, .. } if depth > 0 => {
+                    assignments
+                        .entry(identifier)
+                        .or_default()
+                        .push(instruction.pos.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::compile_from_source;
+
+    #[test]
+    fn reports_variable_assigned_twice_in_atomic_block() {
+        let project = compile_from_source(
+            r#"
+shared {
+    let X = 0;
+    let Y = 0;
+}
+
+program A() {
+    atomic {
+        X = 1;
+        Y = 2;
+        X = X + 1;
+    }
+    X = 3;
+}
+
+main {
+    run A();
+    atomic {
+        Y = 1;
+    }
+    Y = 2;
+}
+"#,
+        );
+
+        let conflicts = find_atomic_write_conflicts(&project);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].location, "A");
+        assert_eq!(conflicts[0].variable, "X");
+        let lines: Vec<usize> = conflicts[0]
+            .positions
+            .iter()
+            .map(|pos| pos.as_ref().unwrap().line)
+            .collect();
+        assert_eq!(lines, vec![9, 11]);
+    }
+
+    #[test]
+    fn ignores_assignments_in_separate_atomic_blocks() {
+        let project = compile_from_source(
+            r#"
+shared {
+    let X = 0;
+}
+
+main {
+    atomic { X = 1; }
+    atomic { X = 2; }
+}
+"#,
+        );
+
+        assert!(find_atomic_write_conflicts(&project).is_empty());
+    }
+}
@@ -1 +1,3 @@
+pub mod atomic_writes;
 pub mod control_flow_graph;
+pub mod wait_dependencies;
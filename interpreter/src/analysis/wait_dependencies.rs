@@ -0,0 +1,199 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::compiler::CompiledProject;
+use crate::error::Pos;
+use crate::vm::instruction::{Instruction, InstructionType};
+
+/// An `await` whose condition cannot change while a process is blocked on it:
+/// if the condition is false when the process reaches it, the process waits forever.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsatisfiableWait {
+    /// The program or function containing the `await`
+    pub location: String,
+    pub pos: Option<Pos>,
+    /// The shared variables the condition depends on, none of them is ever written
+    pub variables: Vec<String>,
+}
+
+impl fmt::Display for UnsatisfiableWait {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(pos) = &self.pos {
+            write!(f, "{}:{}:{}: ", pos.file_path, pos.line, pos.col)?;
+        }
+        if self.variables.is_empty() {
+            write!(
+                f,
+                "the await condition in '{}' only depends on local variables, it can never become true once it is false",
+                self.location
+            )
+        } else {
+            let variables: Vec<String> = self.variables.iter().map(|v| format!("'{v}'")).collect();
+            write!(
+                f,
+                "the await condition in '{}' depends on {} which {} never written, it can never become true once it is false",
+                self.location,
+                variables.join(", "),
+                if variables.len() > 1 { "are" } else { "is" }
+            )
+        }
+    }
+}
+
+/// Finds the `await` statements whose condition can never change once evaluated.
+///
+/// A waiting process cannot modify its own local variables, so a condition can only become
+/// true if another process writes one of the shared variables it depends on, or if it depends
+/// on a channel or joins a program. This is a conservative analysis: waits depending on channels,
+/// on a program termination or on a shared variable written anywhere in the project are never
+/// reported.
+#[must_use]
+pub fn find_unsatisfiable_waits(project: &CompiledProject) -> Vec<UnsatisfiableWait> {
+    let mut bodies: Vec<(&String, &[Instruction])> = project
+        .programs_code
+        .iter()
+        .map(|(name, code)| (name, code.instructions.as_slice()))
+        .chain(
+            project
+                .user_functions
+                .iter()
+                .map(|(name, func)| (name, func.body.as_slice())),
+        )
+        .collect();
+    bodies.sort_by(|a, b| a.0.cmp(b.0));
+
+    let written = written_globals(project, &bodies);
+
+    let mut unsatisfiable = Vec::new();
+    for (name, instructions) in bodies {
+        for instruction in instructions {
+            let InstructionType::WaitStart { dependencies, .. } = &instruction.control else {
+                continue;
+            };
+            if dependencies.variables.is_empty()
+                || !dependencies.channels_state.is_empty()
+                || !dependencies.channels_connection.is_empty()
+                || dependencies.program_termination
+            {
+                continue;
+            }
+            if dependencies.variables.iter().any(|v| written.contains(v)) {
+                continue;
+            }
+            let mut variables: Vec<String> = dependencies
+                .variables
+                .iter()
+                .filter(|v| project.global_table.contains_key(*v))
+                .cloned()
+                .collect();
+            variables.sort();
+            unsatisfiable.push(UnsatisfiableWait {
+                location: name.clone(),
+                pos: instruction.pos.clone(),
+                variables,
+            });
+        }
+    }
+    unsatisfiable
+}
+
+/// The shared variables that may be written by an instruction of the project
+fn written_globals(
+    project: &CompiledProject,
+    bodies: &[(&String, &[Instruction])],
+) -> HashSet<String> {
+    let mut written = HashSet::new();
+    for (_, instructions) in bodies {
+        for instruction in *instructions {
+            match &instruction.control {
+                InstructionType::GlobalAssignment { identifier, .. } => {
+                    written.insert(identifier.clone());
+                }
+                InstructionType::MethodCall {
+                    name,
+                    global_receiver: Some(receiver),
+                    ..
+                } => {
+                    // unknown methods are considered as writes to stay conservative
+                    let read_only = project.global_table.get(receiver).is_some_and(|var| {
+                        project
+                            .stdlib
+                            .interfaces(&var.datatype)
+                            .iter()
+                            .any(|i| i.name == *name && !i.mutates_receiver)
+                    });
+                    if !read_only {
+                        written.insert(receiver.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    written
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::compile_from_source;
+
+    #[test]
+    fn reports_wait_on_never_written_variable() {
+        let project = compile_from_source(
+            r#"
+shared {
+    let Ready = false;
+    let Count = 0;
+}
+
+program A() {
+    await Ready;
+}
+
+program B() {
+    await Count > 0;
+}
+
+main {
+    run A();
+    run B();
+    atomic { Count = Count + 1; }
+}
+"#,
+        );
+
+        let waits = find_unsatisfiable_waits(&project);
+        assert_eq!(waits.len(), 1);
+        assert_eq!(waits[0].location, "A");
+        assert_eq!(waits[0].variables, vec!["Ready".to_string()]);
+        assert_eq!(waits[0].pos.as_ref().unwrap().line, 8);
+    }
+
+    #[test]
+    fn ignores_waits_on_channels_and_mutated_lists() {
+        let project = compile_from_source(
+            r#"
+shared {
+    let Items: list(int);
+}
+
+program A() {
+    await Items.len() > 0;
+    await receive in (x) => {
+        print(x);
+    }
+}
+
+main {
+    let a = run A();
+    channel self.out (int)> a.in;
+    Items.push(1);
+    send out(2);
+}
+"#,
+        );
+
+        assert!(find_unsatisfiable_waits(&project).is_empty());
+    }
+}
@@ -3,11 +3,18 @@ use std::fmt;
 use pest::iterators::Pairs;
 
 use crate::compiler::{CompilerState, InstructionBuilderOk};
-use crate::error::AlthreadResult;
+use crate::error::{AlthreadError, AlthreadResult, ErrorType, Pos};
 use crate::parser::Rule;
 use crate::vm::instruction::{Instruction, InstructionType};
 
-use super::statement::expression::Expression;
+use super::statement::expression::{
+    binary_expression::BinaryExpression, primary_expression::PrimaryExpression,
+    unary_expression::UnaryExpression, Expression,
+};
+use super::token::{
+    binary_operator::BinaryOperator, object_identifier::ObjectIdentifier,
+    unary_operator::UnaryOperator,
+};
 use super::{
     display::{AstDisplay, Prefix},
     node::{InstructionBuilder, Node, NodeBuilder},
@@ -16,6 +23,10 @@ use super::{
 #[derive(Debug)]
 pub struct ConditionBlock {
     pub children: Vec<Node<Expression>>,
+    /// The conditions annotated with `@ignore`, which are not checked
+    pub ignored: Vec<Node<Expression>>,
+    /// The variables listed in `mutex(...)` statements, whose pairs are expanded in `children`
+    pub mutexes: Vec<Node<ObjectIdentifier>>,
 }
 
 impl NodeBuilder for ConditionBlock {
@@ -23,8 +34,46 @@ impl NodeBuilder for ConditionBlock {
         let mut block = Self::new();
 
         for pair in pairs {
-            let node = Node::build(pair, filepath)?;
-            block.children.push(node);
+            match pair.as_rule() {
+                Rule::ignored_statement => {
+                    // skip the directive
+                    let statement = pair.into_inner().nth(1).unwrap();
+                    block.ignored.push(Node::build(statement, filepath)?);
+                }
+                Rule::mutex_statement => {
+                    let pos = Pos::from_span(pair.as_span(), filepath);
+                    let variables = pair
+                        .into_inner()
+                        .map(|variable| Node::build(variable, filepath))
+                        .collect::<AlthreadResult<Vec<Node<ObjectIdentifier>>>>()?;
+                    if variables.len() < 2 {
+                        return Err(AlthreadError::new(
+                            ErrorType::SyntaxError,
+                            Some(pos),
+                            "mutex(...) expects at least two variables".to_string(),
+                        ));
+                    }
+                    for (i, a) in variables.iter().enumerate() {
+                        if variables[..i]
+                            .iter()
+                            .any(|b| b.value.to_string() == a.value.to_string())
+                        {
+                            return Err(AlthreadError::new(
+                                ErrorType::SyntaxError,
+                                Some(a.pos.clone()),
+                                format!("'{}' is listed twice in mutex(...)", a.value),
+                            ));
+                        }
+                    }
+                    for (i, a) in variables.iter().enumerate() {
+                        for b in &variables[i + 1..] {
+                            block.children.push(not_both(a, b, &pos));
+                        }
+                    }
+                    block.mutexes.extend(variables);
+                }
+                _ => block.children.push(Node::build(pair, filepath)?),
+            }
         }
 
         Ok(block)
@@ -54,8 +103,50 @@ impl ConditionBlock {
     pub fn new() -> Self {
         Self {
             children: Vec::new(),
+            ignored: Vec::new(),
+            mutexes: Vec::new(),
+        }
+    }
+}
+
+/// Builds the expression `!(a && b)`, positioned at the `mutex(...)` statement
+fn not_both(a: &Node<ObjectIdentifier>, b: &Node<ObjectIdentifier>, pos: &Pos) -> Node<Expression> {
+    fn at<T>(value: T, pos: &Pos) -> Node<T> {
+        Node {
+            value,
+            pos: pos.clone(),
         }
     }
+    let variable = |identifier: &Node<ObjectIdentifier>| {
+        Box::new(at(
+            Expression::Primary(at(
+                PrimaryExpression::Identifier(identifier.clone()),
+                &identifier.pos,
+            )),
+            &identifier.pos,
+        ))
+    };
+    let both = BinaryExpression {
+        left: variable(a),
+        operator: at(BinaryOperator::And, pos),
+        right: variable(b),
+    };
+    let not_both = UnaryExpression {
+        operator: at(UnaryOperator::Not, pos),
+        operand: Box::new(at(
+            Expression::Primary(at(
+                PrimaryExpression::Expression(Box::new(at(Expression::Binary(at(both, pos)), pos))),
+                pos,
+            )),
+            pos,
+        )),
+    };
+    // wrapped like a parsed expression statement, so that both compile to the same condition
+    let statement = at(Expression::Unary(at(not_both, pos)), pos);
+    at(
+        Expression::Primary(at(PrimaryExpression::Expression(Box::new(statement)), pos)),
+        pos,
+    )
 }
 
 impl AstDisplay for ConditionBlock {
@@ -27,13 +27,18 @@ use crate::{
 
 use crate::checker::ltl::ast::CheckBlock;
 
+/// A function's arguments, return type, body, and whether it is private and pure
+pub type FunctionBlock = (Node<ArgsList>, DataType, Node<Block>, bool, bool);
+
 #[derive(Debug)]
 pub struct Ast {
     pub process_blocks: HashMap<String, (Node<ArgsList>, Node<Block>, bool)>,
     pub condition_blocks: HashMap<ConditionKeyword, Node<ConditionBlock>>,
     pub check_blocks: Vec<Node<CheckBlock>>,
     pub global_block: Option<Node<Block>>,
-    pub function_blocks: HashMap<String, (Node<ArgsList>, DataType, Node<Block>, bool)>,
+    /// Named shared blocks, whose variables are qualified with the namespace name
+    pub shared_namespaces: Vec<(String, Node<Block>)>,
+    pub function_blocks: HashMap<String, FunctionBlock>,
     pub import_block: Option<Node<ImportBlock>>,
 }
 
@@ -44,6 +49,7 @@ impl Ast {
             condition_blocks: HashMap::new(),
             check_blocks: Vec::new(),
             global_block: None,
+            shared_namespaces: Vec::new(),
             function_blocks: HashMap::new(),
             import_block: None,
         }
@@ -85,8 +91,15 @@ impl Ast {
                 Rule::global_block => {
                     let mut pairs = pair.into_inner();
 
-                    let global_block = Node::build(pairs.next().unwrap(), filepath)?;
-                    ast.global_block = Some(global_block);
+                    let first = pairs.next().unwrap();
+                    if first.as_rule() == Rule::identifier {
+                        let namespace = first.as_str().to_string();
+                        let global_block = Node::build(pairs.next().unwrap(), filepath)?;
+                        ast.shared_namespaces.push((namespace, global_block));
+                    } else {
+                        let global_block = Node::build(first, filepath)?;
+                        ast.global_block = Some(global_block);
+                    }
                 }
                 Rule::condition_block => {
                     let mut pairs = pair.into_inner();
@@ -119,6 +132,21 @@ impl Ast {
                     let process_identifier = pairs.next().unwrap().as_str().to_string();
                     let args_list: Node<token::args_list::ArgsList> =
                         Node::build(pairs.next().unwrap(), filepath)?;
+                    for (i, is_constant) in args_list.value.constants.iter().enumerate() {
+                        let datatype = &args_list.value.datatypes[i];
+                        if *is_constant
+                            && !matches!(datatype.value, DataType::Integer | DataType::Boolean)
+                        {
+                            return Err(AlthreadError::new(
+                                ErrorType::TypeError,
+                                Some(datatype.pos.clone()),
+                                format!(
+                                    "The const parameter '{}' must be of type int or bool, not {}",
+                                    args_list.value.identifiers[i].value.value, datatype.value
+                                ),
+                            ));
+                        }
+                    }
                     let program_block = Node::build(pairs.next().unwrap(), filepath)?;
                     ast.process_blocks
                         .insert(process_identifier, (args_list, program_block, is_private));
@@ -133,11 +161,26 @@ impl Ast {
                         is_private = true;
                         pairs.next(); // consume the private directive
                     }
+                    let mut is_pure = false;
+                    if pairs.peek().unwrap().as_rule() == Rule::pure_directive {
+                        is_pure = true;
+                        pairs.next(); // consume the pure directive
+                    }
 
                     let function_identifier = pairs.next().unwrap().as_str().to_string();
 
                     let args_list: Node<token::args_list::ArgsList> =
                         Node::build(pairs.next().unwrap(), filepath)?;
+                    if let Some(i) = args_list.value.constants.iter().position(|c| *c) {
+                        return Err(AlthreadError::new(
+                            ErrorType::SyntaxError,
+                            Some(args_list.value.identifiers[i].pos.clone()),
+                            format!(
+                                "The parameter '{}' of function '{}' cannot be const, only the parameters of a program can",
+                                args_list.value.identifiers[i].value.value, function_identifier
+                            ),
+                        ));
+                    }
                     pairs.next(); // skip the "->" token
                     let return_datatype = DataType::from_str(pairs.next().unwrap().as_str());
 
@@ -154,7 +197,7 @@ impl Ast {
 
                     ast.function_blocks.insert(
                         function_identifier,
-                        (args_list, return_datatype, function_block, is_private),
+                        (args_list, return_datatype, function_block, is_private, is_pure),
                     );
                 }
                 Rule::EOI => (),
@@ -184,6 +227,11 @@ impl AstDisplay for Ast {
             global_node.ast_fmt(f, &prefix.add_branch())?;
         }
 
+        for (namespace, global_node) in &self.shared_namespaces {
+            writeln!(f, "{prefix}shared {namespace}")?;
+            global_node.ast_fmt(f, &prefix.add_branch())?;
+        }
+
         writeln!(f, "")?;
 
         for (condition_name, condition_node) in &self.condition_blocks {
@@ -209,10 +257,11 @@ impl AstDisplay for Ast {
             writeln!(f, "")?;
         }
 
-        for (function_name, (_args, return_type, function_node, is_private)) in
+        for (function_name, (_args, return_type, function_node, is_private, is_pure)) in
             &self.function_blocks
         {
             writeln!(f, "{}", if *is_private { "@private " } else { "" })?;
+            writeln!(f, "{}", if *is_pure { "@pure " } else { "" })?;
             writeln!(f, "{}{} -> {}", prefix, function_name, return_type)?;
             function_node.ast_fmt(f, &prefix.add_branch())?;
             writeln!(f, "")?;
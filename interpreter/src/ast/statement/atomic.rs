@@ -23,8 +23,16 @@ pub struct Atomic {
 
 impl NodeBuilder for Atomic {
     fn build(mut pairs: Pairs<Rule>, filepath: &str) -> AlthreadResult<Self> {
-        let mut statement: Box<Node<Statement>> =
-            Box::new(Node::build(pairs.next().unwrap(), filepath)?);
+        Ok(Self::new(Node::build(pairs.next().unwrap(), filepath)?))
+    }
+}
+
+impl Atomic {
+    /// Makes a statement atomic. A leading `await` or `select` starts the atomic block itself,
+    /// once its condition holds.
+    #[must_use]
+    pub fn new(statement: Node<Statement>) -> Self {
+        let mut statement = Box::new(statement);
         let mut delegated = false;
 
         let mut first_statement = statement.as_mut();
@@ -36,6 +44,10 @@ impl NodeBuilder for Atomic {
                     wait.value.start_atomic = true;
                     true
                 }
+                Statement::Select(select) => {
+                    select.value.start_atomic = true;
+                    true
+                }
                 _ => false,
             }
         };
@@ -56,10 +68,10 @@ impl NodeBuilder for Atomic {
             }
         }
 
-        Ok(Self {
+        Self {
             statement,
             delegated,
-        })
+        }
     }
 }
 
@@ -91,7 +103,14 @@ impl InstructionBuilder for Node<Atomic> {
             control: InstructionType::AtomicEnd,
         });
         if builder.contains_jump() {
-            for idx in builder.break_indexes.get("").unwrap_or(&Vec::new()) {
+            // every jump still pending here leaves the atomic block (whatever the label of the
+            // loop it targets), so the atomic region must end before it is executed
+            for idx in builder
+                .break_indexes
+                .values()
+                .chain(builder.continue_indexes.values())
+                .flatten()
+            {
                 if let InstructionType::Break { stop_atomic, .. } =
                     &mut builder.instructions[*idx as usize].control
                 {
@@ -100,13 +119,13 @@ impl InstructionBuilder for Node<Atomic> {
                     panic!("Expected Break instruction");
                 }
             }
-            for idx in builder.continue_indexes.get("").unwrap_or(&Vec::new()) {
-                if let InstructionType::Break { stop_atomic, .. } =
-                    &mut builder.instructions[*idx as usize].control
+            for idx in &builder.return_indexes {
+                if let InstructionType::Return { stop_atomic, .. } =
+                    &mut builder.instructions[*idx].control
                 {
                     *stop_atomic = true;
                 } else {
-                    panic!("Expected Break instruction");
+                    panic!("Expected Return instruction");
                 }
             }
         }
@@ -21,11 +21,21 @@ pub struct ChannelDeclaration {
     pub ch_right_prog: String,
     pub ch_right_name: String,
     pub datatypes: Vec<DataType>,
+    /// Synchronous (rendezvous) channels: a send only happens together with the matching receive
+    pub sync: bool,
+    /// The maximum number of messages in flight or buffered towards the receiver, a send to a
+    /// full channel waits until the receiver pops a message
+    pub capacity: Option<usize>,
     // todo: direction
 }
 
 impl NodeBuilder for ChannelDeclaration {
-    fn build(mut pairs: Pairs<Rule>, _filepath: &str) -> AlthreadResult<Self> {
+    fn build(mut pairs: Pairs<Rule>, filepath: &str) -> AlthreadResult<Self> {
+        let sync = pairs.peek().unwrap().as_rule() == Rule::sync_modifier;
+        if sync {
+            pairs.next();
+        }
+
         let mut left_pairs = pairs.next().unwrap().into_inner();
         let left_prog = String::from(left_pairs.next().unwrap().as_str());
         let mut left_parts = Vec::new();
@@ -42,6 +52,30 @@ impl NodeBuilder for ChannelDeclaration {
             datatypes.push(datatype);
         }
 
+        let mut capacity = None;
+        if pairs.peek().unwrap().as_rule() == Rule::channel_capacity {
+            let pair = pairs.next().unwrap();
+            let pos = Pos::from_span(pair.as_span(), filepath);
+            let value = pair.into_inner().next().unwrap().as_str();
+            capacity = match value.parse::<usize>() {
+                Ok(capacity) if capacity > 0 => Some(capacity),
+                _ => {
+                    return Err(AlthreadError::new(
+                        ErrorType::SyntaxError,
+                        Some(pos),
+                        format!("Invalid channel capacity '{value}', expected a positive integer"),
+                    ))
+                }
+            };
+            if sync {
+                return Err(AlthreadError::new(
+                    ErrorType::SyntaxError,
+                    Some(pos),
+                    "A synchronous channel cannot have a capacity".to_string(),
+                ));
+            }
+        }
+
         let mut right_pairs = pairs.next().unwrap().into_inner();
         let right_prog = String::from(right_pairs.next().unwrap().as_str());
         let mut right_parts = Vec::new();
@@ -56,6 +90,8 @@ impl NodeBuilder for ChannelDeclaration {
             ch_right_prog: right_prog,
             ch_right_name: right_name,
             datatypes,
+            sync,
+            capacity,
         })
     }
 }
@@ -204,6 +240,8 @@ impl InstructionBuilder for Node<ChannelDeclaration> {
                 receiver_pid: get_var_id(&dec.ch_right_prog, state, &self.pos)?,
                 sender_channel: dec.ch_left_name.clone(),
                 receiver_channel: dec.ch_right_name.clone(),
+                sync: dec.sync,
+                capacity: dec.capacity,
             },
             pos: Some(self.pos.clone()),
         }]))
@@ -212,7 +250,13 @@ impl InstructionBuilder for Node<ChannelDeclaration> {
 
 impl AstDisplay for ChannelDeclaration {
     fn ast_fmt(&self, f: &mut fmt::Formatter, prefix: &Prefix) -> fmt::Result {
-        writeln!(f, "{prefix}channel decl")?;
+        if self.sync {
+            writeln!(f, "{prefix}sync channel decl")?;
+        } else if let Some(capacity) = self.capacity {
+            writeln!(f, "{prefix}channel decl (capacity {capacity})")?;
+        } else {
+            writeln!(f, "{prefix}channel decl")?;
+        }
 
         Ok(())
     }
@@ -20,7 +20,7 @@ use crate::{
 
 use super::expression::SideEffectExpression;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Declaration {
     pub keyword: Node<DeclarationKeyword>,
     pub identifier: Node<ObjectIdentifier>,
@@ -125,17 +125,46 @@ impl InstructionBuilder for Declaration {
                 .clone();
             let unstack_len = state.unstack_current_depth();
 
-            if let Some(declared_datatype) = datatype {
+            if let (Some(DataType::Array(declared_elem, len)), DataType::List(computed_elem)) =
+                (&datatype, &computed_datatype)
+            {
+                // an array is initialized with a list literal of its length
+                let elem_compatible =
+                    **computed_elem == DataType::Void || declared_elem == computed_elem;
+                if !elem_compatible || value.value.list_literal_len() != Some(*len) {
+                    return Err(AlthreadError::new(
+                        ErrorType::TypeError,
+                        Some(value.pos.clone()),
+                        format!(
+                            "An array of type {} must be initialized with a list literal of {} elements of type {} (found {})",
+                            datatype.as_ref().unwrap(),
+                            len,
+                            declared_elem,
+                            computed_datatype
+                        ),
+                    ));
+                }
+                builder.instructions.push(Instruction {
+                    control: InstructionType::ConvertListToArray {
+                        element_type: (**declared_elem).clone(),
+                    },
+                    pos: Some(self.keyword.pos.clone()),
+                });
+            } else if let Some(declared_datatype) = datatype {
                 // Special case: allow assignment of empty list (list(void)) to typed list
-                let types_compatible =
-                    if let (DataType::List(declared_elem), DataType::List(computed_elem)) =
-                        (&declared_datatype, &computed_datatype)
-                    {
-                        // Allow list(void) to be assigned to list(T) for any T (empty list case)
+                let types_compatible = match (&declared_datatype, &computed_datatype) {
+                    // Allow list(void) to be assigned to list(T) for any T (empty list case)
+                    (DataType::List(declared_elem), DataType::List(computed_elem)) => {
                         **computed_elem == DataType::Void || declared_elem == computed_elem
-                    } else {
-                        declared_datatype == computed_datatype
-                    };
+                    }
+                    // and the empty map `{}` to any map
+                    (DataType::Map(..), DataType::Map(computed_key, _))
+                        if **computed_key == DataType::Void =>
+                    {
+                        true
+                    }
+                    _ => declared_datatype == computed_datatype,
+                };
 
                 if !types_compatible {
                     return Err(AlthreadError::new(
@@ -165,10 +194,44 @@ impl InstructionBuilder for Declaration {
                     } else {
                         datatype = Some(computed_datatype);
                     }
+                } else if let (
+                    DataType::Map(declared_key, declared_value),
+                    Some(Instruction {
+                        control:
+                            InstructionType::CreateMapFromStack {
+                                entry_count: 0,
+                                key_type,
+                                value_type,
+                            },
+                        ..
+                    }),
+                ) = (&declared_datatype, builder.instructions.last_mut())
+                {
+                    // the empty map literal is created with the declared types
+                    *key_type = (**declared_key).clone();
+                    *value_type = (**declared_value).clone();
+                    datatype = Some(declared_datatype);
                 } else {
                     datatype = Some(computed_datatype);
                 }
             } else {
+                // Without annotation, the type is inferred from the initializer,
+                // which must then carry a usable type
+                let not_inferable = match &computed_datatype {
+                    DataType::Void => true,
+                    DataType::List(elem) => **elem == DataType::Void,
+                    DataType::Map(key, _) => **key == DataType::Void,
+                    _ => false,
+                };
+                if not_inferable {
+                    return Err(AlthreadError::new(
+                        ErrorType::TypeError,
+                        Some(self.identifier.pos.clone()),
+                        format!(
+                            "Cannot infer the type of {full_var_name} from a value of type {computed_datatype}, add a type annotation"
+                        ),
+                    ));
+                }
                 datatype = Some(computed_datatype);
             }
 
@@ -202,6 +265,7 @@ impl InstructionBuilder for Declaration {
             datatype: datatype.clone(),
             depth: state.current_stack_depth,
             declare_pos: Some(self.identifier.pos.clone()),
+            constant: None,
         });
         
         // Add debug info to the builder (will be adjusted when builders are extended)
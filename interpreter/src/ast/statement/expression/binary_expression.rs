@@ -102,14 +102,17 @@ impl LocalBinaryExpressionNode {
                 }
             }
             BinaryOperator::Modulo => {
-                if left_type.is_a_number() && right_type == DataType::Integer {
-                    Ok(left_type)
+                if left_type == DataType::Integer && right_type == DataType::Integer {
+                    Ok(DataType::Integer)
                 } else {
-                    Err("modulo can only be performed between a number and an integer".to_string())
+                    Err(format!(
+                        "modulo can only be performed between integers (found {left_type} % {right_type})"
+                    ))
                 }
             }
             BinaryOperator::Equals | BinaryOperator::NotEquals => {
-                if left_type == right_type {
+                // the handles of processes running different programs are simply never equal
+                if left_type == right_type || (left_type.is_process().0 && right_type.is_process().0) {
                     Ok(DataType::Boolean)
                 } else {
                     Err(format!("equality check can only be performed between values that have exaclty the same type (found {} {} {})", left_type, self.operator, right_type))
@@ -125,7 +128,7 @@ impl LocalBinaryExpressionNode {
                     Err("arithmetic comparison can only be performed between two number types that are exactly the same".to_string())
                 }
             }
-            BinaryOperator::And | BinaryOperator::Or => {
+            BinaryOperator::And | BinaryOperator::Or | BinaryOperator::Implies => {
                 if left_type.is_boolean() && right_type.is_boolean() {
                     Ok(DataType::Boolean)
                 } else {
@@ -142,7 +145,7 @@ impl LocalBinaryExpressionNode {
                     Err("bitwise shift operations can only be performed between integers".to_string())
                 }
             }
-            BinaryOperator::BitAnd | BinaryOperator::BitOr => {
+            BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::BitXor => {
                 if left_type == DataType::Integer && right_type == DataType::Integer {
                     Ok(DataType::Integer)
                 } else {
@@ -154,6 +157,14 @@ impl LocalBinaryExpressionNode {
 
     pub fn eval(&self, mem: &Memory) -> Result<Literal, String> {
         let left = self.left.eval(mem)?;
+        // `&&`, `||` and `=>` short-circuit: the right operand may only be valid when the left
+        // one does not decide the result (e.g. `i < l.len() && l.at(i) > 0`)
+        match self.operator {
+            BinaryOperator::And if !left.is_true() => return Ok(Literal::Bool(false)),
+            BinaryOperator::Or if left.is_true() => return Ok(Literal::Bool(true)),
+            BinaryOperator::Implies if !left.is_true() => return Ok(Literal::Bool(true)),
+            _ => {}
+        }
         let right = self.right.eval(mem)?;
 
         match self.operator {
@@ -170,10 +181,12 @@ impl LocalBinaryExpressionNode {
             BinaryOperator::GreaterThanOrEqual => left.greater_than_or_equal(&right),
             BinaryOperator::And => left.and(&right),
             BinaryOperator::Or => left.or(&right),
+            BinaryOperator::Implies => left.implies(&right),
             BinaryOperator::ShiftLeft => left.shift_left(&right),
             BinaryOperator::ShiftRight => left.shift_right(&right),
             BinaryOperator::BitAnd => left.bit_and(&right),
             BinaryOperator::BitOr => left.bit_or(&right),
+            BinaryOperator::BitXor => left.bit_xor(&right),
         }
     }
 }
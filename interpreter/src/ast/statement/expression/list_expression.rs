@@ -17,6 +17,10 @@ use crate::{
 
 use super::{Expression, LocalExpressionNode};
 
+/// The maximum number of elements of a range `a..b`, which is materialized as a list: a larger
+/// range is most likely a typo and would exhaust the memory
+pub const MAX_RANGE_LEN: i64 = 1_000_000;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum ListExpression {
     Variable(Box<Node<Expression>>),
@@ -143,11 +147,20 @@ impl LocalRangeListExpressionNode {
     pub fn eval(&self, mem: &Memory) -> Result<Literal, String> {
         let start = self.expression_start.eval(mem)?;
         let end = self.expression_end.eval(mem)?;
+        Self::to_list(&start, &end)
+    }
+
+    /// The list of the integers from `start` (included) to `end` (excluded)
+    pub(crate) fn to_list(start: &Literal, end: &Literal) -> Result<Literal, String> {
+        let (start, end) = (start.to_integer()?, end.to_integer()?);
+        if i128::from(end) - i128::from(start) > i128::from(MAX_RANGE_LEN) {
+            return Err(format!(
+                "the range {start}..{end} has more than {MAX_RANGE_LEN} elements"
+            ));
+        }
         Ok(Literal::List(
             DataType::Integer,
-            (start.to_integer()?..end.to_integer()?)
-                .map(|v| Literal::Int(v))
-                .collect(),
+            (start..end).map(Literal::Int).collect(),
         ))
     }
 }
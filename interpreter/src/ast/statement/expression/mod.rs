@@ -20,14 +20,23 @@ use crate::{
     ast::{
         display::{AstDisplay, Prefix},
         node::{InstructionBuilder, Node, NodeBuilder},
-        token::{datatype::DataType, identifier::Identifier, literal::Literal},
+        token::{
+            binary_operator::BinaryOperator, datatype::DataType, identifier::Identifier,
+            literal::Literal, unary_operator::UnaryOperator,
+        },
+    },
+    compiler::{
+        stdlib::{
+            apply_list_function, check_array_index, invoke_function, invoke_interface_method,
+            list_function_return_type,
+        },
+        CompilerState, InstructionBuilderOk, Variable,
     },
-    compiler::{stdlib::invoke_interface_method, CompilerState, InstructionBuilderOk, Variable},
     error::{AlthreadError, AlthreadResult, ErrorType, Pos},
     no_rule,
     parser::Rule,
     vm::{
-        instruction::{Instruction, InstructionType},
+        instruction::{jump_offset, Instruction, InstructionType},
         Memory,
     },
 };
@@ -39,6 +48,7 @@ lazy_static::lazy_static! {
         use pest::pratt_parser::{Assoc::*, Op};
 
         PrattParser::new()
+            .op(Op::infix(Rule::implies_operator, Right))
             .op(Op::infix(Rule::or_operator, Left))
             .op(Op::infix(Rule::and_operator, Left))
             .op(Op::infix(Rule::bitwise_operator, Left))
@@ -57,6 +67,7 @@ pub enum SideEffectExpression {
     RunCall(Node<RunCall>),
     FnCall(Node<FnCall>),
     Bracket(Node<BracketExpression>),
+    Map(Node<MapExpression>),
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -64,6 +75,12 @@ pub struct BracketExpression {
     pub content: BracketContent,
 }
 
+/// A map literal, e.g. `{"a": 1, "b": 2}`
+#[derive(Debug, PartialEq, Clone)]
+pub struct MapExpression {
+    pub entries: Vec<(Node<Expression>, Node<Expression>)>,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum BracketContent {
     Range(Node<RangeListExpression>),
@@ -79,11 +96,26 @@ impl NodeBuilder for SideEffectExpression {
             Rule::run_call => Ok(Self::RunCall(Node::build(pair, filepath)?)),
             Rule::fn_call => Ok(Self::FnCall(Node::build(pair, filepath)?)),
             Rule::bracket_expression => Ok(Self::Bracket(Node::build(pair, filepath)?)),
+            Rule::map_expression => Ok(Self::Map(Node::build(pair, filepath)?)),
             _ => Err(no_rule!(pair, "SideEffectExpression", filepath)),
         }
     }
 }
 
+impl SideEffectExpression {
+    /// The number of elements of a list literal (e.g. `[1, 2, 3]`), known at compile time
+    #[must_use]
+    pub fn list_literal_len(&self) -> Option<usize> {
+        match self {
+            Self::Bracket(node) => match &node.value.content {
+                BracketContent::ListLiteral(values) => Some(values.len()),
+                BracketContent::Range(_) => None,
+            },
+            _ => None,
+        }
+    }
+}
+
 impl NodeBuilder for BracketExpression {
     fn build(mut pairs: Pairs<Rule>, filepath: &str) -> AlthreadResult<Self> {
         let pair = pairs.next().unwrap();
@@ -107,6 +139,75 @@ impl NodeBuilder for BracketExpression {
     }
 }
 
+impl NodeBuilder for MapExpression {
+    fn build(pairs: Pairs<Rule>, filepath: &str) -> AlthreadResult<Self> {
+        let mut entries = Vec::new();
+        for pair in pairs {
+            let mut entry = pair.into_inner();
+            let key = Node::build(entry.next().unwrap(), filepath)?;
+            let value = Node::build(entry.next().unwrap(), filepath)?;
+            entries.push((key, value));
+        }
+        Ok(MapExpression { entries })
+    }
+}
+
+impl InstructionBuilder for MapExpression {
+    fn compile(&self, state: &mut CompilerState) -> AlthreadResult<InstructionBuilderOk> {
+        let mut instructions = Vec::new();
+
+        // the types of the map are those of its first entry, the empty map `{}` has the type
+        // map(void, void) until it is given the declared type
+        let mut key_type = DataType::Void;
+        let mut value_type = DataType::Void;
+        for (i, (key, value)) in self.entries.iter().enumerate() {
+            for (expression, expected_type, what) in
+                [(key, &mut key_type, "key"), (value, &mut value_type, "value")]
+            {
+                instructions.extend(expression.compile(state)?.instructions);
+                let datatype = state
+                    .program_stack
+                    .last()
+                    .expect("stack should contain a value after an expression is compiled")
+                    .datatype
+                    .clone();
+                if i == 0 {
+                    *expected_type = datatype;
+                } else if datatype != *expected_type {
+                    return Err(AlthreadError::new(
+                        ErrorType::TypeError,
+                        Some(expression.pos.clone()),
+                        format!("Map {what} {i} has type {datatype}, expected {expected_type}"),
+                    ));
+                }
+            }
+        }
+
+        instructions.push(Instruction {
+            pos: None,
+            control: InstructionType::CreateMapFromStack {
+                entry_count: self.entries.len(),
+                key_type: key_type.clone(),
+                value_type: value_type.clone(),
+            },
+        });
+
+        for _ in 0..2 * self.entries.len() {
+            state.program_stack.pop();
+        }
+        state.program_stack.push(Variable {
+            name: String::new(),
+            depth: state.current_stack_depth,
+            mutable: false,
+            datatype: DataType::Map(Box::new(key_type), Box::new(value_type)),
+            declare_pos: None,
+            constant: None,
+        });
+
+        Ok(InstructionBuilderOk::from_instructions(instructions))
+    }
+}
+
 impl InstructionBuilder for SideEffectExpression {
     fn compile(&self, state: &mut CompilerState) -> AlthreadResult<InstructionBuilderOk> {
         match self {
@@ -114,6 +215,7 @@ impl InstructionBuilder for SideEffectExpression {
             Self::RunCall(node) => node.compile(state),
             Self::FnCall(node) => node.compile(state),
             Self::Bracket(node) => node.compile(state),
+            Self::Map(node) => node.compile(state),
         }
     }
 }
@@ -180,6 +282,13 @@ impl InstructionBuilder for BracketExpression {
                             };
                             nested_type
                         }
+                        SideEffectExpression::Map(node) => {
+                            node.compile(state)?;
+                            state
+                                .program_stack
+                                .pop()
+                                .map_or(DataType::Void, |var| var.datatype)
+                        }
                     }
                 } else {
                     DataType::Void // Empty list
@@ -230,7 +339,7 @@ impl InstructionBuilder for BracketExpression {
                                     )
                                 })?
                             }
-                            SideEffectExpression::Bracket(_) => {
+                            SideEffectExpression::Bracket(_) | SideEffectExpression::Map(_) => {
                                 // Get type from the variable that was just pushed to stack
                                 if let Some(last_var) = state.program_stack.last() {
                                     last_var.datatype.clone()
@@ -281,6 +390,7 @@ impl InstructionBuilder for BracketExpression {
                     mutable: false,
                     datatype: list_type,
                     declare_pos: None,
+                    constant: None,
                 });
 
                 Ok(InstructionBuilderOk::from_instructions(instructions))
@@ -296,6 +406,7 @@ impl AstDisplay for SideEffectExpression {
             Self::RunCall(node) => node.ast_fmt(f, prefix),
             Self::FnCall(node) => node.ast_fmt(f, prefix),
             Self::Bracket(node) => node.ast_fmt(f, prefix),
+            Self::Map(node) => node.ast_fmt(f, prefix),
         }
     }
 }
@@ -319,6 +430,18 @@ impl AstDisplay for BracketExpression {
     }
 }
 
+impl AstDisplay for MapExpression {
+    fn ast_fmt(&self, f: &mut fmt::Formatter, prefix: &Prefix) -> fmt::Result {
+        writeln!(f, "{prefix}MapLiteral")?;
+        let new_prefix = prefix.add_branch();
+        for (key, value) in &self.entries {
+            key.ast_fmt(f, &new_prefix)?;
+            value.ast_fmt(f, &new_prefix)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
     Binary(Node<BinaryExpression>),
@@ -767,6 +890,7 @@ impl LocalExpressionNode {
                         datatype: DataType::Void,
                         depth: 0,
                         declare_pos: Some(var.pos.clone()),
+                        constant: None,
                     });
                     let body_local =
                         LocalExpressionNode::from_expression(&body.value, &temp_stack)?;
@@ -788,6 +912,7 @@ impl LocalExpressionNode {
                         datatype: DataType::Void,
                         depth: 0,
                         declare_pos: Some(var.pos.clone()),
+                        constant: None,
                     });
                     let body_local =
                         LocalExpressionNode::from_expression(&body.value, &temp_stack)?;
@@ -840,6 +965,56 @@ impl LocalExpressionNode {
         Ok(root)
     }
 
+    /// Collects the function calls appearing in the expression (arguments of the calls excluded)
+    pub fn collect_fn_calls<'n>(&'n self, calls: &mut Vec<&'n Node<FnCall>>) {
+        match self {
+            LocalExpressionNode::FnCall(n) => calls.push(n.as_ref()),
+            LocalExpressionNode::Binary(n) => {
+                n.left.collect_fn_calls(calls);
+                n.right.collect_fn_calls(calls);
+            }
+            LocalExpressionNode::Unary(n) => n.operand.collect_fn_calls(calls),
+            LocalExpressionNode::Primary(n) => {
+                if let LocalPrimaryExpressionNode::Expression(e) = n {
+                    e.collect_fn_calls(calls);
+                }
+            }
+            LocalExpressionNode::Tuple(n) => {
+                for e in &n.values {
+                    e.collect_fn_calls(calls);
+                }
+            }
+            LocalExpressionNode::Range(n) => {
+                n.expression_start.collect_fn_calls(calls);
+                n.expression_end.collect_fn_calls(calls);
+            }
+            LocalExpressionNode::Reaches(_) => {}
+            LocalExpressionNode::CallChain(n) => {
+                n.base.collect_fn_calls(calls);
+                for seg in &n.segments {
+                    if let LocalCallChainSegment::Call { args, .. } = seg {
+                        args.collect_fn_calls(calls);
+                    }
+                }
+            }
+            LocalExpressionNode::IfExpr(n) => {
+                n.condition.collect_fn_calls(calls);
+                n.then_expr.collect_fn_calls(calls);
+                if let Some(e) = n.else_expr.as_ref() {
+                    e.collect_fn_calls(calls);
+                }
+            }
+            LocalExpressionNode::ForAll(n) => {
+                n.list.collect_fn_calls(calls);
+                n.body.collect_fn_calls(calls);
+            }
+            LocalExpressionNode::Exists(n) => {
+                n.list.collect_fn_calls(calls);
+                n.body.collect_fn_calls(calls);
+            }
+        }
+    }
+
     pub fn contains_fn_call(&self) -> bool {
         match self {
             LocalExpressionNode::FnCall(_) => true,
@@ -891,6 +1066,7 @@ impl LocalExpressionNode {
                 datatype: DataType::Void,
                 depth: 0,
                 declare_pos: None,
+                constant: None,
             })
             .collect()
     }
@@ -958,6 +1134,39 @@ impl LocalExpressionNode {
 
                     if let Some(func_def) = state.user_functions().get(fn_name) {
                         Ok(func_def.return_type.clone())
+                    } else if fn_name == "connected" {
+                        if !state.in_condition_block {
+                            return Err(
+                                "'connected' is only available inside always/check blocks"
+                                    .to_string(),
+                            );
+                        }
+                        let args = LocalExpressionNode::from_expression(
+                            &node.value.values.value,
+                            &state.program_stack,
+                        )
+                        .map_err(|e| e.message)?;
+                        match args.datatype(state)? {
+                            DataType::Tuple(args)
+                                if matches!(
+                                    args.as_slice(),
+                                    [DataType::Process(_), DataType::Process(_)]
+                                ) =>
+                            {
+                                Ok(DataType::Boolean)
+                            }
+                            _ => Err(
+                                "'connected' expects two processes: connected(proc(A), proc(B))"
+                                    .to_string(),
+                            ),
+                        }
+                    } else if fn_name == "mutex" {
+                        Err(
+                            "'mutex(...)' is not an expression, it must be a whole condition of an always block"
+                                .to_string(),
+                        )
+                    } else if let Some(function) = state.stdlib().get_function(fn_name) {
+                        Ok(function.ret)
                     } else {
                         Err(format!("Function {} not found", fn_name))
                     }
@@ -974,6 +1183,17 @@ impl LocalExpressionNode {
                         .find(|v| v.name == receiver_name);
                     let global_var = state.global_table().get(&receiver_name);
                     if let Some(var) = var.or(global_var) {
+                        if let (DataType::List(elem_type), Some(function_name)) = (
+                            &var.datatype,
+                            node.value.function_argument(state.user_functions()),
+                        ) {
+                            let method_name = node.value.method_name().unwrap_or_default();
+                            return list_function_return_type(
+                                &method_name,
+                                elem_type,
+                                &state.user_functions()[&function_name],
+                            );
+                        }
                         let interfaces = state.stdlib().interfaces(&var.datatype);
                         if !interfaces.is_empty() {
                             let method_name = node.value.method_name().ok_or_else(|| {
@@ -1003,7 +1223,7 @@ impl LocalExpressionNode {
                 let var = state
                     .program_stack
                     .get(mem_len - 1 - node.var.index)
-                    .ok_or("process variable index does not exist".to_string())?;
+                    .ok_or_else(|| "process variable index does not exist".to_string())?;
 
                 let (program_name, require_index) = match &var.datatype {
                     DataType::Process(name) => (name.clone(), false),
@@ -1039,9 +1259,8 @@ impl LocalExpressionNode {
                 }
 
                 let program_code = state
-                    .programs_code()
-                    .get(&program_name)
-                    .ok_or(format!("Program '{}' not found", program_name))?;
+                    .program_code(&program_name)
+                    .ok_or_else(|| format!("Program '{program_name}' not found"))?;
 
                 if !program_code.labels.contains_key(&node.label) {
                     return Err(format!(
@@ -1103,9 +1322,8 @@ impl LocalExpressionNode {
                             };
 
                             let program_code = state
-                                .programs_code()
-                                .get(&program_name)
-                                .ok_or(format!("Program '{}' not found", program_name))?;
+                                .program_code(&program_name)
+                                .ok_or_else(|| format!("Program '{program_name}' not found"))?;
 
                             if !program_code.labels.contains_key(label) {
                                 return Err(format!(
@@ -1165,6 +1383,7 @@ impl LocalExpressionNode {
                     mutable: false,
                     datatype: elem_type,
                     declare_pos: None,
+                    constant: None,
                 });
 
                 let temp_state = CompilerState {
@@ -1178,12 +1397,17 @@ impl LocalExpressionNode {
                     in_condition_block: state.in_condition_block,
                     context: state.context.clone(),
                     always_conditions: state.always_conditions.clone(),
+                    never_conditions: state.never_conditions.clone(),
                     ltl_formulas: state.ltl_formulas.clone(),
+                    ignored_conditions: state.ignored_conditions.clone(),
                     user_functions: state.user_functions.clone(),
                     global_table: state.global_table.clone(),
                     program_arguments: state.program_arguments.clone(),
+                    program_constants: state.program_constants.clone(),
+                    specializations: state.specializations.clone(),
                     programs_code: state.programs_code.clone(),
                     global_memory: state.global_memory.clone(),
+                    initial_choices: state.initial_choices.clone(),
                     debug_variables: state.debug_variables.clone(),
                     program_debug_info: state.program_debug_info.clone(),
                 };
@@ -1213,6 +1437,7 @@ impl LocalExpressionNode {
                     mutable: false,
                     datatype: elem_type,
                     declare_pos: None,
+                    constant: None,
                 });
 
                 let temp_state = CompilerState {
@@ -1226,12 +1451,17 @@ impl LocalExpressionNode {
                     in_condition_block: state.in_condition_block,
                     context: state.context.clone(),
                     always_conditions: state.always_conditions.clone(),
+                    never_conditions: state.never_conditions.clone(),
                     ltl_formulas: state.ltl_formulas.clone(),
+                    ignored_conditions: state.ignored_conditions.clone(),
                     user_functions: state.user_functions.clone(),
                     global_table: state.global_table.clone(),
                     program_arguments: state.program_arguments.clone(),
+                    program_constants: state.program_constants.clone(),
+                    specializations: state.specializations.clone(),
                     programs_code: state.programs_code.clone(),
                     global_memory: state.global_memory.clone(),
+                    initial_choices: state.initial_choices.clone(),
                     debug_variables: state.debug_variables.clone(),
                     program_debug_info: state.program_debug_info.clone(),
                 };
@@ -1271,7 +1501,7 @@ impl LocalExpressionNode {
                 LocalPrimaryExpressionNode::Var(local_var) => {
                     let lit = mem
                         .get(mem.len() - 1 - local_var.index)
-                        .ok_or("local variable index does not exist in memory".to_string())?;
+                        .ok_or_else(|| "local variable index does not exist in memory".to_string())?;
                     Ok(lit.clone())
                 }
                 LocalPrimaryExpressionNode::Expression(expr) => expr.as_ref().eval(mem),
@@ -1328,6 +1558,14 @@ impl LocalExpressionNode {
                     let right = binary_exp.right.eval_with_scope(mem, scope, vm)?;
                     left.or(&right)
                 }
+                crate::ast::token::binary_operator::BinaryOperator::Implies => {
+                    let left = binary_exp.left.eval_with_scope(mem, scope, vm)?;
+                    if !left.is_true() {
+                        return Ok(Literal::Bool(true));
+                    }
+                    let right = binary_exp.right.eval_with_scope(mem, scope, vm)?;
+                    left.implies(&right)
+                }
                 _ => {
                     let left = binary_exp.left.eval_with_scope(mem, scope, vm)?;
                     let right = binary_exp.right.eval_with_scope(mem, scope, vm)?;
@@ -1375,6 +1613,9 @@ impl LocalExpressionNode {
                         crate::ast::token::binary_operator::BinaryOperator::BitOr => {
                             left.bit_or(&right)
                         }
+                        crate::ast::token::binary_operator::BinaryOperator::BitXor => {
+                            left.bit_xor(&right)
+                        }
                         _ => unreachable!("short-circuit handled above"),
                     }
                 }
@@ -1396,7 +1637,7 @@ impl LocalExpressionNode {
                 LocalPrimaryExpressionNode::Var(local_var) => {
                     let lit = mem
                         .get(mem.len() - 1 - local_var.index)
-                        .ok_or("local variable index does not exist in memory".to_string())?;
+                        .ok_or_else(|| "local variable index does not exist in memory".to_string())?;
                     Ok(lit.clone())
                 }
                 LocalPrimaryExpressionNode::Expression(expr) => {
@@ -1413,14 +1654,51 @@ impl LocalExpressionNode {
             LocalExpressionNode::Range(list_exp) => {
                 let start = list_exp.expression_start.eval_with_scope(mem, scope, vm)?;
                 let end = list_exp.expression_end.eval_with_scope(mem, scope, vm)?;
-                Ok(Literal::List(
-                    DataType::Integer,
-                    (start.to_integer()?..end.to_integer()?)
-                        .map(|v| Literal::Int(v))
-                        .collect(),
-                ))
+                LocalRangeListExpressionNode::to_list(&start, &end)
             }
             LocalExpressionNode::FnCall(node) => {
+                let full_name = node.value.fn_name_to_string();
+                if vm.user_funcs.contains_key(&full_name) {
+                    let args_expr = LocalExpressionNode::localize_expression_for_scope(
+                        node.value.values.as_ref(),
+                        scope,
+                    )?;
+                    let args_value = args_expr.eval_with_scope(mem, scope, vm)?;
+                    return vm.call_pure_function(&full_name, args_value);
+                }
+
+                if full_name == "connected" {
+                    let args_expr = LocalExpressionNode::localize_expression_for_scope(
+                        node.value.values.as_ref(),
+                        scope,
+                    )?;
+                    // a process variable that is not yet assigned (null) is connected to nothing
+                    return match args_expr.eval_with_scope(mem, scope, vm)? {
+                        Literal::Tuple(args) => match args.as_slice() {
+                            [Literal::Process(_, from), Literal::Process(_, to)] => {
+                                Ok(Literal::Bool(vm.channels.is_connected(*from, *to)))
+                            }
+                            _ => Ok(Literal::Bool(false)),
+                        },
+                        _ => Err("'connected' expects two processes".to_string()),
+                    };
+                }
+
+                if vm.stdlib.is_function(&full_name) {
+                    let args_expr = LocalExpressionNode::localize_expression_for_scope(
+                        node.value.values.as_ref(),
+                        scope,
+                    )?;
+                    let mut args_value = args_expr.eval_with_scope(mem, scope, vm)?;
+                    return invoke_function(
+                        vm.stdlib.as_ref(),
+                        &full_name,
+                        &mut args_value,
+                        Some(node.pos.clone()),
+                    )
+                    .map_err(|e| e.message);
+                }
+
                 if node.value.fn_name.value.parts.len() == 1 {
                     return Err(format!(
                         "Cannot evaluate function call in this context: {:?}",
@@ -1436,6 +1714,14 @@ impl LocalExpressionNode {
                 })?;
                 let mut receiver =
                     LocalExpressionNode::resolve_literal_in_scope(&receiver_name, mem, scope, vm)?;
+                if let Some(function_name) = node.value.function_argument(vm.user_funcs) {
+                    return apply_list_function(
+                        &method_name,
+                        &vm.user_funcs[&function_name],
+                        &receiver,
+                        |arg| vm.call_pure_function(&function_name, arg),
+                    );
+                }
                 let args_expr = LocalExpressionNode::localize_expression_for_scope(
                     node.value.values.as_ref(),
                     scope,
@@ -1453,7 +1739,7 @@ impl LocalExpressionNode {
             LocalExpressionNode::Reaches(node) => {
                 let lit = mem
                     .get(mem.len() - 1 - node.var.index)
-                    .ok_or("process variable index does not exist in memory".to_string())?;
+                    .ok_or_else(|| "process variable index does not exist in memory".to_string())?;
                 let (program_name, pid) = match (lit, node.index.as_ref()) {
                     (Literal::Process(name, pid), None) => (name.clone(), *pid),
                     (Literal::List(DataType::Process(_name), values), Some(index_expr)) => {
@@ -1489,12 +1775,7 @@ impl LocalExpressionNode {
                     return Err("process name mismatch in current state".to_string());
                 }
 
-                let program_code = vm
-                    .programs_code
-                    .get(&program_name)
-                    .ok_or("program code not found".to_string())?;
-
-                let label_pc = program_code.labels.get(&node.label).ok_or(format!(
+                let label_pc = prog_state.code().labels.get(&node.label).ok_or_else(|| format!(
                     "Label '{}' not found in program '{}'",
                     node.label, program_name
                 ))?;
@@ -1560,26 +1841,16 @@ impl LocalExpressionNode {
                                 continue;
                             }
 
-                            let program_code = match vm.programs_code.get(&program_name) {
-                                Some(code) => code,
-                                None => {
-                                    log::debug!("  Program code for {} not found", program_name);
-                                    current = Literal::Bool(false);
-                                    continue;
-                                }
-                            };
-
-                            let label_pc = match program_code.labels.get(label) {
-                                Some(pc) => {
-                                    log::debug!("  Label '{}' found at pc={}", label, pc);
-                                    pc
-                                }
-                                None => {
-                                    log::debug!("  Label '{}' not found in program {}", label, program_name);
-                                    current = Literal::Bool(false);
-                                    continue;
-                                }
+                            let Some(label_pc) = prog_state.code().labels.get(label) else {
+                                log::debug!(
+                                    "  Label '{}' not found in program {}",
+                                    label,
+                                    program_name
+                                );
+                                current = Literal::Bool(false);
+                                continue;
                             };
+                            log::debug!("  Label '{}' found at pc={}", label, label_pc);
 
                             let (_, pc, _) = prog_state.current_state();
                             let reached = pc == *label_pc;
@@ -1676,6 +1947,14 @@ impl CallChainExpression {
                         .program_stack
                         .get(state.program_stack.len() - 2)
                         .unwrap();
+                    check_array_index(
+                        &receiver_var.datatype,
+                        &name.value.value,
+                        args.value.first_constant_argument(),
+                    )
+                    .map_err(|msg| {
+                        AlthreadError::new(ErrorType::ExpressionError, Some(pos.clone()), msg)
+                    })?;
                     let interfaces = state.stdlib().interfaces(&receiver_var.datatype);
                     let method = interfaces.iter().find(|m| m.name == name.value.value);
                     let method = method.ok_or(AlthreadError::new(
@@ -1739,6 +2018,7 @@ impl CallChainExpression {
                         mutable: false,
                         datatype: method.ret.clone(),
                         declare_pos: Some(pos.clone()),
+                        constant: None,
                     });
                 }
                 CallChainSegment::Reaches { .. } => {
@@ -1803,6 +2083,7 @@ impl InstructionBuilder for Node<Expression> {
                     mutable: false,
                     datatype: global_var.datatype.clone(),
                     declare_pos: global_var.declare_pos,
+                    constant: None,
                 });
             }
             instructions.push(Instruction {
@@ -1965,9 +2246,13 @@ impl InstructionBuilder for Node<Expression> {
                 }
             }
 
+            // `stacked` is the number of results of calls already pushed by the enclosing
+            // expression, above the variables of the program
             fn compile_recursive(
                 expr: &LocalExpressionNode,
                 state: &mut CompilerState,
+                stacked: usize,
+                pos: &Pos,
             ) -> AlthreadResult<(LocalExpressionNode, InstructionBuilderOk, usize)> {
                 match expr {
                     LocalExpressionNode::FnCall(node) => {
@@ -1981,7 +2266,7 @@ impl InstructionBuilder for Node<Expression> {
                     LocalExpressionNode::Binary(node) => {
                         // Compile left side first, to match execution order.
                         let (left_expr, mut left_builder, left_calls) =
-                            compile_recursive(&node.left, state)?;
+                            compile_recursive(&node.left, state, stacked, pos)?;
 
                         // Temporarily update the compiler's stack to account for the
                         // return values from the left side's function calls.
@@ -1995,20 +2280,69 @@ impl InstructionBuilder for Node<Expression> {
                                 // but it's only needed to adjust stack indices.
                                 datatype: DataType::Void,
                                 declare_pos: None,
+                                constant: None,
                             });
                         }
 
                         // Compile the right side with the adjusted stack.
                         let (right_expr, right_builder, right_calls) =
-                            compile_recursive(&node.right, state)?;
+                            compile_recursive(&node.right, state, stacked + left_calls, pos)?;
 
                         // Restore the compiler's stack.
                         for _ in 0..temp_vars_added {
                             state.program_stack.pop();
                         }
 
-                        // Combine the instructions.
-                        left_builder.extend(right_builder);
+                        // Combine the instructions. The calls of the right operand of `&&` and
+                        // `||` are only made when the left operand does not decide the result:
+                        // otherwise their results are replaced by placeholders, which the
+                        // short-circuit evaluation of the expression never reads.
+                        let short_circuit = match node.operator {
+                            BinaryOperator::And | BinaryOperator::Implies => Some(false),
+                            BinaryOperator::Or => Some(true),
+                            _ => None,
+                        };
+                        match short_circuit {
+                            Some(decides_on) if right_calls > 0 => {
+                                let left_value =
+                                    shift_non_temp_var_indices(&left_expr, stacked, left_calls);
+                                // jumps over the right calls when the left value is `decides_on`
+                                let condition = if decides_on {
+                                    LocalExpressionNode::Unary(LocalUnaryExpressionNode {
+                                        operand: Box::new(left_value),
+                                        operator: UnaryOperator::Not,
+                                    })
+                                } else {
+                                    left_value
+                                };
+                                let right_len = jump_offset(right_builder.instructions.len());
+                                left_builder.instructions.extend([
+                                    Instruction {
+                                        pos: Some(pos.clone()),
+                                        control: InstructionType::Expression(condition),
+                                    },
+                                    Instruction {
+                                        pos: Some(pos.clone()),
+                                        control: InstructionType::JumpIf {
+                                            jump_false: right_len + 2,
+                                            unstack_len: 1,
+                                        },
+                                    },
+                                ]);
+                                left_builder.extend(right_builder);
+                                left_builder.instructions.push(Instruction {
+                                    pos: Some(pos.clone()),
+                                    control: InstructionType::Jump(jump_offset(right_calls + 1)),
+                                });
+                                for _ in 0..right_calls {
+                                    left_builder.instructions.push(Instruction {
+                                        pos: Some(pos.clone()),
+                                        control: InstructionType::Push(Literal::Null),
+                                    });
+                                }
+                            }
+                            _ => left_builder.extend(right_builder),
+                        }
 
                         // The placeholder for the left result must be shifted by the number
                         // of results from the right side.
@@ -2034,7 +2368,7 @@ impl InstructionBuilder for Node<Expression> {
                     }
                     LocalExpressionNode::Unary(node) => {
                         let (operand_expr, builder, calls) =
-                            compile_recursive(&node.operand, state)?;
+                            compile_recursive(&node.operand, state, stacked, pos)?;
                         let new_expr = LocalExpressionNode::Unary(LocalUnaryExpressionNode {
                             operand: Box::new(operand_expr),
                             operator: node.operator.clone(),
@@ -2042,31 +2376,51 @@ impl InstructionBuilder for Node<Expression> {
                         Ok((new_expr, builder, calls))
                     }
                     LocalExpressionNode::Tuple(node) => {
-                        let mut compiled_elements = Vec::new();
+                        // The calls of the elements are made from left to right, as for a binary
+                        // expression: each element sees the results of the previous ones on the
+                        // stack, and its own results are below those of the next ones.
                         let mut builder = InstructionBuilderOk::new();
                         let mut total_calls = 0;
                         let mut elements_with_calls = Vec::new();
 
-                        for element in node.values.iter().rev() {
-                            let (new_elem, new_builder, num_calls) =
-                                compile_recursive(element, state)?;
-                            elements_with_calls.push((new_elem, num_calls));
+                        for element in &node.values {
+                            for _ in 0..total_calls {
+                                state.program_stack.push(Variable {
+                                    name: "<temp_fn_return>".to_string(),
+                                    depth: state.current_stack_depth,
+                                    mutable: false,
+                                    datatype: DataType::Void,
+                                    declare_pos: None,
+                                    constant: None,
+                                });
+                            }
+                            let compiled =
+                                compile_recursive(element, state, stacked + total_calls, pos);
+                            for _ in 0..total_calls {
+                                state.program_stack.pop();
+                            }
+                            let (new_elem, new_builder, num_calls) = compiled?;
+                            elements_with_calls.push((new_elem, total_calls, num_calls));
                             builder.extend(new_builder);
                             total_calls += num_calls;
                         }
-                        elements_with_calls.reverse();
-
-                        let mut calls_processed = 0;
-                        for (elem, calls) in elements_with_calls {
-                            if calls > 0 {
-                                let shifted_elem =
-                                    shift_var_indices(&elem, total_calls - calls_processed - calls);
-                                compiled_elements.push(shifted_elem);
-                                calls_processed += calls;
-                            } else {
-                                compiled_elements.push(elem);
-                            }
-                        }
+
+                        let compiled_elements = elements_with_calls
+                            .into_iter()
+                            .map(|(elem, before, calls)| {
+                                let elem = if before > 0 {
+                                    shift_non_temp_var_indices(&elem, before, calls)
+                                } else {
+                                    elem
+                                };
+                                let after = total_calls - before - calls;
+                                if after > 0 {
+                                    shift_var_indices(&elem, after)
+                                } else {
+                                    elem
+                                }
+                            })
+                            .collect();
 
                         let new_tuple = LocalExpressionNode::Tuple(LocalTupleExpressionNode {
                             values: compiled_elements,
@@ -2074,17 +2428,23 @@ impl InstructionBuilder for Node<Expression> {
                         Ok((new_tuple, builder, total_calls))
                     }
                     LocalExpressionNode::Primary(LocalPrimaryExpressionNode::Expression(expr)) => {
-                        let (new_expr, builder, calls) = compile_recursive(expr, state)?;
+                        let (new_expr, builder, calls) =
+                            compile_recursive(expr, state, stacked, pos)?;
                         let new_primary = LocalExpressionNode::Primary(
                             LocalPrimaryExpressionNode::Expression(Box::new(new_expr)),
                         );
                         Ok((new_primary, builder, calls))
                     }
                     LocalExpressionNode::Range(node) => {
-                        let (end_expr, end_builder, end_calls) =
-                            compile_recursive(&node.expression_end, state)?;
+                        // the calls of the start are made first
                         let (start_expr, mut start_builder, start_calls) =
-                            compile_recursive(&node.expression_start, state)?;
+                            compile_recursive(&node.expression_start, state, stacked, pos)?;
+                        let (end_expr, end_builder, end_calls) = compile_recursive(
+                            &node.expression_end,
+                            state,
+                            stacked + start_calls,
+                            pos,
+                        )?;
 
                         start_builder.extend(end_builder);
 
@@ -2105,7 +2465,8 @@ impl InstructionBuilder for Node<Expression> {
                 }
             }
 
-            let (final_expr, builder, fn_call_count) = compile_recursive(&local_expr, state)?;
+            let (final_expr, builder, fn_call_count) =
+                compile_recursive(&local_expr, state, 0, &self.pos)?;
             instructions.extend(builder.instructions);
 
             if fn_call_count > 0 {
@@ -2146,6 +2507,7 @@ impl InstructionBuilder for Node<Expression> {
             mutable: false,
             datatype: result_type,
             declare_pos: None,
+            constant: None,
         });
 
         Ok(InstructionBuilderOk::from_instructions(instructions))
@@ -2177,6 +2539,37 @@ impl Expression {
             _ => false,
         }
     }
+
+    /// The value of the expression if it is an integer constant (e.g. `3` or `-1`)
+    #[must_use]
+    pub fn constant_int(&self) -> Option<i64> {
+        match self {
+            Self::Primary(node) => match &node.value {
+                PrimaryExpression::Literal(literal) => match literal.value {
+                    Literal::Int(value) => Some(value),
+                    _ => None,
+                },
+                PrimaryExpression::Expression(expr) => expr.value.constant_int(),
+                _ => None,
+            },
+            Self::Unary(node) => match node.value.operator.value {
+                UnaryOperator::Negative => node.value.operand.value.constant_int()?.checked_neg(),
+                UnaryOperator::Positive => node.value.operand.value.constant_int(),
+                UnaryOperator::Not => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The first argument of a call, given the tuple of its arguments, if it is an integer
+    /// constant
+    #[must_use]
+    pub fn first_constant_argument(&self) -> Option<i64> {
+        match self {
+            Self::Tuple(node) => node.value.values.first()?.value.constant_int(),
+            _ => self.constant_int(),
+        }
+    }
 }
 
 impl Expression {
@@ -2713,6 +3106,94 @@ mod tests {
         assert_eq!(local_expr.eval(&Memory::new()).unwrap(), Literal::Int(7)); // 111 in binary
     }
 
+    #[test]
+    fn test_bitwise_xor_expression() {
+        let literal_node = Node {
+            pos: Pos {
+                line: 0,
+                col: 0,
+                start: 0,
+                end: 0,
+                file_path: "test".to_string(),
+            },
+            value: Literal::Int(6), // 110 in binary
+        };
+        let xor_node = Node {
+            pos: Pos {
+                line: 0,
+                col: 0,
+                start: 0,
+                end: 0,
+                file_path: "test".to_string(),
+            },
+            value: Literal::Int(3), // 011 in binary
+        };
+        let left_expr = Expression::Primary(Node {
+            pos: Pos {
+                line: 0,
+                col: 0,
+                start: 0,
+                end: 0,
+                file_path: "test".to_string(),
+            },
+            value: PrimaryExpression::Literal(literal_node),
+        });
+        let right_expr = Expression::Primary(Node {
+            pos: Pos {
+                line: 0,
+                col: 0,
+                start: 0,
+                end: 0,
+                file_path: "test".to_string(),
+            },
+            value: PrimaryExpression::Literal(xor_node),
+        });
+
+        let expression = Expression::Binary(Node {
+            pos: Pos {
+                line: 0,
+                col: 0,
+                start: 0,
+                end: 0,
+                file_path: "test".to_string(),
+            },
+            value: BinaryExpression {
+                left: Box::new(Node {
+                    pos: Pos {
+                        line: 0,
+                        col: 0,
+                        start: 0,
+                        end: 0,
+                        file_path: "test".to_string(),
+                    },
+                    value: left_expr,
+                }),
+                right: Box::new(Node {
+                    pos: Pos {
+                        line: 0,
+                        col: 0,
+                        start: 0,
+                        end: 0,
+                        file_path: "test".to_string(),
+                    },
+                    value: right_expr,
+                }),
+                operator: Node {
+                    pos: Pos {
+                        line: 0,
+                        col: 0,
+                        start: 0,
+                        end: 0,
+                        file_path: "test".to_string(),
+                    },
+                    value: BinaryOperator::BitXor,
+                },
+            },
+        });
+        let local_expr = LocalExpressionNode::from_expression(&expression, &vec![]).unwrap();
+        assert_eq!(local_expr.eval(&Memory::new()).unwrap(), Literal::Int(5)); // 101 in binary
+    }
+
     #[test]
     fn test_bitwise_operation_type_error() {
         let literal_node = Node {
@@ -307,7 +307,12 @@ impl LocalPrimaryExpressionNode {
                         Some(node.pos.clone()),
                         format!("Variable '{}' not found", full_name),
                     ))?;
-                LocalPrimaryExpressionNode::Var(LocalVarNode { index })
+                match &program_stack[program_stack.len() - 1 - index].constant {
+                    Some(value) => LocalPrimaryExpressionNode::Literal(LocalLiteralNode {
+                        value: value.clone(),
+                    }),
+                    None => LocalPrimaryExpressionNode::Var(LocalVarNode { index }),
+                }
             }
             PrimaryExpression::Expression(node) => {
                 let e = LocalExpressionNode::from_expression(&node.as_ref().value, program_stack)?;
@@ -138,6 +138,7 @@ impl TupleExpression {
                 datatype: types[i].clone(),
                 depth: state.current_stack_depth,
                 declare_pos: Some(pos.clone()),
+                constant: None,
             })
         }
         Ok(Instruction {
@@ -1,4 +1,7 @@
-use std::{collections::HashSet, fmt};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
 
 use pest::iterators::Pairs;
 
@@ -6,15 +9,21 @@ use crate::{
     ast::{
         display::{AstDisplay, Prefix},
         node::{InstructionBuilder, Node, NodeBuilder},
-        token::{datatype::DataType, object_identifier::ObjectIdentifier},
+        token::{datatype::DataType, literal::Literal, object_identifier::ObjectIdentifier},
+    },
+    compiler::{
+        stdlib::{check_array_index, list_function_return_type, LIST_FUNCTION_METHODS},
+        CompilerState, FunctionDefinition, InstructionBuilderOk, Variable,
     },
-    compiler::{CompilerState, InstructionBuilderOk, Variable},
     error::{AlthreadError, AlthreadResult, ErrorType},
     parser::Rule,
     vm::instruction::{Instruction, InstructionType},
 };
 
-use super::{expression::Expression, waiting_case::WaitDependency};
+use super::{
+    expression::{primary_expression::PrimaryExpression, Expression},
+    waiting_case::WaitDependency,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct FnCall {
@@ -55,6 +64,40 @@ impl FnCall {
             .map(|part| part.value.value.clone())
     }
 
+    /// For list function methods (`l.map(f)`, `l.filter(f)`), the name of the user function
+    /// given as the only argument
+    #[must_use]
+    pub fn function_argument(
+        &self,
+        user_functions: &HashMap<String, FunctionDefinition>,
+    ) -> Option<String> {
+        if self.fn_name.value.parts.len() < 2
+            || !LIST_FUNCTION_METHODS.contains(&self.method_name()?.as_str())
+        {
+            return None;
+        }
+        let Expression::Tuple(tuple) = &self.values.value else {
+            return None;
+        };
+        let [arg] = tuple.value.values.as_slice() else {
+            return None;
+        };
+        let Expression::Primary(primary) = &arg.value else {
+            return None;
+        };
+        let PrimaryExpression::Identifier(ident) = &primary.value else {
+            return None;
+        };
+        let name = ident
+            .value
+            .parts
+            .iter()
+            .map(|p| p.value.value.as_str())
+            .collect::<Vec<_>>()
+            .join(".");
+        user_functions.contains_key(&name).then_some(name)
+    }
+
     pub fn add_dependencies(&self, dependencies: &mut WaitDependency) {
         if let Some(receiver_name) = self.receiver_name() {
             dependencies.variables.insert(receiver_name);
@@ -85,10 +128,29 @@ impl InstructionBuilder for Node<FnCall> {
         let full_name = self.value.fn_name_to_string();
         state.current_stack_depth += 1;
 
-        builder.extend(self.value.values.compile(state).map_err(|mut e| {
-            e.push_stack(self.pos.clone());
-            e
-        })?);
+        let function_argument = self.value.function_argument(state.user_functions());
+        if let Some(function_name) = &function_argument {
+            // the function is passed by name, it is resolved when the method is called
+            builder.instructions.push(Instruction {
+                control: InstructionType::Push(Literal::Tuple(vec![Literal::String(
+                    function_name.clone(),
+                )])),
+                pos: Some(self.pos.clone()),
+            });
+            state.program_stack.push(Variable {
+                mutable: false,
+                name: String::new(),
+                datatype: DataType::Tuple(vec![DataType::String]),
+                depth: state.current_stack_depth,
+                declare_pos: Some(self.pos.clone()),
+                constant: None,
+            });
+        } else {
+            builder.extend(self.value.values.compile(state).map_err(|mut e| {
+                e.push_stack(self.pos.clone());
+                e
+            })?);
+        }
 
         let args_on_stack_var = state
             .program_stack
@@ -164,6 +226,7 @@ impl InstructionBuilder for Node<FnCall> {
                     datatype: func_def.return_type.clone(),
                     depth: state.current_stack_depth,
                     declare_pos: Some(self.pos.clone()),
+                    constant: None,
                 });
 
                 builder.instructions.push(Instruction {
@@ -198,12 +261,12 @@ impl InstructionBuilder for Node<FnCall> {
                     "assert" => {
                         let provided_arg_types = args_on_stack_var.datatype.tuple_unwrap();
 
-                        if provided_arg_types.len() != 2 {
+                        if provided_arg_types.is_empty() || provided_arg_types.len() > 2 {
                             state.unstack_current_depth();
                             return Err(AlthreadError::new(
                                 ErrorType::FunctionArgumentCountError,
                                 Some(self.pos.clone()),
-                                "Function 'assert' expects exactly 2 arguments.".to_string(),
+                                "Function 'assert' expects a condition and an optional message.".to_string(),
                             ));
                         }
 
@@ -216,7 +279,7 @@ impl InstructionBuilder for Node<FnCall> {
                             ));
                         }
 
-                        if provided_arg_types[1] != DataType::String {
+                        if provided_arg_types.len() == 2 && provided_arg_types[1] != DataType::String {
                             state.unstack_current_depth();
                             return Err(AlthreadError::new(
                                 ErrorType::FunctionArgumentTypeMismatch,
@@ -226,13 +289,108 @@ impl InstructionBuilder for Node<FnCall> {
                         }
                         DataType::Void
                     }
-                    _ => {
+                    "assume" => {
+                        let provided_arg_types = args_on_stack_var.datatype.tuple_unwrap();
+
+                        if provided_arg_types.len() != 1 {
+                            state.unstack_current_depth();
+                            return Err(AlthreadError::new(
+                                ErrorType::FunctionArgumentCountError,
+                                Some(self.pos.clone()),
+                                "Function 'assume' expects exactly 1 argument.".to_string(),
+                            ));
+                        }
+
+                        if provided_arg_types[0] != DataType::Boolean {
+                            state.unstack_current_depth();
+                            return Err(AlthreadError::new(
+                                ErrorType::FunctionArgumentTypeMismatch,
+                                Some(self.pos.clone()),
+                                format!("Function 'assume' expects an argument of type bool, but got {}.", provided_arg_types[0]),
+                            ));
+                        }
+                        DataType::Void
+                    }
+                    "rand" => {
+                        let provided_arg_types = args_on_stack_var.datatype.tuple_unwrap();
+
+                        if provided_arg_types.len() != 2 {
+                            state.unstack_current_depth();
+                            return Err(AlthreadError::new(
+                                ErrorType::FunctionArgumentCountError,
+                                Some(self.pos.clone()),
+                                "Function 'rand' expects exactly 2 arguments.".to_string(),
+                            ));
+                        }
+
+                        for (idx, arg_type) in provided_arg_types.iter().enumerate() {
+                            if *arg_type != DataType::Integer {
+                                state.unstack_current_depth();
+                                return Err(AlthreadError::new(
+                                    ErrorType::FunctionArgumentTypeMismatch,
+                                    Some(self.pos.clone()),
+                                    format!("Function 'rand' expects argument {} to be of type int, but got {}.", idx + 1, arg_type),
+                                ));
+                            }
+                        }
+                        DataType::Integer
+                    }
+                    "connected" => {
+                        state.unstack_current_depth();
                         return Err(AlthreadError::new(
-                            ErrorType::UndefinedFunction,
+                            ErrorType::InstructionNotAllowed,
                             Some(self.pos.clone()),
-                            format!("undefined function {}", basename),
+                            "'connected' is only available inside always/check blocks"
+                                .to_string(),
                         ));
                     }
+                    _ => {
+                        let Some(function) = state.stdlib().get_function(basename) else {
+                            return Err(AlthreadError::new(
+                                ErrorType::UndefinedFunction,
+                                Some(self.pos.clone()),
+                                format!("undefined function {basename}"),
+                            ));
+                        };
+                        let provided_arg_types = args_on_stack_var.datatype.tuple_unwrap();
+
+                        if provided_arg_types.len() != function.args.len() {
+                            state.unstack_current_depth();
+                            return Err(AlthreadError::new(
+                                ErrorType::FunctionArgumentCountError,
+                                Some(self.pos.clone()),
+                                format!(
+                                    "Function '{}' expects {} arguments, but {} were provided.",
+                                    basename,
+                                    function.args.len(),
+                                    provided_arg_types.len()
+                                ),
+                            ));
+                        }
+
+                        for (idx, (expected_type, provided_type)) in function
+                            .args
+                            .iter()
+                            .zip(provided_arg_types.iter())
+                            .enumerate()
+                        {
+                            if expected_type != provided_type {
+                                state.unstack_current_depth();
+                                return Err(AlthreadError::new(
+                                    ErrorType::FunctionArgumentTypeMismatch,
+                                    Some(self.pos.clone()),
+                                    format!(
+                                        "Function '{}' expects argument {} to be of type {}, but got {}.",
+                                        basename,
+                                        idx + 1,
+                                        expected_type,
+                                        provided_type
+                                    ),
+                                ));
+                            }
+                        }
+                        function.ret
+                    }
                 };
 
                 let unstack_len = state.unstack_current_depth();
@@ -243,6 +401,7 @@ impl InstructionBuilder for Node<FnCall> {
                     datatype: return_type,
                     depth: state.current_stack_depth,
                     declare_pos: Some(self.pos.clone()),
+                    constant: None,
                 });
 
                 builder.instructions.push(Instruction {
@@ -303,21 +462,56 @@ impl InstructionBuilder for Node<FnCall> {
                     ));
                 };
 
-            let interfaces = state.stdlib().interfaces(&receiver_type);
+            check_array_index(
+                &receiver_type,
+                &method_name,
+                self.value.values.value.first_constant_argument(),
+            )
+            .map_err(|msg| {
+                AlthreadError::new(ErrorType::ExpressionError, Some(self.pos.clone()), msg)
+            })?;
+
+            let is_list = matches!(receiver_type, DataType::List(_));
+            let (ret_type, mutates_receiver) =
+                if let (DataType::List(elem_type), Some(function_name)) =
+                    (&receiver_type, &function_argument)
+                {
+                    let func_def = &state.user_functions()[function_name];
+                    let ret_type = list_function_return_type(&method_name, elem_type, func_def)
+                        .map_err(|msg| {
+                            AlthreadError::new(
+                                ErrorType::FunctionArgumentTypeMismatch,
+                                Some(self.pos.clone()),
+                                msg,
+                            )
+                        })?;
+                    (ret_type, false)
+                } else if is_list && LIST_FUNCTION_METHODS.contains(&method_name.as_str()) {
+                    return Err(AlthreadError::new(
+                        ErrorType::FunctionArgumentTypeMismatch,
+                        Some(self.pos.clone()),
+                        format!(
+                            "Method '{method_name}' expects the name of a user function: l.{method_name}(f)"
+                        ),
+                    ));
+                } else {
+                    let interfaces = state.stdlib().interfaces(&receiver_type);
 
-            let fn_idx = interfaces.iter().position(|i| i.name == method_name);
-            if fn_idx.is_none() {
-                return Err(AlthreadError::new(
-                    ErrorType::UndefinedFunction,
-                    Some(self.pos.clone()),
-                    format!("No method {} found on variable of type {}", method_name, receiver_type),
-                ));
-            }
-            let fn_idx = fn_idx.unwrap();
-            let fn_info = &interfaces[fn_idx];
-            let ret_type = fn_info.ret.clone();
+                let fn_idx = interfaces.iter().position(|i| i.name == method_name);
+                if fn_idx.is_none() {
+                    return Err(AlthreadError::new(
+                        ErrorType::UndefinedFunction,
+                        Some(self.pos.clone()),
+                        format!(
+                            "No method {method_name} found on variable of type {receiver_type}"
+                        ),
+                    ));
+                }
+                let fn_info = &interfaces[fn_idx.unwrap()];
+                (fn_info.ret.clone(), fn_info.mutates_receiver)
+            };
 
-            if fn_info.mutates_receiver && !receiver_is_mutable {
+            if mutates_receiver && !receiver_is_mutable {
                 return Err(AlthreadError::new(
                     ErrorType::VariableError,
                     Some(self.pos.clone()),
@@ -336,6 +530,7 @@ impl InstructionBuilder for Node<FnCall> {
                 datatype: ret_type,
                 depth: state.current_stack_depth,
                 declare_pos: Some(self.pos.clone()),
+                constant: None,
             });
 
             builder.instructions.push(Instruction {
@@ -57,7 +57,10 @@ impl InstructionBuilder for FnReturn {
         }
 
         let ret_instr = Instruction {
-            control: InstructionType::Return { has_value },
+            control: InstructionType::Return {
+                has_value,
+                stop_atomic: false,
+            },
             pos: Some(self.pos.clone()),
         };
 
@@ -66,7 +66,7 @@ impl InstructionBuilder for Node<ForControl> {
             .datatype;
 
         let list_type = match dtype {
-            DataType::List(list_type) => list_type.as_ref().clone(),
+            DataType::List(list_type) | DataType::Array(list_type, _) => list_type.as_ref().clone(),
             _ => {
                 return Err(AlthreadError::new(
                     ErrorType::TypeError,
@@ -76,9 +76,7 @@ impl InstructionBuilder for Node<ForControl> {
             }
         };
         // make sure the interface is built for this list:
-        state
-            .stdlib()
-            .interfaces(&DataType::List(Box::new(list_type.clone())));
+        state.stdlib().interfaces(&dtype.clone());
 
         // push the iterator variable
         state.program_stack.push(Variable {
@@ -87,6 +85,7 @@ impl InstructionBuilder for Node<ForControl> {
             declare_pos: Some(self.value.identifier.pos.clone()),
             depth: state.current_stack_depth,
             mutable: true,
+            constant: None,
         });
         builder.instructions.push(Instruction {
             pos: Some(self.value.identifier.pos.clone()),
@@ -99,6 +98,7 @@ impl InstructionBuilder for Node<ForControl> {
             declare_pos: Some(self.value.identifier.pos.clone()),
             depth: state.current_stack_depth,
             mutable: true,
+            constant: None,
         });
         builder.instructions.push(Instruction {
             pos: Some(self.value.identifier.pos.clone()),
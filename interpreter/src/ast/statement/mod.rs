@@ -10,12 +10,16 @@ pub mod for_control;
 pub mod if_control;
 pub mod label;
 pub mod loop_control;
+pub mod on_exit;
 pub mod receive;
+pub mod repeat_control;
 pub mod run_call;
+pub mod select;
 pub mod send;
 pub mod wait;
 pub mod waiting_case;
 pub mod while_control;
+pub mod yield_point;
 
 use std::fmt;
 
@@ -29,11 +33,15 @@ use for_control::ForControl;
 use if_control::IfControl;
 use label::LabelStatement;
 use loop_control::LoopControl;
+use on_exit::OnExit;
 use pest::iterators::Pairs;
+use repeat_control::RepeatControl;
 use run_call::RunCall;
+use select::SelectStatement;
 use send::SendStatement;
 use wait::Wait;
 use while_control::WhileControl;
+use yield_point::YieldStatement;
 
 use crate::{
     compiler::{CompilerState, InstructionBuilderOk},
@@ -60,12 +68,16 @@ pub enum Statement {
     FnReturn(Node<FnReturn>),
     If(Node<IfControl>),
     While(Node<WhileControl>),
+    Repeat(Node<RepeatControl>),
     Loop(Node<LoopControl>),
     For(Node<ForControl>),
     BreakLoop(Node<BreakLoopControl>),
     Label(Node<LabelStatement>),
+    Yield(Node<YieldStatement>),
     Atomic(Node<atomic::Atomic>),
     Wait(Node<Wait>),
+    Select(Node<SelectStatement>),
+    OnExit(Node<OnExit>),
     Block(Node<Block>),
 }
 
@@ -76,7 +88,11 @@ impl NodeBuilder for Statement {
         match pair.as_rule() {
             Rule::assignment => Ok(Self::Assignment(Node::build(pair, filepath)?)),
             Rule::declaration => Ok(Self::Declaration(Node::build(pair, filepath)?)),
-            Rule::wait_statement => Ok(Self::Wait(Node::build(pair, filepath)?)),
+            Rule::wait_statement | Rule::join_statement => {
+                Ok(Self::Wait(Node::build(pair, filepath)?))
+            }
+            Rule::select_statement => Ok(Self::Select(Node::build(pair, filepath)?)),
+            Rule::on_exit_statement => Ok(Self::OnExit(Node::build(pair, filepath)?)),
             Rule::fn_call => Ok(Self::FnCall(Node::build(pair, filepath)?)),
             Rule::return_statement => {
                 // build the node in here
@@ -101,11 +117,13 @@ impl NodeBuilder for Statement {
             Rule::run_call => Ok(Self::Run(Node::build(pair, filepath)?)),
             Rule::if_control => Ok(Self::If(Node::build(pair, filepath)?)),
             Rule::while_control => Ok(Self::While(Node::build(pair, filepath)?)),
+            Rule::repeat_control => Ok(Self::Repeat(Node::build(pair, filepath)?)),
             Rule::atomic_statement => Ok(Self::Atomic(Node::build(pair, filepath)?)),
             Rule::loop_control => Ok(Self::Loop(Node::build(pair, filepath)?)),
             Rule::for_control => Ok(Self::For(Node::build(pair, filepath)?)),
             Rule::break_loop_statement => Ok(Self::BreakLoop(Node::build(pair, filepath)?)),
             Rule::label_statement => Ok(Self::Label(Node::build(pair, filepath)?)),
+            Rule::yield_statement => Ok(Self::Yield(Node::build(pair, filepath)?)),
             Rule::code_block => Ok(Self::Block(Node::build(pair, filepath)?)),
             Rule::send_call => Ok(Self::Send(Node::build(pair, filepath)?)),
             Rule::channel_declaration => Ok(Self::ChannelDeclaration(Node::build(pair, filepath)?)),
@@ -122,14 +140,18 @@ impl InstructionBuilder for Statement {
             Self::Declaration(node) => node.compile(state),
             Self::ChannelDeclaration(node) => node.compile(state),
             Self::While(node) => node.compile(state),
+            Self::Repeat(node) => node.compile(state),
             Self::Loop(node) => node.compile(state),
             Self::For(node) => node.compile(state),
             Self::Atomic(node) => node.compile(state),
             Self::Wait(node) => node.compile(state),
+            Self::Select(node) => node.compile(state),
+            Self::OnExit(node) => node.compile(state),
             Self::Block(node) => node.compile(state),
             Self::Send(node) => node.compile(state),
             Self::BreakLoop(node) => node.compile(state),
             Self::Label(node) => node.compile(state),
+            Self::Yield(node) => node.compile(state),
             Self::Run(node) => {
                 // a run call returns a value, so we have to ustack it
                 let mut builder = node.compile(state)?;
@@ -168,15 +190,19 @@ impl AstDisplay for Statement {
             Statement::Send(node) => node.ast_fmt(f, prefix),
             Statement::ChannelDeclaration(node) => node.ast_fmt(f, prefix),
             Statement::Wait(node) => node.ast_fmt(f, prefix),
+            Statement::Select(node) => node.ast_fmt(f, prefix),
+            Statement::OnExit(node) => node.ast_fmt(f, prefix),
             Statement::FnCall(node) => node.ast_fmt(f, prefix),
             Statement::FnReturn(node) => node.ast_fmt(f, prefix),
             Statement::Run(node) => node.ast_fmt(f, prefix),
             Statement::If(node) => node.ast_fmt(f, prefix),
             Statement::While(node) => node.ast_fmt(f, prefix),
+            Statement::Repeat(node) => node.ast_fmt(f, prefix),
             Statement::Loop(node) => node.ast_fmt(f, prefix),
             Statement::For(node) => node.ast_fmt(f, prefix),
             Statement::BreakLoop(node) => node.ast_fmt(f, prefix),
             Statement::Label(node) => node.ast_fmt(f, prefix),
+            Statement::Yield(node) => node.ast_fmt(f, prefix),
             Statement::Atomic(node) => node.ast_fmt(f, prefix),
             Statement::Block(node) => node.ast_fmt(f, prefix),
         }
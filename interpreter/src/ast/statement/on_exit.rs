@@ -0,0 +1,87 @@
+use std::fmt;
+
+use pest::iterators::Pairs;
+
+use crate::{
+    ast::{
+        block::Block,
+        display::{AstDisplay, Prefix},
+        node::{InstructionBuilder, Node, NodeBuilder},
+    },
+    compiler::{CompilerState, InstructionBuilderOk},
+    error::{AlthreadError, AlthreadResult, ErrorType},
+    parser::Rule,
+};
+
+use super::{atomic::Atomic, Statement};
+
+/// The finalizer of a program, executed atomically when the process reaches the end of its body
+#[derive(Debug, Clone)]
+pub struct OnExit {
+    pub block: Node<Block>,
+}
+
+impl NodeBuilder for OnExit {
+    fn build(mut pairs: Pairs<Rule>, filepath: &str) -> AlthreadResult<Self> {
+        let block = Node::build(pairs.next().unwrap(), filepath)?;
+        Ok(Self { block })
+    }
+}
+
+/// The body of a program with its `on_exit` block moved to its end as an atomic block, where it
+/// still sees the variables of the body. A program has at most one `on_exit` block, at the top
+/// level of its body.
+pub(crate) fn move_finalizer_to_end(body: &Node<Block>) -> AlthreadResult<Node<Block>> {
+    let mut body = body.clone();
+    let (finalizers, children): (Vec<_>, Vec<_>) = body
+        .value
+        .children
+        .into_iter()
+        .partition(|child| matches!(child.value, Statement::OnExit(_)));
+    body.value.children = children;
+
+    let mut finalizers = finalizers.into_iter();
+    if let Some(finalizer) = finalizers.next() {
+        if let Some(duplicate) = finalizers.next() {
+            return Err(AlthreadError::new(
+                ErrorType::InstructionNotAllowed,
+                Some(duplicate.pos),
+                "A program can only have one on_exit block".to_string(),
+            ));
+        }
+        let Statement::OnExit(on_exit) = finalizer.value else {
+            unreachable!("the finalizers are on_exit statements");
+        };
+        let block = on_exit.value.block;
+        let atomic = Atomic::new(Node {
+            pos: block.pos.clone(),
+            value: Statement::Block(block),
+        });
+        body.value.children.push(Node {
+            pos: finalizer.pos.clone(),
+            value: Statement::Atomic(Node {
+                pos: finalizer.pos,
+                value: atomic,
+            }),
+        });
+    }
+    Ok(body)
+}
+
+impl InstructionBuilder for Node<OnExit> {
+    fn compile(&self, _state: &mut CompilerState) -> AlthreadResult<InstructionBuilderOk> {
+        // the on_exit block of a program is moved out of its body before it is compiled
+        Err(AlthreadError::new(
+            ErrorType::InstructionNotAllowed,
+            Some(self.pos.clone()),
+            "on_exit can only be used at the top level of a program".to_string(),
+        ))
+    }
+}
+
+impl AstDisplay for OnExit {
+    fn ast_fmt(&self, f: &mut fmt::Formatter, prefix: &Prefix) -> fmt::Result {
+        writeln!(f, "{prefix}on_exit")?;
+        self.block.ast_fmt(f, &prefix.switch().add_leaf())
+    }
+}
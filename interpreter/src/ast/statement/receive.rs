@@ -6,21 +6,35 @@ use crate::{
     ast::{
         display::{AstDisplay, Prefix},
         node::{InstructionBuilder, Node, NodeBuilder},
-        token::{datatype::DataType, literal::Literal, object_identifier::ObjectIdentifier},
+        statement::expression::{
+            binary_expression::LocalBinaryExpressionNode,
+            primary_expression::{LocalLiteralNode, LocalPrimaryExpressionNode, LocalVarNode},
+            LocalExpressionNode,
+        },
+        token::{
+            binary_operator::BinaryOperator, datatype::DataType, literal::Literal,
+            object_identifier::ObjectIdentifier,
+        },
     },
     compiler::{CompilerState, InstructionBuilderOk, Variable},
     error::{AlthreadError, AlthreadResult, ErrorType},
     no_rule,
     parser::Rule,
-    vm::instruction::{Instruction, InstructionType},
+    vm::instruction::{jump_offset, Instruction, InstructionType},
 };
 
-use super::waiting_case::WaitDependency;
+use super::{expression::Expression, waiting_case::WaitDependency};
 
 #[derive(Debug, Clone)]
 pub struct ReceiveStatement {
     pub channel: String,
+    /// The names bound to the components of the message, empty for the literal components
     pub variables: Vec<String>,
+    /// The literal components of the pattern with their index: the message is only received if
+    /// its components have these values
+    pub literals: Vec<(usize, Node<Literal>)>,
+    /// The condition the peeked message must satisfy to be received
+    pub guard: Option<Node<Expression>>,
 }
 
 impl NodeBuilder for ReceiveStatement {
@@ -47,22 +61,46 @@ impl NodeBuilder for ReceiveStatement {
         }
 
         let mut variables = Vec::new();
+        let mut literals = Vec::new();
         let sub_pairs: Pairs<'_, Rule> = pair.into_inner();
-        for pair in sub_pairs {
-            variables.push(String::from(pair.as_str()));
+        for (index, pair) in sub_pairs.enumerate() {
+            let component = pair.into_inner().next().unwrap();
+            match component.as_rule() {
+                Rule::literal => {
+                    literals.push((index, Node::build(component, filepath)?));
+                    variables.push(String::new());
+                }
+                _ => variables.push(String::from(component.as_str())),
+            }
         }
 
+        let guard = match pairs.next() {
+            Some(pair) => Some(Node::build(pair, filepath)?),
+            None => None,
+        };
+
         Ok(Self {
             channel,
             variables,
+            literals,
+            guard,
         })
     }
 }
 
 impl ReceiveStatement {
     pub fn add_dependencies(&self, dependencies: &mut WaitDependency) {
-        dependencies.variables.extend(self.variables.clone());
+        dependencies
+            .variables
+            .extend(self.variables.iter().filter(|v| !v.is_empty()).cloned());
         dependencies.channels_state.insert(self.channel.clone());
+        if let Some(guard) = &self.guard {
+            guard.value.add_dependencies(dependencies);
+            dependencies.guarded_channels.insert(self.channel.clone());
+        }
+        if !self.literals.is_empty() {
+            dependencies.guarded_channels.insert(self.channel.clone());
+        }
     }
 }
 
@@ -97,6 +135,23 @@ impl InstructionBuilder for Node<ReceiveStatement> {
             ));
         }
 
+        for (index, literal) in &self.value.literals {
+            let datatype = literal.value.get_datatype();
+            if datatype != channel_types[*index] {
+                return Err(AlthreadError::new(
+                    ErrorType::TypeError,
+                    Some(literal.pos.clone()),
+                    format!(
+                        "Channel {} expects a value of type {} at position {}, but the pattern is a {}",
+                        self.value.channel,
+                        channel_types[*index],
+                        index + 1,
+                        datatype
+                    ),
+                ));
+            }
+        }
+
         let mut builder = InstructionBuilderOk::new();
 
         builder.instructions.push(Instruction {
@@ -112,6 +167,7 @@ impl InstructionBuilder for Node<ReceiveStatement> {
                 datatype: channel_types[i].clone(),
                 depth: state.current_stack_depth,
                 declare_pos: Some(pos.clone()),
+                constant: None,
             })
         }
 
@@ -121,25 +177,127 @@ impl InstructionBuilder for Node<ReceiveStatement> {
             datatype: DataType::Boolean,
             depth: state.current_stack_depth,
             declare_pos: None,
+            constant: None,
         });
 
-        builder.instructions.push(Instruction {
-            control: InstructionType::JumpIf {
-                jump_false: 3, // If the channel is empty, ignore the channel pop
-                unstack_len: 0, // we keep the boolean value on the stack
-            },
-            pos: Some(self.pos.clone()),
-        });
+        // the literal components of the pattern are compared with the peeked values (the
+        // boolean of the peek is on top of them), before the message is popped
+        let tag_match = self
+            .value
+            .literals
+            .iter()
+            .map(|(index, literal)| {
+                LocalExpressionNode::Binary(LocalBinaryExpressionNode {
+                    left: Box::new(LocalExpressionNode::Primary(
+                        LocalPrimaryExpressionNode::Var(LocalVarNode {
+                            index: self.value.variables.len() - index,
+                        }),
+                    )),
+                    operator: BinaryOperator::Equals,
+                    right: Box::new(LocalExpressionNode::Primary(
+                        LocalPrimaryExpressionNode::Literal(LocalLiteralNode {
+                            value: literal.value.clone(),
+                        }),
+                    )),
+                })
+            })
+            .reduce(|left, right| {
+                LocalExpressionNode::Binary(LocalBinaryExpressionNode {
+                    left: Box::new(left),
+                    operator: BinaryOperator::And,
+                    right: Box::new(right),
+                })
+            });
 
-        builder.instructions.push(Instruction {
+        // the pattern and the guard are checked on the peeked values, before the message is
+        // popped: if one of them fails, the message stays in the channel
+        let mut guard_builder = InstructionBuilderOk::new();
+        let mut failure_jumps = Vec::new();
+        if let Some(tag_match) = tag_match {
+            guard_builder.instructions.push(Instruction {
+                control: InstructionType::Expression(tag_match),
+                pos: Some(self.pos.clone()),
+            });
+            failure_jumps.push(guard_builder.instructions.len());
+            guard_builder.instructions.push(Instruction {
+                control: InstructionType::JumpIf {
+                    jump_false: 0, // patched to the failure path
+                    unstack_len: 1,
+                },
+                pos: Some(self.pos.clone()),
+            });
+        }
+        if let Some(guard) = &self.value.guard {
+            let stack_len = state.program_stack.len();
+            guard_builder.extend(guard.compile(state)?);
+            // the guard value is on top of the shared variables it reads
+            let guard_vars = state.program_stack.split_off(stack_len);
+            let guard_var = guard_vars
+                .last()
+                .expect("stack should contain a value after an expression is compiled");
+            if guard_var.datatype != DataType::Boolean {
+                return Err(AlthreadError::new(
+                    ErrorType::TypeError,
+                    Some(guard.pos.clone()),
+                    "the guard of a receive must be a boolean".to_string(),
+                ));
+            }
+            failure_jumps.push(guard_builder.instructions.len());
+            guard_builder.instructions.push(Instruction {
+                control: InstructionType::JumpIf {
+                    jump_false: 0, // patched to the failure path
+                    unstack_len: guard_vars.len(),
+                },
+                pos: Some(guard.pos.clone()),
+            });
+        }
+        guard_builder.instructions.push(Instruction {
             control: InstructionType::ChannelPop(channel_name.clone()), // actually do pop the channel
             pos: Some(self.pos.clone()),
         });
-        // now we jump over the push of default values
+        if failure_jumps.is_empty() {
+            // now we jump over the push of default values
+            guard_builder.instructions.push(Instruction {
+                control: InstructionType::Jump(5),
+                pos: Some(self.pos.clone()),
+            });
+        } else {
+            guard_builder.instructions.push(Instruction {
+                control: InstructionType::Jump(8), // over the failure path and the default values
+                pos: Some(self.pos.clone()),
+            });
+            let failure_index = guard_builder.instructions.len();
+            for index in failure_jumps {
+                if let InstructionType::JumpIf { jump_false, .. } =
+                    &mut guard_builder.instructions[index].control
+                {
+                    *jump_false = jump_offset(failure_index - index);
+                }
+            }
+            // replace the true boolean of the peek by false, keeping the peeked values
+            guard_builder.instructions.push(Instruction {
+                control: InstructionType::Unstack { unstack_len: 1 },
+                pos: Some(self.pos.clone()),
+            });
+            guard_builder.instructions.push(Instruction {
+                control: InstructionType::Push(Literal::Bool(false)),
+                pos: Some(self.pos.clone()),
+            });
+            guard_builder.instructions.push(Instruction {
+                control: InstructionType::Jump(5),
+                pos: Some(self.pos.clone()),
+            });
+        }
+
         builder.instructions.push(Instruction {
-            control: InstructionType::Jump (5),
+            control: InstructionType::JumpIf {
+                // If the channel is empty, ignore the channel pop
+                jump_false: jump_offset(guard_builder.instructions.len() + 1),
+                unstack_len: 0, // we keep the boolean value on the stack
+            },
             pos: Some(self.pos.clone()),
         });
+        builder.extend(guard_builder);
         // remove the false boolean
         builder.instructions.push(Instruction {
             control: InstructionType::Unstack{
@@ -182,10 +340,20 @@ impl AstDisplay for ReceiveStatement {
             "{pref} patterns ({})",
             self.variables
                 .iter()
-                .map(|v| v.clone())
+                .enumerate()
+                .map(|(index, v)| {
+                    match self.literals.iter().find(|(i, _)| *i == index) {
+                        Some((_, literal)) => literal.value.to_string(),
+                        None => v.clone(),
+                    }
+                })
                 .collect::<Vec<String>>()
                 .join(",")
         )?;
+        if let Some(guard) = &self.guard {
+            writeln!(f, "{pref} guard")?;
+            guard.ast_fmt(f, &pref.add_leaf())?;
+        }
 
         Ok(())
     }
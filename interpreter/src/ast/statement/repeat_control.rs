@@ -0,0 +1,186 @@
+use std::fmt;
+
+use pest::iterators::Pairs;
+
+use crate::{
+    ast::{
+        block::Block,
+        display::{AstDisplay, Prefix},
+        node::{InstructionBuilder, Node, NodeBuilder},
+        statement::expression::{
+            binary_expression::LocalBinaryExpressionNode,
+            primary_expression::{LocalLiteralNode, LocalPrimaryExpressionNode, LocalVarNode},
+            LocalExpressionNode,
+        },
+        token::{
+            binary_assignment_operator::BinaryAssignmentOperator,
+            binary_operator::BinaryOperator, datatype::DataType, literal::Literal,
+        },
+    },
+    compiler::{CompilerState, InstructionBuilderOk},
+    error::{AlthreadError, AlthreadResult, ErrorType},
+    parser::Rule,
+    vm::instruction::{jump_offset, Instruction, InstructionType},
+};
+
+use super::expression::Expression;
+
+#[derive(Debug, Clone)]
+pub struct RepeatControl {
+    pub count: Node<Expression>,
+    pub then_block: Box<Node<Block>>,
+}
+
+impl NodeBuilder for RepeatControl {
+    fn build(mut pairs: Pairs<Rule>, filepath: &str) -> AlthreadResult<Self> {
+        let count = Node::build(pairs.next().unwrap(), filepath)?;
+        let then_block = Node::build(pairs.next().unwrap(), filepath)?;
+
+        Ok(Self {
+            count,
+            then_block: Box::new(then_block),
+        })
+    }
+}
+
+impl InstructionBuilder for Node<RepeatControl> {
+    fn compile(&self, state: &mut CompilerState) -> AlthreadResult<InstructionBuilderOk> {
+        let mut builder = InstructionBuilderOk::new();
+
+        let stack_len = state.program_stack.len();
+
+        // the count is evaluated once and stays on the stack as the number of remaining iterations
+        state.current_stack_depth += 1;
+        builder.extend(self.value.count.compile(state)?);
+        let count_datatype = &state
+            .program_stack
+            .last()
+            .expect("stack should contain a value after an expression is compiled")
+            .datatype;
+        if *count_datatype != DataType::Integer {
+            return Err(AlthreadError::new(
+                ErrorType::TypeError,
+                Some(self.value.count.pos.clone()),
+                format!("repeat count must be an integer ({count_datatype} is given)"),
+            ));
+        }
+        builder.instructions.push(Instruction {
+            pos: Some(self.value.count.pos.clone()),
+            control: InstructionType::CheckRepeatCount,
+        });
+        let loop_start = builder.instructions.len();
+
+        // remaining > 0
+        builder.instructions.push(Instruction {
+            pos: Some(self.value.count.pos.clone()),
+            control: InstructionType::Expression(LocalExpressionNode::Binary(
+                LocalBinaryExpressionNode {
+                    left: Box::new(LocalExpressionNode::Primary(
+                        LocalPrimaryExpressionNode::Var(LocalVarNode { index: 0 }),
+                    )),
+                    operator: BinaryOperator::GreaterThan,
+                    right: Box::new(LocalExpressionNode::Primary(
+                        LocalPrimaryExpressionNode::Literal(LocalLiteralNode {
+                            value: Literal::Int(0),
+                        }),
+                    )),
+                },
+            )),
+        });
+        let jump_idx = builder.instructions.len();
+        builder.instructions.push(Instruction {
+            pos: Some(self.value.count.pos.clone()),
+            control: InstructionType::JumpIf {
+                jump_false: 0,
+                unstack_len: 1,
+            },
+        });
+        // remaining -= 1
+        builder.instructions.push(Instruction {
+            pos: Some(self.value.count.pos.clone()),
+            control: InstructionType::Push(Literal::Int(1)),
+        });
+        builder.instructions.push(Instruction {
+            pos: Some(self.value.count.pos.clone()),
+            control: InstructionType::LocalAssignment {
+                index: 0,
+                operator: BinaryAssignmentOperator::SubtractAssign,
+                unstack_len: 1,
+            },
+        });
+
+        let block_builder = self.value.then_block.compile(state)?;
+        let block_len = block_builder.instructions.len();
+        builder.extend(block_builder);
+
+        let loop_len = jump_offset(block_len + 4);
+        builder.instructions.push(Instruction {
+            pos: Some(self.pos.clone()),
+            control: InstructionType::Jump(-loop_len),
+        });
+        builder.instructions[jump_idx].control = InstructionType::JumpIf {
+            jump_false: loop_len,
+            unstack_len: 1,
+        };
+
+        let unstack_len = state.unstack_current_depth();
+        builder.instructions.push(Instruction {
+            pos: Some(self.pos.clone()),
+            control: InstructionType::Unstack { unstack_len },
+        });
+
+        assert!(stack_len == state.program_stack.len());
+
+        if builder.contains_jump() {
+            for idx in builder.break_indexes.get("").unwrap_or(&Vec::new()) {
+                let builder_len = builder.instructions.len();
+                if let InstructionType::Break {
+                    jump, unstack_len, ..
+                } = &mut builder.instructions[*idx].control
+                {
+                    *jump = jump_offset(builder_len - idx);
+                    *unstack_len -= stack_len;
+                } else {
+                    panic!("Expected Break instruction");
+                }
+            }
+            builder.break_indexes.remove("");
+            for idx in builder.continue_indexes.get("").unwrap_or(&Vec::new()) {
+                if let InstructionType::Break {
+                    jump, unstack_len, ..
+                } = &mut builder.instructions[*idx].control
+                {
+                    // the remaining count stays on the stack
+                    *jump = -jump_offset(idx - loop_start);
+                    *unstack_len = *unstack_len - stack_len - 1;
+                } else {
+                    panic!("Expected Break instruction");
+                }
+            }
+            builder.continue_indexes.remove("");
+        }
+        Ok(builder)
+    }
+}
+
+impl AstDisplay for RepeatControl {
+    fn ast_fmt(&self, f: &mut fmt::Formatter, prefix: &Prefix) -> fmt::Result {
+        writeln!(f, "{prefix}repeat_control")?;
+
+        let prefix = prefix.add_branch();
+        writeln!(f, "{prefix}count")?;
+        {
+            let prefix = prefix.add_leaf();
+            self.count.ast_fmt(f, &prefix)?;
+        }
+
+        let prefix = prefix.switch();
+        writeln!(f, "{prefix}then")?;
+        {
+            let prefix = prefix.add_leaf();
+            self.then_block.ast_fmt(f, &prefix)?;
+        }
+
+        Ok(())
+    }
+}
@@ -6,20 +6,28 @@ use crate::{
     ast::{
         display::{AstDisplay, Prefix},
         node::{InstructionBuilder, Node, NodeBuilder},
-        token::{datatype::DataType, object_identifier::ObjectIdentifier},
+        token::{
+            datatype::DataType, declaration_keyword::DeclarationKeyword, literal::Literal,
+            object_identifier::ObjectIdentifier,
+        },
     },
     compiler::{CompilerState, InstructionBuilderOk, Variable},
-    error::{AlthreadError, AlthreadResult, ErrorType},
+    error::{AlthreadError, AlthreadResult, ErrorType, Pos},
     parser::Rule,
     vm::instruction::{Instruction, InstructionType},
 };
 
-use super::expression::Expression;
+use super::{
+    declaration::Declaration,
+    expression::{tuple_expression::TupleExpression, Expression, LocalExpressionNode, SideEffectExpression},
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct RunCall {
     pub identifier: Node<ObjectIdentifier>,
     pub args: Node<Expression>,
+    /// The `where` bindings, declared as constants in the scope of the arguments
+    pub bindings: Vec<Node<Declaration>>,
 }
 
 impl RunCall {
@@ -38,6 +46,40 @@ impl NodeBuilder for RunCall {
     fn build(mut pairs: Pairs<Rule>, filepath: &str) -> AlthreadResult<Self> {
         let identifier = Node::build(pairs.next().unwrap(), filepath)?;
         let args: Node<Expression> = Expression::build_top_level(pairs.next().unwrap(), filepath)?;
+        let mut bindings = Vec::new();
+        if let Some(where_clause) = pairs.next() {
+            for binding in where_clause.into_inner() {
+                let pos = Pos {
+                    start: binding.as_span().start(),
+                    end: binding.as_span().end(),
+                    line: binding.line_col().0,
+                    col: binding.line_col().1,
+                    file_path: filepath.to_string(),
+                };
+                let mut pairs = binding.into_inner();
+                let identifier = Node::build(pairs.next().unwrap(), filepath)?;
+                let value: Node<SideEffectExpression> = Node::build(pairs.next().unwrap(), filepath)?;
+                if matches!(value.value, SideEffectExpression::RunCall(_)) {
+                    return Err(AlthreadError::new(
+                        ErrorType::ExpressionError,
+                        Some(value.pos),
+                        "Run calls cannot be used in a where clause".to_string(),
+                    ));
+                }
+                bindings.push(Node {
+                    pos: pos.clone(),
+                    value: Declaration {
+                        keyword: Node {
+                            pos,
+                            value: DeclarationKeyword::Const,
+                        },
+                        identifier,
+                        datatype: None,
+                        value: Some(value),
+                    },
+                });
+            }
+        }
 
         if !args.value.is_tuple() {
             return Err(AlthreadError::new(
@@ -46,7 +88,84 @@ impl NodeBuilder for RunCall {
                 "Run statement expects a tuple of arguments (possibly empty)".to_string(),
             ));
         }
-        Ok(Self { identifier, args })
+        Ok(Self {
+            identifier,
+            args,
+            bindings,
+        })
+    }
+}
+
+impl Node<RunCall> {
+    /// Splits the arguments of the call into the tuple of the arguments passed at runtime and the
+    /// values of the `const` parameters, which must only depend on literals and on the const
+    /// parameters of the calling program
+    fn split_constant_arguments(
+        &self,
+        constant_parameters: &[bool],
+        state: &CompilerState,
+    ) -> AlthreadResult<(Node<Expression>, Vec<Literal>)> {
+        let Expression::Tuple(tuple) = &self.value.args.value else {
+            unreachable!("the arguments of a run call are always a tuple");
+        };
+        if tuple.value.values.len() != constant_parameters.len() {
+            return Err(AlthreadError::new(
+                ErrorType::ExpressionError,
+                Some(self.pos.clone()),
+                format!(
+                    "Expected {} argument(s), got {}",
+                    constant_parameters.len(),
+                    tuple.value.values.len()
+                ),
+            ));
+        }
+
+        let constants: Vec<Variable> = state
+            .program_stack
+            .iter()
+            .filter(|var| var.constant.is_some())
+            .cloned()
+            .collect();
+        let mut runtime_values = Vec::new();
+        let mut constant_args = Vec::new();
+        for (i, (value, is_constant)) in tuple
+            .value
+            .values
+            .iter()
+            .zip(constant_parameters)
+            .enumerate()
+        {
+            if !is_constant {
+                runtime_values.push(value.clone());
+                continue;
+            }
+            let constant = LocalExpressionNode::from_expression(&value.value, &constants)
+                .ok()
+                .and_then(|expression| expression.eval(&Vec::new()).ok())
+                .ok_or_else(|| {
+                    AlthreadError::new(
+                        ErrorType::ExpressionError,
+                        Some(value.pos.clone()),
+                        format!(
+                            "The argument {} of '{}' is const and must be known at compile time",
+                            i + 1,
+                            self.value.program_name_to_string()
+                        ),
+                    )
+                })?;
+            constant_args.push(constant);
+        }
+
+        let args = Node {
+            pos: self.value.args.pos.clone(),
+            value: Expression::Tuple(Node {
+                pos: tuple.pos.clone(),
+                value: TupleExpression {
+                    values: runtime_values,
+                },
+            }),
+        };
+        Ok((args, constant_args))
     }
 }
 
@@ -55,21 +174,62 @@ impl InstructionBuilder for Node<RunCall> {
         let mut builder = InstructionBuilderOk::new();
         let full_program_name = self.value.program_name_to_string();
 
-        // push the args to the stack
+        // the values of the const parameters are not passed at runtime but folded into a
+        // specialized copy of the program
+        let constant_parameters = state
+            .program_constants
+            .get(&full_program_name)
+            .cloned()
+            .unwrap_or_default();
+        let (args, constant_args) = if constant_parameters.contains(&true) {
+            self.split_constant_arguments(&constant_parameters, state)?
+        } else {
+            (self.value.args.clone(), Vec::new())
+        };
+
+        // the bindings are computed in the same atomic step as the spawn, so that no other
+        // process can run in between
+        let atomic = !self.value.bindings.is_empty() && !state.is_atomic;
+        if atomic {
+            builder.instructions.push(Instruction {
+                control: InstructionType::AtomicStart,
+                pos: Some(self.pos.clone()),
+            });
+            state.is_atomic = true;
+        }
+
+        // push the bindings and the args to the stack
         state.current_stack_depth += 1;
-        builder.extend(self.value.args.compile(state).map_err(|mut e| {
+        for binding in &self.value.bindings {
+            builder.extend(binding.compile(state)?);
+        }
+        builder.extend(args.compile(state).map_err(|mut e| {
             e.push_stack(self.pos.clone());
             e
         })?);
 
-        let call_datatype = state
+        let runtime_datatype = state
             .program_stack
             .last()
             .expect("empty stack after expression")
             .datatype
             .clone();
-        let unstack_len = state.unstack_current_depth();
-        let call_datatype = call_datatype.tuple_unwrap();
+        let unstack_len = state.unstack_current_depth_with_debug(&mut builder);
+        let mut runtime_datatype = runtime_datatype.tuple_unwrap().into_iter();
+        let mut constant_datatype = constant_args
+            .iter()
+            .map(super::super::token::literal::Literal::get_datatype);
+        let call_datatype: Vec<DataType> = if constant_args.is_empty() {
+            runtime_datatype.collect()
+        } else {
+            constant_parameters
+                .iter()
+                .filter_map(|is_constant| match is_constant {
+                    true => constant_datatype.next(),
+                    false => runtime_datatype.next(),
+                })
+                .collect()
+        };
 
         // CLONE the program arguments to avoid holding a reference
         let prog_args_opt = state.program_arguments().get(&full_program_name).cloned();
@@ -77,7 +237,7 @@ impl InstructionBuilder for Node<RunCall> {
         if let Some((prog_args, _)) = prog_args_opt {
             if prog_args.len() != call_datatype.len() {
                 return Err(AlthreadError::new(
-                    ErrorType::TypeError,
+                    ErrorType::ExpressionError,
                     Some(self.pos.clone()),
                     format!(
                         "Expected {} argument(s), got {}",
@@ -89,7 +249,7 @@ impl InstructionBuilder for Node<RunCall> {
             for (i, arg) in prog_args.iter().enumerate() {
                 if arg != &call_datatype[i] {
                     return Err(AlthreadError::new(
-                        ErrorType::TypeError,
+                        ErrorType::ExpressionError,
                         Some(self.pos.clone()),
                         format!(
                             "Expected argument {} to be of type {:?}, got {:?}",
@@ -115,15 +275,38 @@ impl InstructionBuilder for Node<RunCall> {
             mutable: false,
             datatype: DataType::Process(full_program_name.clone()),
             declare_pos: Some(self.pos.clone()),
+            constant: None,
         });
 
+        let specialization = if constant_args.is_empty() {
+            None
+        } else {
+            let code_name = format!(
+                "{}{}",
+                full_program_name,
+                Literal::Tuple(constant_args.clone())
+            );
+            state
+                .specializations
+                .insert(code_name.clone(), (full_program_name.clone(), constant_args));
+            Some(code_name)
+        };
+
         builder.instructions.push(Instruction {
             control: InstructionType::RunCall {
                 name: full_program_name,
                 unstack_len,
+                specialization,
             },
             pos: Some(self.pos.clone()),
         });
+        if atomic {
+            builder.instructions.push(Instruction {
+                control: InstructionType::AtomicEnd,
+                pos: Some(self.pos.clone()),
+            });
+            state.is_atomic = false;
+        }
 
         Ok(builder)
     }
@@ -133,6 +316,14 @@ impl AstDisplay for RunCall {
     fn ast_fmt(&self, f: &mut fmt::Formatter, prefix: &Prefix) -> fmt::Result {
         let program_name = self.program_name_to_string();
         writeln!(f, "{prefix}run: {}", program_name)?;
+        if !self.bindings.is_empty() {
+            let prefix = &prefix.add_leaf();
+            writeln!(f, "{prefix}where")?;
+            let prefix = &prefix.switch();
+            for binding in &self.bindings {
+                binding.ast_fmt(f, prefix)?;
+            }
+        }
 
         Ok(())
     }
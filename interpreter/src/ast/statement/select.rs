@@ -0,0 +1,68 @@
+use std::fmt;
+
+use pest::iterators::Pairs;
+
+use crate::{
+    ast::{
+        display::{AstDisplay, Prefix},
+        node::{InstructionBuilder, Node, NodeBuilder},
+    },
+    compiler::{CompilerState, InstructionBuilderOk},
+    error::AlthreadResult,
+    parser::Rule,
+};
+
+use super::{
+    wait::{Wait, WaitingBlockKind},
+    waiting_case::WaitingBlockCase,
+};
+
+/// Waits for a message on any of several channels and receives it with the first arm whose
+/// channel has one. The arms are tried in order, so an arm shadows the ones after it when
+/// several channels have a message.
+#[derive(Debug, Clone)]
+pub struct SelectStatement {
+    /// The arms, each one being a `receive` with its statement
+    pub cases: Vec<Node<WaitingBlockCase>>,
+    pub start_atomic: bool,
+}
+
+impl NodeBuilder for SelectStatement {
+    fn build(pairs: Pairs<Rule>, filepath: &str) -> AlthreadResult<Self> {
+        let mut cases = Vec::new();
+        for pair in pairs {
+            cases.push(Node::build(pair, filepath)?);
+        }
+        Ok(Self {
+            cases,
+            start_atomic: false,
+        })
+    }
+}
+
+impl InstructionBuilder for Node<SelectStatement> {
+    fn compile(&self, state: &mut CompilerState) -> AlthreadResult<InstructionBuilderOk> {
+        // a select is an `await first` block whose cases are all receptions: it waits on every
+        // channel, and peeks them in order to run the first arm that can receive
+        let wait = Node {
+            pos: self.pos.clone(),
+            value: Wait {
+                block_kind: WaitingBlockKind::First,
+                waiting_cases: self.value.cases.clone(),
+                start_atomic: self.value.start_atomic,
+            },
+        };
+        wait.compile(state)
+    }
+}
+
+impl AstDisplay for SelectStatement {
+    fn ast_fmt(&self, f: &mut fmt::Formatter, prefix: &Prefix) -> fmt::Result {
+        writeln!(f, "{prefix}select")?;
+        for case in &self.cases {
+            case.ast_fmt(f, &prefix.add_leaf())?;
+        }
+
+        Ok(())
+    }
+}
@@ -12,7 +12,7 @@ use crate::{
         },
     },
     compiler::{CompilerState, InstructionBuilderOk, Variable},
-    error::{AlthreadError, AlthreadResult, ErrorType},
+    error::{AlthreadError, AlthreadResult, ErrorType, Pos},
     no_rule,
     parser::Rule,
     vm::instruction::{Instruction, InstructionType},
@@ -57,6 +57,20 @@ impl NodeBuilder for Wait {
                 let node: Node<WaitingBlockCase> = Node::build(pair, filepath)?;
                 vec![node]
             }
+            Rule::join_expression => {
+                // `join(handle);` is a shorthand for `await join(handle);`
+                let node: Node<WaitingBlockCase> = Node {
+                    pos: Pos::from_span(pair.as_span(), filepath),
+                    value: WaitingBlockCase {
+                        rule: WaitingBlockCaseRule::Join(Node::build(
+                            pair.into_inner().next().unwrap(),
+                            filepath,
+                        )?),
+                        statement: None,
+                    },
+                };
+                vec![node]
+            }
             _ => {
                 return Err(no_rule!(pair, "Wait", filepath));
             }
@@ -216,6 +230,7 @@ impl InstructionBuilder for Node<Wait> {
             mutable: true,
             depth: state.current_stack_depth,
             declare_pos: None,
+            constant: None,
         });
         builder.instructions.push(Instruction {
             pos: Some(self.pos.clone()),
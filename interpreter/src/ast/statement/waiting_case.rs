@@ -5,8 +5,10 @@ use pest::iterators::Pairs;
 use serde::{Deserialize, Serialize};
 
 use crate::compiler::CompilerState;
-use crate::error::AlthreadResult;
+use crate::ast::token::datatype::DataType;
+use crate::error::{AlthreadError, AlthreadResult, ErrorType};
 use crate::parser::Rule;
+use crate::vm::instruction::{Instruction, InstructionType};
 use crate::{ast::node::InstructionBuilder, compiler::InstructionBuilderOk};
 
 use super::super::{
@@ -20,15 +22,67 @@ use super::receive::ReceiveStatement;
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WaitDependency {
     pub channels_state: HashSet<String>,
+    /// the channels received with a guard, on which a buffered message may not be received
+    pub guarded_channels: HashSet<String>,
     pub channels_connection: HashSet<String>,
+    /// the output channels a send waits on until their bounded receiver has space
+    pub channels_space: HashSet<String>,
     pub variables: HashSet<String>,
+    /// the condition can become true when a program terminates
+    pub program_termination: bool,
 }
 impl WaitDependency {
     pub fn new() -> Self {
         Self {
             channels_state: HashSet::new(),
+            guarded_channels: HashSet::new(),
             channels_connection: HashSet::new(),
+            channels_space: HashSet::new(),
             variables: HashSet::new(),
+            program_termination: false,
+        }
+    }
+
+    /// Describes the events that can end the wait, in the order the VM checks them. The names
+    /// of the received values are also recorded as variables, only those for which
+    /// `is_shared` holds are listed.
+    pub fn describe(&self, is_shared: impl Fn(&String) -> bool) -> String {
+        fn sorted(names: &HashSet<String>) -> Vec<&String> {
+            let mut names: Vec<&String> = names.iter().collect();
+            names.sort();
+            names
+        }
+
+        let mut events = Vec::new();
+        for channel in sorted(&self.channels_state) {
+            if self.guarded_channels.contains(channel) {
+                events.push(format!("a matching message on '{channel}'"));
+            } else {
+                events.push(format!("a message on '{channel}'"));
+            }
+        }
+        for channel in sorted(&self.channels_connection) {
+            events.push(format!("the connection of '{channel}'"));
+        }
+        for channel in sorted(&self.channels_space) {
+            events.push(format!("space in the channel '{channel}'"));
+        }
+        let variables: Vec<&str> = sorted(&self.variables)
+            .into_iter()
+            .filter(|name| is_shared(name))
+            .map(std::string::String::as_str)
+            .collect();
+        if !variables.is_empty() {
+            events.push(format!("a change of {}", variables.join(", ")));
+        }
+        if self.program_termination {
+            events.push("the termination of a process".to_string());
+        }
+
+        if events.is_empty() {
+            "nothing that can change (its condition is constant)".to_string()
+        } else {
+            events.join(" or ")
         }
     }
 }
@@ -37,6 +91,8 @@ impl WaitDependency {
 pub enum WaitingBlockCaseRule {
     Expression(Node<Expression>),
     Receive(Node<ReceiveStatement>),
+    /// `join(handle)`: true once the program referenced by the handle has terminated
+    Join(Node<Expression>),
 }
 
 #[derive(Debug, Clone)]
@@ -50,8 +106,13 @@ impl NodeBuilder for WaitingBlockCase {
         let pair = pairs.next().unwrap();
 
         let rule = match pair.as_rule() {
-            Rule::expression => WaitingBlockCaseRule::Expression(Node::build(pair, filepath)?),
+            Rule::waiting_condition => {
+                WaitingBlockCaseRule::Expression(Node::build(pair, filepath)?)
+            }
             Rule::receive_expression => WaitingBlockCaseRule::Receive(Node::build(pair, filepath)?),
+            Rule::join_expression => {
+                WaitingBlockCaseRule::Join(Node::build(pair.into_inner().next().unwrap(), filepath)?)
+            }
             _ => panic!("Invalid rule while parsing waiting block case"),
         };
 
@@ -89,6 +150,10 @@ impl WaitingBlockCaseRule {
         match self {
             WaitingBlockCaseRule::Expression(expr) => expr.value.add_dependencies(dependencies),
             WaitingBlockCaseRule::Receive(receive) => receive.value.add_dependencies(dependencies),
+            WaitingBlockCaseRule::Join(handle) => {
+                handle.value.add_dependencies(dependencies);
+                dependencies.program_termination = true;
+            }
         }
     }
 }
@@ -97,6 +162,29 @@ impl InstructionBuilder for WaitingBlockCaseRule {
         match self {
             WaitingBlockCaseRule::Expression(expr) => expr.compile(state),
             WaitingBlockCaseRule::Receive(receive) => receive.compile(state),
+            WaitingBlockCaseRule::Join(handle) => {
+                let mut builder = handle.compile(state)?;
+                let handle_var = state
+                    .program_stack
+                    .last_mut()
+                    .expect("the handle expression should be on the stack");
+                if !matches!(handle_var.datatype, DataType::Process(_)) {
+                    return Err(AlthreadError::new(
+                        ErrorType::TypeError,
+                        Some(handle.pos.clone()),
+                        format!(
+                            "join expects a process handle, found a value of type {}",
+                            handle_var.datatype
+                        ),
+                    ));
+                }
+                handle_var.datatype = DataType::Boolean;
+                builder.instructions.push(Instruction {
+                    pos: Some(handle.pos.clone()),
+                    control: InstructionType::HasTerminated,
+                });
+                Ok(builder)
+            }
         }
     }
 }
@@ -105,6 +193,10 @@ impl AstDisplay for WaitingBlockCaseRule {
         match self {
             WaitingBlockCaseRule::Expression(expr) => expr.ast_fmt(f, prefix),
             WaitingBlockCaseRule::Receive(receive) => receive.ast_fmt(f, prefix),
+            WaitingBlockCaseRule::Join(handle) => {
+                writeln!(f, "{prefix}join")?;
+                handle.ast_fmt(f, &prefix.add_leaf())
+            }
         }
     }
 }
@@ -0,0 +1,47 @@
+use std::fmt;
+
+use pest::iterators::Pairs;
+
+use crate::{
+    ast::{
+        display::{AstDisplay, Prefix},
+        node::{InstructionBuilder, Node, NodeBuilder},
+    },
+    compiler::{CompilerState, InstructionBuilderOk},
+    error::{AlthreadError, AlthreadResult, ErrorType},
+    parser::Rule,
+    vm::instruction::{Instruction, InstructionType},
+};
+
+/// An explicit scheduling point: the process may be interleaved with the others there, even if
+/// the surrounding instructions are local
+#[derive(Debug, Clone, PartialEq)]
+pub struct YieldStatement {}
+
+impl NodeBuilder for YieldStatement {
+    fn build(_pairs: Pairs<Rule>, _filepath: &str) -> AlthreadResult<Self> {
+        Ok(Self {})
+    }
+}
+
+impl InstructionBuilder for Node<YieldStatement> {
+    fn compile(&self, state: &mut CompilerState) -> AlthreadResult<InstructionBuilderOk> {
+        if state.is_atomic {
+            return Err(AlthreadError::new(
+                ErrorType::InstructionNotAllowed,
+                Some(self.pos.clone()),
+                "yield cannot be used inside an atomic block".to_string(),
+            ));
+        }
+        Ok(InstructionBuilderOk::from_instructions(vec![Instruction {
+            pos: Some(self.pos.clone()),
+            control: InstructionType::Yield,
+        }]))
+    }
+}
+
+impl AstDisplay for YieldStatement {
+    fn ast_fmt(&self, f: &mut fmt::Formatter, prefix: &Prefix) -> fmt::Result {
+        writeln!(f, "{prefix}yield")
+    }
+}
@@ -13,6 +13,8 @@ use super::{datatype::DataType, identifier::Identifier};
 pub struct ArgsList {
     pub identifiers: Vec<Node<Identifier>>,
     pub datatypes: Vec<Node<DataType>>,
+    /// Whether each parameter is `const`
+    pub constants: Vec<bool>,
 }
 
 impl Node<ArgsList> {
@@ -22,6 +24,7 @@ impl Node<ArgsList> {
             value: ArgsList {
                 identifiers: Vec::new(),
                 datatypes: Vec::new(),
+                constants: Vec::new(),
             },
         }
     }
@@ -31,13 +34,20 @@ impl NodeBuilder for ArgsList {
     fn build(pairs: Pairs<Rule>, filepath: &str) -> AlthreadResult<Self> {
         let mut identifiers = Vec::new();
         let mut datatypes = Vec::new();
+        let mut constants = Vec::new();
+        let mut is_constant = false;
         for pair in pairs {
             match pair.as_rule() {
+                Rule::const_modifier => {
+                    is_constant = true;
+                }
                 Rule::datatype => {
                     datatypes.push(Node::build(pair, filepath)?);
                 }
                 Rule::identifier => {
                     identifiers.push(Node::build(pair, filepath)?);
+                    constants.push(is_constant);
+                    is_constant = false;
                 }
                 _ => return Err(no_rule!(pair, "ArgsList", filepath)),
             }
@@ -45,6 +55,7 @@ impl NodeBuilder for ArgsList {
         Ok(Self {
             identifiers,
             datatypes,
+            constants,
         })
     }
 }
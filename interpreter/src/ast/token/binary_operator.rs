@@ -20,10 +20,12 @@ pub enum BinaryOperator {
     GreaterThanOrEqual,
     And,
     Or,
+    Implies,
     ShiftLeft,
     ShiftRight,
     BitAnd,
     BitOr,
+    BitXor,
 }
 
 impl NodeBuilder for BinaryOperator {
@@ -43,10 +45,12 @@ impl NodeBuilder for BinaryOperator {
             Rule::GE_OP => Ok(Self::GreaterThanOrEqual),
             Rule::AND_OP => Ok(Self::And),
             Rule::OR_OP => Ok(Self::Or),
+            Rule::IMPLIES_OP => Ok(Self::Implies),
             Rule::SHL_OP => Ok(Self::ShiftLeft),
             Rule::SHR_OP => Ok(Self::ShiftRight),
             Rule::BITWISE_AND_OP => Ok(Self::BitAnd),
             Rule::BITWISE_OR_OP => Ok(Self::BitOr),
+            Rule::BITWISE_XOR_OP => Ok(Self::BitXor),
             _ => Err(no_rule!(pair, "BinaryOperator", filepath)),
         }
     }
@@ -68,10 +72,12 @@ impl fmt::Display for BinaryOperator {
             BinaryOperator::GreaterThanOrEqual => ">=",
             BinaryOperator::And => "&&",
             BinaryOperator::Or => "||",
+            BinaryOperator::Implies => "=>",
             BinaryOperator::ShiftLeft => "<<",
             BinaryOperator::ShiftRight => ">>",
             BinaryOperator::BitAnd => "&",
             BinaryOperator::BitOr => "|",
+            BinaryOperator::BitXor => "^",
         };
 
         write!(f, "{}", op)
@@ -1,6 +1,11 @@
-use std::fmt;
+use std::{collections::BTreeMap, fmt};
 
-use crate::{ast::node::NodeBuilder, error::AlthreadResult, no_rule, parser::Rule};
+use crate::{
+    ast::node::NodeBuilder,
+    error::{AlthreadError, AlthreadResult, ErrorType, Pos},
+    no_rule,
+    parser::Rule,
+};
 use ordered_float::OrderedFloat;
 use pest::iterators::Pairs;
 use serde::{Deserialize, Serialize};
@@ -17,6 +22,10 @@ pub enum DataType {
     Process(String),
     Tuple(Vec<DataType>),
     List(Box<DataType>),
+    /// A list of a fixed length, known at compile time
+    Array(Box<DataType>, usize),
+    /// A dictionary from keys of the first type to values of the second
+    Map(Box<DataType>, Box<DataType>),
 }
 
 impl DataType {
@@ -30,6 +39,10 @@ impl DataType {
             DataType::Process(_) => Literal::Null,
             DataType::Tuple(v) => Literal::Tuple(v.iter().map(|d| d.default()).collect()),
             DataType::List(t) => Literal::List(t.as_ref().clone(), vec![]),
+            DataType::Array(t, len) => Literal::Array(t.as_ref().clone(), vec![t.default(); *len]),
+            DataType::Map(k, v) => {
+                Literal::Map(k.as_ref().clone(), v.as_ref().clone(), BTreeMap::new())
+            }
         }
     }
     pub fn from_str(value: &str) -> Self {
@@ -68,6 +81,8 @@ impl DataType {
                     .join(", ")
             ),
             DataType::List(t) => format!("list({})", t.to_string()),
+            DataType::Array(t, len) => format!("[{t}; {len}]"),
+            DataType::Map(k, v) => format!("map({k}, {v})"),
         }
     }
 
@@ -98,7 +113,7 @@ impl DataType {
 
     pub fn is_process(&self) -> (bool, String) {
         match self {
-            Self::List(datatype) => datatype.is_process(),
+            Self::List(datatype) | Self::Array(datatype, _) => datatype.is_process(),
             Self::Process(name) => (true, name.clone()),
             _ => (false, String::new()),
         }
@@ -138,6 +153,25 @@ impl NodeBuilder for DataType {
                 let datatype = DataType::build(pairs.next().unwrap().into_inner(), filepath)?;
                 Ok(Self::List(Box::new(datatype)))
             }
+            Rule::ARRAY_TYPE => {
+                let mut pairs = pair.into_inner();
+                let datatype = DataType::build(pairs.next().unwrap().into_inner(), filepath)?;
+                let len_pair = pairs.next().unwrap();
+                let len = len_pair.as_str().parse::<usize>().map_err(|_| {
+                    AlthreadError::new(
+                        ErrorType::SyntaxError,
+                        Some(Pos::from_span(len_pair.as_span(), filepath)),
+                        format!("Invalid array length {}", len_pair.as_str()),
+                    )
+                })?;
+                Ok(Self::Array(Box::new(datatype), len))
+            }
+            Rule::MAP_TYPE => {
+                let mut pairs = pair.into_inner();
+                let key = DataType::build(pairs.next().unwrap().into_inner(), filepath)?;
+                let value = DataType::build(pairs.next().unwrap().into_inner(), filepath)?;
+                Ok(Self::Map(Box::new(key), Box::new(value)))
+            }
             _ => Err(no_rule!(pair, "DataType", filepath)),
         }
     }
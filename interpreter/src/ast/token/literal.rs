@@ -2,7 +2,7 @@ use core::fmt;
 use ordered_float::OrderedFloat;
 use pest::iterators::{Pair, Pairs};
 use serde::ser::{Serialize, SerializeStruct, Serializer};
-use std::{fmt::Formatter, hash::Hash, str::FromStr};
+use std::{collections::BTreeMap, fmt::Formatter, hash::Hash, str::FromStr};
 
 use crate::{
     ast::{
@@ -26,6 +26,11 @@ pub enum Literal {
     Process(String, usize),
     Tuple(Vec<Literal>),
     List(DataType, Vec<Literal>),
+    /// The values of a `[t; n]` array, whose length never changes
+    Array(DataType, Vec<Literal>),
+    /// The entries of a map with the given key and value types, indexed by the canonical form of
+    /// their key (see `Literal::map_key`) so that equal maps hash the same
+    Map(DataType, DataType, BTreeMap<String, (Literal, Literal)>),
 }
 
 impl<'a> Serialize for Literal {
@@ -49,6 +54,15 @@ impl<'a> Serialize for Literal {
                 state.serialize_field("list_datatype", datatype)?;
                 state.serialize_field("list", values)?;
             }
+            Self::Array(datatype, values) => {
+                state.serialize_field("array_datatype", datatype)?;
+                state.serialize_field("array", values)?;
+            }
+            Self::Map(key_datatype, value_datatype, entries) => {
+                state.serialize_field("map_key_datatype", key_datatype)?;
+                state.serialize_field("map_value_datatype", value_datatype)?;
+                state.serialize_field("map", &entries.values().collect::<Vec<_>>())?;
+            }
         }
         state.end()
     }
@@ -104,14 +118,46 @@ impl NodeBuilder for Literal {
             Rule::FLOAT => Self::Float(safe_parse(&pair, filepath)?),
             Rule::STR => {
                 let s = pair.as_str();
-                let unquoted = &s[1..s.len() - 1];
-                Self::String(unquoted.to_string())
+                let unquoted = match s.strip_prefix("\"\"\"") {
+                    // the line break following the opening quotes of a multi-line string is not
+                    // part of it, so that its first line can be aligned with the others
+                    Some(multiline) => {
+                        let multiline = &multiline[..multiline.len() - 3];
+                        multiline
+                            .strip_prefix("\r\n")
+                            .or_else(|| multiline.strip_prefix('\n'))
+                            .unwrap_or(multiline)
+                    }
+                    None => &s[1..s.len() - 1],
+                };
+                Self::String(unescape(unquoted))
             }
             _ => return Err(no_rule!(pair, "Literal", filepath)),
         })
     }
 }
 
+/// Replaces the escape sequences of a string literal, which were validated by the grammar
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('0') => unescaped.push('\0'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
 impl Literal {
     pub fn get_datatype(&self) -> DataType {
         match self {
@@ -123,6 +169,8 @@ impl Literal {
             Self::Process(n, _) => DataType::Process(n.to_string()),
             Self::Tuple(t) => DataType::Tuple(t.iter().map(|l| l.get_datatype()).collect()),
             Self::List(d, _) => DataType::List(Box::new(d.clone())),
+            Self::Array(d, values) => DataType::Array(Box::new(d.clone()), values.len()),
+            Self::Map(k, v, _) => DataType::Map(Box::new(k.clone()), Box::new(v.clone())),
         }
     }
 
@@ -154,6 +202,13 @@ impl Literal {
         }
     }
 
+    /// The canonical form of the value as the key of a map: two keys are the same entry exactly
+    /// when they are equal, and the entries are sorted by this form
+    #[must_use]
+    pub fn map_key(&self) -> String {
+        format!("{self:?}")
+    }
+
     pub fn is_true(&self) -> bool {
         match self {
             Self::Null => false,
@@ -246,7 +301,6 @@ impl Literal {
     pub fn modulo(&self, other: &Self) -> Result<Self, String> {
         match (self, other) {
             (Self::Int(i), Self::Int(j)) if *j != 0 => Ok(Self::Int(i % j)),
-            (Self::Float(i), Self::Float(j)) if *j != 0.0 => Ok(Self::Float(i % *j)),
             (i, j) => Err(format!(
                 "No modulo between {} and {}",
                 i.get_datatype(),
@@ -262,6 +316,12 @@ impl Literal {
             (Self::Float(i), Self::Float(j)) => Ok(Self::Bool(i == j)),
             (Self::Bool(i), Self::Bool(j)) => Ok(Self::Bool(i == j)),
             (Self::String(i), Self::String(j)) => Ok(Self::Bool(i == j)),
+            // process handles are identified by their pid
+            (Self::Process(_, i), Self::Process(_, j)) => Ok(Self::Bool(i == j)),
+            // a process handle that is not set yet is null
+            (Self::Process(..), Self::Null) | (Self::Null, Self::Process(..)) => {
+                Ok(Self::Bool(false))
+            }
             (i, j) => Err(format!(
                 "Cannot compare {} and {}",
                 i.get_datatype(),
@@ -344,6 +404,21 @@ impl Literal {
         }
     }
 
+    /// The logical implication `self => other`
+    ///
+    /// # Errors
+    /// If either operand is not a boolean
+    pub fn implies(&self, other: &Self) -> Result<Self, String> {
+        match (self, other) {
+            (Self::Bool(i), Self::Bool(j)) => Ok(Self::Bool(!*i || *j)),
+            (i, j) => Err(format!(
+                "Cannot perform IMPLIES operation between {} and {}",
+                i.get_datatype(),
+                j.get_datatype()
+            )),
+        }
+    }
+
     pub fn increment(&self) -> Result<Self, String> {
         match self {
             Self::Int(i) => Ok(Self::Int(i + 1)),
@@ -365,7 +440,7 @@ impl Literal {
         (Self::Int(i), Self::Int(j)) if *j >= 0 && *j < i64::BITS as i64 => {
             i.checked_shl(*j as u32)
                 .map(Self::Int)
-                .ok_or("Cannot shift left".to_string())
+                .ok_or_else(|| "Cannot shift left".to_string())
         }
         (Self::Int(_), Self::Int(_)) => Err("Shift count out of range".to_string()),
         (a, b) => Err(format!("Cannot shift {} by {}", a.get_datatype(), b.get_datatype())),
@@ -377,7 +452,7 @@ impl Literal {
             (Self::Int(i), Self::Int(j)) if *j >= 0 && *j < i64::BITS as i64 => {
                 i.checked_shr(*j as u32)
                     .map(Self::Int)
-                    .ok_or("Cannot shift right".to_string())
+                    .ok_or_else(|| "Cannot shift right".to_string())
             }
             (Self::Int(_), Self::Int(_)) => Err("Shift count out of range".to_string()),
             (a, b) => Err(format!("Cannot shift {} by {}", a.get_datatype(), b.get_datatype())),
@@ -395,6 +470,17 @@ impl Literal {
             (a, b) => Err(format!("Cannot perform bitwise OR between {} and {}", a.get_datatype(), b.get_datatype())),
         }
     }
+
+    /// The bitwise exclusive or of two integers
+    ///
+    /// # Errors
+    /// If either operand is not an integer
+    pub fn bit_xor(&self, other: &Self) -> Result<Self, String> {
+        match (self, other) {
+            (Self::Int(i), Self::Int(j)) => Ok(Self::Int(i ^ j)),
+            (a, b) => Err(format!("Cannot perform bitwise XOR between {} and {}", a.get_datatype(), b.get_datatype())),
+        }
+    }
 }
 
 impl fmt::Display for Literal {
@@ -424,6 +510,24 @@ impl fmt::Display for Literal {
                     .collect::<Vec<String>>()
                     .join(", ")
             ),
+            Self::Array(_datatype, values) => write!(
+                f,
+                "[{}]",
+                values
+                    .iter()
+                    .map(std::string::ToString::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Self::Map(_, _, entries) => write!(
+                f,
+                "{{{}}}",
+                entries
+                    .values()
+                    .map(|(k, v)| format!("{k}: {v}"))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
         }
     }
 }
@@ -451,6 +555,21 @@ impl AstDisplay for Literal {
                 }
                 Ok(())
             }
+            Self::Array(datatype, values) => {
+                writeln!(f, "{prefix}[{datatype}; {}]", values.len())?;
+                for value in values {
+                    value.ast_fmt(f, &prefix.add_leaf())?;
+                }
+                Ok(())
+            }
+            Self::Map(key_datatype, value_datatype, entries) => {
+                writeln!(f, "{prefix}map({key_datatype}, {value_datatype})")?;
+                for (key, value) in entries.values() {
+                    key.ast_fmt(f, &prefix.add_leaf())?;
+                    value.ast_fmt(f, &prefix.add_leaf())?;
+                }
+                Ok(())
+            }
         }
     }
 }
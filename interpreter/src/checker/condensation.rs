@@ -0,0 +1,269 @@
+//! Condensation of the state graph into its strongly connected components.
+//!
+//! The state graph of a model is usually too big to be read, but collapsing each strongly
+//! connected component (a set of states that can all reach each other) into a single node gives
+//! a DAG showing its high-level structure: the progress cycles, the phases the execution goes
+//! through, and the dead ends.
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use super::{StateGraph, StateId};
+
+/// The strongly connected components of a state graph and the DAG they form
+#[derive(Debug)]
+pub struct Condensation {
+    /// The states of each component. The components are numbered in topological order: the
+    /// edges always go from a component to a component with a greater index.
+    pub components: Vec<Vec<StateId>>,
+    /// The component of each state
+    pub component_of: Vec<usize>,
+    /// The edges between distinct components
+    pub edges: BTreeSet<(usize, usize)>,
+    /// Whether each component contains a cycle, i.e. several states or a state looping on itself
+    pub cyclic: Vec<bool>,
+}
+
+impl Condensation {
+    /// The components without any outgoing edge that the execution cannot leave
+    #[must_use]
+    pub fn is_terminal(&self, component: usize) -> bool {
+        !self.edges.iter().any(|(from, _)| *from == component)
+    }
+
+    /// Renders the condensed DAG in DOT, each component being labeled with its number of states.
+    ///
+    /// The components containing a cycle are drawn as double circles, the terminal components
+    /// without a cycle (the dead ends) as boxes, and the initial components are pointed to by an
+    /// arrow.
+    #[must_use]
+    pub fn to_dot(&self, state_graph: &StateGraph) -> String {
+        let mut dot = String::new();
+
+        writeln!(dot, "digraph Reachability {{").unwrap();
+        writeln!(dot, "  node [shape=circle];").unwrap();
+        writeln!(dot).unwrap();
+
+        let initial: BTreeSet<usize> = state_graph
+            .initial_states
+            .iter()
+            .map(|state| self.component_of[*state])
+            .collect();
+        for component in &initial {
+            writeln!(dot, "  _init_{component} [shape=point];").unwrap();
+            writeln!(dot, "  _init_{component} -> C{component};").unwrap();
+        }
+        writeln!(dot).unwrap();
+
+        for (component, states) in self.components.iter().enumerate() {
+            let shape = if self.cyclic[component] {
+                "doublecircle"
+            } else if self.is_terminal(component) {
+                "box"
+            } else {
+                "circle"
+            };
+            // a state that was not expanded (state limit reached) may have unknown successors
+            let style = if states
+                .iter()
+                .any(|state| !state_graph.nodes[*state].expanded)
+            {
+                ", style=dashed"
+            } else {
+                ""
+            };
+            writeln!(
+                dot,
+                "  C{} [shape={}{}, label=\"{} state{}\"];",
+                component,
+                shape,
+                style,
+                states.len(),
+                if states.len() == 1 { "" } else { "s" }
+            )
+            .unwrap();
+        }
+        writeln!(dot).unwrap();
+
+        for (from, to) in &self.edges {
+            writeln!(dot, "  C{from} -> C{to};").unwrap();
+        }
+
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
+
+/// Computes the strongly connected components of the state graph (Tarjan's algorithm)
+#[must_use]
+pub fn condense(state_graph: &StateGraph) -> Condensation {
+    let count = state_graph.nodes.len();
+    let successors: Vec<Vec<StateId>> = state_graph
+        .nodes
+        .iter()
+        .map(|node| node.successors.iter().map(|link| link.to).collect())
+        .collect();
+    let components = strongly_connected_components(&successors);
+
+    let mut component_of = vec![0; count];
+    for (component, states) in components.iter().enumerate() {
+        for state in states {
+            component_of[*state] = component;
+        }
+    }
+
+    let mut edges = BTreeSet::new();
+    let mut cyclic: Vec<bool> = components.iter().map(|states| states.len() > 1).collect();
+    for (state, node) in state_graph.nodes.iter().enumerate() {
+        for link in &node.successors {
+            let (from, to) = (component_of[state], component_of[link.to]);
+            if from == to {
+                cyclic[from] |= link.to == state;
+            } else {
+                edges.insert((from, to));
+            }
+        }
+    }
+
+    Condensation {
+        components,
+        component_of,
+        edges,
+        cyclic,
+    }
+}
+
+/// The strongly connected components of a graph given by the successors of each of its nodes,
+/// in topological order (Tarjan's algorithm)
+pub(crate) fn strongly_connected_components(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+
+    let count = successors.len();
+    let mut index = vec![UNVISITED; count];
+    let mut low_link = vec![0; count];
+    let mut on_stack = vec![false; count];
+    let mut stack: Vec<StateId> = Vec::new();
+    let mut next_index = 0;
+    // Tarjan's algorithm finds the components in reverse topological order
+    let mut components: Vec<Vec<StateId>> = Vec::new();
+
+    for root in 0..count {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        // each state of the DFS is paired with the index of its next successor to visit
+        let mut dfs = vec![(root, 0)];
+        index[root] = next_index;
+        low_link[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some((state, next)) = dfs.last_mut() {
+            let state = *state;
+            if let Some(&to) = successors[state].get(*next) {
+                *next += 1;
+                if index[to] == UNVISITED {
+                    index[to] = next_index;
+                    low_link[to] = next_index;
+                    next_index += 1;
+                    stack.push(to);
+                    on_stack[to] = true;
+                    dfs.push((to, 0));
+                } else if on_stack[to] {
+                    low_link[state] = low_link[state].min(index[to]);
+                }
+            } else {
+                dfs.pop();
+                if let Some((parent, _)) = dfs.last() {
+                    low_link[*parent] = low_link[*parent].min(low_link[state]);
+                }
+                if low_link[state] == index[state] {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == state {
+                            break;
+                        }
+                    }
+                    component.sort_unstable();
+                    components.push(component);
+                }
+            }
+        }
+    }
+    components.reverse();
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::{compile_from_source, explore, CheckOptions};
+
+    #[test]
+    fn condenses_cycle_into_single_component() {
+        let project = compile_from_source(
+            r#"
+shared {
+    let X = 0;
+    let Done = false;
+}
+
+program A() {
+    loop atomic {
+        if Done { break; }
+        X = (X + 1) % 3;
+    }
+}
+
+main {
+    run A();
+    Done = true;
+}
+"#,
+        );
+        let state_graph = explore(&project, &CheckOptions::default()).unwrap();
+        let condensation = condense(&state_graph);
+
+        let cyclic: Vec<usize> = (0..condensation.components.len())
+            .filter(|component| condensation.cyclic[*component])
+            .collect();
+        assert_eq!(cyclic.len(), 1);
+        assert_eq!(condensation.components[cyclic[0]].len(), 3);
+
+        // the components are numbered in topological order
+        assert!(condensation.edges.iter().all(|(from, to)| from < to));
+        assert_eq!(condensation.component_of[state_graph.initial_state], 0);
+
+        let dead_ends = (0..condensation.components.len())
+            .filter(|component| condensation.is_terminal(*component))
+            .count();
+        assert_eq!(dead_ends, 3);
+
+        let dot = condensation.to_dot(&state_graph);
+        assert!(dot.contains("shape=doublecircle, label=\"3 states\""));
+    }
+
+    #[test]
+    fn keeps_every_state_of_acyclic_graph() {
+        let project = compile_from_source(
+            r#"
+shared {
+    let X = 0;
+}
+
+main {
+    X = 1;
+    X = 2;
+}
+"#,
+        );
+        let state_graph = explore(&project, &CheckOptions::default()).unwrap();
+        let condensation = condense(&state_graph);
+
+        assert_eq!(condensation.components.len(), state_graph.nodes.len());
+        assert!(condensation.cyclic.iter().all(|cyclic| !cyclic));
+    }
+}
@@ -0,0 +1,192 @@
+//! Narration of counterexamples.
+//!
+//! Turns a path returned by the checker into English sentences, built from the instructions
+//! of each `StateLink` and the changes of the global variables along the path.
+
+use std::fmt::Write;
+
+use crate::vm::{GlobalAction, GlobalMemory, VM};
+
+use super::{StateGraph, StateLink};
+
+/// Explains a counterexample path, one sentence per step, followed by a sentence describing
+/// the violation. `source` is the source code of the checked program, used to quote the
+/// violated invariant.
+pub fn explain_trace(state_graph: &StateGraph, path: &[StateLink], source: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let Some(last) = path.last() else {
+        return sentences;
+    };
+
+    if last.name == "_init_" {
+        let line = last.lines.first().copied().unwrap_or_default();
+        sentences.push(format!(
+            "The invariant {} is violated in the initial state.",
+            quote_line(source, line)
+        ));
+        return sentences;
+    }
+
+    let start = state_graph.path_start(path);
+    let mut from = start;
+    for (i, link) in path.iter().enumerate() {
+        let mut events = Vec::new();
+        if let (Some(before), Some(after)) = (
+            state_graph.states[from].as_ref(),
+            state_graph.states[link.to].as_ref(),
+        ) {
+            let changes = global_changes(&before.globals, &after.globals);
+            if !changes.is_empty() {
+                events.push(format!("sets {}", changes.join(", ")));
+            }
+        }
+        events.extend(link.actions.iter().filter_map(describe_action));
+        if events.is_empty() {
+            events.push("executes a step".to_string());
+        }
+
+        sentences.push(format!(
+            "{}{}#{} {}{}.",
+            if i == 0 { "" } else { "Then " },
+            link.name,
+            link.pid,
+            join_events(&events),
+            describe_lines(&link.lines),
+        ));
+        from = link.to;
+    }
+
+    let looped = path[..path.len() - 1].iter().any(|l| l.to == last.to)
+        || last.to == start;
+    match state_graph.states[last.to].as_ref() {
+        Some(vm) if vm.check_invariants().is_err() => {
+            sentences.push(describe_invariant_violation(vm, source));
+        }
+        _ if looped => sentences.push(
+            "The execution is back in an earlier state, so it can repeat forever without satisfying the property."
+                .to_string(),
+        ),
+        _ => sentences.push(
+            "No process can make progress any more and the property is never satisfied."
+                .to_string(),
+        ),
+    }
+
+    sentences
+}
+
+fn global_changes(before: &GlobalMemory, after: &GlobalMemory) -> Vec<String> {
+    after
+        .iter()
+        .filter(|(name, value)| before.get(*name) != Some(*value))
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect()
+}
+
+fn describe_action(action: &GlobalAction) -> Option<String> {
+    match action {
+        GlobalAction::StartProgram(name, pid, _, _, _) => Some(format!("starts {name}#{pid}")),
+        GlobalAction::Print(msg) => Some(format!("prints \"{msg}\"")),
+        GlobalAction::Send(info) => Some(format!(
+            "sends {} on channel {}",
+            info.message, info.to.channel_name
+        )),
+        GlobalAction::Broadcast(infos) => infos.first().map(|info| {
+            format!(
+                "broadcasts {} on channel {}",
+                info.message, info.to.channel_name
+            )
+        }),
+        GlobalAction::Deliver(info) => Some(format!(
+            "delivers {} from {}#{} to {}#{} on channel {}",
+            info.message,
+            info.from.process_name,
+            info.from.process_id,
+            info.to.process_name,
+            info.to.process_id,
+            info.channel_name
+        )),
+        GlobalAction::EndProgram => Some("terminates".to_string()),
+        GlobalAction::Wait => Some("waits".to_string()),
+        GlobalAction::Exit => Some("exits the program".to_string()),
+        GlobalAction::Write(_) | GlobalAction::Connect(_, _) => None,
+    }
+}
+
+fn join_events(events: &[String]) -> String {
+    match events.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+fn describe_lines(lines: &[usize]) -> String {
+    match lines {
+        [] => String::new(),
+        [line] => format!(" (line {line})"),
+        _ => format!(
+            " (lines {})",
+            lines
+                .iter()
+                .map(std::string::ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Quotes the condition written at a given line of the source, with its line number
+fn quote_line(source: &str, line: usize) -> String {
+    match source.lines().nth(line.saturating_sub(1)) {
+        Some(text) if line > 0 => format!(
+            "`{}` (line {})",
+            text.trim().trim_end_matches(';').trim_end(),
+            line
+        ),
+        _ => format!("at line {line}"),
+    }
+}
+
+fn describe_invariant_violation(vm: &VM, source: &str) -> String {
+    for (_, read_vars, expr, pos) in vm.always_conditions {
+        if vm
+            .eval_condition(read_vars, expr)
+            .is_ok_and(|value| value.is_true())
+        {
+            continue;
+        }
+        let values = read_vars
+            .iter()
+            .filter_map(|name| vm.globals.get(name).map(|v| format!("{name}={v}")))
+            .collect::<Vec<_>>();
+        let mut sentence = format!("The invariant {} is violated", quote_line(source, pos.line));
+        if !values.is_empty() {
+            write!(sentence, " because {}", join_events(&values)).unwrap();
+        }
+        sentence.push('.');
+        return sentence;
+    }
+    for (_, read_vars, expr, pos) in vm.never_conditions {
+        if !vm
+            .eval_condition(read_vars, expr)
+            .is_ok_and(|value| value.is_true())
+        {
+            continue;
+        }
+        let values = read_vars
+            .iter()
+            .filter_map(|name| vm.globals.get(name).map(|v| format!("{name}={v}")))
+            .collect::<Vec<_>>();
+        let mut sentence = format!(
+            "The 'never' condition {} is satisfied",
+            quote_line(source, pos.line)
+        );
+        if !values.is_empty() {
+            write!(sentence, " because {}", join_events(&values)).unwrap();
+        }
+        sentence.push('.');
+        return sentence;
+    }
+    "The invariant is violated.".to_string()
+}
@@ -0,0 +1,92 @@
+//! Weak fairness for the LTL checker.
+//!
+//! Without fairness, an execution in which the scheduler never picks a process violates an
+//! `eventually` property as soon as another process can loop forever, even if the ignored
+//! process would have satisfied it. Under weak fairness, a process that stays enabled along a
+//! cycle must be scheduled in this cycle, so only the fair accepting cycles are counterexamples.
+//!
+//! A strongly connected component contains a fair cycle if and only if every process enabled in
+//! all of its nodes has a transition inside of it: the cycle going through all of its nodes is
+//! then fair, and any cycle of a component without this property leaves such a process waiting.
+
+use std::collections::BTreeSet;
+
+use super::condensation::strongly_connected_components;
+
+/// A graph whose transitions are labeled by the process executing them
+#[derive(Debug, Default)]
+pub struct FairnessGraph {
+    /// The transitions leaving each node, as the target node and the pid of the process, or
+    /// `None` for a transition that is not a step of a process (e.g. a terminal state stuttering)
+    pub successors: Vec<Vec<(usize, Option<usize>)>>,
+    /// The processes enabled in each node
+    pub enabled: Vec<BTreeSet<usize>>,
+}
+
+impl FairnessGraph {
+    /// Adds a node with the given enabled processes, returning its index
+    pub fn add_node(&mut self, enabled: BTreeSet<usize>) -> usize {
+        self.successors.push(Vec::new());
+        self.enabled.push(enabled);
+        self.successors.len() - 1
+    }
+
+    /// Searches for a weakly fair cycle going through a node accepted by `accepting`, returning
+    /// this node
+    pub fn find_fair_cycle(&self, accepting: impl Fn(usize) -> bool) -> Option<usize> {
+        let targets: Vec<Vec<usize>> = self
+            .successors
+            .iter()
+            .map(|links| links.iter().map(|(to, _)| *to).collect())
+            .collect();
+
+        strongly_connected_components(&targets)
+            .into_iter()
+            .find_map(|component| {
+                let inside = |node: &usize| component.binary_search(node).is_ok();
+                let internal: Vec<&(usize, Option<usize>)> = component
+                    .iter()
+                    .flat_map(|node| self.successors[*node].iter())
+                    .filter(|(to, _)| inside(to))
+                    .collect();
+                if internal.is_empty() {
+                    return None;
+                }
+                let moved: BTreeSet<usize> =
+                    internal.iter().filter_map(|(_, pid)| *pid).collect();
+                let fair = component
+                    .iter()
+                    .map(|node| self.enabled[*node].clone())
+                    .reduce(|a, b| a.intersection(&b).copied().collect())
+                    .is_some_and(|enabled_everywhere| enabled_everywhere.is_subset(&moved));
+                if !fair {
+                    return None;
+                }
+                component.iter().copied().find(|node| accepting(*node))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_cycles_leaving_an_enabled_process_waiting() {
+        // node 0: process 1 loops on itself while process 2 is enabled and leads to node 1
+        let mut graph = FairnessGraph::default();
+        let spinning = graph.add_node(BTreeSet::from([1, 2]));
+        let done = graph.add_node(BTreeSet::new());
+        graph.successors[spinning].push((spinning, Some(1)));
+        graph.successors[spinning].push((done, Some(2)));
+        graph.successors[done].push((done, None));
+
+        assert_eq!(graph.find_fair_cycle(|node| node == spinning), None);
+        // stuttering in a terminal state is fair
+        assert_eq!(graph.find_fair_cycle(|_| true), Some(done));
+
+        // once process 2 can also loop in the component, the cycle is fair
+        graph.successors[spinning].push((spinning, Some(2)));
+        assert_eq!(graph.find_fair_cycle(|node| node == spinning), Some(spinning));
+    }
+}
@@ -0,0 +1,116 @@
+//! Interleavings of a region of the source code.
+//!
+//! To locate the hotspots of the state explosion, the transitions executing an instruction of a
+//! region (a range of lines) are counted, along with the states they connect and the executions
+//! passing through them. A region with many interleavings is a good candidate for an `atomic`
+//! block.
+
+use std::ops::RangeInclusive;
+
+use super::{StateGraph, StateLink};
+
+/// The interleavings of a region of the source code in a state graph
+#[derive(Debug, PartialEq, Eq)]
+pub struct RegionInterleavings {
+    /// The transitions executing an instruction of the region
+    pub transitions: usize,
+    /// The distinct states entered or left by these transitions
+    pub states: usize,
+    /// The executions, from an initial state to a final state, passing through the region.
+    /// `None` if the state graph has a cycle, in which case there are infinitely many.
+    pub paths: Option<u128>,
+}
+
+/// Counts the interleavings of the instructions located on `lines`
+#[must_use]
+pub fn count_interleavings(
+    state_graph: &StateGraph,
+    lines: &RangeInclusive<usize>,
+) -> RegionInterleavings {
+    let in_region = |link: &StateLink| link.lines.iter().any(|line| lines.contains(line));
+
+    let mut transitions = 0;
+    let mut states = vec![false; state_graph.nodes.len()];
+    for (state, node) in state_graph.nodes.iter().enumerate() {
+        for link in node.successors.iter().filter(|link| in_region(link)) {
+            transitions += 1;
+            states[state] = true;
+            states[link.to] = true;
+        }
+    }
+
+    let paths = topological_order(state_graph).map(|order| {
+        // the number of executions from each state, and of those avoiding the region
+        let mut all = vec![0u128; state_graph.nodes.len()];
+        let mut avoiding = vec![0u128; state_graph.nodes.len()];
+        for state in order.into_iter().rev() {
+            let successors = &state_graph.nodes[state].successors;
+            if successors.is_empty() {
+                all[state] = 1;
+                avoiding[state] = 1;
+                continue;
+            }
+            for link in successors {
+                all[state] = all[state].saturating_add(all[link.to]);
+                if !in_region(link) {
+                    avoiding[state] = avoiding[state].saturating_add(avoiding[link.to]);
+                }
+            }
+        }
+        state_graph
+            .initial_states
+            .iter()
+            .fold(0u128, |paths, root| {
+                paths.saturating_add(all[*root].saturating_sub(avoiding[*root]))
+            })
+    });
+
+    RegionInterleavings {
+        transitions,
+        states: states.into_iter().filter(|state| *state).count(),
+        paths,
+    }
+}
+
+/// The states reachable from the initial states, each one before its successors, or `None` if
+/// the graph has a cycle
+fn topological_order(state_graph: &StateGraph) -> Option<Vec<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        Unvisited,
+        InProgress,
+        Done,
+    }
+
+    let mut marks = vec![Mark::Unvisited; state_graph.nodes.len()];
+    let mut post_order = Vec::new();
+    for root in state_graph.initial_states.iter().copied() {
+        if marks[root] != Mark::Unvisited {
+            continue;
+        }
+        // each state on the stack is paired with the index of its next successor to visit
+        let mut stack = vec![(root, 0)];
+        marks[root] = Mark::InProgress;
+
+        while let Some((state, next)) = stack.last_mut() {
+            let state = *state;
+            if let Some(link) = state_graph.nodes[state].successors.get(*next) {
+                *next += 1;
+                match marks[link.to] {
+                    Mark::InProgress => return None,
+                    Mark::Done => {}
+                    Mark::Unvisited => {
+                        marks[link.to] = Mark::InProgress;
+                        stack.push((link.to, 0));
+                    }
+                }
+            } else {
+                marks[state] = Mark::Done;
+                post_order.push(state);
+                stack.pop();
+            }
+        }
+    }
+    post_order.reverse();
+    Some(post_order)
+}
@@ -8,7 +8,7 @@ use crate::{
         node::{Node, NodeBuilder},
         statement::expression::{list_expression::RangeListExpression, Expression},
     },
-    error::AlthreadResult,
+    error::{AlthreadResult, Pos},
     no_rule,
     parser::Rule,
 };
@@ -47,18 +47,44 @@ pub enum LtlExpression {
 #[derive(Debug, Clone)]
 pub struct CheckBlock {
     pub formulas: Vec<LtlExpression>,
+    /// The position of each formula in the source
+    pub positions: Vec<Pos>,
+    /// The position of the formulas annotated with `@ignore`, which are not checked
+    pub ignored: Vec<Pos>,
 }
 
 impl NodeBuilder for CheckBlock {
     fn build(pairs: Pairs<Rule>, filepath: &str) -> AlthreadResult<Self> {
         let mut formulas = Vec::new();
+        let mut positions = Vec::new();
+        let mut ignored = Vec::new();
 
         for pair in pairs {
             match pair.as_rule() {
+                Rule::ignored_ltl_statement => {
+                    let statement = pair.into_inner().nth(1).unwrap();
+                    let expr_pair = statement.into_inner().next().unwrap();
+                    let (line, col) = expr_pair.line_col();
+                    ignored.push(Pos {
+                        start: expr_pair.as_span().start(),
+                        end: expr_pair.as_span().end(),
+                        line,
+                        col,
+                        file_path: filepath.to_string(),
+                    });
+                }
                 Rule::ltl_statement => {
                     // formulas.push(build_ltl_statement(pair, filepath)?);
                     let mut inner = pair.into_inner();
                     let expr_pair = inner.next().unwrap();
+                    let (line, col) = expr_pair.line_col();
+                    positions.push(Pos {
+                        start: expr_pair.as_span().start(),
+                        end: expr_pair.as_span().end(),
+                        line,
+                        col,
+                        file_path: filepath.to_string(),
+                    });
                     let formula = build_ltl_expression(expr_pair, filepath)?;
                     formulas.push(formula);
                 }
@@ -66,7 +92,11 @@ impl NodeBuilder for CheckBlock {
             }
         }
 
-        Ok(Self { formulas })
+        Ok(Self {
+            formulas,
+            positions,
+            ignored,
+        })
     }
 }
 /*
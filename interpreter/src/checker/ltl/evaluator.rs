@@ -72,7 +72,7 @@ pub fn evaluate_ltl_predicate(
                     return Err(AlthreadError::new(
                         ErrorType::ExpressionError,
                         None,
-                        format!("For loop expects a list, got {:?}", list_value),
+                        format!("For loop expects a list, got {list_value}"),
                     ))
                 }
             };
@@ -119,7 +119,7 @@ pub fn evaluate_ltl_predicate(
                     return Err(AlthreadError::new(
                         ErrorType::ExpressionError,
                         None,
-                        format!("Exists expects a list, got {:?}", list_value),
+                        format!("Exists expects a list, got {list_value}"),
                     ))
                 }
             };
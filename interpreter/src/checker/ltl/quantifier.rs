@@ -86,7 +86,7 @@ pub fn initialize_monitoring(
                     return Err(AlthreadError::new(
                         ErrorType::ExpressionError,
                         None,
-                        format!("Top-level for loop expects a list, got {:?}", list_value),
+                        format!("Top-level for loop expects a list, got {list_value}"),
                     ))
                 }
             };
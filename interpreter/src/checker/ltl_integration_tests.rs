@@ -8,23 +8,18 @@
 mod tests {
     use crate::{
         checker::{
-            check_program, ltl::automaton::BuchiAutomaton, ltl::compiled::CompiledLtlExpression,
+            check_program, compile_from_source, ltl::automaton::BuchiAutomaton,
+            ltl::compiled::CompiledLtlExpression, CheckOptions,
         },
         compiler::CompiledProject,
         error::AlthreadResult,
     };
-    use crate::{ast::Ast, module_resolver::StandardFileSystem};
-    use std::collections::HashMap;
-    use std::path::Path;
-
-    fn compile_from_source(source: &str) -> CompiledProject {
-        let mut input_map = HashMap::new();
-        input_map.insert("".to_string(), source.to_string());
-
-        let pairs = crate::parser::parse(source, "").unwrap();
-        let ast = Ast::build(pairs, "").unwrap();
-        ast.compile(Path::new(""), StandardFileSystem, &mut input_map)
-            .unwrap()
+
+    fn bounded(max_states: usize) -> CheckOptions<'static> {
+        CheckOptions {
+            max_states: Some(max_states),
+            ..Default::default()
+        }
     }
 
     // ============================================================
@@ -53,7 +48,7 @@ mod tests {
         let project = CompiledProject::default_for_testing();
 
         // Check without LTL (should use regular checker)
-        let (_violations, _graph) = check_program(&project, Some(10))?;
+        let (_violations, _graph) = check_program(&project, &bounded(10))?;
 
         // Should complete without errors
         Ok(())
@@ -84,7 +79,7 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, graph) = check_program(&project, Some(1000))?;
+        let (violations, graph) = check_program(&project, &bounded(1000))?;
         
         // Debug output
         println!("Number of violations: {}", violations.len());
@@ -100,6 +95,37 @@ check {
         Ok(())
     }
 
+    #[test]
+    fn test_ltl_check_reports_deadlock() -> AlthreadResult<()> {
+        let source = r#"
+shared {
+    let Flag: bool = false;
+}
+
+program Worker() {
+    await Flag;
+}
+
+main {
+    run Worker();
+}
+
+check {
+    always (!Flag);
+}
+"#;
+
+        let project = compile_from_source(source);
+        let (violations, graph) = check_program(&project, &bounded(1000))?;
+        assert!(
+            !violations.is_empty(),
+            "Expected the deadlock to be reported"
+        );
+        let deadlock = graph.deadlock.expect("the worker waits forever");
+        assert!(deadlock.message.contains("waiting for a change of Flag"));
+        Ok(())
+    }
+
     #[test]
     fn test_ltl_top_level_for_eventually_passes_when_all_end() -> AlthreadResult<()> {
         let source = r#"
@@ -117,7 +143,7 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, _graph) = check_program(&project, Some(1000))?;
+        let (violations, _graph) = check_program(&project, &bounded(1000))?;
         assert!(violations.is_empty(), "Expected no LTL violation");
         Ok(())
     }
@@ -144,7 +170,7 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, _graph) = check_program(&project, Some(1000))?;
+        let (violations, _graph) = check_program(&project, &bounded(1000))?;
         assert!(violations.is_empty(), "Expected no LTL violation for guarded .at access");
         Ok(())
     }
@@ -175,7 +201,7 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, _graph) = check_program(&project, Some(1000))?;
+        let (violations, _graph) = check_program(&project, &bounded(1000))?;
         assert!(violations.is_empty(), "Expected no LTL violation for always X >= 0");
         Ok(())
     }
@@ -202,7 +228,7 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, _graph) = check_program(&project, Some(1000))?;
+        let (violations, _graph) = check_program(&project, &bounded(1000))?;
         assert!(!violations.is_empty(), "Expected LTL violation when X becomes negative");
         Ok(())
     }
@@ -232,7 +258,7 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, _graph) = check_program(&project, Some(1000))?;
+        let (violations, _graph) = check_program(&project, &bounded(1000))?;
         assert!(violations.is_empty(), "Expected no LTL violation - Done eventually becomes true");
         Ok(())
     }
@@ -259,7 +285,7 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, graph) = check_program(&project, Some(2))?;
+        let (violations, graph) = check_program(&project, &bounded(2))?;
 
         assert!(violations.is_empty(), "Partial exploration must not invent a liveness counterexample at the frontier");
         assert!(!graph.exhaustive, "Expected the graph to be truncated by the state limit");
@@ -293,7 +319,7 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, _graph) = check_program(&project, Some(1000))?;
+        let (violations, _graph) = check_program(&project, &bounded(1000))?;
         assert!(
             violations.is_empty(),
             "Expected no LTL violation when both shared list updates eventually become visible"
@@ -325,7 +351,7 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, graph) = check_program(&project, Some(1000))?;
+        let (violations, graph) = check_program(&project, &bounded(1000))?;
         
         println!("Violations: {}", violations.len());
         println!("States: {}", graph.nodes.len());
@@ -376,7 +402,7 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, graph) = check_program(&project, Some(1000))?;
+        let (violations, graph) = check_program(&project, &bounded(1000))?;
         
         println!("Response property test:");
         println!("  Violations: {}", violations.len());
@@ -421,7 +447,7 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, _graph) = check_program(&project, Some(1000))?;
+        let (violations, _graph) = check_program(&project, &bounded(1000))?;
         assert!(
             violations.is_empty(),
             "Expected no violation: await first should eventually consume the later receive case on every schedule"
@@ -476,7 +502,7 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, _graph) = check_program(&project, Some(5000))?;
+        let (violations, _graph) = check_program(&project, &bounded(5000))?;
         assert!(
             violations.is_empty(),
             "Expected no violation: await seq should not deadlock regardless of whether tail or block message is delivered first"
@@ -513,7 +539,7 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, _graph) = check_program(&project, Some(1000))?;
+        let (violations, _graph) = check_program(&project, &bounded(1000))?;
         assert!(violations.is_empty(), "Expected no violations for multiple valid formulas");
         Ok(())
     }
@@ -548,10 +574,61 @@ check {
 "#;
 
         let project = compile_from_source(source);
-        let (violations, _graph) = check_program(&project, Some(1000))?;
+        let (violations, _graph) = check_program(&project, &bounded(1000))?;
         // With atomic, both should be set together
         println!("Implication violations: {}", violations.len());
         Ok(())
     }
+
+    // ============================================================
+    // Weak Fairness Tests
+    // ============================================================
+
+    #[test]
+    fn test_weak_fairness_discards_unfair_cycles() -> AlthreadResult<()> {
+        let source = r#"
+shared {
+    let Flag: bool = false;
+    let X: int = 0;
+}
+
+program Spinner() {
+    loop {
+        X = 1 - X;
+    }
+}
+
+program Setter() {
+    Flag = true;
+}
+
+main {
+    run Spinner();
+    run Setter();
+}
+
+check {
+    eventually Flag;
+}
+"#;
+
+        // the scheduler may run the spinner forever without ever running the setter
+        let project = compile_from_source(source);
+        let (violations, _graph) = check_program(&project, &bounded(1000))?;
+        assert!(!violations.is_empty());
+
+        let fair = CheckOptions {
+            weak_fairness: true,
+            ..bounded(1000)
+        };
+        let (violations, _graph) = check_program(&project, &fair)?;
+        assert!(violations.is_empty());
+
+        // a setter that never sets the flag is a fair counterexample
+        let project = compile_from_source(&source.replace("Flag = true;", "X = 0;"));
+        let (violations, _graph) = check_program(&project, &fair)?;
+        assert!(!violations.is_empty());
+        Ok(())
+    }
 }
 
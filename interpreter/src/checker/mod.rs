@@ -1,8 +1,22 @@
 //! Model checking module for Althread programs.
 //!
 //! This module provides state-space exploration and verification capabilities:
-//! - Basic invariant checking via `check_program`
-//! - LTL model checking via `check_program_with_ltl` using Büchi automatons
+//! - Basic invariant checking via `check_program`, or `check_program_all_violations` to gather
+//!   every violated invariant
+//! - Custom invariants written in Rust via `check_program_with_hooks`
+//! - Reachability queries via `reachable`
+//! - LTL model checking via `check_program_with_ltl` using Büchi automatons, optionally under
+//!   weak fairness (`fairness::FairnessGraph`)
+//! - Narration of counterexamples via `explain::explain_trace`
+//! - Replayable schedules of counterexamples via `schedule::Schedule`
+//! - Bounded starvation detection via `starvation::find_starvation`
+//! - Interleaving count of a region of the source via `interleavings::count_interleavings`
+//! - Condensation of the state graph into its strongly connected components via
+//!   `condensation::condense`
+//! - Number of values taken by each shared variable via `variable_ranges::variable_ranges`
+//! - Messages left in the channels of terminated processes via
+//!   `unhandled_messages::find_unhandled_messages`
+//! - Termination of every execution via `termination::find_non_termination`
 //!
 //! # LTL Verification Algorithm
 //!
@@ -13,28 +27,135 @@
 //! 4. Use Nested DFS to detect accepting cycles
 //! 5. An accepting cycle means the negated formula is satisfiable → original violated
 
+pub mod condensation;
+pub mod explain;
+pub mod fairness;
+pub mod interleavings;
 pub mod ltl;
+pub mod schedule;
+pub mod starvation;
+pub mod termination;
+pub mod unhandled_messages;
+pub mod variable_ranges;
 
 #[cfg(test)]
 mod ltl_integration_tests;
 
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt::{self, Write},
     hash::Hash,
     rc::Rc,
+    str::FromStr,
+    time::{Duration, Instant},
 };
 
+use fastrand::Rng;
+
+use fairness::FairnessGraph;
 use ltl::{automaton::BuchiAutomaton, compiled::CompiledLtlExpression, monitor::MonitoringState};
 use serde::ser::{Serialize, SerializeStruct, Serializer};
 
 use crate::{
+    ast::statement::expression::LocalExpressionNode,
     compiler::CompiledProject,
     error::{AlthreadError, AlthreadResult, ErrorType},
-    vm::{instruction::Instruction, GlobalAction, VM},
+    vm::{
+        instruction::Instruction, ExecutionStepInfo, ForkedState, GlobalAction, GlobalMemory,
+        RunOptions, VM,
+    },
 };
 
 pub type StateId = usize;
 
+/// The order in which the checker expands the discovered states
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExploreOrder {
+    /// Breadth-first: the counterexamples found are the shortest ones
+    #[default]
+    Bfs,
+    /// Depth-first: the frontier stays small, and long executions are reached first
+    Dfs,
+    /// The next state is drawn at random from the frontier
+    Random,
+}
+
+impl ExploreOrder {
+    /// Takes the next state to expand from the frontier
+    fn pop(self, frontier: &mut VecDeque<StateId>, rng: &mut Rng) -> Option<StateId> {
+        match self {
+            Self::Bfs => frontier.pop_front(),
+            Self::Dfs => frontier.pop_back(),
+            Self::Random if frontier.is_empty() => None,
+            Self::Random => frontier.swap_remove_back(rng.usize(..frontier.len())),
+        }
+    }
+}
+
+impl FromStr for ExploreOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bfs" => Ok(Self::Bfs),
+            "dfs" => Ok(Self::Dfs),
+            "random" => Ok(Self::Random),
+            _ => Err(format!(
+                "unknown exploration order '{s}', expected 'dfs', 'bfs' or 'random'"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ExploreOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bfs => write!(f, "bfs"),
+            Self::Dfs => write!(f, "dfs"),
+            Self::Random => write!(f, "random"),
+        }
+    }
+}
+
+/// The options of a check, chosen by its caller rather than by the compiled project
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CheckOptions<'a> {
+    /// The options of the VMs exploring the state space
+    pub run: RunOptions<'a>,
+
+    /// When set, the checker stops exploring once this number of states is discovered
+    pub max_states: Option<usize>,
+
+    /// When set, the checker keeps at most this number of states in memory. The least recently
+    /// seen states are evicted and may be explored again, so the check is approximate: the
+    /// reported violations are real, but liveness (eventually) properties are not checked once a
+    /// state has been evicted.
+    pub state_cache: Option<usize>,
+
+    /// When set, the checker stops exploring once the state graph is estimated to use this number
+    /// of bytes, instead of being killed when the memory is exhausted
+    pub max_memory: Option<usize>,
+
+    /// When set, the checker stops exploring once this wall-clock duration has elapsed, so that a
+    /// model too large to be checked does not hang a CI pipeline
+    pub timeout: Option<Duration>,
+
+    /// The order in which the checker expands the states: breadth-first by default
+    pub explore_order: ExploreOrder,
+
+    /// When set, the checker does not expand the states reached after this number of steps,
+    /// so that the models with an infinite state space can be partially verified
+    pub max_depth: Option<usize>,
+
+    /// When set, the checker keeps a single link between two states for each process, merging
+    /// the transitions (e.g. sends of different values) that lead to the same state
+    pub prune_equivalent_sends: bool,
+
+    /// When set, the checker only reports the violations of the `eventually` conditions in which
+    /// no process stays enabled forever without being scheduled (weak fairness)
+    pub weak_fairness: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct StateLink {
     pub instructions: Vec<Instruction>,
@@ -56,10 +177,58 @@ pub struct GraphNode {
 
 #[derive(Debug)]
 pub struct StateGraph<'a> {
-    pub states: Vec<Rc<VM<'a>>>,
+    /// The VM of each state, `None` once released by a bounded state cache
+    pub states: Vec<Option<Rc<VM<'a>>>>,
     pub nodes: Vec<GraphNode>,
     pub initial_state: StateId,
+    /// The roots of the exploration, one per possible initial state (see `VM::start_all`), the
+    /// first one being `initial_state`
+    pub initial_states: Vec<StateId>,
     pub exhaustive: bool,
+    /// Number of states evicted from a bounded state cache. When it is not zero, some states
+    /// may have been explored several times and the graph is only an approximation.
+    pub evicted_states: usize,
+    /// Number of transitions merged with an equivalent one (see
+    /// `CheckOptions::prune_equivalent_sends`)
+    pub pruned_links: usize,
+    /// A rough estimate of the memory used by the graph, in bytes
+    pub estimated_memory: usize,
+    /// Whether the exploration was stopped by `CheckOptions::max_memory`
+    pub memory_limit_reached: bool,
+    /// Whether the exploration was stopped by `CheckOptions::timeout`
+    pub time_limit_reached: bool,
+    /// Whether some states were left unexpanded at `CheckOptions::max_depth`
+    pub depth_limit_reached: bool,
+    /// The deadlock reached by the path returned by `check_program`, if the path does not lead
+    /// to a violated invariant but to a state where the processes wait forever
+    pub deadlock: Option<AlthreadError>,
+}
+
+/// The size of a state graph
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct StateGraphStats {
+    /// The number of distinct states
+    pub states: usize,
+    /// The number of transitions between the states
+    pub transitions: usize,
+    /// The length of the longest of the shortest paths from an initial state
+    pub max_depth: usize,
+    /// The number of states marked by the analysis of the `eventually` conditions
+    pub eventually_states: usize,
+}
+
+impl StateLink {
+    /// The messages printed by the processes during the transition, in order
+    #[must_use]
+    pub fn printed(&self) -> Vec<&str> {
+        self.actions
+            .iter()
+            .filter_map(|action| match action {
+                GlobalAction::Print(msg) => Some(msg.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 impl std::fmt::Display for StateLink {
@@ -114,7 +283,7 @@ impl<'a> Serialize for StateGraph<'a> {
                 .states
                 .iter()
                 .zip(self.nodes.iter())
-                .map(|(vm, node)| (vm.as_ref(), node))
+                .filter_map(|(vm, node)| Some((vm.as_deref()?, node)))
                 .collect::<Vec<(&VM, &GraphNode)>>(),
         )?;
         state.serialize_field("exhaustive", &self.exhaustive)?;
@@ -137,10 +306,18 @@ impl GraphNode {
 impl<'a> StateGraph<'a> {
     pub fn new(initial_vm: Rc<VM<'a>>) -> Self {
         Self {
-            states: vec![initial_vm],
+            estimated_memory: initial_vm.estimated_size() + std::mem::size_of::<GraphNode>(),
+            states: vec![Some(initial_vm)],
             nodes: vec![GraphNode::new(None, 0)],
             initial_state: 0,
+            initial_states: vec![0],
             exhaustive: true,
+            evicted_states: 0,
+            pruned_links: 0,
+            memory_limit_reached: false,
+            time_limit_reached: false,
+            depth_limit_reached: false,
+            deadlock: None,
         }
     }
 
@@ -151,20 +328,168 @@ impl<'a> StateGraph<'a> {
         level: usize,
     ) -> StateId {
         let id = self.states.len();
-        self.states.push(vm);
+        self.estimated_memory += vm.estimated_size() + std::mem::size_of::<GraphNode>();
+        self.states.push(Some(vm));
         self.nodes.push(GraphNode::new(predecessor, level));
         id
     }
 
+    /// Releases the VM of a state, keeping its node in the graph
+    fn release(&mut self, state_id: StateId) {
+        if let Some(vm) = self.states[state_id].take() {
+            self.estimated_memory -= vm.estimated_size();
+        }
+    }
+
     pub fn vm(&self, state_id: StateId) -> &Rc<VM<'a>> {
-        &self.states[state_id]
+        self.states[state_id]
+            .as_ref()
+            .expect("the state was released by the state cache")
+    }
+
+    /// The root of the exploration a path of the graph starts from
+    #[must_use]
+    pub fn path_start(&self, path: &[StateLink]) -> StateId {
+        let Some(first) = path.first() else {
+            return self.initial_state;
+        };
+        if first.name == "_init_" {
+            return first.to;
+        }
+        self.initial_states
+            .iter()
+            .copied()
+            .find(|root| {
+                self.nodes[*root]
+                    .successors
+                    .iter()
+                    .any(|link| link.to == first.to && link.pid == first.pid)
+            })
+            .unwrap_or(self.initial_state)
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> StateGraphStats {
+        StateGraphStats {
+            states: self.nodes.len(),
+            transitions: self.nodes.iter().map(|node| node.successors.len()).sum(),
+            max_depth: self.nodes.iter().map(|node| node.level).max().unwrap_or(0),
+            eventually_states: self.nodes.iter().filter(|node| node.eventually).count(),
+        }
+    }
+
+    /// Whether the graph may contain the same state several times
+    #[must_use]
+    pub fn is_approximate(&self) -> bool {
+        self.evicted_states > 0
+    }
+    /// The distinct valuations of the shared variables in the explored states, in exploration
+    /// order (states released by a bounded state cache are skipped)
+    #[must_use]
+    pub fn global_valuations(&self) -> Vec<&GlobalMemory> {
+        let mut seen = HashSet::new();
+        self.states
+            .iter()
+            .flatten()
+            .map(|vm| &vm.globals)
+            .filter(|globals| seen.insert(*globals))
+            .collect()
+    }
+
+    /// Renders the whole graph in DOT, each transition being labeled with the process executing
+    /// it (see `condensation::Condensation::to_dot` for a compact view)
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+
+        writeln!(dot, "digraph StateGraph {{").unwrap();
+        writeln!(dot, "  node [shape=circle];").unwrap();
+        writeln!(dot).unwrap();
+
+        for state in &self.initial_states {
+            writeln!(dot, "  _init_{state} [shape=point];").unwrap();
+            writeln!(dot, "  _init_{state} -> S{state};").unwrap();
+        }
+        writeln!(dot).unwrap();
+
+        for (state, node) in self.nodes.iter().enumerate() {
+            let style = if node.expanded { "" } else { ", style=dashed" };
+            writeln!(dot, "  S{state} [label=\"{state}\"{style}];").unwrap();
+        }
+        writeln!(dot).unwrap();
+
+        for (state, node) in self.nodes.iter().enumerate() {
+            for link in &node.successors {
+                writeln!(
+                    dot,
+                    "  S{} -> S{} [label=\"{}#{}\"];",
+                    state, link.to, link.name, link.pid
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
+
+/// The states already seen during the exploration, mapped to their id in the state graph.
+///
+/// With a capacity, only the most recently seen states are kept: a state seen again after its
+/// eviction is explored again as a new state.
+struct StateCache<'a> {
+    states: HashMap<Rc<VM<'a>>, (StateId, u64)>,
+    /// The cached states ordered from the least to the most recently seen
+    recency: BTreeMap<u64, Rc<VM<'a>>>,
+    capacity: Option<usize>,
+    tick: u64,
+}
+
+impl<'a> StateCache<'a> {
+    fn new(capacity: Option<usize>) -> Self {
+        Self {
+            states: HashMap::new(),
+            recency: BTreeMap::new(),
+            capacity,
+            tick: 0,
+        }
+    }
+
+    /// Looks up a state, marking it as the most recently seen one
+    fn get(&mut self, vm: &Rc<VM<'a>>) -> Option<StateId> {
+        let (id, last_seen) = self.states.get_mut(vm)?;
+        if self.capacity.is_some() {
+            self.tick += 1;
+            let cached = self.recency.remove(last_seen).unwrap();
+            self.recency.insert(self.tick, cached);
+            *last_seen = self.tick;
+        }
+        Some(*id)
+    }
+
+    /// Inserts a state, returning the id of the least recently seen state if it had to be
+    /// evicted to stay within the capacity
+    fn insert(&mut self, vm: Rc<VM<'a>>, id: StateId) -> Option<StateId> {
+        let Some(capacity) = self.capacity else {
+            self.states.insert(vm, (id, 0));
+            return None;
+        };
+        self.tick += 1;
+        self.recency.insert(self.tick, vm.clone());
+        self.states.insert(vm, (id, self.tick));
+        if self.states.len() <= capacity {
+            return None;
+        }
+        let (_, evicted) = self.recency.pop_first()?;
+        self.states.remove(&evicted).map(|(evicted_id, _)| evicted_id)
     }
 }
 
 fn collect_instruction_lines(instructions: &[Instruction]) -> Vec<usize> {
     let mut lines: Vec<usize> = instructions
         .iter()
-        .map(|instruction| instruction.pos.clone().unwrap_or_default().line)
+        .map(|instruction| instruction.pos.as_ref().map_or(0, |pos| pos.line))
         .filter(|line| *line > 0)
         .collect();
     lines.sort();
@@ -172,49 +497,125 @@ fn collect_instruction_lines(instructions: &[Instruction]) -> Vec<usize> {
     lines
 }
 
+/// Explores the state space of a project, in the order given by `CheckOptions::explore_order`.
+///
+/// With a `state_cache` capacity, the VMs of the states evicted from the cache are released once
+/// expanded. Their invariants are checked before, and the exploration stops at the first state
+/// violating them so that it is kept in the graph.
 fn build_state_graph<'a>(
     compiled_project: &'a CompiledProject,
-    max_states: Option<usize>,
+    options: &CheckOptions<'a>,
 ) -> AlthreadResult<StateGraph<'a>> {
-    let mut init_vm = VM::new(compiled_project);
-    init_vm.start(0);
+    let state_cache = options.state_cache;
+    let mut initial_vms = VM::new(compiled_project, options.run)
+        .start_all()?
+        .into_iter()
+        .map(Rc::new);
 
-    let initial_vm = Rc::new(init_vm);
+    let initial_vm = initial_vms
+        .next()
+        .expect("a VM has at least one initial state");
     let mut state_graph = StateGraph::new(initial_vm.clone());
-    let mut known_states = HashMap::new();
+    let mut known_states = StateCache::new(state_cache);
     known_states.insert(initial_vm, state_graph.initial_state);
+    // the evicted states that still have to be expanded before being released
+    let mut evicted_unexpanded = HashSet::new();
+
+    for vm in initial_vms {
+        if known_states.get(&vm).is_some() {
+            continue;
+        }
+        let initial_state = state_graph.push_state(vm.clone(), None, 0);
+        state_graph.initial_states.push(initial_state);
+        if let Some(evicted) = known_states.insert(vm, initial_state) {
+            state_graph.evicted_states += 1;
+            evicted_unexpanded.insert(evicted);
+        }
+    }
 
-    let mut next_nodes = VecDeque::new();
-    next_nodes.push_back(state_graph.initial_state);
+    let mut next_nodes = VecDeque::from(state_graph.initial_states.clone());
+    let mut rng = Rng::new();
+    // the clock is only read when a timeout is set, as it is not available on every target
+    let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
 
-    while let Some(current_state) = next_nodes.pop_front() {
-        if let Some(max) = max_states {
+    while let Some(current_state) = options.explore_order.pop(&mut next_nodes, &mut rng) {
+        if let Some(max) = options.max_states {
             if state_graph.nodes.len() >= max {
                 state_graph.exhaustive = false;
                 break;
             }
         }
+        if let Some(max) = options.max_memory {
+            if state_graph.estimated_memory >= max {
+                state_graph.exhaustive = false;
+                state_graph.memory_limit_reached = true;
+                break;
+            }
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            state_graph.exhaustive = false;
+            state_graph.time_limit_reached = true;
+            break;
+        }
 
         let current_vm = state_graph.vm(current_state).clone();
+        if state_cache.is_some() && current_vm.check_invariants().is_err() {
+            break;
+        }
         let current_level = state_graph.nodes[current_state].level;
-        let successors = current_vm.next()?;
+        if options.max_depth.is_some_and(|max| current_level >= max) {
+            // the state is kept in the graph, but its successors are not explored
+            state_graph.exhaustive = false;
+            state_graph.depth_limit_reached = true;
+            if evicted_unexpanded.remove(&current_state) {
+                state_graph.release(current_state);
+            }
+            continue;
+        }
+        let successors = current_vm.fork_states()?;
 
-        for (name, pid, instructions, actions, vm) in successors.into_iter() {
+        for ForkedState { step, vm } in successors {
+            let ExecutionStepInfo {
+                prog_name: name,
+                prog_id: pid,
+                instructions,
+                actions,
+                ..
+            } = step;
             let next_vm = Rc::new(vm);
             let lines = collect_instruction_lines(&instructions);
             let next_state = if let Some(existing_state) = known_states.get(&next_vm) {
-                *existing_state
+                existing_state
             } else {
                 let new_state = state_graph.push_state(
                     next_vm.clone(),
                     Some(current_state),
                     current_level + 1,
                 );
-                known_states.insert(next_vm.clone(), new_state);
+                if let Some(evicted) = known_states.insert(next_vm, new_state) {
+                    state_graph.evicted_states += 1;
+                    if state_graph.nodes[evicted].expanded {
+                        state_graph.release(evicted);
+                    } else {
+                        evicted_unexpanded.insert(evicted);
+                    }
+                }
                 next_nodes.push_back(new_state);
                 new_state
             };
 
+            if options.prune_equivalent_sends
+                && state_graph.nodes[current_state]
+                    .successors
+                    .iter()
+                    .any(|link| link.to == next_state && link.pid == pid)
+            {
+                state_graph.pruned_links += 1;
+                continue;
+            }
+
+            state_graph.estimated_memory += std::mem::size_of::<StateLink>()
+                + instructions.len() * std::mem::size_of::<Instruction>();
             state_graph.nodes[current_state].successors.push(StateLink {
                 to: next_state,
                 lines,
@@ -226,16 +627,79 @@ fn build_state_graph<'a>(
         }
 
         state_graph.nodes[current_state].expanded = true;
+        if evicted_unexpanded.remove(&current_state) {
+            state_graph.release(current_state);
+        }
     }
 
     Ok(state_graph)
 }
+
+/// Explores the whole state space of a project, without checking its invariants.
+///
+/// # Errors
+/// If a step of a program fails
+pub fn explore<'a>(
+    compiled_project: &'a CompiledProject,
+    options: &CheckOptions<'a>,
+) -> AlthreadResult<StateGraph<'a>> {
+    build_state_graph(compiled_project, options)
+}
+
 /// Checks a given project, returning a path from an initial state to the first state that violates an invariant. (return an empty vector if no invariant is violated)
 pub fn check_program<'a>(
     compiled_project: &'a CompiledProject,
-    max_states: Option<usize>,
+    options: &CheckOptions<'a>,
+) -> AlthreadResult<(Vec<StateLink>, StateGraph<'a>)> {
+    check_program_impl(compiled_project, options, &[])
+}
+
+/// A custom invariant, written in Rust for the properties the `always` blocks cannot express.
+/// It returns the message of the violation when it does not hold in a state.
+pub type InvariantHook = Box<dyn Fn(&VM) -> Result<(), String>>;
+
+/// Checks a given project like `check_program`, additionally running the `hooks` on each state
+/// alongside the `always` invariants. A failing hook is reported as an invariant violation, with
+/// the path leading to the state.
+///
+/// # Errors
+/// If a step of a program fails
+pub fn check_program_with_hooks<'a>(
+    compiled_project: &'a CompiledProject,
+    options: &CheckOptions<'a>,
+    hooks: &[InvariantHook],
+) -> AlthreadResult<(Vec<StateLink>, StateGraph<'a>)> {
+    check_program_impl(compiled_project, options, hooks)
+}
+
+/// Runs the custom invariants on a state
+fn check_hooks(vm: &VM, hooks: &[InvariantHook]) -> AlthreadResult<()> {
+    for hook in hooks {
+        hook(vm).map_err(|message| AlthreadError::new(ErrorType::InvariantError, None, message))?;
+    }
+    Ok(())
+}
+
+fn check_program_impl<'a>(
+    compiled_project: &'a CompiledProject,
+    options: &CheckOptions<'a>,
+    hooks: &[InvariantHook],
 ) -> AlthreadResult<(Vec<StateLink>, StateGraph<'a>)> {
     if !compiled_project.compiled_ltl_formulas.is_empty() {
+        if options.state_cache.is_some() {
+            return Err(AlthreadError::new(
+                ErrorType::NotImplemented,
+                None,
+                "a bounded state cache cannot be used to check LTL formulas".to_string(),
+            ));
+        }
+        if !hooks.is_empty() {
+            return Err(AlthreadError::new(
+                ErrorType::NotImplemented,
+                None,
+                "custom invariants cannot be used to check LTL formulas".to_string(),
+            ));
+        }
         println!(
             "Found {} compiled LTL formulas in the project",
             compiled_project.compiled_ltl_formulas.len()
@@ -244,13 +708,19 @@ pub fn check_program<'a>(
             println!("Compiled LTL Formula #{}: {}", i + 1, formula);
         }
         println!("Starting LTL verification...");
-        return check_program_with_ltl(compiled_project, max_states);
+        return check_program_with_ltl(compiled_project, options);
     }
 
-    let mut state_graph = build_state_graph(compiled_project, max_states)?;
+    let mut state_graph = build_state_graph(compiled_project, options)?;
 
     for current_state in 0..state_graph.nodes.len() {
-        let check_ret = state_graph.vm(current_state).check_invariants();
+        // released states were checked during the exploration
+        let Some(vm) = &state_graph.states[current_state] else {
+            continue;
+        };
+        let check_ret = vm
+            .check_invariants()
+            .and_then(|ret| check_hooks(vm, hooks).map(|()| ret));
         if let Err(e) = check_ret {
             let mut path = Vec::new();
             let mut back_node = current_state;
@@ -291,10 +761,16 @@ pub fn check_program<'a>(
         } else if check_ret.is_ok_and(|x| x == 1) {
             state_graph.nodes[current_state].eventually = true;
         }
+
+        if let Some((deadlock, path)) = find_deadlock(&state_graph, current_state, vm)? {
+            state_graph.deadlock = Some(deadlock);
+            return Ok((path, state_graph));
+        }
     }
 
-    // If the search was not exhaustive, we cannot check eventually violations
-    if !state_graph.exhaustive {
+    // If the search was not exhaustive or some states were explored several times, we cannot
+    // check eventually violations
+    if !state_graph.exhaustive || state_graph.is_approximate() {
         return Ok((vec![], state_graph));
     }
 
@@ -304,63 +780,39 @@ pub fn check_program<'a>(
     let mut path_visit: Vec<usize> = Vec::new();
     let mut path = Vec::new();
     let mut path_set = std::collections::HashSet::new();
-    // if root node check eventually condition no path can exist
-    if state_graph.nodes[state_graph.initial_state].eventually {
-        return Ok((vec![], state_graph));
-    }
-
-    path.push(state_graph.initial_state);
-    path_set.insert(state_graph.initial_state);
-    // no successors have yet been visited
-    path_visit.push(0);
-
-    while !path.is_empty() {
-        let curr_state = {
-            let temp = path.last().unwrap();
-            *temp
-        };
+    for root in state_graph.initial_states.clone() {
+        // if root node check eventually condition no path can exist
+        if state_graph.nodes[root].eventually {
+            continue;
+        }
 
-        let mut visited_succ = path_visit.pop().unwrap();
+        path.push(root);
+        path_set.insert(root);
+        // no successors have yet been visited
+        path_visit.push(0);
 
-        // get all the successors of the current node
-        let mut succ = Vec::new();
-        for link in state_graph.nodes[curr_state].successors.iter().skip(visited_succ) {
-            succ.push(link.clone());
-        }
+        while !path.is_empty() {
+            let curr_state = {
+                let temp = path.last().unwrap();
+                *temp
+            };
 
-        // if the current node have no successors then we found an invalid path of execution
-        if succ.is_empty() && visited_succ == 0 {
-            let ret = reconstruct_path(path, &state_graph);
+            let mut visited_succ = path_visit.pop().unwrap();
 
-            match ret {
-                Ok(vec) => {
-                    return Ok((vec.into_iter().rev().collect(), state_graph));
-                }
-                Err(e) => {
-                    return Err(AlthreadError::new(
-                        ErrorType::ExpressionError,
-                        None,
-                        e.message,
-                    ))
-                }
+            // get all the successors of the current node
+            let mut succ = Vec::new();
+            for link in state_graph.nodes[curr_state].successors.iter().skip(visited_succ) {
+                succ.push(link.clone());
             }
-        }
 
-        // we search an explorable path in the successors list
-        let mut explorable_path = false;
-        while !succ.is_empty() && !explorable_path {
-            let curr_succ = succ.pop().unwrap();
-            visited_succ += 1;
-
-            // if the successor is already in the path we found an invalid execution path
-            if path_set.contains(&curr_succ.to) {
-                // If it is in the path, we push it temporarily just to have it for reconstruction,
-                // OR we can reconstruct including the cycle closing edge.
-                path.push(curr_succ.to.clone());
+            // if the current node have no successors then we found an invalid path of execution
+            if succ.is_empty() && visited_succ == 0 {
                 let ret = reconstruct_path(path, &state_graph);
+
                 match ret {
-                    Ok(vec) => return Ok((vec.into_iter().rev().collect(), state_graph)),
-                    // safety purpose
+                    Ok(vec) => {
+                        return Ok((vec.into_iter().rev().collect(), state_graph));
+                    }
                     Err(e) => {
                         return Err(AlthreadError::new(
                             ErrorType::ExpressionError,
@@ -371,30 +823,184 @@ pub fn check_program<'a>(
                 }
             }
 
-            // we get the corresponding graphnode and check wheter he has the eventually flag or not
-            let graph_node = &state_graph.nodes[curr_succ.to];
-            if !graph_node.eventually {
-                explorable_path = true;
-                path.push(curr_succ.to);
-                path_set.insert(curr_succ.to);
-                // we update the number of visited successors of the current node
-                path_visit.push(visited_succ);
-                // we then init the number of visited successors from the new node in the path
-                path_visit.push(0);
+            // we search an explorable path in the successors list
+            let mut explorable_path = false;
+            while !succ.is_empty() && !explorable_path {
+                let curr_succ = succ.pop().unwrap();
+                visited_succ += 1;
+
+                // if the successor is already in the path we found an invalid execution path
+                if path_set.contains(&curr_succ.to) {
+                    // If it is in the path, we push it temporarily just to have it for reconstruction,
+                    // OR we can reconstruct including the cycle closing edge.
+                    path.push(curr_succ.to);
+                    let ret = reconstruct_path(path, &state_graph);
+                    match ret {
+                        Ok(vec) => return Ok((vec.into_iter().rev().collect(), state_graph)),
+                        // safety purpose
+                        Err(e) => {
+                            return Err(AlthreadError::new(
+                                ErrorType::ExpressionError,
+                                None,
+                                e.message,
+                            ))
+                        }
+                    }
+                }
+
+                // we get the corresponding graphnode and check wheter he has the eventually flag or not
+                let graph_node = &state_graph.nodes[curr_succ.to];
+                if !graph_node.eventually {
+                    explorable_path = true;
+                    path.push(curr_succ.to);
+                    path_set.insert(curr_succ.to);
+                    // we update the number of visited successors of the current node
+                    path_visit.push(visited_succ);
+                    // we then init the number of visited successors from the new node in the path
+                    path_visit.push(0);
+                }
             }
-        }
-        // if no explorable path was found we condemn this node (it is a dead end)
-        if !explorable_path {
-            state_graph.nodes[curr_state].eventually = true;
-            let popped = path.pop();
-            if let Some(p) = popped {
-                path_set.remove(&p);
+            // if no explorable path was found we condemn this node (it is a dead end)
+            if !explorable_path {
+                state_graph.nodes[curr_state].eventually = true;
+                let popped = path.pop();
+                if let Some(p) = popped {
+                    path_set.remove(&p);
+                }
             }
         }
     }
     Ok((vec![], state_graph))
 }
 
+/// The deadlock of a state that was expanded without successors, with the path reaching it
+fn find_deadlock(
+    state_graph: &StateGraph,
+    state_id: StateId,
+    vm: &VM,
+) -> AlthreadResult<Option<(AlthreadError, Vec<StateLink>)>> {
+    let node = &state_graph.nodes[state_id];
+    if !node.expanded || !node.successors.is_empty() {
+        return Ok(None);
+    }
+    let Some(deadlock) = vm.deadlock() else {
+        return Ok(None);
+    };
+    let path = if node.predecessor.is_none() {
+        vec![StateLink {
+            to: state_id,
+            lines: vec![],
+            instructions: vec![],
+            actions: vec![],
+            pid: 0,
+            name: "_init_".to_string(),
+        }]
+    } else {
+        build_violation_path(state_graph, state_id)?
+    };
+    Ok(Some((deadlock, path)))
+}
+
+/// A violated invariant, with the path from the initial state to the closest state violating it
+#[derive(Debug)]
+pub struct Violation {
+    pub error: AlthreadError,
+    pub path: Vec<StateLink>,
+}
+
+/// Checks a given project like `check_program`, but explores the whole state space and returns
+/// every distinct violated invariant instead of stopping at the first one.
+///
+/// Violations are distinguished by the position of the invariant, each one comes with the
+/// shortest path reaching it: the states are always explored breadth-first, whatever the
+/// `explore_order` of the options. Liveness (eventually) properties are not checked.
+///
+/// # Errors
+/// If a step of a program fails
+pub fn check_program_all_violations<'a>(
+    compiled_project: &'a CompiledProject,
+    options: &CheckOptions<'a>,
+) -> AlthreadResult<(Vec<Violation>, StateGraph<'a>)> {
+    if !compiled_project.compiled_ltl_formulas.is_empty() {
+        return Err(AlthreadError::new(
+            ErrorType::NotImplemented,
+            None,
+            "all the violations cannot be gathered when checking LTL formulas".to_string(),
+        ));
+    }
+
+    let state_graph = build_state_graph(
+        compiled_project,
+        &CheckOptions {
+            state_cache: None,
+            explore_order: ExploreOrder::Bfs,
+            ..*options
+        },
+    )?;
+
+    let mut violations: Vec<Violation> = Vec::new();
+    // states are numbered in breadth-first order, so the first path found is the shortest one
+    for state_id in 0..state_graph.nodes.len() {
+        for error in state_graph.vm(state_id).violated_invariants() {
+            if violations.iter().any(|v| v.error.pos == error.pos) {
+                continue;
+            }
+            let path = if state_graph.nodes[state_id].predecessor.is_none() {
+                vec![StateLink {
+                    to: state_id,
+                    lines: error.pos.iter().map(|pos| pos.line).collect(),
+                    instructions: vec![],
+                    actions: vec![],
+                    pid: 0,
+                    name: "_init_".to_string(),
+                }]
+            } else {
+                build_violation_path(&state_graph, state_id)?
+            };
+            violations.push(Violation { error, path });
+        }
+    }
+
+    Ok((violations, state_graph))
+}
+
+/// Searches the state space of a project for a state where `condition` holds.
+///
+/// `condition` is evaluated over the global variables listed in `read_vars`, exactly like
+/// the conditions of an `always` block. Returns the path from the initial state to the
+/// first (closest) state satisfying the condition, or `None` if no explored state does. Like
+/// `check_program_all_violations`, the states are always explored breadth-first.
+///
+/// # Errors
+/// If a step of a program fails or the condition cannot be evaluated
+pub fn reachable<'a>(
+    compiled_project: &'a CompiledProject,
+    read_vars: &[String],
+    condition: &LocalExpressionNode,
+    options: &CheckOptions<'a>,
+) -> AlthreadResult<Option<Vec<StateLink>>> {
+    let state_graph = build_state_graph(
+        compiled_project,
+        &CheckOptions {
+            state_cache: None,
+            explore_order: ExploreOrder::Bfs,
+            ..*options
+        },
+    )?;
+
+    // states are numbered in breadth-first order, so the first match is the closest one
+    for state_id in 0..state_graph.nodes.len() {
+        let value = state_graph
+            .vm(state_id)
+            .eval_condition(read_vars, condition)
+            .map_err(|e| AlthreadError::new(ErrorType::ExpressionError, None, e))?;
+        if value.is_true() {
+            return Ok(Some(build_violation_path(&state_graph, state_id)?));
+        }
+    }
+    Ok(None)
+}
+
 pub fn reconstruct_path<'a>(
     mut vec_vm: Vec<StateId>,
     state_graph: &StateGraph<'a>,
@@ -436,7 +1042,7 @@ struct CombinedProductState {
 /// 4. An accepting cycle means the negated LTL formula is satisfiable → original formula violated
 fn check_program_with_ltl<'a>(
     compiled_project: &'a CompiledProject,
-    max_states: Option<usize>,
+    options: &CheckOptions<'a>,
 ) -> AlthreadResult<(Vec<StateLink>, StateGraph<'a>)> {
     // Step 1: Build Büchi automatons from compiled LTL formulas
     let automatons: Vec<BuchiAutomaton> = compiled_project
@@ -463,15 +1069,34 @@ fn check_program_with_ltl<'a>(
     println!("Built {} Büchi automatons", automatons.len());
 
     // Step 2: Build the VM state graph once and reuse it for all formulas.
-    let state_graph = build_state_graph(compiled_project, max_states)?;
-    let initial_vm = state_graph.vm(state_graph.initial_state).clone();
-
-    // Step 3: Initialize monitoring state with proper quantifier handling
-    let initial_monitoring = ltl::quantifier::initialize_monitoring(
-        &compiled_project.compiled_ltl_formulas,
-        &automatons,
-        initial_vm.as_ref(),
-    )?;
+    let mut state_graph = build_state_graph(compiled_project, options)?;
+
+    // A deadlock is reported before the formulas, which would only see the deadlocked state
+    // stuttering forever
+    for state_id in 0..state_graph.nodes.len() {
+        let Some(vm) = &state_graph.states[state_id] else {
+            continue;
+        };
+        if let Some((deadlock, path)) = find_deadlock(&state_graph, state_id, vm)? {
+            state_graph.deadlock = Some(deadlock);
+            return Ok((path, state_graph));
+        }
+    }
+
+    // Step 3: Initialize monitoring state with proper quantifier handling, for every root
+    let mut initial_product_states = Vec::new();
+    for root in &state_graph.initial_states {
+        let initial_vm = state_graph.vm(*root).clone();
+        let initial_monitoring = ltl::quantifier::initialize_monitoring(
+            &compiled_project.compiled_ltl_formulas,
+            &automatons,
+            initial_vm.as_ref(),
+        )?;
+        initial_product_states.push(CombinedProductState {
+            vm: *root,
+            monitors: initial_monitoring,
+        });
+    }
 
     // ============================================================
     // NESTED DFS ALGORITHM FOR ACCEPTING CYCLE DETECTION
@@ -484,18 +1109,21 @@ fn check_program_with_ltl<'a>(
     // Track visited states for the inner DFS (reset for each accepting state)
     let mut visited_inner: HashSet<CombinedProductState> = HashSet::new();
     
-    // Store the graph edges for path reconstruction
-    let mut product_edges: HashMap<CombinedProductState, Vec<CombinedProductState>> = HashMap::new();
-    
-    // Initial product state
-    let initial_product_state = CombinedProductState {
-        vm: state_graph.initial_state,
-        monitors: initial_monitoring.clone(),
-    };
+    // Store the graph edges for path reconstruction, with the pid of the process taking them
+    // (None for the stuttering of a terminal state)
+    let mut product_edges: HashMap<CombinedProductState, Vec<(CombinedProductState, Option<usize>)>> =
+        HashMap::new();
+    // The product states in the order of their first visit, to number them under weak fairness
+    let mut visit_order: Vec<CombinedProductState> = Vec::new();
     
     // Stack for iterative DFS: (state, phase)
     // phase 0 = first visit, phase 1 = post-order (after children explored)
-    let mut dfs_stack: Vec<(CombinedProductState, usize)> = vec![(initial_product_state.clone(), 0)];
+    // The roots are pushed in reverse order so that the first one is explored first
+    let mut dfs_stack: Vec<(CombinedProductState, usize)> = initial_product_states
+        .into_iter()
+        .rev()
+        .map(|state| (state, 0))
+        .collect();
     
     while let Some((current_state, phase)) = dfs_stack.pop() {
         if phase == 0 {
@@ -506,6 +1134,9 @@ fn check_program_with_ltl<'a>(
             
             visited_outer.insert(current_state.clone());
             on_stack.insert(current_state.clone());
+            if options.weak_fairness {
+                visit_order.push(current_state.clone());
+            }
             let current_vm_id = current_state.vm;
             
             // ================================================================
@@ -570,7 +1201,7 @@ fn check_program_with_ltl<'a>(
                     product_edges
                         .entry(current_state.clone())
                         .or_insert_with(Vec::new)
-                        .push(next_product_state.clone());
+                        .push((next_product_state.clone(), None));
                     
                     // If this is a new product state, add it to DFS
                     if !visited_outer.contains(&next_product_state) {
@@ -613,7 +1244,7 @@ fn check_program_with_ltl<'a>(
                     product_edges
                         .entry(current_state.clone())
                         .or_insert_with(Vec::new)
-                        .push(next_product_state.clone());
+                        .push((next_product_state.clone(), Some(successor.pid)));
                     
                     // Add to DFS stack if not visited
                     if !visited_outer.contains(&next_product_state) {
@@ -632,7 +1263,8 @@ fn check_program_with_ltl<'a>(
                 &compiled_project.compiled_ltl_formulas,
             );
             
-            if is_accepting {
+            // under weak fairness, the accepting cycles are searched once the product is built
+            if is_accepting && !options.weak_fairness {
                 log::debug!("DEBUG: Post-order visit of accepting state, launching inner DFS");
                 
                 // Launch inner DFS to find a cycle back to this accepting state
@@ -647,7 +1279,7 @@ fn check_program_with_ltl<'a>(
                     
                     // Get successors of inner_current
                     if let Some(successors) = product_edges.get(&inner_current) {
-                        for successor in successors {
+                        for (successor, _) in successors {
                             // Check if we found a cycle back to the accepting state
                             if *successor == current_state {
                                 log::debug!("DEBUG: Found accepting cycle!");
@@ -677,6 +1309,38 @@ fn check_program_with_ltl<'a>(
         }
     }
 
+    // Under weak fairness, an accepting cycle is only a counterexample if no process stays
+    // enabled along it without being scheduled
+    if options.weak_fairness {
+        let mut graph = FairnessGraph::default();
+        let mut index: HashMap<&CombinedProductState, usize> = HashMap::new();
+        for state in &visit_order {
+            let enabled = state_graph.nodes[state.vm]
+                .successors
+                .iter()
+                .map(|link| link.pid)
+                .collect();
+            index.insert(state, graph.add_node(enabled));
+        }
+        for state in &visit_order {
+            for (to, pid) in product_edges.get(state).into_iter().flatten() {
+                graph.successors[index[state]].push((index[to], *pid));
+            }
+        }
+        let fair_cycle = graph.find_fair_cycle(|node| {
+            monitors_in_accepting_state(
+                &visit_order[node].monitors,
+                &automatons,
+                &compiled_project.compiled_ltl_formulas,
+            )
+        });
+        if let Some(node) = fair_cycle {
+            println!("LTL violation detected: fair accepting cycle found");
+            let violation_path = build_violation_path(&state_graph, visit_order[node].vm)?;
+            return Ok((violation_path, state_graph));
+        }
+    }
+
     // Traditional invariant checking (separate pass for safety properties)
     // This is done on the state graph we built
     for state_id in 0..state_graph.nodes.len() {
@@ -838,3 +1502,140 @@ fn is_propositional(expr: &CompiledLtlExpression) -> bool {
         CompiledLtlExpression::ForLoop { .. } | CompiledLtlExpression::Exists { .. } => false,
     }
 }
+
+/// Compiles a single-file project, for the tests of the checker and of the analyses
+#[cfg(test)]
+pub(crate) fn compile_from_source(source: &str) -> CompiledProject {
+    use crate::{ast::Ast, module_resolver::VirtualFileSystem, parser};
+    use std::path::Path;
+
+    let mut input_map = HashMap::new();
+    input_map.insert("main.alt".to_string(), source.to_string());
+
+    let pairs = parser::parse(source, "main.alt").unwrap();
+    let ast = Ast::build(pairs, "main.alt").unwrap();
+    ast.compile(
+        Path::new("main.alt"),
+        VirtualFileSystem::new(input_map.clone()),
+        &mut input_map,
+    )
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{
+        statement::expression::{
+            binary_expression::LocalBinaryExpressionNode,
+            primary_expression::{LocalLiteralNode, LocalPrimaryExpressionNode, LocalVarNode},
+        },
+        token::{binary_operator::BinaryOperator, literal::Literal},
+    };
+
+    fn var_equals(value: i64) -> LocalExpressionNode {
+        LocalExpressionNode::Binary(LocalBinaryExpressionNode {
+            left: Box::new(LocalExpressionNode::Primary(LocalPrimaryExpressionNode::Var(
+                LocalVarNode { index: 0 },
+            ))),
+            operator: BinaryOperator::Equals,
+            right: Box::new(LocalExpressionNode::Primary(
+                LocalPrimaryExpressionNode::Literal(LocalLiteralNode {
+                    value: Literal::Int(value),
+                }),
+            )),
+        })
+    }
+
+    const COUNTER: &str = r#"
+shared {
+    let X = 0;
+}
+program inc() {
+    atomic { X = X + 1; }
+}
+main {
+    run inc();
+    run inc();
+}
+"#;
+
+    #[test]
+    fn reachable_returns_trace_to_first_matching_state() {
+        let project = compile_from_source(COUNTER);
+        let path = reachable(
+            &project,
+            &["X".to_string()],
+            &var_equals(2),
+            &CheckOptions::default(),
+        )
+        .unwrap()
+        .expect("X == 2 should be reachable");
+
+        let writes = path
+            .iter()
+            .flat_map(|link| link.actions.iter())
+            .filter(|action| matches!(action, GlobalAction::Write(name) if name == "X"))
+            .count();
+        assert_eq!(writes, 2);
+    }
+
+    #[test]
+    fn reachable_returns_none_for_unreachable_state() {
+        let project = compile_from_source(COUNTER);
+        let path = reachable(
+            &project,
+            &["X".to_string()],
+            &var_equals(3),
+            &CheckOptions::default(),
+        )
+        .unwrap();
+        assert!(path.is_none());
+    }
+
+    #[test]
+    fn violation_path_keeps_printed_output() {
+        let project = compile_from_source(
+            r#"
+shared {
+    let X = 0;
+}
+program inc() {
+    let v = X;
+    print("read", v);
+    X = v + 1;
+}
+main {
+    run inc();
+    run inc();
+}
+always {
+    X < 2;
+}
+"#,
+        );
+        let (path, _) = check_program(&project, &CheckOptions::default()).unwrap();
+        let printed: Vec<&str> = path.iter().flat_map(|link| link.printed()).collect();
+        assert!(printed.len() >= 2);
+        assert!(printed.iter().all(|msg| msg.starts_with("read ")));
+    }
+
+    #[test]
+    fn stats_count_states_and_transitions() {
+        let project = compile_from_source(COUNTER);
+        let state_graph = explore(&project, &CheckOptions::default()).unwrap();
+        let stats = state_graph.stats();
+
+        assert_eq!(stats.states, state_graph.nodes.len());
+        let transitions: usize = state_graph
+            .nodes
+            .iter()
+            .map(|node| node.successors.len())
+            .sum();
+        assert_eq!(stats.transitions, transitions);
+        // the two increments are interleaved, so some states have two successors
+        assert!(stats.transitions > stats.states - 1);
+        assert!(stats.max_depth >= 3);
+        assert_eq!(stats.eventually_states, 0);
+    }
+}
@@ -0,0 +1,214 @@
+//! Recorded schedules of counterexamples.
+//!
+//! A counterexample found by the checker is recorded as the sequence of the processes scheduled
+//! along it. Replaying the schedule with the `run --replay` command reproduces the execution
+//! deterministically, so that a bug found by the checker can be kept as a regression test.
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+    compiler::CompiledProject,
+    error::{AlthreadError, AlthreadResult, ErrorType},
+    vm::{RunOptions, VM},
+};
+
+use super::{collect_instruction_lines, StateGraph, StateLink};
+
+/// A step of a schedule
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledStep {
+    pub pid: usize,
+    /// The name of the scheduled process, or of the delivery (`__deliver__ channel#pid`)
+    pub name: String,
+    /// The index of the step among the steps the process can take from the same state, when it
+    /// has several (e.g. a call to `rand`)
+    pub choice: usize,
+}
+
+/// The execution of a counterexample, replayable from the initial state
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    /// The index of the initial state, when the shared variables have several initial values
+    /// (see `VM::start_all`)
+    pub initial: usize,
+    pub steps: Vec<ScheduledStep>,
+}
+
+impl Schedule {
+    /// Records the schedule of a path of the state graph.
+    ///
+    /// The steps are replayed to tell apart the steps of a process leading to different states,
+    /// which is only possible for the states still in memory: with a bounded state cache, the
+    /// first step of the process is recorded for the released ones.
+    ///
+    /// # Errors
+    /// If a step of the path cannot be replayed
+    pub fn from_path(
+        compiled_project: &CompiledProject,
+        options: RunOptions,
+        state_graph: &StateGraph,
+        path: &[StateLink],
+    ) -> AlthreadResult<Self> {
+        let start = state_graph.path_start(path);
+        let initial_vms = VM::new(compiled_project, options).start_all()?;
+        let initial = match &state_graph.states[start] {
+            Some(vm) => initial_vms.iter().position(|initial| initial == vm.as_ref()),
+            None => None,
+        }
+        .unwrap_or_default();
+
+        let mut vm = initial_vms.into_iter().nth(initial).ok_or_else(|| {
+            AlthreadError::new(
+                ErrorType::RuntimeError,
+                None,
+                "the initial state of the path was not found".to_string(),
+            )
+        })?;
+        let mut steps = Vec::new();
+        for link in path.iter().filter(|link| link.name != "_init_") {
+            let mut candidates: Vec<VM> = vm
+                .next()?
+                .into_iter()
+                .filter(|(name, pid, ..)| *pid == link.pid && *name == link.name)
+                .map(|(.., next)| next)
+                .collect();
+            let choice = match &state_graph.states[link.to] {
+                Some(target) => candidates.iter().position(|next| next == target.as_ref()),
+                None => None,
+            }
+            .unwrap_or_default();
+            if choice >= candidates.len() {
+                return Err(AlthreadError::new(
+                    ErrorType::RuntimeError,
+                    None,
+                    format!("the step of {}#{} could not be replayed", link.name, link.pid),
+                ));
+            }
+            vm = candidates.swap_remove(choice);
+            steps.push(ScheduledStep {
+                pid: link.pid,
+                name: link.name.clone(),
+                choice,
+            });
+        }
+
+        Ok(Self { initial, steps })
+    }
+
+    /// The initial state the schedule starts from
+    ///
+    /// # Errors
+    /// If the initial state of the schedule cannot be built
+    pub fn initial_vm<'a>(
+        &self,
+        compiled_project: &'a CompiledProject,
+        options: RunOptions<'a>,
+    ) -> AlthreadResult<VM<'a>> {
+        VM::new(compiled_project, options)
+            .start_all()?
+            .into_iter()
+            .nth(self.initial)
+            .ok_or_else(|| {
+                AlthreadError::new(
+                    ErrorType::RuntimeError,
+                    None,
+                    format!(
+                        "the schedule starts from the initial state {}, which does not exist",
+                        self.initial
+                    ),
+                )
+            })
+    }
+}
+
+impl ScheduledStep {
+    /// Executes the step from the state `vm`, failing if the program cannot take it. Returns the
+    /// executed step, as in a path of the checker (its `to` is meaningless), and the next state.
+    ///
+    /// # Errors
+    /// If the program cannot take the step from `vm`
+    pub fn replay<'a>(&self, vm: &VM<'a>) -> AlthreadResult<(StateLink, VM<'a>)> {
+        vm.next()?
+            .into_iter()
+            .filter(|(name, pid, ..)| *pid == self.pid && *name == self.name)
+            .nth(self.choice)
+            .map(|(name, pid, instructions, actions, vm)| {
+                let link = StateLink {
+                    lines: collect_instruction_lines(&instructions),
+                    instructions,
+                    actions,
+                    pid,
+                    name,
+                    to: 0,
+                };
+                (link, vm)
+            })
+            .ok_or_else(|| {
+                AlthreadError::new(
+                    ErrorType::RuntimeError,
+                    None,
+                    format!(
+                        "{}#{} cannot take the scheduled step, the schedule does not match the program",
+                        self.name, self.pid
+                    ),
+                )
+            })
+    }
+}
+
+/// One line per step, `<pid> <choice> <name>`, after the index of the initial state. The lines
+/// starting with `#` are comments.
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "initial {}", self.initial)?;
+        for step in &self.steps {
+            writeln!(f, "{} {} {}", step.pid, step.choice, step.name)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let initial = match lines.next() {
+            Some((_, line)) => line
+                .strip_prefix("initial ")
+                .and_then(|initial| initial.trim().parse().ok())
+                .ok_or_else(|| format!("expected 'initial <index>', got '{line}'"))?,
+            None => return Err("the schedule is empty".to_string()),
+        };
+
+        let mut steps = Vec::new();
+        for (index, line) in lines {
+            let mut parts = line.splitn(3, ' ');
+            let (Some(pid), Some(choice), Some(name)) = (parts.next(), parts.next(), parts.next())
+            else {
+                return Err(format!(
+                    "line {}: expected '<pid> <choice> <name>', got '{}'",
+                    index + 1,
+                    line
+                ));
+            };
+            let number = |value: &str| {
+                value
+                    .parse::<usize>()
+                    .map_err(|_| format!("line {}: invalid number '{}'", index + 1, value))
+            };
+            steps.push(ScheduledStep {
+                pid: number(pid)?,
+                choice: number(choice)?,
+                name: name.to_string(),
+            });
+        }
+
+        Ok(Self { initial, steps })
+    }
+}
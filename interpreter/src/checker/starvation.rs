@@ -0,0 +1,104 @@
+//! Bounded starvation analysis.
+//!
+//! A process starves when it stays enabled (it has an outgoing transition) while the other
+//! processes are scheduled instead. With a budget of K steps, the analysis looks for an execution
+//! in which a process remains continuously enabled across K transitions without executing.
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use super::{StateGraph, StateId, StateLink};
+
+/// A visited state, with the number of consecutive transitions the process has been starved for
+type Visit = (StateId, usize);
+
+/// A process that stayed enabled across `budget` transitions without being scheduled
+#[derive(Debug)]
+pub struct Starvation {
+    pub pid: usize,
+    pub name: String,
+    /// The path from the initial state to the last transition of the starving streak
+    pub path: Vec<StateLink>,
+}
+
+/// Searches the state graph for a process that is continuously enabled across `budget`
+/// transitions executed by other processes, returning the shortest such execution.
+#[must_use]
+pub fn find_starvation(state_graph: &StateGraph, budget: usize) -> Option<Starvation> {
+    if budget == 0 {
+        return None;
+    }
+    let pids: BTreeSet<(usize, &str)> = state_graph
+        .nodes
+        .iter()
+        .flat_map(|node| node.successors.iter())
+        .map(|link| (link.pid, link.name.as_str()))
+        .collect();
+
+    pids.into_iter()
+        .filter_map(|(pid, name)| {
+            let path = starving_path(state_graph, pid, name, budget)?;
+            Some(Starvation {
+                pid,
+                name: name.to_string(),
+                path,
+            })
+        })
+        .min_by_key(|starvation| starvation.path.len())
+}
+
+/// Breadth-first search over the states paired with the number of transitions the process has
+/// been waiting for while enabled
+fn starving_path(
+    state_graph: &StateGraph,
+    pid: usize,
+    name: &str,
+    budget: usize,
+) -> Option<Vec<StateLink>> {
+    let is_process = |link: &StateLink| link.pid == pid && link.name == name;
+    // predecessor of each visit, with the index of the link taken
+    let mut visited: HashMap<Visit, Option<(Visit, usize)>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    for root in &state_graph.initial_states {
+        let start = (*root, 0);
+        if visited.insert(start, None).is_none() {
+            queue.push_back(start);
+        }
+    }
+
+    while let Some((state, streak)) = queue.pop_front() {
+        let successors = &state_graph.nodes[state].successors;
+        let enabled = successors.iter().any(is_process);
+
+        for (index, link) in successors.iter().enumerate() {
+            let next_streak = if enabled && !is_process(link) {
+                streak + 1
+            } else {
+                0
+            };
+            let next = (link.to, next_streak);
+            if visited.contains_key(&next) {
+                continue;
+            }
+            visited.insert(next, Some(((state, streak), index)));
+            if next_streak >= budget {
+                return Some(rebuild_path(state_graph, &visited, next));
+            }
+            queue.push_back(next);
+        }
+    }
+    None
+}
+
+fn rebuild_path(
+    state_graph: &StateGraph,
+    visited: &HashMap<Visit, Option<(Visit, usize)>>,
+    mut current: Visit,
+) -> Vec<StateLink> {
+    let mut path = Vec::new();
+    while let Some(Some((pred, index))) = visited.get(&current) {
+        path.push(state_graph.nodes[pred.0].successors[*index].clone());
+        current = *pred;
+    }
+    path.reverse();
+    path
+}
@@ -0,0 +1,125 @@
+//! Termination analysis.
+//!
+//! A model terminates when every execution eventually stops. In a finite state graph, an
+//! execution runs forever exactly when it enters a cycle, so the model terminates when no
+//! strongly connected component of the graph contains a cycle. The executions ending in a
+//! deadlock also stop, but they are reported by the invariant check.
+
+use std::collections::{HashMap, VecDeque};
+
+use super::{build_violation_path, condensation::condense, StateGraph, StateId, StateLink};
+
+/// An execution that never terminates, as a lasso: a path followed by a cycle repeated forever
+#[derive(Debug)]
+pub struct NonTermination {
+    /// The path from the initial state to the first state of the cycle
+    pub path: Vec<StateLink>,
+    /// The transitions of the cycle, leading back to the state reached by `path`
+    pub cycle: Vec<StateLink>,
+}
+
+/// Searches the state graph for a reachable cycle, returning the one closest to the initial
+/// states in topological order. The result is only a proof of termination if the graph is
+/// exhaustive.
+#[must_use]
+pub fn find_non_termination(state_graph: &StateGraph) -> Option<NonTermination> {
+    let condensation = condense(state_graph);
+    let component = (0..condensation.components.len()).find(|c| condensation.cyclic[*c])?;
+    let entry = condensation.components[component]
+        .iter()
+        .copied()
+        .min_by_key(|state| state_graph.nodes[*state].level)?;
+
+    Some(NonTermination {
+        path: build_violation_path(state_graph, entry).ok()?,
+        cycle: cycle_through(state_graph, entry, |state| {
+            condensation.component_of[state] == component
+        })?,
+    })
+}
+
+/// Breadth-first search for the shortest cycle going through `entry`, staying in the states
+/// accepted by `inside`
+fn cycle_through(
+    state_graph: &StateGraph,
+    entry: StateId,
+    inside: impl Fn(StateId) -> bool,
+) -> Option<Vec<StateLink>> {
+    // the link leading to each visited state, as (predecessor, index of the link)
+    let mut visited: HashMap<StateId, (StateId, usize)> = HashMap::new();
+    let mut queue = VecDeque::from([entry]);
+
+    while let Some(state) = queue.pop_front() {
+        for (index, link) in state_graph.nodes[state].successors.iter().enumerate() {
+            if !inside(link.to) || visited.contains_key(&link.to) {
+                continue;
+            }
+            visited.insert(link.to, (state, index));
+            if link.to == entry {
+                let mut cycle = Vec::new();
+                let mut current = entry;
+                loop {
+                    let (pred, index) = visited[&current];
+                    cycle.push(state_graph.nodes[pred].successors[index].clone());
+                    current = pred;
+                    if current == entry {
+                        break;
+                    }
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            queue.push_back(link.to);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::{compile_from_source, explore, CheckOptions};
+
+    #[test]
+    fn finds_cycle_of_non_terminating_model() {
+        let source = r#"
+shared {
+    let X = 0;
+    let Done = false;
+}
+
+program A() {
+    while !Done {
+        X = (X + 1) % 3;
+    }
+}
+
+main {
+    run A();
+    Done = true;
+}
+"#;
+        let project = compile_from_source(source);
+        let state_graph = explore(&project, &CheckOptions::default()).unwrap();
+        let lasso = find_non_termination(&state_graph).unwrap();
+        assert!(!lasso.cycle.is_empty());
+        assert!(lasso.cycle.iter().all(|link| link.name == "A"));
+        // the cycle leads back to the state reached by the path
+        let entry = lasso
+            .path
+            .last()
+            .map_or(state_graph.initial_state, |link| link.to);
+        assert_eq!(lasso.cycle.last().unwrap().to, entry);
+        assert!(state_graph.nodes[entry]
+            .successors
+            .iter()
+            .any(|link| link.to == lasso.cycle[0].to));
+
+        // a bounded loop always terminates
+        let source = source.replace("while !Done {", "repeat 4 {");
+        let project = compile_from_source(&source);
+        let state_graph = explore(&project, &CheckOptions::default()).unwrap();
+        assert!(state_graph.exhaustive);
+        assert!(find_non_termination(&state_graph).is_none());
+    }
+}
@@ -0,0 +1,101 @@
+//! Messages left unhandled at the end of an execution.
+//!
+//! A message buffered in the channel of a process that has terminated will never be received.
+//! It is often the sign of lost work, e.g. a worker stopping before it has handled all of its
+//! requests, which no invariant on the shared variables can catch.
+
+use crate::ast::token::literal::Literal;
+
+use super::{build_violation_path, StateGraph, StateLink};
+
+/// Messages still buffered in a channel of a terminated process when the execution ends
+#[derive(Debug)]
+pub struct UnhandledMessages {
+    pub pid: usize,
+    pub name: String,
+    pub channel: String,
+    /// The values carried by the messages, without their sender
+    pub messages: Vec<Literal>,
+    /// The path from the initial state to the terminal state
+    pub path: Vec<StateLink>,
+}
+
+/// Searches the terminal states of the graph (the explored states without successors) for a
+/// channel of a terminated process that still holds messages, returning the first one found in
+/// exploration order.
+#[must_use]
+pub fn find_unhandled_messages(state_graph: &StateGraph) -> Option<UnhandledMessages> {
+    state_graph
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| node.expanded && node.successors.is_empty())
+        .find_map(|(state_id, _)| {
+            let vm = state_graph.states[state_id].as_ref()?;
+            let ((pid, channel), messages) =
+                vm.channels.state().iter().find(|((pid, _), messages)| {
+                    !messages.is_empty() && vm.get_program(*pid).has_terminated()
+                })?;
+            Some(UnhandledMessages {
+                pid: *pid,
+                name: vm.get_program(*pid).name.clone(),
+                channel: channel.clone(),
+                messages: messages
+                    .iter()
+                    // a buffered message is the tuple ((sender, clock), values)
+                    .map(|message| match message {
+                        Literal::Tuple(envelope) if envelope.len() == 2 => envelope[1].clone(),
+                        _ => message.clone(),
+                    })
+                    .collect(),
+                path: build_violation_path(state_graph, state_id).ok()?,
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::{compile_from_source, explore, CheckOptions};
+
+    #[test]
+    fn finds_message_left_to_terminated_process() {
+        let source = r#"
+program Worker() {
+    await receive in (x) => {
+        print("handled", x);
+    }
+}
+
+program Client() {
+    send out(1);
+    send out(2);
+}
+
+main {
+    let w = run Worker();
+    let c = run Client();
+    channel c.out (int)> w.in;
+}
+"#;
+        let project = compile_from_source(source);
+        let state_graph = explore(&project, &CheckOptions::default()).unwrap();
+        let unhandled = find_unhandled_messages(&state_graph).unwrap();
+        assert_eq!(unhandled.name, "Worker");
+        assert_eq!(unhandled.channel, "in");
+        assert_eq!(
+            unhandled.messages,
+            vec![Literal::Tuple(vec![Literal::Int(2)])]
+        );
+        assert!(!unhandled.path.is_empty());
+
+        // a worker handling both messages leaves nothing behind
+        let source = source.replace(
+            "    await receive in (x) => {\n        print(\"handled\", x);\n    }\n",
+            "    repeat 2 {\n        await receive in (x) => {\n            print(\"handled\", x);\n        }\n    }\n",
+        );
+        let project = compile_from_source(&source);
+        let state_graph = explore(&project, &CheckOptions::default()).unwrap();
+        assert!(find_unhandled_messages(&state_graph).is_none());
+    }
+}
@@ -0,0 +1,89 @@
+//! Values taken by the shared variables in a state graph.
+//!
+//! The number of states grows with the product of the numbers of values of the shared variables,
+//! so a variable ranging over thousands of values is the usual cause of a state explosion.
+//! Ranking the variables by their number of distinct values shows where an abstraction (e.g.
+//! `--abstract-ints`) or a bound would shrink the state space the most.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::ast::token::literal::Literal;
+
+use super::StateGraph;
+
+/// The values taken by a shared variable in the states of a state graph
+#[derive(Debug, PartialEq, Eq)]
+pub struct VariableRange {
+    pub variable: String,
+    /// The number of distinct values of the variable
+    pub distinct_values: usize,
+    /// The smallest and the largest value of an integer variable
+    pub int_bounds: Option<(i64, i64)>,
+}
+
+/// Counts the distinct values of each shared variable in the states of the graph, the widest
+/// variables first. The states released by a bounded state cache are not counted.
+#[must_use]
+pub fn variable_ranges(state_graph: &StateGraph) -> Vec<VariableRange> {
+    let mut values: BTreeMap<&String, HashSet<&Literal>> = BTreeMap::new();
+    for vm in state_graph.states.iter().flatten() {
+        for (variable, value) in &vm.globals {
+            values.entry(variable).or_default().insert(value);
+        }
+    }
+
+    let mut ranges: Vec<VariableRange> = values
+        .into_iter()
+        .map(|(variable, values)| {
+            let ints = values.iter().filter_map(|value| match value {
+                Literal::Int(i) => Some(*i),
+                _ => None,
+            });
+            let int_bounds = ints.clone().min().zip(ints.max());
+            VariableRange {
+                variable: variable.clone(),
+                distinct_values: values.len(),
+                int_bounds,
+            }
+        })
+        .collect();
+    // the sort is stable, so the variables with as many values stay sorted by name
+    ranges.sort_by_key(|b| std::cmp::Reverse(b.distinct_values));
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checker::{compile_from_source, explore, CheckOptions};
+
+    #[test]
+    fn ranks_variables_by_distinct_values() {
+        let project = compile_from_source(
+            r#"
+shared {
+    let Counter = 0;
+    let Done = false;
+    let Name = "a";
+}
+
+main {
+    while Counter < 5 {
+        Counter = Counter + 1;
+    }
+    Done = true;
+}
+"#,
+        );
+        let state_graph = explore(&project, &CheckOptions::default()).unwrap();
+        let ranges = variable_ranges(&state_graph);
+
+        let variables: Vec<&str> = ranges.iter().map(|range| range.variable.as_str()).collect();
+        assert_eq!(variables, vec!["Counter", "Done", "Name"]);
+        assert_eq!(ranges[0].distinct_values, 6);
+        assert_eq!(ranges[0].int_bounds, Some((0, 5)));
+        assert_eq!(ranges[1].distinct_values, 2);
+        assert_eq!(ranges[1].int_bounds, None);
+        assert_eq!(ranges[2].distinct_values, 1);
+    }
+}
@@ -1,18 +1,21 @@
 use std::{
     cell::RefCell,
     collections::{BTreeMap, HashMap, HashSet},
+    ops::RangeInclusive,
     path::Path,
     rc::Rc,
 };
 
 use crate::{
     ast::{
+        block::Block,
         node::{InstructionBuilder, Node},
         statement::{
             expression::{
                 BracketContent, BracketExpression, Expression, LocalExpressionNode,
                 SideEffectExpression,
             },
+            on_exit::move_finalizer_to_end,
             Statement,
         },
         token::{
@@ -25,13 +28,16 @@ use crate::{
         stdlib::{self},
         CompilationContext, CompiledProject, CompilerState, FunctionDefinition, Variable,
     },
-    error::{AlthreadError, AlthreadResult, ErrorType},
+    error::{AlthreadError, AlthreadResult, ErrorType, Pos},
     module_resolver::{module_resolver::ModuleResolver, FileSystem},
     vm::instruction::{Instruction, InstructionType, ProgramCode},
 };
 
 use super::ltl;
 
+/// The maximum number of specialized copies of the programs with const parameters
+const MAX_SPECIALIZATIONS: usize = 1000;
+
 impl Ast {
     fn module_prefix(name: &str) -> &str {
         match name.rfind('.') {
@@ -46,6 +52,7 @@ impl Ast {
                 .global_block
                 .as_ref()
                 .map_or(true, |block| block.value.children.is_empty())
+            && self.shared_namespaces.is_empty()
             && self.function_blocks.is_empty()
             && self.import_block.is_none()
             && self.condition_blocks.is_empty()
@@ -63,6 +70,49 @@ impl Ast {
         }
     }
 
+    /// Qualifies the variables of the conditions of a module with its prefix. The conditions of
+    /// the same-level modules, which are qualified on their own, are dropped, as well as the
+    /// conditions that do not depend on any qualified variable.
+    fn qualify_conditions(
+        &self,
+        conditions: &[(HashSet<String>, Vec<String>, LocalExpressionNode, Pos)],
+        same_level_module_names: &[String],
+        module_prefix: &str,
+    ) -> Vec<(HashSet<String>, Vec<String>, LocalExpressionNode, Pos)> {
+        let mut qualified = Vec::new();
+        for (deps, read_vars, expr, pos) in conditions {
+            // skip if any dependency starts with any same-level module name
+            if same_level_module_names.iter().any(|mod_name| {
+                deps.iter()
+                    .any(|dep| dep.starts_with(&format!("{mod_name}.")))
+            }) {
+                log::debug!(
+                    "[{}] Skipping condition as it starts with a same-level module name",
+                    module_prefix
+                );
+                continue;
+            }
+
+            let updated_deps: HashSet<String> = deps
+                .iter()
+                .map(|dep| self.build_qualified_name(dep, module_prefix))
+                .collect();
+            let updated_read_vars: Vec<String> = read_vars
+                .iter()
+                .map(|var| self.build_qualified_name(var, module_prefix))
+                .collect();
+
+            qualified.push((updated_deps, updated_read_vars, expr.clone(), pos.clone()));
+        }
+
+        // remove conditions that are not qualified
+        if !module_prefix.is_empty() {
+            log::debug!("[{}] Removing unqualified conditions", module_prefix);
+            qualified.retain(|(deps, _read_vars, _expr, _pos)| deps.iter().any(|dep| dep.contains('.')));
+        }
+        qualified
+    }
+
     pub fn check_privacy_violations(&self, state: &CompilerState) -> AlthreadResult<()> {
         // check function and program calls inside programs
         for (prog_name, prog_code) in state.programs_code().iter() {
@@ -159,6 +209,161 @@ impl Ast {
         Ok(())
     }
 
+    /// Checks that the functions marked `@pure` do not write shared variables, use channels,
+    /// start programs or call functions that are not pure themselves.
+    ///
+    /// # Errors
+    /// If a pure function has a side effect
+    pub fn check_function_purity(&self, state: &CompilerState) -> AlthreadResult<()> {
+        for (func_name, func_def) in state.user_functions() {
+            if !func_def.is_pure {
+                continue;
+            }
+            for instruction in &func_def.body {
+                let impure_reason = match &instruction.control {
+                    InstructionType::GlobalAssignment { identifier, .. } => {
+                        Some(format!("writes the shared variable '{identifier}'"))
+                    }
+                    InstructionType::MethodCall {
+                        name,
+                        global_receiver: Some(identifier),
+                        ..
+                    } => {
+                        let mutates = state.global_table().get(identifier).is_none_or(|var| {
+                            state
+                                .stdlib()
+                                .interfaces(&var.datatype)
+                                .iter()
+                                .any(|i| &i.name == name && i.mutates_receiver)
+                        });
+                        if mutates {
+                            Some(format!("modifies the shared variable '{identifier}'"))
+                        } else {
+                            None
+                        }
+                    }
+                    InstructionType::Send { .. }
+                    | InstructionType::Broadcast { .. }
+                    | InstructionType::ChannelPeek(_)
+                    | InstructionType::ChannelPop(_)
+                    | InstructionType::Connect { .. } => Some("uses channels".to_string()),
+                    InstructionType::WaitStart { .. } => Some("waits".to_string()),
+                    InstructionType::RunCall { name, .. } => {
+                        Some(format!("starts the program '{name}'"))
+                    }
+                    InstructionType::Exit => Some("exits".to_string()),
+                    InstructionType::FnCall { name, .. } => state
+                        .user_functions()
+                        .get(name)
+                        .filter(|callee| !callee.is_pure)
+                        .map(|_| format!("calls the function '{name}' that is not @pure")),
+                    _ => None,
+                };
+                if let Some(reason) = impure_reason {
+                    return Err(AlthreadError::new(
+                        ErrorType::InstructionNotAllowed,
+                        instruction.pos.clone(),
+                        format!("Function '{func_name}' is marked @pure but {reason}"),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Only `@pure` user functions can be called inside a condition
+    fn check_condition_calls(
+        condition: &LocalExpressionNode,
+        state: &CompilerState,
+    ) -> AlthreadResult<()> {
+        let mut calls = Vec::new();
+        condition.collect_fn_calls(&mut calls);
+        for call in calls {
+            let name = call.value.fn_name_to_string();
+            if let Some(func_def) = state.user_functions().get(&name) {
+                if !func_def.is_pure {
+                    return Err(AlthreadError::new(
+                        ErrorType::InstructionNotAllowed,
+                        Some(call.pos.clone()),
+                        format!("Function '{name}' must be marked @pure to be used in a condition"),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Declares the variables of a `shared` block. The variables of a named block are
+    /// qualified with its name, like the variables of an imported module.
+    fn compile_shared_block(
+        global: &Node<Block>,
+        namespace: Option<&str>,
+        state: &mut CompilerState,
+    ) -> AlthreadResult<()> {
+        for node in &global.value.children {
+            match &node.value {
+                Statement::Declaration(decl) => {
+                    let available_globals = state.global_memory().clone();
+                    let available_table = state.global_table().clone();
+
+                    node.compile(state)?;
+
+                    let var_name = &decl.value.identifier.value.parts[0].value.value;
+                    let var_name = match namespace {
+                        Some(namespace) => format!("{namespace}.{var_name}"),
+                        None => var_name.clone(),
+                    };
+                    if namespace.is_some() && available_table.contains_key(&var_name) {
+                        return Err(AlthreadError::new(
+                            ErrorType::VariableError,
+                            Some(decl.value.identifier.pos.clone()),
+                            format!("Variable {var_name} already declared"),
+                        ));
+                    }
+
+                    // the variable is only visible through its qualified name
+                    let last_program_stack = state.program_stack.last_mut().unwrap();
+                    last_program_stack.name.clone_from(&var_name);
+                    let last_program_stack = last_program_stack.clone();
+
+                    // a variable initialized with `rand` has several initial values
+                    let choices = Self::evaluate_shared_rand_range(
+                        decl.value.value.as_ref(),
+                        &available_table,
+                        &available_globals,
+                    )?;
+                    // Use context instead of local global_table
+                    let literal = match choices {
+                        Some(range) => {
+                            let literal = Literal::Int(*range.start());
+                            state.initial_choices.insert(var_name.clone(), range);
+                            literal
+                        }
+                        None => Self::evaluate_shared_initializer(
+                            decl.value.value.as_ref(),
+                            &last_program_stack.datatype,
+                            &available_table,
+                            &available_globals,
+                        )?,
+                    };
+
+                    state
+                        .global_table_mut()
+                        .insert(var_name.clone(), last_program_stack);
+                    state.global_memory_mut().insert(var_name, literal);
+                }
+                _ => {
+                    return Err(AlthreadError::new(
+                        ErrorType::InstructionNotAllowed,
+                        Some(node.pos.clone()),
+                        "The 'shared' block can only contain declarations".to_string(),
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn build_shared_const_scope(
         global_table: &HashMap<String, Variable>,
         global_memory: &BTreeMap<String, Literal>,
@@ -324,6 +529,17 @@ impl Ast {
             SideEffectExpression::Bracket(node) => {
                 Self::evaluate_shared_bracket_expression(node, scope, memory)
             }
+            SideEffectExpression::Map(node) => {
+                let mut entries = BTreeMap::new();
+                let mut datatypes = (DataType::Void, DataType::Void);
+                for (key, value) in &node.value.entries {
+                    let key = Self::evaluate_shared_expression(key, scope, memory)?;
+                    let value = Self::evaluate_shared_expression(value, scope, memory)?;
+                    datatypes = (key.get_datatype(), value.get_datatype());
+                    entries.insert(key.map_key(), (key, value));
+                }
+                Ok(Literal::Map(datatypes.0, datatypes.1, entries))
+            }
             SideEffectExpression::FnCall(_) => Err(AlthreadError::new(
                 ErrorType::InstructionNotAllowed,
                 Some(expression.pos.clone()),
@@ -337,6 +553,34 @@ impl Ast {
         }
     }
 
+    /// The range of the initial values of a shared variable initialized with `rand(lo, hi)`
+    fn evaluate_shared_rand_range(
+        value: Option<&Node<SideEffectExpression>>,
+        global_table: &HashMap<String, Variable>,
+        global_memory: &BTreeMap<String, Literal>,
+    ) -> AlthreadResult<Option<RangeInclusive<i64>>> {
+        let Some(Node {
+            value: SideEffectExpression::FnCall(call),
+            pos,
+        }) = value
+        else {
+            return Ok(None);
+        };
+        if call.value.fn_name_to_string() != "rand" {
+            return Ok(None);
+        }
+        let (scope, memory) = Self::build_shared_const_scope(global_table, global_memory)?;
+        let args = Self::evaluate_shared_expression(&call.value.values, &scope, &memory)?;
+        match args.into_tuple().as_deref() {
+            Ok([Literal::Int(lo), Literal::Int(hi)]) if lo <= hi => Ok(Some(*lo..=*hi)),
+            _ => Err(AlthreadError::new(
+                ErrorType::ExpressionError,
+                Some(pos.clone()),
+                "rand expects two integers lo <= hi".to_string(),
+            )),
+        }
+    }
+
     fn evaluate_shared_initializer(
         value: Option<&Node<SideEffectExpression>>,
         datatype: &DataType,
@@ -354,6 +598,18 @@ impl Ast {
             (Literal::List(_, elements), DataType::List(element_type)) if elements.is_empty() => {
                 Ok(Literal::List((**element_type).clone(), elements))
             }
+            (Literal::List(_, elements), DataType::Array(element_type, _)) => {
+                Ok(Literal::Array((**element_type).clone(), elements))
+            }
+            (Literal::Map(_, _, entries), DataType::Map(key_type, value_type))
+                if entries.is_empty() =>
+            {
+                Ok(Literal::Map(
+                    (**key_type).clone(),
+                    (**value_type).clone(),
+                    entries,
+                ))
+            }
             (literal, _) => Ok(literal),
         }
     }
@@ -363,9 +619,29 @@ impl Ast {
         current_file_path: &Path,
         filesystem: F,
         input_map: &mut HashMap<String, String>,
+    ) -> AlthreadResult<CompiledProject> {
+        self.compile_with_stdlib(
+            current_file_path,
+            filesystem,
+            Rc::new(stdlib::Stdlib::new()),
+            input_map,
+        )
+    }
+
+    /// Compiles the project with a standard library that may contain functions registered by the
+    /// host program (see `Stdlib::register_function`)
+    ///
+    /// # Errors
+    /// If the project does not compile
+    pub fn compile_with_stdlib<F: FileSystem + Clone>(
+        &self,
+        current_file_path: &Path,
+        filesystem: F,
+        stdlib: Rc<stdlib::Stdlib>,
+        input_map: &mut HashMap<String, String>,
     ) -> AlthreadResult<CompiledProject> {
         // Create shared compilation context
-        let context = Rc::new(RefCell::new(CompilationContext::new()));
+        let context = Rc::new(RefCell::new(CompilationContext::with_stdlib(stdlib)));
 
         self.compile_with_context(
             current_file_path,
@@ -392,12 +668,15 @@ impl Ast {
                 global_memory: BTreeMap::new(),
                 program_arguments: HashMap::new(),
                 global_table: HashMap::new(),
+                initial_choices: BTreeMap::new(),
                 user_functions: HashMap::new(),
                 programs_code: HashMap::new(),
                 always_conditions: Vec::new(),
+                never_conditions: Vec::new(),
                 ltl_formulas: Vec::new(),
                 compiled_ltl_formulas: Vec::new(),
-                stdlib: Rc::new(stdlib::Stdlib::new()),
+                ignored_conditions: Vec::new(),
+                stdlib: context.borrow().stdlib.clone(),
                 program_debug_info: HashMap::new(),
             });
         }
@@ -473,9 +752,18 @@ impl Ast {
                 state
                     .always_conditions_mut()
                     .extend(compiled_module.always_conditions);
+                state
+                    .never_conditions_mut()
+                    .extend(compiled_module.never_conditions);
+                state
+                    .ignored_conditions
+                    .extend(compiled_module.ignored_conditions);
                 state
                     .global_memory_mut()
                     .extend(compiled_module.global_memory);
+                state
+                    .initial_choices
+                    .extend(compiled_module.initial_choices);
                 state
                     .program_arguments_mut()
                     .extend(compiled_module.program_arguments);
@@ -495,40 +783,10 @@ impl Ast {
         state.current_stack_depth = 1;
         state.is_shared = true;
         if let Some(global) = self.global_block.as_ref() {
-            for node in global.value.children.iter() {
-                match &node.value {
-                    Statement::Declaration(decl) => {
-                        let available_globals = state.global_memory().clone();
-                        let available_table = state.global_table().clone();
-
-                        node.compile(&mut state)?;
-
-                        let var_name = &decl.value.identifier.value.parts[0].value.value;
-                        // Use context instead of local global_table
-                        let last_program_stack = state.program_stack.last().unwrap().clone();
-                        let literal = Self::evaluate_shared_initializer(
-                            decl.value.value.as_ref(),
-                            &last_program_stack.datatype,
-                            &available_table,
-                            &available_globals,
-                        )?;
-
-                        state
-                            .global_table_mut()
-                            .insert(var_name.clone(), last_program_stack);
-                        state
-                            .global_memory_mut()
-                            .insert(var_name.clone(), literal);
-                    }
-                    _ => {
-                        return Err(AlthreadError::new(
-                            ErrorType::InstructionNotAllowed,
-                            Some(node.pos.clone()),
-                            "The 'shared' block can only contain declarations".to_string(),
-                        ))
-                    }
-                }
-            }
+            Self::compile_shared_block(global, None, &mut state)?;
+        }
+        for (namespace, global) in &self.shared_namespaces {
+            Self::compile_shared_block(global, Some(namespace), &mut state)?;
         }
 
         log::debug!(
@@ -545,7 +803,7 @@ impl Ast {
         // this creates FunctionDefinitions without the compiled body, so that
         // compilation can be done no matter the order of the functions
         // or if they are recursive
-        for (func_name, (args_list, return_datatype, func_block, is_private)) in
+        for (func_name, (args_list, return_datatype, func_block, is_private, is_pure)) in
             &self.function_blocks
         {
             // check if the function is already defined
@@ -572,6 +830,7 @@ impl Ast {
                 body: Vec::new(),
                 pos: func_block.pos.clone(),
                 is_private: *is_private,
+                is_pure: *is_pure,
             };
 
             if let Err(e) = Ast::check_function_returns(&func_name, func_block, return_datatype) {
@@ -604,6 +863,23 @@ impl Ast {
 
         // Update context instead of state
         state.program_arguments_mut().extend(program_args);
+        for (name, (args, _, _)) in &self.process_blocks {
+            if !args.value.constants.contains(&true) {
+                continue;
+            }
+            if !module_prefix.is_empty() {
+                return Err(AlthreadError::new(
+                    ErrorType::NotImplemented,
+                    Some(args.pos.clone()),
+                    format!(
+                        "The program '{name}' of the module '{module_prefix}' has const parameters, which are only supported in the main module"
+                    ),
+                ));
+            }
+            state
+                .program_constants
+                .insert(name.clone(), args.value.constants.clone());
+        }
 
         // Compile all the programs
         state.is_shared = false;
@@ -625,22 +901,44 @@ impl Ast {
             }
 
             if module_prefix.is_empty() {
-                let code = self.compile_program("main", &mut state, module_prefix)?;
+                let code = self.compile_program("main", &mut state, module_prefix, &[])?;
                 state.programs_code_mut().insert("main".to_string(), code);
                 assert!(state.current_stack_depth == 0);
             }
         }
 
         for name in self.process_blocks.keys() {
-            if name == "main" {
+            if name == "main" || state.program_constants.contains_key(name) {
                 continue;
             }
 
-            let code = self.compile_program(name, &mut state, module_prefix)?;
+            let code = self.compile_program(name, &mut state, module_prefix, &[])?;
             state.programs_code_mut().insert(name.clone(), code);
             assert!(state.current_stack_depth == 0);
         }
 
+        // The programs with const parameters are compiled once for each value of these
+        // parameters they are run with, which can request more specializations
+        while let Some((code_name, (name, constants))) = state
+            .specializations
+            .iter()
+            .find(|(code_name, _)| !state.programs_code.contains_key(*code_name))
+            .map(|(code_name, specialization)| (code_name.clone(), specialization.clone()))
+        {
+            if state.specializations.len() > MAX_SPECIALIZATIONS {
+                return Err(AlthreadError::new(
+                    ErrorType::NotImplemented,
+                    Some(self.process_blocks[&name].0.pos.clone()),
+                    format!(
+                        "More than {MAX_SPECIALIZATIONS} specializations of the programs with const parameters are run, is '{name}' run recursively with ever-changing constants?"
+                    ),
+                ));
+            }
+            let code = self.compile_program(&name, &mut state, module_prefix, &constants)?;
+            state.programs_code_mut().insert(code_name, code);
+            assert!(state.current_stack_depth == 0);
+        }
+
         // check if all the channels used have been declared
         for (channel_name, (_, pos)) in state.undefined_channels().iter() {
             return Err(AlthreadError::new(
@@ -670,6 +968,7 @@ impl Ast {
                             ))),
                             depth: 0,
                             declare_pos: None,
+                            constant: None,
                         },
                     );
                 }
@@ -684,6 +983,7 @@ impl Ast {
                             ))),
                             depth: 0,
                             declare_pos: None,
+                            constant: None,
                         },
                     );
                 }
@@ -692,8 +992,37 @@ impl Ast {
 
         state.in_condition_block = true;
         for (name, condition_block) in self.condition_blocks.iter() {
+            state.ignored_conditions.extend(
+                condition_block
+                    .value
+                    .ignored
+                    .iter()
+                    .map(|condition| condition.pos.clone()),
+            );
+            for variable in &condition_block.value.mutexes {
+                if *name == ConditionKeyword::Never {
+                    return Err(AlthreadError::new(
+                        ErrorType::InstructionNotAllowed,
+                        Some(variable.pos.clone()),
+                        "mutex(...) can only be used in an always block".to_string(),
+                    ));
+                }
+                let var_name = variable.value.to_string();
+                match state.global_table().get(&var_name) {
+                    Some(var) if var.datatype == DataType::Boolean => {}
+                    _ => {
+                        return Err(AlthreadError::new(
+                            ErrorType::TypeError,
+                            Some(variable.pos.clone()),
+                            format!(
+                            "mutex(...) expects boolean shared variables, '{var_name}' is not one"
+                        ),
+                        ))
+                    }
+                }
+            }
             match name {
-                ConditionKeyword::Always => {
+                ConditionKeyword::Always | ConditionKeyword::Never => {
                     for condition in condition_block.value.children.iter() {
                         let compiled = condition.compile(&mut state)?.instructions;
                         if compiled.len() == 1 {
@@ -713,12 +1042,21 @@ impl Ast {
                         if let InstructionType::GlobalReads { variables, .. } = &compiled[0].control
                         {
                             if let InstructionType::Expression(exp) = &compiled[1].control {
-                                state.always_conditions_mut().push((
+                                Ast::check_condition_calls(exp, &state)?;
+                                let compiled_condition = (
                                     variables.iter().map(|s| s.clone()).collect(),
                                     variables.clone(),
                                     exp.clone(),
                                     condition.pos.clone(),
-                                ));
+                                );
+                                match name {
+                                    ConditionKeyword::Always => {
+                                        state.always_conditions_mut().push(compiled_condition);
+                                    }
+                                    ConditionKeyword::Never => {
+                                        state.never_conditions_mut().push(compiled_condition);
+                                    }
+                                }
                             } else {
                                 return Err(AlthreadError::new(
                                     ErrorType::InstructionNotAllowed,
@@ -735,9 +1073,6 @@ impl Ast {
                         }
                     }
                 }
-                ConditionKeyword::Never => {
-                    // 'never' blocks are not yet implemented, but reserved for future use.
-                }
             }
         }
         state.in_condition_block = false;
@@ -746,10 +1081,13 @@ impl Ast {
             for formula in &check_block.value.formulas {
                 state.ltl_formulas_mut().push(formula.clone());
             }
+            state
+                .ignored_conditions
+                .extend(check_block.value.ignored.iter().cloned());
         }
 
         // now compile the function bodies
-        for (func_name, (args_list, return_datatype, func_block, is_private)) in
+        for (func_name, (args_list, return_datatype, func_block, is_private, is_pure)) in
             &self.function_blocks
         {
             state.in_function = true;
@@ -768,6 +1106,7 @@ impl Ast {
                         mutable: true,
                         datatype: dt.value.clone(),
                         declare_pos: Some(id.pos.clone()),
+                        constant: None,
                     });
                     (id.value.clone(), dt.value.clone())
                 })
@@ -785,7 +1124,9 @@ impl Ast {
                 // check if it has a return instruction as the last instruction
                 match compiled_body.instructions.last() {
                     Some(last_instruction) => {
-                        if let InstructionType::Return { has_value: false } =
+                        if let InstructionType::Return {
+                            has_value: false, ..
+                        } =
                             &last_instruction.control
                         {
                             has_return = true;
@@ -796,7 +1137,10 @@ impl Ast {
                 // if it does not have a return instruction, add one
                 if !has_return {
                     compiled_body.instructions.push(Instruction {
-                        control: InstructionType::Return { has_value: false },
+                        control: InstructionType::Return {
+                            has_value: false,
+                            stop_atomic: false,
+                        },
                         pos: Some(func_block.pos.clone()),
                     });
                 }
@@ -811,6 +1155,7 @@ impl Ast {
                     body: compiled_body.instructions,
                     pos: func_block.pos.clone(),
                     is_private: *is_private,
+                    is_pure: *is_pure,
                 },
             );
 
@@ -874,7 +1219,12 @@ impl Ast {
                     InstructionType::FnCall {
                         name: call_name, ..
                     } => {
-                        if call_name == "print" || call_name == "assert" {
+                        if call_name == "print"
+                            || call_name == "assert"
+                            || call_name == "assume"
+                            || call_name == "rand"
+                            || state.stdlib().is_function(call_name)
+                        {
                             // do not qualify standard library function calls
                             continue;
                         }
@@ -909,6 +1259,14 @@ impl Ast {
                     } => {
                         *call_name = self.build_qualified_name(call_name, module_prefix);
                     }
+                    InstructionType::WaitStart { dependencies, .. } => {
+                        let updated_vars: std::collections::HashSet<String> = dependencies
+                            .variables
+                            .iter()
+                            .map(|dep| self.build_qualified_name(dep, module_prefix))
+                            .collect();
+                        dependencies.variables = updated_vars;
+                    }
                     _ => {}
                 }
             }
@@ -920,6 +1278,7 @@ impl Ast {
                 body: old_func_def.body.clone(),
                 pos: old_func_def.pos.clone(),
                 is_private: old_func_def.is_private,
+                is_pure: old_func_def.is_pure,
             };
 
             // println!("[{}] New function definition: {:?}", module_prefix, func_def);
@@ -993,6 +1352,11 @@ impl Ast {
                 .global_memory_mut()
                 .insert(qualified_var_name.clone(), value);
             state.global_memory_mut().remove(&var_name);
+            if let Some(range) = state.initial_choices.remove(&var_name) {
+                state
+                    .initial_choices
+                    .insert(qualified_var_name.clone(), range);
+            }
 
             if let Some(var_meta) = var_meta {
                 let mut var_meta_cloned = var_meta.clone();
@@ -1010,52 +1374,19 @@ impl Ast {
             state.global_memory().keys()
         );
 
-        log::debug!("[{}] Qualifying always conditions", module_prefix);
-        // Collect conditions into a temporary vector to avoid borrow conflicts
-        let always_conditions: Vec<_> = state.always_conditions().clone();
-        let mut new_always_conditions = Vec::new();
-        for condition in always_conditions.iter() {
-            // skip if any dependency starts with any same-level module name
-            if same_level_module_names.iter().any(|mod_name| {
-                condition
-                    .0
-                    .iter()
-                    .any(|dep| dep.starts_with(&format!("{}.", mod_name)))
-            }) {
-                log::debug!(
-                    "[{}] Skipping condition as it starts with a same-level module name",
-                    module_prefix
-                );
-                continue;
-            }
-
-            let (deps, read_vars, expr, pos) = condition;
-
-            let updated_deps: HashSet<String> = deps
-                .iter()
-                .map(|dep| self.build_qualified_name(dep, module_prefix))
-                .collect();
-            let updated_read_vars: Vec<String> = read_vars
-                .iter()
-                .map(|var| self.build_qualified_name(var, module_prefix))
-                .collect();
-
-            new_always_conditions.push((
-                updated_deps,
-                updated_read_vars,
-                expr.clone(),
-                pos.clone(),
-            ));
-        }
-        *state.always_conditions_mut() = new_always_conditions;
-
-        // remove conditions that are not qualified
-        if !module_prefix.is_empty() {
-            log::debug!("[{}] Removing unqualified conditions", module_prefix);
-            state
-                .always_conditions_mut()
-                .retain(|(deps, _read_vars, _expr, _pos)| deps.iter().any(|dep| dep.contains('.')));
-        }
+        log::debug!("[{}] Qualifying always and never conditions", module_prefix);
+        let always_conditions = self.qualify_conditions(
+            state.always_conditions(),
+            &same_level_module_names,
+            module_prefix,
+        );
+        *state.always_conditions_mut() = always_conditions;
+        let never_conditions = self.qualify_conditions(
+            state.never_conditions(),
+            &same_level_module_names,
+            module_prefix,
+        );
+        *state.never_conditions_mut() = never_conditions;
 
         log::debug!("[{}] Qualifying programs", module_prefix);
         // Collect programs to update first to avoid borrow conflicts
@@ -1118,14 +1449,20 @@ impl Ast {
                     .insert(qualified_prog_name.clone(), prog_args);
             }
 
-            prog_code.name = qualified_prog_name.clone();
+            // the code of a specialized program is named after the program, not its specialization
+            prog_code.name = self.build_qualified_name(&prog_code.name, module_prefix);
 
             for instruction in &mut prog_code.instructions {
                 match &mut instruction.control {
                     InstructionType::FnCall {
                         name: call_name, ..
                     } => {
-                        if call_name == "print" || call_name == "assert" {
+                        if call_name == "print"
+                            || call_name == "assert"
+                            || call_name == "assume"
+                            || call_name == "rand"
+                            || state.stdlib().is_function(call_name)
+                        {
                             // do not qualify standard library function calls
                             continue;
                         }
@@ -1208,6 +1545,7 @@ impl Ast {
         }
 
         self.check_privacy_violations(&state)?;
+        self.check_function_purity(&state)?;
 
         // Return using context data instead of local variables
         Ok(CompiledProject {
@@ -1215,20 +1553,25 @@ impl Ast {
             program_arguments: state.program_arguments().clone(),
             user_functions: state.user_functions().clone(),
             global_table: state.global_table().clone(),
+            initial_choices: state.initial_choices.clone(),
             programs_code: state.programs_code().clone(),
             always_conditions: state.always_conditions().clone(),
+            never_conditions: state.never_conditions().clone(),
             ltl_formulas: state.ltl_formulas().clone(),
             compiled_ltl_formulas: ltl::compile_ltl_formulas(state.ltl_formulas(), &state)?,
+            ignored_conditions: state.ignored_conditions.clone(),
             stdlib: state.stdlib().clone(),
             program_debug_info: state.program_debug_info.clone(),
         })
     }
 
+    /// Compiles a program, with the values `constants` of its const parameters folded in
     fn compile_program(
         &self,
         name: &str,
         state: &mut CompilerState,
         module_prefix: &str,
+        constants: &[Literal],
     ) -> AlthreadResult<ProgramCode> {
         let mut process_code = ProgramCode {
             instructions: Vec::new(),
@@ -1246,9 +1589,31 @@ impl Ast {
         // Capture argument names for debug info
         let mut argument_names = Vec::new();
         let mut debug_variables = Vec::new();
-        
+
+        // the const parameters are at the bottom of the stack, where they take no memory since
+        // they are never read at runtime
+        let stack_base = state.program_stack.len();
+        let mut constants = constants.iter();
         for (i, var) in args.value.identifiers.iter().enumerate() {
+            if args.value.constants[i] {
+                state.program_stack.push(Variable {
+                    name: var.value.value.clone(),
+                    depth: state.current_stack_depth,
+                    mutable: false,
+                    datatype: args.value.datatypes[i].value.clone(),
+                    declare_pos: Some(var.pos.clone()),
+                    constant: constants.next().cloned(),
+                });
+            }
+        }
+        let constant_count = state.program_stack.len() - stack_base;
+
+        for (i, var) in args.value.identifiers.iter().enumerate() {
+            if args.value.constants[i] {
+                continue;
+            }
             let var_name = var.value.value.clone();
+            let stack_index = argument_names.len();
             argument_names.push(var_name.clone());
             
             state.program_stack.push(Variable {
@@ -1257,19 +1622,21 @@ impl Ast {
                 mutable: true,
                 datatype: args.value.datatypes[i].value.clone(),
                 declare_pos: Some(var.pos.clone()),
+                constant: None,
             });
             
             // Add debug variable for program arguments (available from the start)
             debug_variables.push(crate::compiler::LocalVariableDebugInfo {
                 name: var_name,
                 datatype: args.value.datatypes[i].value.clone(),
-                stack_index: i,
+                stack_index,
                 scope_start_ip: 0,
                 scope_end_ip: None,
                 declare_pos: Some(var.pos.clone()),
             });
         }
 
+        let prog = move_finalizer_to_end(prog)?;
         let compiled = prog.compile(state).map_err(|mut e| {
             e.push_stack(prog.pos.clone());
             e
@@ -1278,10 +1645,15 @@ impl Ast {
             unimplemented!("breaks or return statements in programs are not yet implemented");
         }
         
+        state.program_stack.truncate(stack_base);
+
         // Collect debug variables from the compiled builder
-        debug_variables.extend(compiled.debug_variables);
+        debug_variables.extend(compiled.debug_variables.into_iter().map(|mut debug_variable| {
+            debug_variable.stack_index -= constant_count;
+            debug_variable
+        }));
         
-        if !args.value.identifiers.is_empty() {
+        if !argument_names.is_empty() {
             process_code.instructions.push(Instruction {
                 control: InstructionType::Destruct,
                 pos: Some(args.pos.clone()),
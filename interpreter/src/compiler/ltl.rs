@@ -113,6 +113,7 @@ fn compile_ltl_node(
                 datatype: element_type,
                 depth: 0,
                 declare_pos: None,
+                constant: None,
             });
 
             // Compile body
@@ -1,6 +1,7 @@
 use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
+use std::ops::RangeInclusive;
 use std::rc::Rc;
 
 pub mod compiler;
@@ -26,6 +27,9 @@ pub struct FunctionDefinition {
     pub body: Vec<Instruction>,
     pub pos: Pos,
     pub is_private: bool,
+    /// Pure functions (`@pure fn`) do not write shared variables, use channels or start
+    /// programs, so they can be evaluated by the checker inside conditions
+    pub is_pure: bool,
 }
 
 #[derive(Debug)]
@@ -134,6 +138,9 @@ pub struct Variable {
     pub datatype: DataType,
     pub depth: usize,
     pub declare_pos: Option<Pos>,
+    /// The value of a `const` parameter of the program being compiled, folded into the
+    /// expressions instead of being read from the memory
+    pub constant: Option<Literal>,
 }
 
 /// Debug information for a local variable
@@ -165,8 +172,13 @@ pub struct CompilationContext {
 
 impl CompilationContext {
     pub fn new() -> Self {
+        Self::with_stdlib(Rc::new(stdlib::Stdlib::new()))
+    }
+
+    /// A context whose standard library may contain functions registered by the host program
+    pub fn with_stdlib(stdlib: Rc<stdlib::Stdlib>) -> Self {
         Self {
-            stdlib: Rc::new(stdlib::Stdlib::new()),
+            stdlib,
             channels: HashMap::new(),
             undefined_channels: HashMap::new(),
         }
@@ -191,14 +203,25 @@ pub struct CompilerState {
 
     // add always and eventually conditions
     pub always_conditions: Vec<(HashSet<String>, Vec<String>, LocalExpressionNode, Pos)>,
+    pub never_conditions: Vec<(HashSet<String>, Vec<String>, LocalExpressionNode, Pos)>,
 
     pub ltl_formulas: Vec<LtlExpression>,
 
+    /// The conditions and formulas annotated with `@ignore`
+    pub ignored_conditions: Vec<Pos>,
+
     pub user_functions: HashMap<String, FunctionDefinition>,
     pub global_table: HashMap<String, Variable>,
     pub program_arguments: HashMap<String, (Vec<DataType>, bool)>,
+    /// Whether each parameter of the programs is `const`
+    pub program_constants: HashMap<String, Vec<bool>>,
+    /// The specialized copies of the programs requested by the `run` calls, by name of their
+    /// code, with the program and the values of its `const` parameters
+    pub specializations: BTreeMap<String, (String, Vec<Literal>)>,
     pub programs_code: HashMap<String, ProgramCode>,
     pub global_memory: BTreeMap<String, Literal>,
+    /// The shared variables initialized with `rand(lo, hi)`, with the range of their initial value
+    pub initial_choices: BTreeMap<String, RangeInclusive<i64>>,
     
     /// Debug information for local variables being tracked during compilation
     pub debug_variables: Vec<LocalVariableDebugInfo>,
@@ -222,9 +245,14 @@ impl CompilerState {
             user_functions: HashMap::new(),
             global_table: HashMap::new(),
             program_arguments: HashMap::new(),
+            program_constants: HashMap::new(),
+            specializations: BTreeMap::new(),
             global_memory: BTreeMap::new(),
+            initial_choices: BTreeMap::new(),
             always_conditions: Vec::new(),
+            never_conditions: Vec::new(),
             ltl_formulas: Vec::new(),
+            ignored_conditions: Vec::new(),
             programs_code: HashMap::new(),
             debug_variables: Vec::new(),
             program_debug_info: HashMap::new(),
@@ -305,6 +333,19 @@ impl CompilerState {
         &mut self.always_conditions
     }
 
+    #[must_use]
+    pub fn never_conditions(
+        &self,
+    ) -> &Vec<(HashSet<String>, Vec<String>, LocalExpressionNode, Pos)> {
+        &self.never_conditions
+    }
+
+    pub fn never_conditions_mut(
+        &mut self,
+    ) -> &mut Vec<(HashSet<String>, Vec<String>, LocalExpressionNode, Pos)> {
+        &mut self.never_conditions
+    }
+
     pub fn ltl_formulas(&self) -> &Vec<LtlExpression> {
         &self.ltl_formulas
     }
@@ -316,6 +357,16 @@ impl CompilerState {
         &self.programs_code
     }
 
+    /// The code of a program, or of one of its specializations if it has `const` parameters
+    #[must_use]
+    pub fn program_code(&self, program_name: &str) -> Option<&ProgramCode> {
+        self.programs_code.get(program_name).or_else(|| {
+            self.programs_code
+                .values()
+                .find(|code| code.name == program_name)
+        })
+    }
+
     pub fn programs_code_mut(&mut self) -> &mut HashMap<String, ProgramCode> {
         &mut self.programs_code
     }
@@ -370,14 +421,21 @@ pub struct CompiledProject {
     pub user_functions: HashMap<String, FunctionDefinition>,
     pub global_memory: BTreeMap<String, Literal>,
     pub global_table: HashMap<String, Variable>,
+    /// The shared variables initialized with `rand(lo, hi)`, with the range of their initial
+    /// value. Their value in `global_memory` is the lower bound.
+    pub initial_choices: BTreeMap<String, RangeInclusive<i64>>,
 
     /// The conditions that should always be true
     /// The first element is the variables that are used in the condition
     /// The second element is the two instructions that are used to check the condition
     /// (the first in struction is the read operation and the second is the expression)
     pub always_conditions: Vec<(HashSet<String>, Vec<String>, LocalExpressionNode, Pos)>,
+    /// The conditions that should never be true, in the same layout as `always_conditions`
+    pub never_conditions: Vec<(HashSet<String>, Vec<String>, LocalExpressionNode, Pos)>,
     pub ltl_formulas: Vec<LtlExpression>,
     pub compiled_ltl_formulas: Vec<CompiledLtlExpression>,
+    /// The conditions and formulas annotated with `@ignore`, which are not checked
+    pub ignored_conditions: Vec<Pos>,
 
     pub stdlib: Rc<stdlib::Stdlib>,
     
@@ -427,9 +485,12 @@ impl CompiledProject {
             user_functions: HashMap::new(),
             global_memory: BTreeMap::new(),
             global_table: HashMap::new(),
+            initial_choices: BTreeMap::new(),
             always_conditions: Vec::new(),
+            never_conditions: Vec::new(),
             ltl_formulas: Vec::new(),
             compiled_ltl_formulas: Vec::new(),
+            ignored_conditions: Vec::new(),
             stdlib: Rc::new(stdlib::Stdlib::new()),
             program_debug_info: HashMap::new(),
         }
@@ -65,7 +65,7 @@ impl Ast {
         match statement {
             Statement::ChannelDeclaration(channel_decl) => {
                 self.register_channel_declaration(
-                    &channel_decl.value,
+                    channel_decl,
                     state,
                     module_prefix,
                     var_to_program,
@@ -103,6 +103,14 @@ impl Ast {
                     var_to_program,
                 )?;
             }
+            Statement::Repeat(repeat_statement) => {
+                self.extract_channel_declarations_from_block(
+                    &repeat_statement.value.then_block.value,
+                    state,
+                    module_prefix,
+                    var_to_program,
+                )?;
+            }
             Statement::Loop(loop_statement) => {
                 self.extract_channel_declarations_from_statement(
                     &loop_statement.value.statement.value,
@@ -118,11 +126,14 @@ impl Ast {
 
     fn register_channel_declaration(
         &self,
-        channel_decl: &ChannelDeclaration,
+        channel_decl: &Node<ChannelDeclaration>,
         state: &mut CompilerState,
         module_prefix: &str,
         var_to_program: &HashMap<String, String>,
     ) -> AlthreadResult<()> {
+        let pos = channel_decl.pos.clone();
+        let channel_decl = &channel_decl.value;
+
         // Resolve program names for both sides of the channel
         let left_prog =
             self.prescan_get_prog_name(&channel_decl.ch_left_prog, module_prefix, var_to_program)?;
@@ -134,7 +145,6 @@ impl Ast {
         let right_key = (right_prog, channel_decl.ch_right_name.clone());
 
         // Register the channel types - both sides get the same datatype info
-        let pos = crate::error::Pos::default(); // We don't have position info during prescan
         state.channels_mut().insert(
             left_key.clone(),
             (channel_decl.datatypes.clone(), pos.clone()),
@@ -213,7 +223,7 @@ impl Ast {
             )?;
         }
         // Scan all function blocks
-        for (function_name, (_, _, function_block, _)) in &self.function_blocks {
+        for (function_name, (_, _, function_block, _, _)) in &self.function_blocks {
             self.scan_block_for_run_statements(
                 &function_block.value,
                 var_to_program,
@@ -360,6 +370,14 @@ impl Ast {
                     current_program,
                 )?;
             }
+            Statement::Repeat(repeat_statement) => {
+                self.scan_block_for_run_statements(
+                    &repeat_statement.value.then_block.value,
+                    var_to_program,
+                    process_lists,
+                    current_program,
+                )?;
+            }
             Statement::Loop(loop_statement) => {
                 self.scan_statement_for_run_statements(
                     &loop_statement.value.statement.value,
@@ -448,7 +466,7 @@ impl Ast {
         }
 
         // Scan ALL function blocks for channel declarations
-        for (function_name, (_, _, function_block, _)) in &self.function_blocks {
+        for (function_name, (_, _, function_block, _, _)) in &self.function_blocks {
             log::debug!(
                 "Scanning function '{}' for channel declarations",
                 function_name
@@ -7,9 +7,26 @@ use std::{
 
 use crate::{
     ast::token::{datatype::DataType, literal::Literal},
+    compiler::FunctionDefinition,
     error::{AlthreadError, ErrorType, Pos},
 };
 
+/// Methods of `list(t)` taking the name of a user function that is applied to every element.
+/// They are not regular interfaces since running user code needs the interpreter.
+pub const LIST_FUNCTION_METHODS: [&str; 2] = ["map", "filter"];
+
+/// Methods of `[t; n]` taking an index as first argument, which is checked at compile time when
+/// it is a constant
+pub const ARRAY_INDEX_METHODS: [&str; 2] = ["at", "set"];
+
+/// A pure operation on two integers, e.g. `min` or `add_wrapping`
+type IntOperation = fn(i64, i64) -> i64;
+
+/// The integer literal of a length or of an index
+fn int_literal(value: usize) -> Literal {
+    Literal::Int(i64::try_from(value).expect("a collection has fewer than i64::MAX elements"))
+}
+
 #[derive(Clone)]
 pub struct Interface {
     pub name: String,
@@ -23,6 +40,8 @@ pub struct Interface {
 #[derive(Debug)]
 pub struct Stdlib {
     pub interfaces: RefCell<HashMap<DataType, Vec<Interface>>>,
+    /// The native functions registered by the host program, callable like the builtins
+    pub functions: RefCell<HashMap<String, Interface>>,
 }
 
 impl Debug for Interface {
@@ -37,9 +56,84 @@ impl Debug for Interface {
 
 impl Stdlib {
     pub fn new() -> Self {
-        Self {
+        let stdlib = Self {
             interfaces: RefCell::new(HashMap::new()),
+            functions: RefCell::new(HashMap::new()),
+        };
+        stdlib.register_math_functions();
+        stdlib
+    }
+
+    /// The pure integer builtins `min(a, b)`, `max(a, b)` and `abs(a)`
+    fn register_math_functions(&self) {
+        let binary: [(&str, IntOperation); 2] = [("min", i64::min), ("max", i64::max)];
+        for (name, op) in binary {
+            self.register_function(
+                name,
+                vec![DataType::Integer, DataType::Integer],
+                DataType::Integer,
+                move |args, pos| match args {
+                    [Literal::Int(a), Literal::Int(b)] => Ok(Literal::Int(op(*a, *b))),
+                    _ => Err(AlthreadError::new(
+                        ErrorType::RuntimeError,
+                        pos,
+                        format!("{name}() expects two integers: {name}(a, b)"),
+                    )),
+                },
+            );
         }
+        self.register_function(
+            "abs",
+            vec![DataType::Integer],
+            DataType::Integer,
+            |args, pos| match args {
+                [Literal::Int(a)] => a.checked_abs().map(Literal::Int).ok_or_else(|| {
+                    AlthreadError::new(
+                        ErrorType::RuntimeError,
+                        pos.clone(),
+                        format!("abs({a}) overflows"),
+                    )
+                }),
+                _ => Err(AlthreadError::new(
+                    ErrorType::RuntimeError,
+                    pos,
+                    "abs() expects one integer: abs(a)".to_string(),
+                )),
+            },
+        );
+    }
+
+    /// Registers a native function that the models can call as `name(args...)`. The closure
+    /// receives the values of the arguments, whose types are checked at compile time against
+    /// `args`. The builtins (`print`, `assert`, ...) and the functions declared in the models take
+    /// precedence over a registered function of the same name.
+    pub fn register_function(
+        &self,
+        name: &str,
+        args: Vec<DataType>,
+        ret: DataType,
+        f: impl Fn(&[Literal], Option<Pos>) -> Result<Literal, AlthreadError> + 'static,
+    ) {
+        self.functions.borrow_mut().insert(
+            name.to_string(),
+            Interface {
+                name: name.to_string(),
+                args,
+                ret,
+                mutates_receiver: false,
+                f: Rc::new(move |_receiver, args, pos| {
+                    f(args.to_tuple().map_or(&[][..], Vec::as_slice), pos)
+                }),
+            },
+        );
+    }
+
+    pub fn get_function(&self, name: &str) -> Option<Interface> {
+        self.functions.borrow().get(name).cloned()
+    }
+
+    pub fn is_function(&self, name: &str) -> bool {
+        self.functions.borrow().contains_key(name)
     }
 
     pub fn get_interfaces(&self, dtype: &DataType) -> Option<Vec<Interface>> {
@@ -61,6 +155,33 @@ impl Stdlib {
 
         let mut new_interfaces = vec![];
 
+        // structural equality, available on every type (including the values `==` cannot compare)
+        new_interfaces.push(Interface {
+            name: "equals".to_string(),
+            args: vec![dtype.clone()],
+            ret: DataType::Boolean,
+            mutates_receiver: false,
+            f: Rc::new(|value, v, pos| {
+                let args = v.to_tuple().unwrap();
+                if args.len() != 1 {
+                    return Err(AlthreadError::new(
+                        ErrorType::RuntimeError,
+                        pos,
+                        ".equals() expects one argument: a.equals(b);".to_string(),
+                    ));
+                }
+                Ok(Literal::Bool(*value == args[0]))
+            }),
+        });
+        // textual representation, the same as the one printed by `print`
+        new_interfaces.push(Interface {
+            name: "to_string".to_string(),
+            args: vec![],
+            ret: DataType::String,
+            mutates_receiver: false,
+            f: Rc::new(|value, _v, _pos| Ok(Literal::String(value.to_string()))),
+        });
+
         match dtype.clone() {
             DataType::List(t) => {
                 new_interfaces.push(Interface {
@@ -155,6 +276,70 @@ impl Stdlib {
                         }
                     }),
                 });
+                new_interfaces.push(Interface {
+                    name: "pop".to_string(),
+                    args: vec![],
+                    ret: t.as_ref().clone(),
+                    mutates_receiver: true,
+                    f: Rc::new(|list, _v, pos| match list {
+                        Literal::List(_, list) => list.pop().ok_or_else(|| {
+                            AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                ".pop() cannot be called on an empty list".to_string(),
+                            )
+                        }),
+                        _ => Err(AlthreadError::new(
+                            ErrorType::RuntimeError,
+                            pos,
+                            "Expected List".to_string(),
+                        )),
+                    }),
+                });
+                new_interfaces.push(Interface {
+                    name: "insert".to_string(),
+                    args: vec![DataType::Integer, t.as_ref().clone()],
+                    ret: DataType::Void,
+                    mutates_receiver: true,
+                    f: Rc::new(|list, v, pos| {
+                        let args = v.to_tuple().unwrap();
+                        if args.len() != 2 {
+                            return Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                ".insert() expects two arguments: l.insert(index, value);".to_string(),
+                            ));
+                        }
+                        let idx = args[0].to_integer().unwrap();
+                        if let Literal::List(dtype, list) = list {
+                            // inserting at the length appends the value
+                            let Some(index) =
+                                usize::try_from(idx).ok().filter(|index| *index <= list.len())
+                            else {
+                                return Err(AlthreadError::new(
+                                    ErrorType::RuntimeError,
+                                    pos,
+                                    format!("Index out of bounds: {idx}"),
+                                ));
+                            };
+                            if dtype != &args[1].get_datatype() {
+                                return Err(AlthreadError::new(
+                                    ErrorType::RuntimeError,
+                                    pos,
+                                    format!("List of type {} can only accept values of the same type ({} given)", dtype, args[1].get_datatype())
+                                ));
+                            }
+                            list.insert(index, args[1].clone());
+                            Ok(Literal::Null)
+                        } else {
+                            Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                "Expected List".to_string(),
+                            ))
+                        }
+                    }),
+                });
                 new_interfaces.push(Interface {
                     name: "set".to_string(),
                     args: vec![DataType::Integer, t.as_ref().clone()],
@@ -223,6 +408,580 @@ impl Stdlib {
                         }
                     }),
                 });
+                // removes the consecutive duplicates, with the structural equality of `equals`
+                new_interfaces.push(Interface {
+                    name: "dedup".to_string(),
+                    args: vec![],
+                    ret: DataType::Void,
+                    mutates_receiver: true,
+                    f: Rc::new(|list, _v, pos| match list {
+                        Literal::List(_, list) => {
+                            list.dedup();
+                            Ok(Literal::Null)
+                        }
+                        _ => Err(AlthreadError::new(
+                            ErrorType::RuntimeError,
+                            pos,
+                            "Expected List".to_string(),
+                        )),
+                    }),
+                });
+                new_interfaces.push(Interface {
+                    name: "count".to_string(),
+                    args: vec![t.as_ref().clone()],
+                    ret: DataType::Integer,
+                    mutates_receiver: false,
+                    f: Rc::new(|list, v, pos| {
+                        let args = v.to_tuple().unwrap();
+                        if args.len() != 1 {
+                            return Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                ".count() expects one argument: l.count(value);".to_string(),
+                            ));
+                        }
+                        match list {
+                            Literal::List(_, list) => Ok(int_literal(
+                                list.iter().filter(|value| **value == args[0]).count(),
+                            )),
+                            _ => Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                "Expected List".to_string(),
+                            )),
+                        }
+                    }),
+                });
+                new_interfaces.push(Interface {
+                    name: "contains".to_string(),
+                    args: vec![t.as_ref().clone()],
+                    ret: DataType::Boolean,
+                    mutates_receiver: false,
+                    f: Rc::new(|list, v, pos| {
+                        let args = v.to_tuple().unwrap();
+                        if args.len() != 1 {
+                            return Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                ".contains() expects one argument: l.contains(value);".to_string(),
+                            ));
+                        }
+                        match list {
+                            Literal::List(dtype, _) if dtype != &args[0].get_datatype() => {
+                                Err(AlthreadError::new(
+                                    ErrorType::RuntimeError,
+                                    pos,
+                                    format!(
+                                        "List of type {} can only contain values of the same type ({} given)",
+                                        dtype,
+                                        args[0].get_datatype()
+                                    ),
+                                ))
+                            }
+                            Literal::List(_, list) => Ok(Literal::Bool(list.contains(&args[0]))),
+                            _ => Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                "Expected List".to_string(),
+                            )),
+                        }
+                    }),
+                });
+                // the index of the first occurrence, or -1 when the value is absent
+                new_interfaces.push(Interface {
+                    name: "index_of".to_string(),
+                    args: vec![t.as_ref().clone()],
+                    ret: DataType::Integer,
+                    mutates_receiver: false,
+                    f: Rc::new(|list, v, pos| {
+                        let args = v.to_tuple().unwrap();
+                        if args.len() != 1 {
+                            return Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                ".index_of() expects one argument: l.index_of(value);".to_string(),
+                            ));
+                        }
+                        match list {
+                            Literal::List(dtype, _) if dtype != &args[0].get_datatype() => {
+                                Err(AlthreadError::new(
+                                    ErrorType::RuntimeError,
+                                    pos,
+                                    format!(
+                                        "List of type {} can only contain values of the same type ({} given)",
+                                        dtype,
+                                        args[0].get_datatype()
+                                    ),
+                                ))
+                            }
+                            Literal::List(_, list) => Ok(list
+                                .iter()
+                                .position(|value| *value == args[0])
+                                .map_or(Literal::Int(-1), int_literal)),
+                            _ => Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                "Expected List".to_string(),
+                            )),
+                        }
+                    }),
+                });
+                // l.contains_all(other) and other.is_subset(l) compare every pair of elements,
+                // in O(n·m)
+                for (name, receiver_contains) in [("contains_all", true), ("is_subset", false)] {
+                    new_interfaces.push(Interface {
+                        name: name.to_string(),
+                        args: vec![DataType::List(t.clone())],
+                        ret: DataType::Boolean,
+                        mutates_receiver: false,
+                        f: Rc::new(move |list, v, pos| {
+                            let args = v.to_tuple().unwrap();
+                            match (list, args.first()) {
+                                (Literal::List(_, list), Some(Literal::List(_, other))) => {
+                                    let (list, other) = (list.as_slice(), other.as_slice());
+                                    let (superset, subset) = if receiver_contains {
+                                        (list, other)
+                                    } else {
+                                        (other, list)
+                                    };
+                                    Ok(Literal::Bool(
+                                        subset.iter().all(|value| superset.contains(value)),
+                                    ))
+                                }
+                                _ => Err(AlthreadError::new(
+                                    ErrorType::RuntimeError,
+                                    pos,
+                                    format!(".{name}() expects a list of the same type"),
+                                )),
+                            }
+                        }),
+                    });
+                }
+                if *t == DataType::Boolean {
+                    for (name, all) in [("all", true), ("any", false)] {
+                        new_interfaces.push(Interface {
+                            name: name.to_string(),
+                            args: vec![],
+                            ret: DataType::Boolean,
+                            mutates_receiver: false,
+                            f: Rc::new(move |list, _v, pos| match list {
+                                // all() of an empty list is true, any() is false
+                                Literal::List(_, v) => Ok(Literal::Bool(if all {
+                                    v.iter().all(Literal::is_true)
+                                } else {
+                                    v.iter().any(Literal::is_true)
+                                })),
+                                _ => Err(AlthreadError::new(
+                                    ErrorType::RuntimeError,
+                                    pos,
+                                    "Expected List".to_string(),
+                                )),
+                            }),
+                        });
+                    }
+                }
+            }
+            DataType::Array(t, len) => {
+                new_interfaces.push(Interface {
+                    name: "len".to_string(),
+                    args: vec![],
+                    ret: DataType::Integer,
+                    mutates_receiver: false,
+                    f: Rc::new(move |_array, _v, _pos| Ok(int_literal(len))),
+                });
+                new_interfaces.push(Interface {
+                    name: "at".to_string(),
+                    args: vec![DataType::Integer],
+                    ret: t.as_ref().clone(),
+                    mutates_receiver: false,
+                    f: Rc::new(|array, v, pos| {
+                        let args = v.to_tuple().unwrap();
+                        let idx = args.first().unwrap().to_integer().unwrap();
+                        match array {
+                            Literal::Array(_, values) => usize::try_from(idx)
+                                .ok()
+                                .and_then(|index| values.get(index))
+                                .cloned()
+                                .ok_or_else(|| {
+                                    AlthreadError::new(
+                                        ErrorType::RuntimeError,
+                                        pos,
+                                        format!("Index out of bounds: {idx}"),
+                                    )
+                                }),
+                            _ => Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                "Expected Array".to_string(),
+                            )),
+                        }
+                    }),
+                });
+                new_interfaces.push(Interface {
+                    name: "set".to_string(),
+                    args: vec![DataType::Integer, t.as_ref().clone()],
+                    ret: DataType::Void,
+                    mutates_receiver: true,
+                    f: Rc::new(|array, v, pos| {
+                        let args = v.to_tuple().unwrap();
+                        if args.len() != 2 {
+                            return Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                ".set() expects two arguments: a.set(index, value);".to_string(),
+                            ));
+                        }
+                        let idx = args[0].to_integer().unwrap();
+                        match array {
+                            Literal::Array(_, values) => {
+                                match usize::try_from(idx)
+                                    .ok()
+                                    .and_then(|index| values.get_mut(index))
+                                {
+                                    Some(value) => {
+                                        *value = args[1].clone();
+                                        Ok(Literal::Null)
+                                    }
+                                    None => Err(AlthreadError::new(
+                                        ErrorType::RuntimeError,
+                                        pos,
+                                        format!("Index out of bounds: {idx}"),
+                                    )),
+                                }
+                            }
+                            _ => Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                "Expected Array".to_string(),
+                            )),
+                        }
+                    }),
+                });
+            }
+            DataType::Integer => {
+                // arithmetic with an explicit overflow behavior, instead of an overflow error
+                let operations: [(&'static str, IntOperation); 6] = [
+                    ("add_wrapping", i64::wrapping_add),
+                    ("add_saturating", i64::saturating_add),
+                    ("sub_wrapping", i64::wrapping_sub),
+                    ("sub_saturating", i64::saturating_sub),
+                    ("mul_wrapping", i64::wrapping_mul),
+                    ("mul_saturating", i64::saturating_mul),
+                ];
+                for (name, operation) in operations {
+                    new_interfaces.push(Interface {
+                        name: name.to_string(),
+                        args: vec![DataType::Integer],
+                        ret: DataType::Integer,
+                        mutates_receiver: false,
+                        f: Rc::new(move |value, v, pos| {
+                            let args = v.to_tuple().unwrap();
+                            match (value, args.as_slice()) {
+                                (Literal::Int(i), [Literal::Int(j)]) => {
+                                    Ok(Literal::Int(operation(*i, *j)))
+                                }
+                                _ => Err(AlthreadError::new(
+                                    ErrorType::RuntimeError,
+                                    pos,
+                                    format!(".{name}() expects one integer argument: x.{name}(y);"),
+                                )),
+                            }
+                        }),
+                    });
+                }
+            }
+            DataType::String => {
+                new_interfaces.push(Interface {
+                    name: "len".to_string(),
+                    args: vec![],
+                    ret: DataType::Integer,
+                    mutates_receiver: false,
+                    f: Rc::new(|string, _v, pos| match string {
+                        Literal::String(s) => Ok(int_literal(s.chars().count())),
+                        _ => Err(AlthreadError::new(
+                            ErrorType::RuntimeError,
+                            pos,
+                            "Expected String".to_string(),
+                        )),
+                    }),
+                });
+                for (name, upper) in [("to_upper", true), ("to_lower", false)] {
+                    new_interfaces.push(Interface {
+                        name: name.to_string(),
+                        args: vec![],
+                        ret: DataType::String,
+                        mutates_receiver: false,
+                        f: Rc::new(move |string, _v, pos| match string {
+                            Literal::String(s) => Ok(Literal::String(if upper {
+                                s.to_uppercase()
+                            } else {
+                                s.to_lowercase()
+                            })),
+                            _ => Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                "Expected String".to_string(),
+                            )),
+                        }),
+                    });
+                }
+                new_interfaces.push(Interface {
+                    name: "contains".to_string(),
+                    args: vec![DataType::String],
+                    ret: DataType::Boolean,
+                    mutates_receiver: false,
+                    f: Rc::new(|string, v, pos| {
+                        let args = v.to_tuple().unwrap();
+                        match (string, args.as_slice()) {
+                            (Literal::String(s), [Literal::String(sub)]) => {
+                                Ok(Literal::Bool(s.contains(sub.as_str())))
+                            }
+                            _ => Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                ".contains() expects one string argument: s.contains(sub);"
+                                    .to_string(),
+                            )),
+                        }
+                    }),
+                });
+                new_interfaces.push(Interface {
+                    name: "split".to_string(),
+                    args: vec![DataType::String],
+                    ret: DataType::List(Box::new(DataType::String)),
+                    mutates_receiver: false,
+                    f: Rc::new(|string, v, pos| {
+                        let args = v.to_tuple().unwrap();
+                        match (string, args.as_slice()) {
+                            (Literal::String(_), [Literal::String(sep)]) if sep.is_empty() => {
+                                Err(AlthreadError::new(
+                                    ErrorType::RuntimeError,
+                                    pos,
+                                    ".split() expects a non-empty separator".to_string(),
+                                ))
+                            }
+                            (Literal::String(s), [Literal::String(sep)]) => Ok(Literal::List(
+                                DataType::String,
+                                s.split(sep.as_str())
+                                    .map(|part| Literal::String(part.to_string()))
+                                    .collect(),
+                            )),
+                            _ => Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                ".split() expects one string argument: s.split(sep);".to_string(),
+                            )),
+                        }
+                    }),
+                });
+                new_interfaces.push(Interface {
+                    name: "char_at".to_string(),
+                    args: vec![DataType::Integer],
+                    ret: DataType::String,
+                    mutates_receiver: false,
+                    f: Rc::new(|string, v, pos| {
+                        let args = v.to_tuple().unwrap();
+                        match (string, args.as_slice()) {
+                            (Literal::String(s), [Literal::Int(idx)]) => {
+                                match usize::try_from(*idx).ok().and_then(|i| s.chars().nth(i)) {
+                                    Some(c) => Ok(Literal::String(c.to_string())),
+                                    None => Err(AlthreadError::new(
+                                        ErrorType::RuntimeError,
+                                        pos,
+                                        format!("Index out of bounds: {idx}"),
+                                    )),
+                                }
+                            }
+                            _ => Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                ".char_at() expects one integer argument: s.char_at(index);"
+                                    .to_string(),
+                            )),
+                        }
+                    }),
+                });
+            }
+            DataType::Tuple(types) => {
+                new_interfaces.push(Interface {
+                    name: "len".to_string(),
+                    args: vec![],
+                    ret: DataType::Integer,
+                    mutates_receiver: false,
+                    f: Rc::new(|tuple, _v, pos| match tuple {
+                        Literal::Tuple(values) => Ok(int_literal(values.len())),
+                        _ => Err(AlthreadError::new(
+                            ErrorType::RuntimeError,
+                            pos,
+                            "Expected Tuple".to_string(),
+                        )),
+                    }),
+                });
+                // the type of the appended value is fixed by the interface, so append is only
+                // available on the tuples whose elements all have the same type
+                if let Some(t) = types.first().filter(|t| types.iter().all(|u| u == *t)) {
+                    let mut extended = types.clone();
+                    extended.push(t.clone());
+                    new_interfaces.push(Interface {
+                        name: "append".to_string(),
+                        args: vec![t.clone()],
+                        ret: DataType::Tuple(extended),
+                        mutates_receiver: false,
+                        f: Rc::new(|tuple, v, pos| {
+                            let args = v.to_tuple().unwrap();
+                            if args.len() != 1 {
+                                return Err(AlthreadError::new(
+                                    ErrorType::RuntimeError,
+                                    pos,
+                                    ".append() expects one argument: t.append(value);".to_string(),
+                                ));
+                            }
+                            match tuple {
+                                Literal::Tuple(values) => {
+                                    let mut values = values.clone();
+                                    values.push(args[0].clone());
+                                    Ok(Literal::Tuple(values))
+                                }
+                                _ => Err(AlthreadError::new(
+                                    ErrorType::RuntimeError,
+                                    pos,
+                                    "Expected Tuple".to_string(),
+                                )),
+                            }
+                        }),
+                    });
+                }
+            }
+            DataType::Map(k, v) => {
+                new_interfaces.push(Interface {
+                    name: "len".to_string(),
+                    args: vec![],
+                    ret: DataType::Integer,
+                    mutates_receiver: false,
+                    f: Rc::new(|map, _v, pos| match map {
+                        Literal::Map(_, _, entries) => Ok(int_literal(entries.len())),
+                        _ => Err(AlthreadError::new(
+                            ErrorType::RuntimeError,
+                            pos,
+                            "Expected Map".to_string(),
+                        )),
+                    }),
+                });
+                new_interfaces.push(Interface {
+                    name: "insert".to_string(),
+                    args: vec![k.as_ref().clone(), v.as_ref().clone()],
+                    ret: DataType::Void,
+                    mutates_receiver: true,
+                    f: Rc::new(|map, v, pos| {
+                        let args = v.to_tuple().unwrap();
+                        if args.len() != 2 {
+                            return Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                ".insert() expects two arguments: m.insert(key, value);".to_string(),
+                            ));
+                        }
+                        match map {
+                            Literal::Map(_, _, entries) => {
+                                entries.insert(args[0].map_key(), (args[0].clone(), args[1].clone()));
+                                Ok(Literal::Null)
+                            }
+                            _ => Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                "Expected Map".to_string(),
+                            )),
+                        }
+                    }),
+                });
+                new_interfaces.push(Interface {
+                    name: "get".to_string(),
+                    args: vec![k.as_ref().clone()],
+                    ret: v.as_ref().clone(),
+                    mutates_receiver: false,
+                    f: Rc::new(|map, v, pos| {
+                        let args = v.to_tuple().unwrap();
+                        if args.len() != 1 {
+                            return Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                ".get() expects one argument: m.get(key);".to_string(),
+                            ));
+                        }
+                        match map {
+                            Literal::Map(_, _, entries) => entries
+                                .get(&args[0].map_key())
+                                .map(|(_, value)| value.clone())
+                                .ok_or_else(|| {
+                                    AlthreadError::new(
+                                        ErrorType::RuntimeError,
+                                        pos,
+                                        format!("Key not found: {}", args[0]),
+                                    )
+                                }),
+                            _ => Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                "Expected Map".to_string(),
+                            )),
+                        }
+                    }),
+                });
+                new_interfaces.push(Interface {
+                    name: "contains_key".to_string(),
+                    args: vec![k.as_ref().clone()],
+                    ret: DataType::Boolean,
+                    mutates_receiver: false,
+                    f: Rc::new(|map, v, pos| {
+                        let args = v.to_tuple().unwrap();
+                        if args.len() != 1 {
+                            return Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                ".contains_key() expects one argument: m.contains_key(key);"
+                                    .to_string(),
+                            ));
+                        }
+                        match map {
+                            Literal::Map(_, _, entries) => {
+                                Ok(Literal::Bool(entries.contains_key(&args[0].map_key())))
+                            }
+                            _ => Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                "Expected Map".to_string(),
+                            )),
+                        }
+                    }),
+                });
+                // removing a missing key does nothing, so that `remove` can be used unconditionally
+                new_interfaces.push(Interface {
+                    name: "remove".to_string(),
+                    args: vec![k.as_ref().clone()],
+                    ret: DataType::Void,
+                    mutates_receiver: true,
+                    f: Rc::new(|map, v, pos| {
+                        let args = v.to_tuple().unwrap();
+                        if args.len() != 1 {
+                            return Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                ".remove() expects one argument: m.remove(key);".to_string(),
+                            ));
+                        }
+                        match map {
+                            Literal::Map(_, _, entries) => {
+                                entries.remove(&args[0].map_key());
+                                Ok(Literal::Null)
+                            }
+                            _ => Err(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                pos,
+                                "Expected Map".to_string(),
+                            )),
+                        }
+                    }),
+                });
             }
             _ => {}
         }
@@ -234,6 +993,135 @@ impl Stdlib {
     }
 }
 
+/// Checks the index given to `at` or `set` on a `[t; n]` array when it is a constant, so that an
+/// access out of its bounds is an error at compile time
+pub(crate) fn check_array_index(
+    receiver: &DataType,
+    method_name: &str,
+    index: Option<i64>,
+) -> Result<(), String> {
+    match (receiver, index) {
+        (DataType::Array(_, len), Some(index))
+            if ARRAY_INDEX_METHODS.contains(&method_name)
+                && !usize::try_from(index).is_ok_and(|index| index < *len) =>
+        {
+            Err(format!(
+                "Index {index} is out of the bounds of an array of type {receiver}"
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks the function given to a `list(t)` function method (`map` or `filter`) and returns the
+/// type of the resulting list.
+pub(crate) fn list_function_return_type(
+    method: &str,
+    elem_type: &DataType,
+    func: &FunctionDefinition,
+) -> Result<DataType, String> {
+    // the function runs to completion within the calling step, so it cannot have side effects
+    if !func.is_pure {
+        return Err(format!(
+            "Function '{}' must be marked @pure to be used with .{}()",
+            func.name, method
+        ));
+    }
+    if func.arguments.len() != 1 || func.arguments[0].1 != *elem_type {
+        return Err(format!(
+            "Function '{}' given to .{}() must take exactly one argument of type {}",
+            func.name, method, elem_type
+        ));
+    }
+    match method {
+        "map" => {
+            if func.return_type == DataType::Void {
+                return Err(format!(
+                    "Function '{}' given to .map() must return a value",
+                    func.name
+                ));
+            }
+            Ok(DataType::List(Box::new(func.return_type.clone())))
+        }
+        "filter" => {
+            if func.return_type != DataType::Boolean {
+                return Err(format!(
+                    "Function '{}' given to .filter() must return bool, but returns {}",
+                    func.name, func.return_type
+                ));
+            }
+            Ok(DataType::List(Box::new(elem_type.clone())))
+        }
+        _ => Err(format!("{method} is not a list function method")),
+    }
+}
+
+/// Applies a list function method to `list`, `call` running the user function `func` on the
+/// arguments tuple built from one element.
+pub(crate) fn apply_list_function(
+    method: &str,
+    func: &FunctionDefinition,
+    list: &Literal,
+    mut call: impl FnMut(Literal) -> Result<Literal, String>,
+) -> Result<Literal, String> {
+    let Literal::List(dtype, values) = list else {
+        return Err("Expected List".to_string());
+    };
+    match method {
+        "map" => {
+            let mut mapped = Vec::with_capacity(values.len());
+            for value in values {
+                mapped.push(call(Literal::Tuple(vec![value.clone()]))?);
+            }
+            Ok(Literal::List(func.return_type.clone(), mapped))
+        }
+        "filter" => {
+            let mut kept = Vec::new();
+            for value in values {
+                if call(Literal::Tuple(vec![value.clone()]))?.is_true() {
+                    kept.push(value.clone());
+                }
+            }
+            Ok(Literal::List(dtype.clone(), kept))
+        }
+        _ => Err(format!("{method} is not a list function method")),
+    }
+}
+
+/// Calls the registered function `name`, checking that it returns a value of its declared type
+pub(crate) fn invoke_function(
+    stdlib: &Stdlib,
+    name: &str,
+    args: &mut Literal,
+    pos: Option<Pos>,
+) -> Result<Literal, AlthreadError> {
+    let function = stdlib.get_function(name).ok_or_else(|| {
+        AlthreadError::new(
+            ErrorType::UndefinedFunction,
+            pos.clone(),
+            format!("undefined function {name}"),
+        )
+    })?;
+
+    let ret = function.f.as_ref()(&mut Literal::Null, args, pos.clone())?;
+    if function.ret == DataType::Void {
+        return Ok(Literal::Null);
+    }
+    if ret.get_datatype() != function.ret {
+        return Err(AlthreadError::new(
+            ErrorType::RuntimeError,
+            pos,
+            format!(
+                "function {} returned a value of type {} instead of {}",
+                name,
+                ret.get_datatype(),
+                function.ret
+            ),
+        ));
+    }
+    Ok(ret)
+}
+
 pub fn invoke_interface_method(
     stdlib: &Stdlib,
     name: &str,
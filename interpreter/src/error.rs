@@ -88,12 +88,14 @@ pub enum ErrorType {
     FunctionMissingReturnStatement,
     FunctionReturnTypeMismatch,
     AssertionFailed,
+    AssumptionViolated,
     ImportNameConflict,
     ModuleNotFound,
     ImportMainConflict,
     VariableAlreadyDefined,
     ProgramAlreadyDefined,
     PrivateFunctionCall,
+    DeadlockError,
 }
 
 impl fmt::Display for ErrorType {
@@ -125,12 +127,14 @@ impl fmt::Display for ErrorType {
             }
             ErrorType::FunctionReturnTypeMismatch => write!(f, "Function return type mismatch"),
             ErrorType::AssertionFailed => write!(f, "Assertion failed"),
+            ErrorType::AssumptionViolated => write!(f, "Assumption violated"),
             ErrorType::ImportNameConflict => write!(f, "Import name conflict"),
             ErrorType::ModuleNotFound => write!(f, "Module not found"),
             ErrorType::ImportMainConflict => write!(f, "Import main conflict"),
             ErrorType::VariableAlreadyDefined => write!(f, "Variable already defined"),
             ErrorType::ProgramAlreadyDefined => write!(f, "Program already defined"),
             ErrorType::PrivateFunctionCall => write!(f, "Private function call"),
+            ErrorType::DeadlockError => write!(f, "Deadlock"),
         }
     }
 }
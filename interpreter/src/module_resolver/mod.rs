@@ -1,3 +1,4 @@
 pub mod filesystem;
 pub mod module_resolver;
+pub mod std_modules;
 pub use filesystem::{FileSystem, StandardFileSystem, VirtualFileSystem};
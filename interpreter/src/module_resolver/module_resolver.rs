@@ -1,4 +1,5 @@
 use super::filesystem::FileSystem;
+use super::std_modules::{self, STD_PREFIX};
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
@@ -97,7 +98,10 @@ impl<F: FileSystem + Clone> ModuleResolver<F> {
             let resolved = self.resolve_import_item(&import_item.value)?;
             let access_name = resolved.alias.clone().unwrap_or(resolved.name.clone());
 
-            let module_content = self.filesystem.read_file(&resolved.path)?;
+            let module_content = match std_modules::std_module_source(&resolved.path) {
+                Some(source) => source.to_string(),
+                None => self.filesystem.read_file(&resolved.path)?,
+            };
             input_map.insert(
                 resolved.path.to_string_lossy().to_string(),
                 module_content.clone(),
@@ -169,6 +173,26 @@ impl<F: FileSystem + Clone> ModuleResolver<F> {
     fn resolve_path(&self, import_path: &ImportPath) -> AlthreadResult<(PathBuf, ModuleType)> {
         let relative_path_str = import_path.segments.join("/");
 
+        // The standard library prefix is reserved and never resolved from the filesystem
+        if let Some((STD_PREFIX, module)) = relative_path_str.split_once('/') {
+            return std_modules::std_module_path(module)
+                .map(|path| (path, ModuleType::File))
+                .ok_or_else(|| {
+                    AlthreadError::new(
+                        ErrorType::ModuleNotFound,
+                        None,
+                        format!(
+                            "Module '{}' not found in the standard library (available modules: {})",
+                            relative_path_str,
+                            std_modules::std_module_names()
+                                .map(|name| format!("{STD_PREFIX}/{name}"))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    )
+                });
+        }
+
         // First, try to resolve as a local path
         let mut local_path = self.current_file_dir.clone();
         local_path.push(&relative_path_str);
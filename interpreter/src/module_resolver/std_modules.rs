@@ -0,0 +1,42 @@
+//! Standard library modules bundled with the interpreter.
+//!
+//! They are imported with the reserved `std/` prefix (e.g. `import [std/lock]`) and are
+//! served from the content embedded in the binary instead of the filesystem.
+
+use std::path::{Path, PathBuf};
+
+/// The first segment of the import paths of the standard library
+pub const STD_PREFIX: &str = "std";
+
+/// The directory of the (virtual) paths given to the standard library modules
+const STD_DIR: &str = "<std>";
+
+const STD_MODULES: &[(&str, &str)] = &[
+    ("lock", include_str!("../../std/lock.alt")),
+    ("queue", include_str!("../../std/queue.alt")),
+    ("semaphore", include_str!("../../std/semaphore.alt")),
+];
+
+/// The path of a standard library module, `name` being its import path without the `std/` prefix
+#[must_use]
+pub fn std_module_path(name: &str) -> Option<PathBuf> {
+    STD_MODULES
+        .iter()
+        .any(|(module, _)| *module == name)
+        .then(|| Path::new(STD_DIR).join(format!("{name}.alt")))
+}
+
+/// The source code of the standard library module at `path`, if it is one
+#[must_use]
+pub fn std_module_source(path: &Path) -> Option<&'static str> {
+    let name = path.strip_prefix(STD_DIR).ok()?.with_extension("");
+    STD_MODULES
+        .iter()
+        .find(|(module, _)| Path::new(module) == name)
+        .map(|(_, source)| *source)
+}
+
+/// The names of the available standard library modules
+pub fn std_module_names() -> impl Iterator<Item = &'static str> {
+    STD_MODULES.iter().map(|(name, _)| *name)
+}
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use crate::ast::token::literal::Literal;
 
@@ -24,6 +24,14 @@ pub struct Channels {
     /// Keyed by (from_pid, from_channel, to_pid, to_channel).
     /// Delivery preserves per-link FIFO.
     pending_deliveries: PendingDeliveriesState,
+
+    /// The senders (program id, channel name) connected through a synchronous channel.
+    /// A message sent on such a link is delivered and received in the same step.
+    sync_links: BTreeSet<(usize, String)>,
+
+    /// The capacity of the bounded input channels (program id, channel name): the maximum number
+    /// of messages in flight towards the channel or buffered in it
+    capacities: BTreeMap<(usize, String), usize>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -47,6 +55,8 @@ impl Channels {
             connections: HashMap::new(),
             waiting_send: HashMap::new(),
             pending_deliveries: BTreeMap::new(),
+            sync_links: BTreeSet::new(),
+            capacities: BTreeMap::new(),
         }
     }
 
@@ -99,6 +109,7 @@ impl Channels {
     /**
      * Connect a proc to another proc
      * If the sender proc was waiting to send on the channel, it will send the values
+     * (even on a synchronous channel, as they were sent before the connection existed)
      */
     pub fn connect(
         &mut self,
@@ -106,6 +117,7 @@ impl Channels {
         channel_name: String,
         to_program_id: usize,
         to_channel_name: String,
+        sync: bool,
     ) -> Result<bool, String> {
         if self
             .connections
@@ -117,6 +129,9 @@ impl Channels {
             (program_id, channel_name.clone()),
             (to_program_id, to_channel_name.clone()),
         );
+        if sync {
+            self.sync_links.insert((program_id, channel_name.clone()));
+        }
 
         if let Some(values) = self
             .waiting_send
@@ -137,6 +152,32 @@ impl Channels {
         Ok(false)
     }
 
+    /// Bounds the number of messages in flight towards an input channel or buffered in it
+    pub fn set_capacity(&mut self, program_id: usize, channel_name: String, capacity: usize) {
+        self.capacities.insert((program_id, channel_name), capacity);
+    }
+
+    /// Whether a send on the output channel of a program would exceed the capacity of the
+    /// receiver. A channel that is not connected yet is never full.
+    pub fn is_full(&self, program_id: usize, channel_name: &str) -> bool {
+        let Some(receiver) = self.connections.get(&(program_id, channel_name.to_string())) else {
+            return false;
+        };
+        let Some(capacity) = self.capacities.get(receiver) else {
+            return false;
+        };
+        let buffered = self.states.get(receiver).map_or(0, Vec::len);
+        let in_flight: usize = self
+            .pending_deliveries
+            .iter()
+            .filter(|((_, _, to_pid, to_channel), _)| {
+                *to_pid == receiver.0 && *to_channel == receiver.1
+            })
+            .map(|(_, messages)| messages.len())
+            .sum();
+        buffered + in_flight >= *capacity
+    }
+
     /// Returns the list of links that currently have at least one pending message to deliver.
     pub fn pending_links(&self) -> Vec<ChannelLinkKey> {
         self.pending_deliveries
@@ -160,6 +201,42 @@ impl Channels {
             .contains_key(&(program_id, channel_name.to_string()))
     }
 
+    /// Whether a channel of `from_pid` is connected to a channel of `to_pid`
+    #[must_use]
+    pub fn is_connected(&self, from_pid: usize, to_pid: usize) -> bool {
+        self.connections
+            .iter()
+            .any(|((from, _), (to, _))| *from == from_pid && *to == to_pid)
+    }
+
+    /// The link used by a sender if it is connected through a synchronous channel
+    #[must_use]
+    pub fn sync_link(&self, program_id: usize, channel_name: &str) -> Option<ChannelLinkKey> {
+        let key = (program_id, channel_name.to_string());
+        if !self.sync_links.contains(&key) {
+            return None;
+        }
+        let (to_pid, to_channel) = self.connections.get(&key)?;
+        Some((program_id, key.1, *to_pid, to_channel.clone()))
+    }
+
+    /// Delivers the message that was just sent on a synchronous link. The rendezvous is not
+    /// possible (`None`) if other messages are still in flight on the link.
+    pub fn deliver_rendezvous(&mut self, link: ChannelLinkKey) -> Option<DeliveryInfo> {
+        if self.pending_deliveries.get(&link).map(Vec::len) != Some(1) {
+            return None;
+        }
+        self.deliver_one(link)
+    }
+
+    /// Whether a delivered message (as returned in `DeliveryInfo`) is still in the input buffer
+    #[must_use]
+    pub fn has_message(&self, program_id: usize, channel_name: &str, message: &Literal) -> bool {
+        self.states
+            .get(&(program_id, channel_name.to_string()))
+            .is_some_and(|queue| queue.contains(message))
+    }
+
     /// Deliver exactly one pending message for a given link.
     pub fn deliver_one(&mut self, link: ChannelLinkKey) -> Option<DeliveryInfo> {
         let (from_pid, from_channel, to_pid, to_channel) = link.clone();
@@ -249,6 +326,11 @@ impl Channels {
     pub fn get_waiting_send(&self) -> HashMap<(usize, String), Vec<Literal>> {
         self.waiting_send.clone()
     }
+
+    #[must_use]
+    pub fn get_sync_links(&self) -> &BTreeSet<(usize, String)> {
+        &self.sync_links
+    }
 }
 
 pub fn parse_message_tuple(msg: &Literal) -> Option<(usize, usize, String)> {
@@ -285,7 +367,7 @@ mod tests {
         let mut channels = Channels::new();
 
         channels
-            .connect(1, "out".to_string(), 0, "in".to_string())
+            .connect(1, "out".to_string(), 0, "in".to_string(), false)
             .unwrap();
 
         let receiver = channels.send(1, "out".to_string(), Literal::Int(42), 1);
@@ -306,10 +388,10 @@ mod tests {
         let mut channels = Channels::new();
 
         channels
-            .connect(1, "out".to_string(), 0, "in".to_string())
+            .connect(1, "out".to_string(), 0, "in".to_string(), false)
             .unwrap();
         channels
-            .connect(2, "out".to_string(), 0, "in".to_string())
+            .connect(2, "out".to_string(), 0, "in".to_string(), false)
             .unwrap();
 
         channels.send(1, "out".to_string(), Literal::Int(1), 1);
@@ -326,4 +408,39 @@ mod tests {
         assert_eq!(channels.pop(0, "in".to_string()), Some(Literal::Int(2)));
         assert_eq!(channels.pop(0, "in".to_string()), Some(Literal::Int(1)));
     }
+
+    #[test]
+    fn bounded_channel_is_full_until_pop() {
+        let mut channels = Channels::new();
+
+        channels
+            .connect(1, "out".to_string(), 0, "in".to_string(), false)
+            .unwrap();
+        channels.set_capacity(0, "in".to_string(), 1);
+        assert!(!channels.is_full(1, "out"));
+
+        // a message in flight counts towards the capacity
+        channels.send(1, "out".to_string(), Literal::Int(1), 1);
+        assert!(channels.is_full(1, "out"));
+
+        channels
+            .deliver_one((1, "out".to_string(), 0, "in".to_string()))
+            .unwrap();
+        assert!(channels.is_full(1, "out"));
+
+        channels.pop(0, "in".to_string());
+        assert!(!channels.is_full(1, "out"));
+    }
+
+    #[test]
+    fn connection_graph_is_directed() {
+        let mut channels = Channels::new();
+
+        channels
+            .connect(1, "out".to_string(), 2, "in".to_string(), false)
+            .unwrap();
+
+        assert!(channels.is_connected(1, 2));
+        assert!(!channels.is_connected(2, 1));
+    }
 }
@@ -1,4 +1,7 @@
-use std::{collections::HashMap, fmt};
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+};
 
 use crate::{
     ast::{
@@ -53,9 +56,23 @@ pub enum InstructionType {
     ConvertEmptyListType {
         to_element_type: DataType,
     },
+    /// Builds a map from the keys and values on top of the stack, pushed one entry after the
+    /// other
+    CreateMapFromStack {
+        entry_count: usize,
+        key_type: DataType,
+        value_type: DataType,
+    },
+    /// Turns the list on top of the stack into an array of the same elements
+    ConvertListToArray {
+        element_type: DataType,
+    },
     RunCall {
         name: String,
         unstack_len: usize,
+        /// The name of the code specialized on the values of the `const` parameters, if the
+        /// program has some
+        specialization: Option<String>,
     },
     FnCall {
         name: String,
@@ -72,6 +89,8 @@ pub enum InstructionType {
     },
     Return {
         has_value: bool,
+        /// the return leaves an atomic block, which ends before the return is executed
+        stop_atomic: bool,
     },
     JumpIf {
         jump_false: i64,
@@ -85,6 +104,10 @@ pub enum InstructionType {
     },
     ChannelPeek(String),
     ChannelPop(String),
+    /// replaces the process handle on top of the stack by whether the process has terminated
+    HasTerminated,
+    /// fails if the count of a repeat loop, on top of the stack, is negative
+    CheckRepeatCount,
 
     WaitStart {
         dependencies: WaitDependency,
@@ -109,12 +132,18 @@ pub enum InstructionType {
         receiver_pid: Option<usize>,
         sender_channel: String,
         receiver_channel: String,
+        /// synchronous channels deliver each message in the same step as its reception
+        sync: bool,
+        /// the number of messages the receiver can have in flight or buffered, if bounded
+        capacity: Option<usize>,
     },
     AtomicStart,
     AtomicEnd,
     Label {
         name: String,
     },
+    /// An explicit scheduling point, ending the sequence of instructions executed in a step
+    Yield,
     EndProgram,
     Exit,
 }
@@ -205,7 +234,7 @@ impl fmt::Display for InstructionType {
                     )?
                 }
             }
-            Self::Return { has_value } => {
+            Self::Return { has_value, .. } => {
                 write!(f, "return {:?}", if *has_value { "value" } else { "void" })?
             }
             Self::Exit => write!(f, "exit")?, //TODO check again ???
@@ -225,6 +254,19 @@ impl fmt::Display for InstructionType {
             Self::ConvertEmptyListType { to_element_type } => {
                 write!(f, "convert empty list to type {:?}", to_element_type)?;
             }
+            Self::CreateMapFromStack {
+                entry_count,
+                key_type,
+                value_type,
+            } => {
+                write!(
+                    f,
+                    "create map from stack ({entry_count} entries of type {key_type} -> {value_type})"
+                )?;
+            }
+            Self::ConvertListToArray { element_type } => {
+                write!(f, "convert list to array of type {element_type:?}")?;
+            }
             Self::Push(l) => write!(f, "push ({})", l)?,
             Self::WaitStart { start_atomic, .. } => {
                 write!(f, "await start")?;
@@ -246,15 +288,21 @@ impl fmt::Display for InstructionType {
             } => write!(f, "broadcast to {} (unstack {})", channel_name, unstack_len)?,
             Self::ChannelPeek(s) => write!(f, "peek '{}'", s)?,
             Self::ChannelPop(s) => write!(f, "pop '{}'", s)?,
+            Self::HasTerminated => write!(f, "has terminated")?,
+            Self::CheckRepeatCount => write!(f, "check repeat count")?,
             Self::Connect {
                 sender_pid,
                 receiver_pid,
                 sender_channel,
                 receiver_channel,
+                sync,
+                capacity,
             } => {
                 write!(
                     f,
-                    "connect [&{}] {}->{} [&{}]",
+                    "connect{}{} [&{}] {}->{} [&{}]",
+                    if *sync { " sync" } else { "" },
+                    capacity.map_or(String::new(), |capacity| format!(" [{capacity}]")),
                     if sender_pid.is_none() {
                         "self".to_string()
                     } else {
@@ -273,6 +321,7 @@ impl fmt::Display for InstructionType {
             Self::AtomicStart => write!(f, "atomic start")?,
             Self::AtomicEnd => write!(f, "atomic end")?,
             Self::Label { name } => write!(f, "label {}", name)?,
+            Self::Yield => write!(f, "yield")?,
         }
         Ok(())
     }
@@ -283,11 +332,13 @@ impl InstructionType {
         match self {
               Self::GlobalAssignment {..}
             | Self::ChannelPeek(_)
+            | Self::HasTerminated // reads the state of another process
             | Self::AtomicStart // starts a block that surely contains a global operation           
             
              // Labels are NOT local - they create a state in the state graph
             // This allows checking if a process is at a specific label using reaches()
             | Self::Label {..}
+            | Self::Yield // an explicit scheduling point
             | Self::WaitStart {..} => false, // wait starts an atomic block to evaluate the conditions
 
             Self::GlobalReads {only_const, ..} => *only_const, // a global read is local only if it reads constant variables
@@ -330,6 +381,9 @@ impl InstructionType {
             | Self::Declaration {..}
             | Self::CreateListFromStack {..}
             | Self::ConvertEmptyListType {..}
+            | Self::CreateMapFromStack {..}
+            | Self::ConvertListToArray {..}
+            | Self::CheckRepeatCount
             | Self::AtomicEnd
             | Self::EndProgram
             | Self::Exit
@@ -353,6 +407,7 @@ impl InstructionType {
         match self {
             Self::AtomicEnd => true,
             Self::Break { stop_atomic, .. } => *stop_atomic,
+            Self::Return { stop_atomic, .. } => *stop_atomic,
             Self::EndProgram => true,
             _ => false,
         }
@@ -380,6 +435,11 @@ impl Instruction {
     }
 }
 
+/// The offset of a jump over `len` instructions
+pub(crate) fn jump_offset(len: usize) -> i64 {
+    i64::try_from(len).expect("a program has fewer than i64::MAX instructions")
+}
+
 impl fmt::Display for Instruction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self.pos {
@@ -399,6 +459,40 @@ pub struct ProgramCode {
     pub argument_names: Vec<String>,
 }
 
+impl ProgramCode {
+    /// Lists the instructions of the program, numbered by their index, each followed by the
+    /// source line it was compiled from when the file is in `sources` (keyed by file path)
+    #[must_use]
+    pub fn disassemble(&self, sources: &HashMap<String, String>) -> String {
+        let width = self.instructions.len().saturating_sub(1).to_string().len();
+        let controls: Vec<String> = self
+            .instructions
+            .iter()
+            .map(|inst| inst.control.to_string())
+            .collect();
+        let control_width = controls.iter().map(|c| c.chars().count()).max().unwrap_or(0);
+
+        let mut out = format!("{}\n", self.name);
+        for (idx, (inst, control)) in self.instructions.iter().zip(controls).enumerate() {
+            let mut line = format!("{idx:>width$}  {control:<control_width$}");
+            if let Some(pos) = &inst.pos {
+                let source_line = sources
+                    .get(&pos.file_path)
+                    .and_then(|source| source.lines().nth(pos.line.wrapping_sub(1)));
+                match source_line {
+                    Some(source_line) => {
+                        write!(line, "  // {}: {}", pos.line, source_line.trim()).unwrap();
+                    }
+                    None => write!(line, "  // {}", pos.line).unwrap(),
+                }
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+}
+
 // impl display for ProcessCode
 impl fmt::Display for ProgramCode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -0,0 +1,201 @@
+use std::{cell::Cell, fmt, str::FromStr};
+
+use crate::ast::token::literal::Literal;
+
+/// What happens to an integer that goes out of the abstracted range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntOverflow {
+    /// The value is replaced by the closest bound of the range
+    #[default]
+    Saturate,
+    /// The value wraps around the range, as with a modular counter
+    Wrap,
+}
+
+impl FromStr for IntOverflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "saturate" => Ok(Self::Saturate),
+            "wrap" => Ok(Self::Wrap),
+            _ => Err(format!(
+                "unknown overflow mode '{s}', expected 'saturate' or 'wrap'"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for IntOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Saturate => write!(f, "saturate"),
+            Self::Wrap => write!(f, "wrap"),
+        }
+    }
+}
+
+/// Bounds the integers produced by the programs to `[min, max]`, so that models with
+/// unbounded counters have a finite state space.
+///
+/// The values are clamped when they are stored: initial values of the shared variables,
+/// results of expressions, assignments and tuples. The number of values that had to be clamped is recorded so the user can be
+/// warned that the abstraction changed the behavior of the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntAbstraction {
+    pub min: i64,
+    pub max: i64,
+    pub overflow: IntOverflow,
+    clamped: Cell<usize>,
+}
+
+impl IntAbstraction {
+    /// The abstraction of the integers to `min..=max`
+    ///
+    /// # Errors
+    /// If `min` is greater than `max`
+    pub fn new(min: i64, max: i64, overflow: IntOverflow) -> Result<Self, String> {
+        if min > max {
+            return Err(format!(
+                "invalid integer range {min}..{max}, the lower bound is greater than the upper bound"
+            ));
+        }
+        Ok(Self {
+            min,
+            max,
+            overflow,
+            clamped: Cell::new(0),
+        })
+    }
+
+    /// The number of values that were out of range and had to be clamped
+    pub fn clamped_count(&self) -> usize {
+        self.clamped.get()
+    }
+
+    /// Brings an integer back into `min..=max`, saturating or wrapping it around the range
+    ///
+    /// # Panics
+    /// Never: a wrapped value lies between `min` and `max`
+    pub fn clamp_int(&self, value: i64) -> i64 {
+        if (self.min..=self.max).contains(&value) {
+            return value;
+        }
+        self.clamped.set(self.clamped.get() + 1);
+        match self.overflow {
+            IntOverflow::Saturate => value.clamp(self.min, self.max),
+            IntOverflow::Wrap => {
+                let size = i128::from(self.max) - i128::from(self.min) + 1;
+                let wrapped = i128::from(self.min)
+                    + (i128::from(value) - i128::from(self.min)).rem_euclid(size);
+                i64::try_from(wrapped).expect("a wrapped value lies between min and max")
+            }
+        }
+    }
+
+    /// Clamps the integers of a literal, including the ones nested in tuples, lists, arrays and
+    /// maps. The entries of a map keep their canonical key, only their literals are clamped
+    pub fn clamp(&self, lit: Literal) -> Literal {
+        match lit {
+            Literal::Int(i) => Literal::Int(self.clamp_int(i)),
+            Literal::Tuple(values) => {
+                Literal::Tuple(values.into_iter().map(|v| self.clamp(v)).collect())
+            }
+            Literal::List(datatype, values) => Literal::List(
+                datatype,
+                values.into_iter().map(|v| self.clamp(v)).collect(),
+            ),
+            Literal::Array(datatype, values) => Literal::Array(
+                datatype,
+                values.into_iter().map(|v| self.clamp(v)).collect(),
+            ),
+            Literal::Map(key_datatype, value_datatype, entries) => Literal::Map(
+                key_datatype,
+                value_datatype,
+                entries
+                    .into_iter()
+                    .map(|(canonical, (key, value))| {
+                        (canonical, (self.clamp(key), self.clamp(value)))
+                    })
+                    .collect(),
+            ),
+            lit => lit,
+        }
+    }
+}
+
+impl FromStr for IntAbstraction {
+    type Err = String;
+
+    /// Parses a range of the form `min..max`, both bounds included
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (min, max) = s
+            .split_once("..")
+            .ok_or_else(|| format!("invalid integer range '{s}', expected 'min..max'"))?;
+        let parse_bound = |bound: &str| {
+            bound
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| format!("invalid integer range bound '{bound}'"))
+        };
+        Self::new(parse_bound(min)?, parse_bound(max)?, IntOverflow::default())
+    }
+}
+
+impl fmt::Display for IntAbstraction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}, {}] ({})", self.min, self.max, self.overflow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::token::datatype::DataType;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn saturates_and_counts_out_of_range_values() {
+        let abstraction: IntAbstraction = "0..3".parse().unwrap();
+        assert_eq!(abstraction.clamp_int(2), 2);
+        assert_eq!(abstraction.clamp_int(5), 3);
+        assert_eq!(abstraction.clamp_int(-1), 0);
+        assert_eq!(abstraction.clamped_count(), 2);
+    }
+
+    #[test]
+    fn wraps_around_the_range() {
+        let abstraction = IntAbstraction::new(-2, 2, IntOverflow::Wrap).unwrap();
+        assert_eq!(abstraction.clamp_int(3), -2);
+        assert_eq!(abstraction.clamp_int(-3), 2);
+        assert_eq!(abstraction.clamp_int(12), 2);
+        assert_eq!(abstraction.clamp_int(i64::MAX), 2);
+        assert!("3..1".parse::<IntAbstraction>().is_err());
+    }
+
+    #[test]
+    fn clamps_the_elements_of_arrays() {
+        let abstraction: IntAbstraction = "0..3".parse().unwrap();
+        let array = Literal::Array(DataType::Integer, vec![Literal::Int(1), Literal::Int(7)]);
+        assert_eq!(
+            abstraction.clamp(array),
+            Literal::Array(DataType::Integer, vec![Literal::Int(1), Literal::Int(3)])
+        );
+        assert_eq!(abstraction.clamped_count(), 1);
+    }
+
+    #[test]
+    fn clamps_the_values_of_maps() {
+        let abstraction: IntAbstraction = "0..3".parse().unwrap();
+        let map = |value: i64| {
+            let key = Literal::String("a".to_string());
+            Literal::Map(
+                DataType::String,
+                DataType::Integer,
+                BTreeMap::from([(key.map_key(), (key, Literal::Int(value)))]),
+            )
+        };
+        assert_eq!(abstraction.clamp(map(9)), map(3));
+        assert_eq!(abstraction.clamped_count(), 1);
+    }
+}
@@ -1,16 +1,19 @@
 use core::panic;
 use std::{
-    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     fmt,
     hash::{Hash, Hasher},
+    ops::{ControlFlow, RangeInclusive},
     rc::Rc,
 };
 
-use channels::{ChannelLinkKey, Channels, ChannelsState};
+use channels::{ChannelLinkKey, Channels, ChannelsState, DeliveryInfo};
 use fastrand::Rng;
 
 use instruction::{Instruction, InstructionType, ProgramCode};
-use running_program::RunningProgramState;
+use int_abstraction::IntAbstraction;
+use random::{RandomReplay, RandomSource, MAX_RAND_CHOICES};
+use running_program::{run_pure_function, RunningProgramState};
 use serde::{ser::SerializeStruct, Serialize, Serializer};
 
 use crate::{
@@ -24,6 +27,8 @@ use crate::{
 
 pub mod channels;
 pub mod instruction;
+pub mod int_abstraction;
+pub mod random;
 pub mod running_program;
 
 pub type Memory = Vec<Literal>;
@@ -35,11 +40,42 @@ pub struct ExecutionStepInfo {
     pub prog_id: usize,
     pub instructions: Vec<Instruction>,
     pub invariant_error: AlthreadResult<i32>,
+    /// The actions performed during the step, in the order the instructions producing them
+    /// were executed. A step only executes a single program, so `Print` actions of a step
+    /// always come out in program order. The only exception is the rendezvous on a synchronous
+    /// channel, where the `Deliver` and the actions of the receiver follow the sender's ones.
     pub actions: Vec<GlobalAction>,
 }
 
-fn str_to_expr_error(pos: Option<Pos>) -> impl Fn(String) -> AlthreadError {
-    return move |msg| AlthreadError::new(ErrorType::ExpressionError, pos.clone(), msg);
+impl Serialize for ExecutionStepInfo {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let lines: Vec<usize> = self
+            .instructions
+            .iter()
+            .filter_map(|inst| inst.pos.as_ref().map(|p| p.line))
+            .collect();
+        let instructions: Vec<String> = self
+            .instructions
+            .iter()
+            .map(|inst| inst.control.to_string())
+            .collect();
+
+        let mut state = serializer.serialize_struct("ExecutionStepInfo", 6)?;
+        state.serialize_field("prog_name", &self.prog_name)?;
+        state.serialize_field("prog_id", &self.prog_id)?;
+        state.serialize_field("lines", &lines)?;
+        state.serialize_field("instructions", &instructions)?;
+        state.serialize_field("actions", &self.actions)?;
+        state.serialize_field("invariant_error", &self.invariant_error.as_ref().err())?;
+        state.end()
+    }
+}
+
+fn str_to_expr_error(pos: Option<&Pos>) -> impl Fn(String) -> AlthreadError + '_ {
+    move |msg| AlthreadError::new(ErrorType::ExpressionError, pos.cloned(), msg)
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize)]
@@ -72,6 +108,8 @@ pub struct DeliverInfo {
 
 #[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum GlobalAction {
+    /// Starts a program from the code of the given name, which is the name of the program or of
+    /// its specialization on its `const` parameters
     StartProgram(String, usize, Literal, Option<usize>, Option<Pos>),
     Print(String),
     Write(String),
@@ -84,54 +122,218 @@ pub enum GlobalAction {
     Exit,
 }
 
+/// A one-line summary of the action, the values being rendered with their `Display`
+impl fmt::Display for GlobalAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let send = |info: &SendInfo| {
+            format!(
+                "send {} from {}#{} on {}",
+                info.message, info.from.process_name, info.from.process_id, info.to.channel_name
+            )
+        };
+        match self {
+            Self::StartProgram(name, pid, args, ..) => write!(f, "run {name}#{pid}{args}"),
+            Self::Print(msg) => write!(f, "print {msg}"),
+            Self::Write(msg) => write!(f, "write {msg}"),
+            Self::Send(info) => write!(f, "{}", send(info)),
+            Self::Broadcast(infos) => write!(
+                f,
+                "broadcast [{}]",
+                infos.iter().map(send).collect::<Vec<String>>().join(", ")
+            ),
+            Self::Deliver(info) => write!(
+                f,
+                "deliver {} from {}#{} to {}#{} on {}",
+                info.message,
+                info.from.process_name,
+                info.from.process_id,
+                info.to.process_name,
+                info.to.process_id,
+                info.channel_name
+            ),
+            Self::Connect(pid, channel) => write!(f, "connect {pid}.{channel}"),
+            Self::EndProgram => write!(f, "end"),
+            Self::Wait => write!(f, "wait"),
+            Self::Exit => write!(f, "exit"),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct GlobalActions {
+    /// Actions in execution order (see `ExecutionStepInfo::actions`)
     pub actions: Vec<GlobalAction>,
     pub wait: bool,
     pub end: bool,
 }
 
+/// A successor of a state of the VM, as listed by `VM::fork_states`
+#[derive(Debug)]
+pub struct ForkedState<'a> {
+    /// The step leading from the state to the successor
+    pub step: ExecutionStepInfo,
+    pub vm: VM<'a>,
+}
+
 #[derive(Debug, Clone)]
 pub struct VM<'a> {
     pub globals: GlobalMemory,
     pub channels: Channels,
-    pub running_programs: Vec<RunningProgramState<'a>>,
+    /// The state of each process, shared between the VM and its successors until one of them
+    /// executes a step of the process
+    pub running_programs: Vec<Rc<RunningProgramState<'a>>>,
     pub programs_code: &'a HashMap<String, ProgramCode>,
     pub user_funcs: &'a HashMap<String, FunctionDefinition>,
     pub executable_programs: BTreeSet<usize>, // needs to be sorted to have a deterministic behavior
     pub always_conditions: &'a Vec<(HashSet<String>, Vec<String>, LocalExpressionNode, Pos)>,
+    pub never_conditions: &'a Vec<(HashSet<String>, Vec<String>, LocalExpressionNode, Pos)>,
     pub program_debug_info: &'a HashMap<String, crate::compiler::ProgramDebugInfo>,
+    /// The shared variables initialized with `rand`, whose initial value is chosen by `start`
+    initial_choices: &'a BTreeMap<String, RangeInclusive<i64>>,
 
     /// The programs that are waiting for a condition to be true
     /// The condition depends on the global variables that are in the HashSet
     waiting_programs: HashMap<usize, WaitDependency>,
+    /// Index of the waiting programs by the global variables their condition depends on,
+    /// so that a write only wakes the programs that depend on the written variable
+    waiting_on_variables: HashMap<String, HashSet<usize>>,
     next_program_id: usize,
+    /// The seed the random generator was started with (see `scheduler_seed`)
+    seed: u64,
     rng: Rng,
+    /// When set, the values of `rand` are replayed instead of drawn from `rng`, so that the
+    /// checker can enumerate them
+    random_replay: Option<RandomReplay>,
+    /// Whether the project declares synchronous channels, in which case a random step may
+    /// have to be rolled back when the receiver of a synchronous send is not ready
+    uses_sync_channels: bool,
+    /// The options given by the caller of the execution
+    options: RunOptions<'a>,
+    /// The programs that are started but never scheduled, to replay an execution with fewer
+    /// processes (see `disable_program`)
+    disabled_programs: BTreeSet<usize>,
+    /// The messages printed so far, only recorded if `RunOptions::output_in_state` is set
+    output: Vec<String>,
+    /// The states preceding the steps recorded by `step_forward_recorded`, the most recent last
+    history: VecDeque<VM<'a>>,
+    /// The maximum number of states kept in `history`
+    history_depth: usize,
 
     pub stdlib: Rc<Stdlib>,
 }
 
+/// The default number of steps that `VM::step_back` can undo
+pub const DEFAULT_HISTORY_DEPTH: usize = 1000;
+
+/// The options of an execution, chosen by its caller rather than by the compiled project
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunOptions<'a> {
+    /// When set, the integers produced by the programs are clamped to this range to keep the
+    /// state space finite
+    pub int_abstraction: Option<&'a IntAbstraction>,
+
+    /// The maximum number of instructions a process may execute in a single step, after which the
+    /// execution fails instead of looping forever in an atomic block
+    pub instr_limit_per_step: Option<usize>,
+
+    /// When set, a random execution checks the invariants after every step. By default they are
+    /// only checked after the steps that can change their value (a write to a shared variable, a
+    /// new connection, or a process started or stopped). The checker always checks every state.
+    pub check_invariants_every_step: bool,
+
+    /// When set, the output printed by the processes is part of the state, so that the checker
+    /// distinguishes states reached through different print sequences
+    pub output_in_state: bool,
+}
+
 impl<'a> VM<'a> {
-    pub fn new(compiled_project: &'a CompiledProject) -> Self {
+    #[must_use]
+    pub fn new(compiled_project: &'a CompiledProject, options: RunOptions<'a>) -> Self {
         Self {
-            globals: compiled_project.global_memory.clone(),
+            globals: match options.int_abstraction {
+                Some(abstraction) => compiled_project
+                    .global_memory
+                    .iter()
+                    .map(|(name, value)| (name.clone(), abstraction.clamp(value.clone())))
+                    .collect(),
+                None => compiled_project.global_memory.clone(),
+            },
             channels: Channels::new(),
             running_programs: Vec::new(),
             executable_programs: BTreeSet::new(),
             programs_code: &compiled_project.programs_code,
             user_funcs: &compiled_project.user_functions,
             always_conditions: &compiled_project.always_conditions,
+            never_conditions: &compiled_project.never_conditions,
             program_debug_info: &compiled_project.program_debug_info,
+            initial_choices: &compiled_project.initial_choices,
             next_program_id: 0,
             waiting_programs: HashMap::new(),
-            rng: Rng::new(),
+            waiting_on_variables: HashMap::new(),
+            seed: 0,
+            rng: Rng::with_seed(0),
+            random_replay: None,
+            uses_sync_channels: compiled_project
+                .programs_code
+                .values()
+                .map(|code| &code.instructions)
+                .chain(compiled_project.user_functions.values().map(|f| &f.body))
+                .flatten()
+                .any(|instruction| {
+                    matches!(
+                        instruction.control,
+                        InstructionType::Connect { sync: true, .. }
+                    )
+                }),
+            options,
+            disabled_programs: BTreeSet::new(),
+            output: Vec::new(),
+            history: VecDeque::new(),
+            history_depth: DEFAULT_HISTORY_DEPTH,
             stdlib: compiled_project.stdlib.clone(),
         }
     }
 
+    /// Copies the VM to explore one of its successors.
+    ///
+    /// Only the runtime state is deep-copied: the shared memory, the channels, the sets of
+    /// executable and waiting programs, the recorded output and the state of the random
+    /// generator. The running programs are shared until one of the copies executes a step of
+    /// them. The compiled code, functions, invariants and debug information are borrowed from the
+    /// compiled project and the stdlib is reference counted, so the copy shares them with the
+    /// original. The `rand` replay of the current step and the recorded history are not copied.
+    #[must_use]
+    pub fn clone_light(&self) -> Self {
+        Self {
+            globals: self.globals.clone(),
+            channels: self.channels.clone(),
+            running_programs: self.running_programs.clone(),
+            programs_code: self.programs_code,
+            user_funcs: self.user_funcs,
+            executable_programs: self.executable_programs.clone(),
+            always_conditions: self.always_conditions,
+            never_conditions: self.never_conditions,
+            program_debug_info: self.program_debug_info,
+            initial_choices: self.initial_choices,
+            waiting_programs: self.waiting_programs.clone(),
+            waiting_on_variables: self.waiting_on_variables.clone(),
+            next_program_id: self.next_program_id,
+            seed: self.seed,
+            rng: self.rng.clone(),
+            random_replay: None,
+            uses_sync_channels: self.uses_sync_channels,
+            options: self.options,
+            disabled_programs: self.disabled_programs.clone(),
+            output: self.output.clone(),
+            history: VecDeque::new(),
+            history_depth: self.history_depth,
+            stdlib: self.stdlib.clone(),
+        }
+    }
+
     fn run_program(
         &mut self,
-        program_name: &str,
+        code_name: &str,
         pid: usize,
         args: Literal,
         caller_program_id: Option<usize>,
@@ -143,25 +345,138 @@ impl<'a> VM<'a> {
             pid
         );
 
+        let code = &self.programs_code[code_name];
         let mut new_program = RunningProgramState::new(
             pid,
-            program_name.to_string(),
-            &self.programs_code[program_name],
+            code.name.clone(),
+            code,
             self.user_funcs,
             args,
             self.stdlib.clone(),
         );
 
         // Set the caller context
+        new_program.int_abstraction = self.options.int_abstraction;
+        new_program.instr_limit_per_step = self.options.instr_limit_per_step;
         new_program.caller_program_id = caller_program_id;
         new_program.call_site_pos = call_site_pos;
 
-        self.running_programs.insert(pid, new_program);
-        self.executable_programs.insert(pid);
+        self.running_programs.insert(pid, Rc::new(new_program));
+        if !self.disabled_programs.contains(&pid) {
+            self.executable_programs.insert(pid);
+        }
+    }
+
+    /// Prevents the program that will get the id `pid` from ever running: it is started (its
+    /// handle is valid) but never scheduled. Every execution of the VM is then an execution of
+    /// the original project where this process starves, so a violation found this way is real.
+    pub fn disable_program(&mut self, pid: usize) {
+        self.disabled_programs.insert(pid);
     }
 
     pub fn start(&mut self, seed: u64) {
+        self.seed = seed;
         self.rng = Rng::with_seed(seed);
+        for (name, range) in self.initial_choices {
+            let value = self.rng.i64(range.clone());
+            self.globals.insert(name.clone(), Literal::Int(value));
+        }
+        self.run_main();
+    }
+
+    /// The seed the VM was started with.
+    ///
+    /// Every random choice of the VM (the initial values of the shared variables, the scheduled
+    /// step and the values of `rand`) is drawn from a generator seeded with it, in an order that
+    /// only depends on the state: the candidate steps are listed by increasing pid, then by
+    /// channel link. Two VMs started with the same seed therefore execute the same steps, which
+    /// makes a random execution replayable from its seed. A VM that was not started reports 0.
+    #[must_use]
+    pub fn scheduler_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Sets the maximum number of steps that `step_back` can undo, forgetting the oldest recorded
+    /// states beyond it. Each recorded state is a full copy of the runtime state of the VM (see
+    /// `clone_light`), so a deep history of a large model uses a lot of memory, while a depth of
+    /// 0 disables the recording.
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history_depth = depth;
+        while self.history.len() > depth {
+            self.history.pop_front();
+        }
+    }
+
+    /// Moves to `next`, one of the successors returned by `next`, recording the current state so
+    /// that `step_back` can return to it
+    pub fn step_forward_recorded(&mut self, next: VM<'a>) {
+        let mut history = std::mem::take(&mut self.history);
+        let previous = std::mem::replace(self, next);
+        if self.history_depth > 0 {
+            if history.len() == self.history_depth {
+                history.pop_front();
+            }
+            history.push_back(previous);
+        }
+        self.history = history;
+    }
+
+    /// Whether a step recorded by `step_forward_recorded` can be undone by `step_back`
+    #[must_use]
+    pub fn can_step_back(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    /// Restores the state preceding the last step recorded by `step_forward_recorded`. Returns
+    /// false, leaving the VM unchanged, if there is no recorded step to undo.
+    pub fn step_back(&mut self) -> bool {
+        let Some(previous) = self.history.pop_back() else {
+            return false;
+        };
+        let history = std::mem::take(&mut self.history);
+        *self = previous;
+        self.history = history;
+        true
+    }
+
+    /// Starts the VM from each of its possible initial states: one per combination of the
+    /// initial values of the shared variables initialized with `rand`
+    ///
+    /// # Errors
+    /// If the initial state of a program cannot be computed
+    pub fn start_all(&self) -> AlthreadResult<Vec<Self>> {
+        let mut initial_vms = vec![self.clone_light()];
+        for (name, range) in self.initial_choices {
+            if i128::from(*range.end()) - i128::from(*range.start()) >= i128::from(MAX_RAND_CHOICES)
+            {
+                return Err(AlthreadError::new(
+                    ErrorType::RuntimeError,
+                    None,
+                    format!(
+                        "the shared variable {name} has too many possible initial values to be checked (at most {MAX_RAND_CHOICES})"
+                    ),
+                ));
+            }
+            initial_vms = initial_vms
+                .into_iter()
+                .flat_map(|vm| {
+                    range.clone().map(move |value| {
+                        let mut vm = vm.clone_light();
+                        vm.globals.insert(name.clone(), Literal::Int(value));
+                        vm
+                    })
+                })
+                .collect();
+        }
+        for vm in &mut initial_vms {
+            vm.seed = 0;
+            vm.rng = Rng::with_seed(0);
+            vm.run_main();
+        }
+        Ok(initial_vms)
+    }
+
+    fn run_main(&mut self) {
         self.next_program_id = 1;
         self.run_program("main", 0, Literal::empty_tuple(), None, None); // No caller for main
     }
@@ -174,13 +489,292 @@ impl<'a> VM<'a> {
         dependencies
             .channels_state
             .iter()
+            .filter(|channel_name| !dependencies.guarded_channels.contains(*channel_name))
             .any(|channel_name| self.channels.has_buffered_message(program_id, channel_name))
             || dependencies.channels_connection.iter().any(|channel_name| {
                 self.channels.has_connection_from(program_id, channel_name)
             })
+            || dependencies
+                .channels_space
+                .iter()
+                .any(|channel_name| !self.channels.is_full(program_id, channel_name))
+    }
+
+    /// What a program is blocked on: the dependencies it is parked with, or those of the wait or
+    /// of the send on a full channel it is about to execute. `None` if it is not at a wait.
+    #[must_use]
+    pub fn wait_dependency(&self, pid: usize) -> Option<WaitDependency> {
+        if let Some(dependencies) = self.waiting_programs.get(&pid) {
+            return Some(dependencies.clone());
+        }
+        match &self.running_programs.get(pid)?.current_instruction().ok()?.control {
+            InstructionType::WaitStart { dependencies, .. } => Some(dependencies.clone()),
+            InstructionType::Send { channel_name, .. }
+                if self.channels.is_full(pid, channel_name) =>
+            {
+                let mut dependencies = WaitDependency::new();
+                dependencies.channels_space.insert(channel_name.clone());
+                Some(dependencies)
+            }
+            _ => None,
+        }
+    }
+
+    /// Moves a program from the executable set to the waiting set
+    fn park_program(&mut self, pid: usize, dependencies: WaitDependency) {
+        self.executable_programs.remove(&pid);
+        for var_name in &dependencies.variables {
+            self.waiting_on_variables
+                .entry(var_name.clone())
+                .or_default()
+                .insert(pid);
+        }
+        self.waiting_programs.insert(pid, dependencies);
+    }
+
+    /// Removes a program from the waiting set, returning what it was waiting for
+    fn unpark_program(&mut self, pid: usize) -> Option<WaitDependency> {
+        let dependencies = self.waiting_programs.remove(&pid)?;
+        for var_name in &dependencies.variables {
+            if let Some(pids) = self.waiting_on_variables.get_mut(var_name) {
+                pids.remove(&pid);
+                if pids.is_empty() {
+                    self.waiting_on_variables.remove(var_name);
+                }
+            }
+        }
+        Some(dependencies)
+    }
+
+    /// Moves a waiting program back to the executable set
+    fn wake_program(&mut self, pid: usize) {
+        if self.unpark_program(pid).is_some() {
+            self.executable_programs.insert(pid);
+        }
+    }
+
+    /// Wakes every waiting program whose condition reads `var_name`
+    fn wake_variable_dependents(&mut self, var_name: &str) {
+        if let Some(pids) = self.waiting_on_variables.get(var_name).cloned() {
+            for pid in pids {
+                self.wake_program(pid);
+            }
+        }
+    }
+
+    /// Wakes every waiting program whose condition joins a program
+    fn wake_termination_dependents(&mut self) {
+        let pids: Vec<usize> = self
+            .waiting_programs
+            .iter()
+            .filter(|(_, dependencies)| dependencies.program_termination)
+            .map(|(pid, _)| *pid)
+            .collect();
+        for pid in pids {
+            self.wake_program(pid);
+        }
+    }
+
+    /// Wakes every program whose send waits on a channel that is no longer full
+    fn wake_space_dependents(&mut self) {
+        let pids: Vec<usize> = self
+            .waiting_programs
+            .iter()
+            .filter(|(pid, dependencies)| {
+                dependencies
+                    .channels_space
+                    .iter()
+                    .any(|channel_name| !self.channels.is_full(**pid, channel_name))
+            })
+            .map(|(pid, _)| *pid)
+            .collect();
+        for pid in pids {
+            self.wake_program(pid);
+        }
+    }
+
+    /// The ids of the programs that have reached their end
+    fn terminated_programs(&self) -> BTreeSet<usize> {
+        self.running_programs
+            .iter()
+            .filter(|program| program.has_terminated())
+            .map(|program| program.id)
+            .collect()
+    }
+
+    /// Builds the `Deliver` action of a delivered message, waking the receiver if it waits
+    /// for a message on the channel
+    fn delivery_action(&mut self, delivery_info: DeliveryInfo) -> GlobalAction {
+        if let Some(dependency) = self.waiting_programs.get(&delivery_info.to.program_id) {
+            if dependency
+                .channels_state
+                .contains(&delivery_info.to.channel_name)
+            {
+                self.wake_program(delivery_info.to.program_id);
+            }
+        }
+
+        let from_name = self
+            .running_programs
+            .get(delivery_info.from_program_id)
+            .map_or_else(
+                || format!("PID_{}", delivery_info.from_program_id),
+                |p| p.name.clone(),
+            );
+        let to_name = self
+            .running_programs
+            .get(delivery_info.to.program_id)
+            .map_or_else(
+                || format!("PID_{}", delivery_info.to.program_id),
+                |p| p.name.clone(),
+            );
+
+        let (sender_id, sender_clock, _content) = crate::vm::channels::parse_message_tuple(
+            &delivery_info.message,
+        )
+        .unwrap_or((delivery_info.from_program_id, 0, String::new()));
+
+        GlobalAction::Deliver(crate::vm::DeliverInfo {
+            from: crate::vm::ProcessInfo {
+                process_id: sender_id,
+                process_name: from_name,
+            },
+            to: crate::vm::ProcessInfo {
+                process_id: delivery_info.to.program_id,
+                process_name: to_name,
+            },
+            channel_name: delivery_info.to.channel_name,
+            message: delivery_info.message,
+            sender_clock,
+        })
+    }
+
+    /// Completes the rendezvous of the messages sent on synchronous channels during a step:
+    /// each message is delivered and its receiver immediately takes a step, which must consume
+    /// it. Returns false if a receiver is not ready, the step is then not possible.
+    fn complete_rendezvous(&mut self, exec_info: &mut ExecutionStepInfo) -> AlthreadResult<bool> {
+        let sends: Vec<(usize, String)> = exec_info
+            .actions
+            .iter()
+            .flat_map(|action| match action {
+                GlobalAction::Send(info) => std::slice::from_ref(info),
+                GlobalAction::Broadcast(infos) => infos.as_slice(),
+                _ => &[],
+            })
+            .map(|info| (info.from.process_id, info.to.channel_name.clone()))
+            .collect();
+
+        for (from_pid, channel_name) in sends {
+            let Some(link) = self.channels.sync_link(from_pid, &channel_name) else {
+                continue;
+            };
+            let Some(delivery_info) = self.channels.deliver_rendezvous(link) else {
+                return Ok(false);
+            };
+            let to_pid = delivery_info.to.program_id;
+            let to_channel = delivery_info.to.channel_name.clone();
+            let message = delivery_info.message.clone();
+            let deliver_action = self.delivery_action(delivery_info);
+
+            if to_pid == from_pid || !self.executable_programs.contains(&to_pid) {
+                return Ok(false);
+            }
+            let Some(mut receiver_info) = self.next_step_pid(to_pid)? else {
+                return Ok(false);
+            };
+            if self.channels.has_message(to_pid, &to_channel, &message)
+                || !self.complete_rendezvous(&mut receiver_info)?
+            {
+                return Ok(false);
+            }
+
+            exec_info.actions.push(deliver_action);
+            exec_info.actions.extend(receiver_info.actions);
+            exec_info.instructions.extend(receiver_info.instructions);
+        }
+        Ok(true)
+    }
+
+    /// The error describing a state where no process can make progress: each `waiting` process,
+    /// with the line it is blocked on and the events it waits for, then the `blocked` senders
+    fn deadlock_error(
+        &self,
+        waiting: &[(usize, WaitDependency)],
+        blocked: impl Iterator<Item = usize>,
+    ) -> AlthreadError {
+        let name = |pid: usize| {
+            self.running_programs
+                .get(pid)
+                .map(|program| program.name.clone())
+                .unwrap_or_default()
+        };
+        AlthreadError::new(
+            ErrorType::DeadlockError,
+            None,
+            format!(
+                "All programs are waiting, deadlock:\n{}",
+                waiting
+                    .iter()
+                    .map(|(pid, dependency)| {
+                        let line = self.running_programs[*pid]
+                            .current_instruction()
+                            .ok()
+                            .and_then(|instruction| instruction.pos.as_ref())
+                            .map_or_else(|| "?".to_string(), |pos| pos.line.to_string());
+                        format!(
+                            "-{}#{} at line {}: waiting for {}",
+                            name(*pid),
+                            pid,
+                            line,
+                            dependency.describe(|variable| self.globals.contains_key(variable))
+                        )
+                    })
+                    .chain(blocked.map(|pid| format!(
+                        "-{}#{}: blocked on a synchronous send",
+                        name(pid),
+                        pid
+                    )))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+        )
+    }
+
+    /// The deadlock of a state without successors, in which at least one process waits for an
+    /// event that can no longer happen and the others are blocked on a synchronous send. `None`
+    /// if every process has terminated, or if a process is not blocked (e.g. its step is pruned
+    /// by an `assume`).
+    #[must_use]
+    pub fn deadlock(&self) -> Option<AlthreadError> {
+        let mut waiting = Vec::new();
+        let mut blocked = Vec::new();
+        for program in &self.running_programs {
+            if program.has_terminated() {
+                continue;
+            }
+            match self.wait_dependency(program.id) {
+                Some(dependency) => waiting.push((program.id, dependency)),
+                None => match program.current_instruction().ok()?.control {
+                    InstructionType::Send { .. } => blocked.push(program.id),
+                    _ => return None,
+                },
+            }
+        }
+        if waiting.is_empty() {
+            return None;
+        }
+        Some(self.deadlock_error(&waiting, blocked.into_iter()))
     }
 
     pub fn next_random(&mut self) -> AlthreadResult<ExecutionStepInfo> {
+        self.next_random_excluding(&mut BTreeSet::new())
+    }
+
+    /// Executes a random step among the programs that are not blocked on a synchronous send
+    fn next_random_excluding(
+        &mut self,
+        blocked_senders: &mut BTreeSet<usize>,
+    ) -> AlthreadResult<ExecutionStepInfo> {
         enum Candidate {
             Program(usize),
             Delivery(ChannelLinkKey),
@@ -190,6 +784,7 @@ impl<'a> VM<'a> {
             .executable_programs
             .iter()
             .copied()
+            .filter(|pid| !blocked_senders.contains(pid))
             .map(Candidate::Program)
             .collect();
 
@@ -201,32 +796,13 @@ impl<'a> VM<'a> {
         );
 
         if candidates.is_empty() {
-            return Err(AlthreadError::new(
-                ErrorType::RuntimeError,
-                None,
-                format!(
-                    "All programs are waiting, deadlock:\n{}",
-                    self.waiting_programs
-                        .iter()
-                        .map(|(id, dep)| format!(
-                            "-{}#{} at line {}: {:?}",
-                            self.running_programs.get(*id).unwrap().name,
-                            id,
-                            self.running_programs
-                                .get(*id)
-                                .unwrap()
-                                .current_instruction()
-                                .unwrap()
-                                .pos
-                                .as_ref()
-                                .unwrap()
-                                .line,
-                            dep
-                        ))
-                        .collect::<Vec<_>>()
-                        .join("\n")
-                ),
-            ));
+            let mut waiting: Vec<(usize, WaitDependency)> = self
+                .waiting_programs
+                .iter()
+                .map(|(pid, dependency)| (*pid, dependency.clone()))
+                .collect();
+            waiting.sort_by_key(|(pid, _)| *pid);
+            return Err(self.deadlock_error(&waiting, blocked_senders.iter().copied()));
         }
 
         let choice_idx = self.rng.usize(0..candidates.len());
@@ -238,53 +814,23 @@ impl<'a> VM<'a> {
                 .channels
                 .deliver_one(link)
                 .expect("pending link must have a deliverable message");
-            if let Some(dependency) = self.waiting_programs.get(&delivery_info.to.program_id) {
-                if dependency
-                    .channels_state
-                    .contains(&delivery_info.to.channel_name)
-                {
-                    self.waiting_programs.remove(&delivery_info.to.program_id);
-                    self.executable_programs.insert(delivery_info.to.program_id);
-                }
-            }
-
-            let from_name = self
-                .running_programs
-                .get(delivery_info.from_program_id)
-                .map(|p| p.name.clone())
-                .unwrap_or_else(|| format!("PID_{}", delivery_info.from_program_id));
-            let to_name = self
-                .running_programs
-                .get(delivery_info.to.program_id)
-                .map(|p| p.name.clone())
-                .unwrap_or_else(|| format!("PID_{}", delivery_info.to.program_id));
-
-            let (sender_id, sender_clock, _content) = crate::vm::channels::parse_message_tuple(
-                &delivery_info.message,
-            )
-            .unwrap_or((delivery_info.from_program_id, 0, "".to_string()));
+            let prog_name = format!(
+                "__deliver__ {}#{}",
+                delivery_info.to.channel_name, delivery_info.to.program_id
+            );
+            let prog_id = delivery_info.to.program_id;
+            let action = self.delivery_action(delivery_info);
 
             return Ok(ExecutionStepInfo {
-                prog_name: format!(
-                    "__deliver__ {}#{}",
-                    delivery_info.to.channel_name, delivery_info.to.program_id
-                ),
-                prog_id: delivery_info.to.program_id,
+                prog_name,
+                prog_id,
                 instructions: Vec::new(),
-                invariant_error: Ok(0),
-                actions: vec![GlobalAction::Deliver(crate::vm::DeliverInfo {
-                    from: crate::vm::ProcessInfo {
-                        process_id: sender_id,
-                        process_name: from_name,
-                    },
-                    to: crate::vm::ProcessInfo {
-                        process_id: delivery_info.to.program_id,
-                        process_name: to_name,
-                    },
-                    channel_name: delivery_info.to.channel_name,
-                    message: delivery_info.message,
-                    sender_clock,
-                })],
+                invariant_error: if self.options.check_invariants_every_step {
+                    self.check_invariants()
+                } else {
+                    Ok(0)
+                },
+                actions: vec![action],
             });
         }
 
@@ -292,10 +838,15 @@ impl<'a> VM<'a> {
             unreachable!("delivery handled above")
         };
 
-        let program = self
-            .running_programs
-            .get_mut(program_id)
-            .expect("program is executable but not found in running programs");
+        // the step is rolled back if a synchronous send cannot find its receiver ready
+        let backup = self.uses_sync_channels.then(|| self.clone());
+
+        let terminated_programs = self.terminated_programs();
+        let program = Rc::make_mut(
+            self.running_programs
+                .get_mut(program_id)
+                .expect("program is executable but not found in running programs"),
+        );
 
         let mut exec_info = ExecutionStepInfo {
             prog_name: program.name.clone(),
@@ -305,10 +856,16 @@ impl<'a> VM<'a> {
             invariant_error: Ok(0),
         };
 
+        let mut random = match &mut self.random_replay {
+            Some(replay) => RandomSource::Replay(replay),
+            None => RandomSource::Rng(&mut self.rng),
+        };
         let (actions, executed_instructions) = program.next_global(
             &mut self.globals,
             &mut self.channels,
             &mut self.next_program_id,
+            &terminated_programs,
+            &mut random,
         )?;
         // maybe should be replace to avoid recurrent calls
         if actions.wait {
@@ -327,19 +884,23 @@ impl<'a> VM<'a> {
                 .control
             {
                 InstructionType::WaitStart { dependencies, .. } => dependencies.clone(),
-                _ => unreachable!("waiting on an instruction that is not a WaitStart instruction"),
+                InstructionType::Send { channel_name, .. } => {
+                    let mut dependencies = WaitDependency::new();
+                    dependencies.channels_space.insert(channel_name.clone());
+                    dependencies
+                }
+                _ => unreachable!("waiting on an instruction that is neither a WaitStart nor a Send"),
             };
 
             if self.wait_dependencies_satisfied_now(program_id, &dependencies) {
-                return self.next_random();
+                return self.next_random_excluding(blocked_senders);
             }
 
-            self.executable_programs.remove(&program_id);
-            self.waiting_programs.insert(program_id, dependencies);
-            return self.next_random();
+            self.park_program(program_id, dependencies);
+            return self.next_random_excluding(blocked_senders);
         }
 
-        let mut need_to_check_invariants = false;
+        let mut need_to_check_invariants = self.options.check_invariants_every_step;
 
         for action in actions.actions.iter() {
             match action {
@@ -354,20 +915,16 @@ impl<'a> VM<'a> {
                     // specific connection. Otherwise it can be safely ignored.
                     if let Some(dependency) = self.waiting_programs.get(sender_id) {
                         if dependency.channels_connection.contains(sender_channel) {
-                            self.waiting_programs.remove(sender_id);
-                            self.executable_programs.insert(*sender_id);
+                            self.wake_program(*sender_id);
                         }
                     }
+
+                    // the invariants can depend on the topology through `connected`
+                    need_to_check_invariants = true;
                 }
                 GlobalAction::Write(var_name) => {
-                    // Check if the variable appears in the conditions of a waiting program
-                    self.waiting_programs.retain(|prog_id, dependencies| {
-                        if dependencies.variables.contains(var_name) {
-                            self.executable_programs.insert(*prog_id);
-                            return false;
-                        }
-                        true
-                    });
+                    // Wake the waiting programs whose condition depends on the variable
+                    self.wake_variable_dependents(var_name);
 
                     need_to_check_invariants = true;
                 }
@@ -379,20 +936,53 @@ impl<'a> VM<'a> {
                         *caller_program_id,
                         call_site_pos.clone(),
                     );
+
+                    // the invariants can depend on the running processes through `$.procs`
+                    need_to_check_invariants = true;
                 }
                 GlobalAction::EndProgram => {
                     panic!("EndProgram action should not be in the list of actions");
                 }
-                GlobalAction::Exit => self.running_programs.clear(),
-                GlobalAction::Print(_) => {} // do nothing, this is just a print action
+                GlobalAction::Exit => {
+                    self.running_programs.clear();
+                    need_to_check_invariants = true;
+                }
+                GlobalAction::Print(message) => {
+                    if self.options.output_in_state {
+                        self.output.push(message.clone());
+                    }
+                }
                 GlobalAction::Send(_) => {}  // do nothing, sending is already handled
                 GlobalAction::Broadcast(_) => {} 
             }
         }
+        if executed_instructions
+            .iter()
+            .any(|instruction| matches!(instruction.control, InstructionType::ChannelPop(_)))
+        {
+            // a received message frees space in its channel
+            self.wake_space_dependents();
+        }
         if actions.end {
             let remove_id = program_id;
             self.executable_programs.remove(&remove_id);
-            self.waiting_programs.remove(&remove_id);
+            self.unpark_program(remove_id);
+            self.wake_termination_dependents();
+        }
+
+        exec_info.instructions = executed_instructions;
+        exec_info.actions = actions.actions;
+
+        if let Some(backup) = backup {
+            if !self.complete_rendezvous(&mut exec_info)? {
+                *self = backup;
+                blocked_senders.insert(program_id);
+                return self.next_random_excluding(blocked_senders);
+            }
+            need_to_check_invariants |= exec_info
+                .actions
+                .iter()
+                .any(|action| matches!(action, GlobalAction::Write(_)));
         }
 
         // TODO this method should be modified so eventually violation generate an error,
@@ -401,17 +991,16 @@ impl<'a> VM<'a> {
             exec_info.invariant_error = self.check_invariants();
         }
 
-        exec_info.instructions = executed_instructions;
-        exec_info.actions = actions.actions;
-
         Ok(exec_info)
     }
 
     pub fn next_step_pid(&mut self, pid: usize) -> AlthreadResult<Option<ExecutionStepInfo>> {
-        let program = self
-            .running_programs
-            .get_mut(pid)
-            .expect("program is executable but not found in running programs");
+        let terminated_programs = self.terminated_programs();
+        let program = Rc::make_mut(
+            self.running_programs
+                .get_mut(pid)
+                .expect("program is executable but not found in running programs"),
+        );
 
         if program.has_terminated() {
             return Ok(None);
@@ -425,10 +1014,16 @@ impl<'a> VM<'a> {
             invariant_error: Ok(0),
         };
 
+        let mut random = match &mut self.random_replay {
+            Some(replay) => RandomSource::Replay(replay),
+            None => RandomSource::Rng(&mut self.rng),
+        };
         let (actions, executed_instructions) = program.next_global(
             &mut self.globals,
             &mut self.channels,
             &mut self.next_program_id,
+            &terminated_programs,
+            &mut random,
         )?;
         // maybe should be replace to avoid recurrent calls
         if actions.wait {
@@ -447,22 +1042,23 @@ impl<'a> VM<'a> {
                 .control
             {
                 InstructionType::WaitStart { dependencies, .. } => dependencies.clone(),
-                _ => unreachable!("waiting on an instruction that is not a WaitStart instruction"),
+                InstructionType::Send { channel_name, .. } => {
+                    let mut dependencies = WaitDependency::new();
+                    dependencies.channels_space.insert(channel_name.clone());
+                    dependencies
+                }
+                _ => unreachable!("waiting on an instruction that is neither a WaitStart nor a Send"),
             };
 
             if self.wait_dependencies_satisfied_now(pid, &dependencies) {
                 return self.next_step_pid(pid);
             }
 
-            self.executable_programs.remove(&pid);
-            self.waiting_programs.insert(pid, dependencies);
+            self.park_program(pid, dependencies);
             return Ok(None);
         }
 
-        // Store actions before processing them
-        exec_info.actions = actions.actions.clone();
-
-        for action in actions.actions {
+        for action in &actions.actions {
             match action {
                 GlobalAction::Wait => {
                     unreachable!("await action should not be in the list of actions");
@@ -473,41 +1069,53 @@ impl<'a> VM<'a> {
                 GlobalAction::Connect(sender_id, sender_channel) => {
                     // Connect is only relevant if the sender is currently blocked on that
                     // specific connection. Otherwise it can be safely ignored.
-                    if let Some(dependency) = self.waiting_programs.get(&sender_id) {
-                        if dependency.channels_connection.contains(&sender_channel) {
-                            self.waiting_programs.remove(&sender_id);
-                            self.executable_programs.insert(sender_id);
+                    if let Some(dependency) = self.waiting_programs.get(sender_id) {
+                        if dependency.channels_connection.contains(sender_channel) {
+                            self.wake_program(*sender_id);
                         }
                     }
                 }
                 GlobalAction::Write(var_name) => {
-                    // Check if the variable appears in the conditions of a waiting program
-                    self.waiting_programs.retain(|prog_id, dependencies| {
-                        if dependencies.variables.contains(&var_name) {
-                            self.executable_programs.insert(*prog_id);
-                            return false;
-                        }
-                        true
-                    });
+                    // Wake the waiting programs whose condition depends on the variable
+                    self.wake_variable_dependents(var_name);
                 }
                 GlobalAction::StartProgram(name, pid, args, caller_program_id, call_site_pos) => {
-                    self.run_program(&name, pid, args, caller_program_id, call_site_pos);
+                    self.run_program(
+                        name,
+                        *pid,
+                        args.clone(),
+                        *caller_program_id,
+                        call_site_pos.clone(),
+                    );
                 }
                 GlobalAction::EndProgram => {
                     panic!("EndProgram action should not be in the list of actions");
                 }
                 GlobalAction::Exit => self.running_programs.clear(),
-                GlobalAction::Print(_) => {} // do nothing, this is just a print action
+                GlobalAction::Print(message) => {
+                    if self.options.output_in_state {
+                        self.output.push(message.clone());
+                    }
+                }
                 GlobalAction::Send(_) => {}  // do nothing, sending is already handled
                 GlobalAction::Broadcast(_) => {} 
             }
         }
+        if executed_instructions
+            .iter()
+            .any(|instruction| matches!(instruction.control, InstructionType::ChannelPop(_)))
+        {
+            // a received message frees space in its channel
+            self.wake_space_dependents();
+        }
         if actions.end {
             let remove_id = pid;
             self.executable_programs.remove(&remove_id);
-            self.waiting_programs.remove(&remove_id);
+            self.unpark_program(remove_id);
+            self.wake_termination_dependents();
         }
 
+        exec_info.actions = actions.actions;
         exec_info.instructions = executed_instructions;
 
         Ok(Some(exec_info))
@@ -523,11 +1131,76 @@ impl<'a> VM<'a> {
     pub fn next(
         &self,
     ) -> AlthreadResult<Vec<(String, usize, Vec<Instruction>, Vec<GlobalAction>, VM<'a>)>> {
-        if self.running_programs.len() == 0 {
-            return Ok(Vec::new());
-        }
+        Ok(self
+            .fork_states()?
+            .into_iter()
+            .map(|ForkedState { step, vm }| {
+                (step.prog_name, step.prog_id, step.instructions, step.actions, vm)
+            })
+            .collect())
+    }
 
+    /// Lists the successors of the state, each with the step leading to it.
+    ///
+    /// A successor only owns a copy of what its step can modify: the processes are shared with
+    /// this VM until they execute a step (see `running_programs`), and the compiled code is
+    /// borrowed from the project, so forking a state with many processes stays cheap.
+    ///
+    /// # Errors
+    /// If a step of a program fails
+    pub fn fork_states(&self) -> AlthreadResult<Vec<ForkedState<'a>>> {
         let mut next_states = Vec::new();
+        self.visit_next_steps(&mut |step, vm| {
+            next_states.push(ForkedState { step, vm });
+            ControlFlow::Continue(())
+        })?;
+        Ok(next_states)
+    }
+
+    /// Takes the `index`-th of the steps listed by `next`, in place. Only the steps up to the
+    /// chosen one are computed, which makes it cheaper than `next` to follow a recorded choice.
+    ///
+    /// # Errors
+    /// If the chosen step does not exist or fails
+    pub fn next_with_choice(&mut self, index: usize) -> AlthreadResult<ExecutionStepInfo> {
+        let mut remaining = index;
+        let mut chosen = None;
+        self.visit_next_steps(&mut |step, vm| {
+            if remaining > 0 {
+                remaining -= 1;
+                return ControlFlow::Continue(());
+            }
+            chosen = Some((step, vm));
+            ControlFlow::Break(())
+        })?;
+
+        let Some((mut step, vm)) = chosen else {
+            return Err(AlthreadError::new(
+                ErrorType::RuntimeError,
+                None,
+                format!(
+                    "cannot take the step {}, only {} steps are possible",
+                    index,
+                    index - remaining
+                ),
+            ));
+        };
+        *self = vm;
+        step.invariant_error = self.check_invariants();
+        Ok(step)
+    }
+
+    /// Enumerates the steps that can be taken from the current state with the state each one
+    /// leads to: the steps of the executable programs by increasing pid (one per sequence of
+    /// values their `rand` calls can return), then the message deliveries. The enumeration
+    /// stops when `visit` breaks.
+    fn visit_next_steps(
+        &self,
+        visit: &mut dyn FnMut(ExecutionStepInfo, VM<'a>) -> ControlFlow<()>,
+    ) -> AlthreadResult<()> {
+        if self.running_programs.is_empty() {
+            return Ok(());
+        }
 
         // for each non-waiting program, execute the next instruction and store the result
         for program_id in self.executable_programs.iter() {
@@ -537,77 +1210,85 @@ impl<'a> VM<'a> {
                 continue;
             }
 
-            let mut vm = self.clone();
-            if let Some(result) = vm.next_step_pid(program.id)? {
-                next_states.push((
-                    program.name.clone(),
-                    program.id,
-                    result.instructions,
-                    result.actions,
-                    vm,
-                ));
+            // the step is replayed for every sequence of values its `rand` calls can return
+            let mut replays = VecDeque::from([Vec::new()]);
+            while let Some(values) = replays.pop_front() {
+                let mut vm = self.clone_light();
+                vm.random_replay = Some(RandomReplay::new(values));
+                let step = vm.next_step_pid(program.id).and_then(|result| match result {
+                    Some(mut result) => Ok(vm.complete_rendezvous(&mut result)?.then_some(result)),
+                    None => Ok(None),
+                });
+                let replay = vm.random_replay.take().expect("the replay is set above");
+                replays.extend(replay.alternatives());
+                match step {
+                    Ok(Some(result)) => {
+                        if visit(result, vm).is_break() {
+                            return Ok(());
+                        }
+                    }
+                    // the program is waiting, or the receiver of a synchronous send is not ready
+                    Ok(None) => {}
+                    // an `assume` does not hold, the path is pruned
+                    Err(e) if matches!(e.error_type, ErrorType::AssumptionViolated) => {}
+                    Err(e) => return Err(e),
+                }
             }
         }
 
         // message deliveries are also schedulable steps
         for link in self.channels.pending_links().into_iter() {
-            let mut vm = self.clone();
+            let mut vm = self.clone_light();
             let delivery_info = vm
                 .channels
                 .deliver_one(link)
                 .expect("pending link must have a deliverable message");
+            let prog_name = format!(
+                "__deliver__ {}#{}",
+                delivery_info.to.channel_name, delivery_info.to.program_id
+            );
+            let prog_id = delivery_info.to.program_id;
+            let action = vm.delivery_action(delivery_info);
 
-            if let Some(dependency) = vm.waiting_programs.get(&delivery_info.to.program_id) {
-                if dependency
-                    .channels_state
-                    .contains(&delivery_info.to.channel_name)
-                {
-                    vm.waiting_programs.remove(&delivery_info.to.program_id);
-                    vm.executable_programs.insert(delivery_info.to.program_id);
-                }
+            let step = ExecutionStepInfo {
+                prog_name,
+                prog_id,
+                instructions: Vec::new(),
+                invariant_error: Ok(0),
+                actions: vec![action],
+            };
+            if visit(step, vm).is_break() {
+                return Ok(());
             }
-
-            let from_name = vm
-                .running_programs
-                .get(delivery_info.from_program_id)
-                .map(|p| p.name.clone())
-                .unwrap_or_else(|| format!("PID_{}", delivery_info.from_program_id));
-            let to_name = vm
-                .running_programs
-                .get(delivery_info.to.program_id)
-                .map(|p| p.name.clone())
-                .unwrap_or_else(|| format!("PID_{}", delivery_info.to.program_id));
-
-            let (sender_id, sender_clock, _content) = crate::vm::channels::parse_message_tuple(
-                &delivery_info.message,
-            )
-            .unwrap_or((delivery_info.from_program_id, 0, "".to_string()));
-
-            next_states.push((
-                format!(
-                    "__deliver__ {}#{}",
-                    delivery_info.to.channel_name, delivery_info.to.program_id
-                ),
-                delivery_info.to.program_id,
-                Vec::new(),
-                vec![GlobalAction::Deliver(crate::vm::DeliverInfo {
-                    from: crate::vm::ProcessInfo {
-                        process_id: sender_id,
-                        process_name: from_name,
-                    },
-                    to: crate::vm::ProcessInfo {
-                        process_id: delivery_info.to.program_id,
-                        process_name: to_name,
-                    },
-                    channel_name: delivery_info.to.channel_name,
-                    message: delivery_info.message,
-                    sender_clock,
-                })],
-                vm,
-            ));
         }
 
-        Ok(next_states)
+        Ok(())
+    }
+
+    /// A rough estimate of the memory used by the VM, in bytes: the shared memory, the channels
+    /// and the memory of the processes are counted, but not the content of strings and lists
+    #[must_use]
+    pub fn estimated_size(&self) -> usize {
+        let literal = std::mem::size_of::<Literal>();
+        let globals: usize = self
+            .globals
+            .keys()
+            .map(|name| std::mem::size_of::<String>() + name.len() + literal)
+            .sum();
+        let channels: usize = self
+            .channels
+            .state()
+            .values()
+            .map(|messages| std::mem::size_of::<(usize, String)>() + messages.len() * literal)
+            .sum();
+        let programs: usize = self
+            .running_programs
+            .iter()
+            .map(|program| {
+                std::mem::size_of::<RunningProgramState>() + program.current_state().0.len() * literal
+            })
+            .sum();
+        std::mem::size_of::<Self>() + globals + channels + programs
     }
 
     pub fn is_finished(&self) -> bool {
@@ -636,58 +1317,157 @@ impl<'a> VM<'a> {
         (&self.globals, self.channels.state(), local_states)
     }
 
-    //42 this checks invariants (the always block conditions)
+    /// The current state as lines of text: the shared variables, the content of the channels
+    /// and the memory (after the instruction pointer) of each process. The values are rendered with their `Display`, as in the
+    /// rest of the user-facing output.
+    #[must_use]
+    pub fn state_lines(&self) -> Vec<String> {
+        let (globals, channels, local_states) = self.current_state();
+        let mut lines = vec![format!(
+            "global: {{{}}}",
+            globals
+                .iter()
+                .map(|(name, value)| format!("{name}: {value}"))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )];
+        for ((pid, cname), state) in channels {
+            lines.push(format!("channel {pid},{cname}"));
+            for v in state {
+                lines.push(format!("  * {v}"));
+            }
+        }
+        for (pid, (memory, instruction_pointer, _)) in local_states.iter().enumerate() {
+            lines.push(format!(
+                "{} ({}): {}",
+                pid,
+                instruction_pointer,
+                memory
+                    .iter()
+                    .map(std::string::ToString::to_string)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ));
+        }
+        lines
+    }
+
+    //42 this checks invariants (the always and never block conditions)
     // return OK(1) if all invariants hold
     pub fn check_invariants(&self) -> AlthreadResult<i32> {
+        match self.violated_invariants().into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(1),
+        }
+    }
+
+    /// Returns an error for each invariant that does not hold in the current state
+    #[must_use]
+    pub fn violated_invariants(&self) -> Vec<AlthreadError> {
+        let mut violated = Vec::new();
         for (_deps, read_vars, expr, pos) in self.always_conditions.iter() {
-            //if _deps.contains(&var_name) { //TODO improve by checking if the variable is in the dependencies
-            // Check if the condition is true
-            // create a small memory stack with the value of the variables
-            let mut memory = Vec::new();
-            for var_name in read_vars.iter() {
-                if let Some(proc_name) = var_name.strip_prefix("$.procs.") {
-                    let values = self
-                        .running_programs
-                        .iter()
-                        .filter(|p| p.name == proc_name)
-                        .map(|p| Literal::Process(p.name.clone(), p.id))
-                        .collect::<Vec<_>>();
-                    memory.push(Literal::List(
-                        DataType::Process(proc_name.to_string()),
-                        values,
+            match self.eval_condition(read_vars, expr) {
+                Ok(cond) => {
+                    if !cond.is_true() {
+                        violated.push(AlthreadError::new(
+                            ErrorType::InvariantError,
+                            Some(pos.clone()),
+                            "The invariant is not respected".to_string(),
+                        ));
+                    }
+                }
+                Err(e) => {
+                    violated.push(AlthreadError::new(
+                        ErrorType::ExpressionError,
+                        Some(pos.clone()),
+                        e,
                     ));
-                } else {
-                    memory.push(
-                        self.globals
-                            .get(var_name)
-                            .expect(format!("global variable '{}' not found", var_name).as_str())
-                            .clone(),
-                    );
                 }
             }
-            match expr.eval_with_scope(&memory, read_vars, self) {
+        }
+        for (_deps, read_vars, expr, pos) in self.never_conditions {
+            match self.eval_condition(read_vars, expr) {
                 Ok(cond) => {
-                    if !cond.is_true() {
-                        return Err(AlthreadError::new(
+                    if cond.is_true() {
+                        violated.push(AlthreadError::new(
                             ErrorType::InvariantError,
                             Some(pos.clone()),
-                            "The invariant is not respected".to_string(),
+                            "The 'never' condition was satisfied".to_string(),
                         ));
                     }
                 }
                 Err(e) => {
-                    return Err(AlthreadError::new(
+                    violated.push(AlthreadError::new(
                         ErrorType::ExpressionError,
                         Some(pos.clone()),
                         e,
                     ));
                 }
             }
+        }
+        violated
+    }
+
+    /// Evaluates an expression over the global variables listed in `read_vars`
+    /// (in the same layout as the compiled `always` conditions) in the current state.
+    ///
+    /// # Errors
+    /// If the condition cannot be evaluated
+    pub fn eval_condition(
+        &self,
+        read_vars: &[String],
+        expr: &LocalExpressionNode,
+    ) -> Result<Literal, String> {
+        // create a small memory stack with the value of the variables
+        let mut memory = Vec::new();
+        for var_name in read_vars {
+            if let Some(proc_name) = var_name.strip_prefix("$.procs.") {
+                let values = self
+                    .running_programs
+                    .iter()
+                    .filter(|p| p.name == proc_name)
+                    .map(|p| Literal::Process(p.name.clone(), p.id))
+                    .collect::<Vec<_>>();
+                memory.push(Literal::List(
+                    DataType::Process(proc_name.to_string()),
+                    values,
+                ));
+            } else {
+                memory.push(
+                    self.globals
+                        .get(var_name)
+                        .ok_or_else(|| format!("global variable '{var_name}' not found"))?
+                        .clone(),
+                );
+            }
+        }
+        expr.eval_with_scope(&memory, read_vars, self)
+    }
 
-            //}
+    /// Runs a `@pure` user function on a scratch program, without touching the state of the VM.
+    /// This is used to evaluate function calls inside conditions.
+    ///
+    /// # Errors
+    /// If the function does not exist or fails
+    pub fn call_pure_function(&self, name: &str, args: Literal) -> Result<Literal, String> {
+        let func_def = self
+            .user_funcs
+            .get(name)
+            .ok_or_else(|| format!("undefined function {name}"))?;
+        if !func_def.is_pure {
+            return Err(format!(
+                "function '{name}' is not marked @pure and cannot be evaluated in a condition"
+            ));
         }
 
-        Ok(1)
+        run_pure_function(
+            self.user_funcs,
+            self.stdlib.clone(),
+            &self.globals,
+            self.next_program_id,
+            name,
+            args,
+        )
     }
 }
 
@@ -724,7 +1504,10 @@ impl<'a> Hash for VM<'a> {
             waiting.insert(k, v);
         }
         waiting.hash(state);
+        self.channels.get_sync_links().hash(state);
         self.running_programs.hash(state);
+        // empty unless the output is part of the state
+        self.output.hash(state);
     }
 
     fn hash_slice<H: Hasher>(data: &[Self], state: &mut H)
@@ -754,6 +1537,12 @@ impl std::cmp::PartialEq for VM<'_> {
         if self.channels.get_waiting_send() != other.channels.get_waiting_send() {
             return false;
         }
+        if self.channels.get_sync_links() != other.channels.get_sync_links() {
+            return false;
+        }
+        if self.output != other.output {
+            return false;
+        }
         self.running_programs == other.running_programs && self.programs_code == other.programs_code
     }
 }
@@ -769,6 +1558,12 @@ mod tests {
     use super::*;
 
     fn compile_vm(source: &str) -> VM<'static> {
+        let mut vm = VM::new(compile_project(source), RunOptions::default());
+        vm.start(0);
+        vm
+    }
+
+    fn compile_project(source: &str) -> &'static CompiledProject {
         let mut input_map = HashMap::new();
         input_map.insert("main.alt".to_string(), source.to_string());
 
@@ -782,11 +1577,7 @@ mod tests {
             )
             .unwrap(),
         );
-        let compiled_project = Box::leak(compiled_project);
-
-        let mut vm = VM::new(compiled_project);
-        vm.start(0);
-        vm
+        Box::leak(compiled_project)
     }
 
     fn step_program_to_wait_start(vm: &mut VM<'_>, pid: usize) {
@@ -810,8 +1601,7 @@ mod tests {
 
         if let Some(dependency) = vm.waiting_programs.get(&to_pid) {
             if dependency.channels_state.contains(to_channel) {
-                vm.waiting_programs.remove(&to_pid);
-                vm.executable_programs.insert(to_pid);
+                vm.wake_program(to_pid);
             }
         }
     }
@@ -838,9 +1628,15 @@ main {
         let mut vm = compile_vm(source);
         step_program_to_wait_start(&mut vm, 0);
 
-        let program = vm.running_programs.get_mut(0).unwrap();
+        let program = Rc::make_mut(vm.running_programs.get_mut(0).unwrap());
         let (actions, executed_instructions) = program
-            .next_global(&mut vm.globals, &mut vm.channels, &mut vm.next_program_id)
+            .next_global(
+                &mut vm.globals,
+                &mut vm.channels,
+                &mut vm.next_program_id,
+                &BTreeSet::new(),
+                &mut RandomSource::Unavailable,
+            )
             .unwrap();
 
         assert!(actions.wait);
@@ -1033,6 +1829,422 @@ main {
 
                 assert!(block_index < tail_index);
         }
+
+    #[test]
+    fn execution_step_info_serializes_to_json() {
+        let source = r#"
+main {
+    print("hello");
+}
+        "#;
+
+        let mut vm = compile_vm(source);
+        let step = vm.next_step_pid(0).unwrap().unwrap();
+        let json = serde_json::to_value(&step).unwrap();
+
+        assert_eq!(json["prog_name"], "main");
+        assert_eq!(json["prog_id"], 0);
+        assert_eq!(json["lines"][0], 3);
+        assert!(json["instructions"].as_array().is_some_and(|i| !i.is_empty()));
+        assert_eq!(json["actions"][0]["Print"], "hello");
+        assert!(json["invariant_error"].is_null());
+    }
+
+    #[test]
+    fn prints_of_a_step_are_in_program_order() {
+        let source = r#"
+main {
+    atomic {
+        print("one");
+        print("two");
+        print("three");
+    }
+}
+        "#;
+
+        let mut vm = compile_vm(source);
+        let step = vm.next_step_pid(0).unwrap().unwrap();
+        let prints = step
+            .actions
+            .iter()
+            .filter_map(|action| match action {
+                GlobalAction::Print(message) => Some(message.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(prints, vec!["one", "two", "three"]);
+    }
+
+    #[test]
+    fn clone_light_shares_the_compiled_project() {
+        let source = r#"
+shared {
+    let X = 0;
+}
+program A() {
+    X = 1;
+}
+main {
+    run A();
+    X = 2;
+}
+        "#;
+
+        let vm = compile_vm(source);
+        let mut copy = vm.clone_light();
+
+        assert!(copy == vm);
+        assert!(std::ptr::eq(copy.programs_code, vm.programs_code));
+        assert!(std::ptr::eq(copy.user_funcs, vm.user_funcs));
+        assert!(Rc::ptr_eq(&copy.stdlib, &vm.stdlib));
+
+        // the runtime state is not shared
+        copy.next_step_pid(0).unwrap();
+        assert!(copy != vm);
+        assert_eq!(vm.running_programs.len(), 1);
+    }
+
+    #[test]
+    fn same_seed_executes_the_same_instructions() {
+        let source = r#"
+shared {
+    let X = 0;
+    let Done = 0;
+}
+program Worker(id: int) {
+    let delay = rand(1, 10);
+    X = X + delay;
+    await receive in (v) => {
+        X = X + v;
+    }
+    Done = Done + 1;
+}
+program Sender() {
+    let value = rand(0, 100);
+    send out(value);
+    send other(2);
+}
+main {
+    let a = run Worker(1);
+    let b = run Worker(2);
+    let s = run Sender();
+    channel s.out (int)> a.in;
+    channel s.other (int)> b.in;
+    await Done == 2;
+}
+        "#;
+        let project = compile_project(source);
+        let execute = |seed: u64| {
+            let mut vm = VM::new(project, RunOptions::default());
+            vm.start(seed);
+            assert_eq!(vm.scheduler_seed(), seed);
+            let mut trace = Vec::new();
+            while !vm.is_finished() {
+                let info = vm.next_random().unwrap();
+                trace.push(format!(
+                    "{}#{} {:?} {:?}",
+                    info.prog_name, info.prog_id, info.instructions, info.actions
+                ));
+            }
+            (trace, vm.globals)
+        };
+
+        let (trace, globals) = execute(42);
+        assert!(trace.len() > 10);
+        assert_eq!(execute(42), (trace, globals));
+    }
+
+    #[test]
+    fn write_only_wakes_programs_depending_on_the_variable() {
+        let source = r#"
+shared {
+    let X = 0;
+    let Y = 0;
+}
+program wait_x() {
+    await X > 0;
+}
+program wait_y() {
+    await Y > 0;
+}
+main {
+    run wait_x();
+    run wait_y();
+}
+        "#;
+
+        let mut vm = compile_vm(source);
+        while vm.next_step_pid(0).unwrap().is_some() {}
+        assert!(vm.next_step_pid(1).unwrap().is_none());
+        assert!(vm.next_step_pid(2).unwrap().is_none());
+        assert!(vm.waiting_programs.contains_key(&1));
+        assert!(vm.waiting_programs.contains_key(&2));
+
+        vm.globals.insert("X".to_string(), Literal::Int(1));
+        vm.wake_variable_dependents("X");
+
+        assert!(vm.executable_programs.contains(&1));
+        assert!(!vm.waiting_programs.contains_key(&1));
+        assert!(vm.waiting_programs.contains_key(&2));
+        assert!(!vm.waiting_on_variables.contains_key("X"));
+        assert_eq!(vm.waiting_on_variables.get("Y").map(HashSet::len), Some(1));
+    }
+
+    #[test]
+    fn wait_dependency_reports_parked_and_pending_waits() {
+        let source = r#"
+shared {
+    let X = 0;
+}
+program waiter() {
+    await X > 0;
+}
+main {
+    let w = run waiter();
+    channel self.out (int)[1] w.in;
+    send out(1);
+    send out(2);
+}
+        "#;
+
+        let mut vm = compile_vm(source);
+        vm.next_step_pid(0).unwrap();
+
+        // the waiter has not evaluated its condition yet, the main process is at a full channel
+        assert!(vm.executable_programs.contains(&1));
+        let waiter = vm.wait_dependency(1).unwrap();
+        assert!(waiter.variables.contains("X"));
+        let main = vm.wait_dependency(0).unwrap();
+        assert!(main.channels_space.contains("out"));
+
+        assert!(vm.next_step_pid(1).unwrap().is_none());
+        assert_eq!(vm.wait_dependency(1), Some(waiter));
+    }
+
+    #[test]
+    fn next_with_choice_takes_the_step_listed_by_next() {
+        let source = r#"
+shared {
+    let X = 0;
+}
+program sender() {
+    let v = rand(0, 1);
+    send out(v);
+    X = 1;
+}
+main {
+    let s = run sender();
+    channel s.out (int)> self.in;
+    X = 2;
+}
+        "#;
+
+        let mut vm = compile_vm(source);
+        vm.next_step_pid(0).unwrap();
+
+        let next_states = vm.next().unwrap();
+        // the step of main and the two steps of sender, one per value drawn by rand
+        assert_eq!(next_states.len(), 3);
+        for (index, (name, pid, _, actions, next_vm)) in next_states.into_iter().enumerate() {
+            let mut chosen = vm.clone();
+            let step = chosen.next_with_choice(index).unwrap();
+            assert_eq!((step.prog_name, step.prog_id), (name, pid));
+            assert_eq!(step.actions, actions);
+            assert!(chosen == next_vm);
+        }
+
+        assert!(vm.clone().next_with_choice(3).is_err());
+    }
+
+    #[test]
+    fn state_lines_render_values_with_display() {
+        let source = r#"
+shared {
+    let X = 5;
+    let L = [1, 2];
+}
+program sender() {
+    send out(3, true);
+}
+main {
+    let s = run sender();
+    channel s.out (int, bool)> self.in;
+    print("done");
+}
+        "#;
+
+        let mut vm = compile_vm(source);
+        vm.next_step_pid(0).unwrap();
+        let step = vm.next_step_pid(1).unwrap().unwrap();
+
+        let lines = vm.state_lines();
+        assert_eq!(lines[0], "global: {L: list(1, 2), X: 5}");
+        assert!(lines.iter().all(|line| !line.contains("Int(")));
+        assert!(step
+            .actions
+            .iter()
+            .any(|action| action.to_string() == "send (3, true) from sender#1 on out"));
+    }
+
+    /// All the states reachable from `vm`, with the actions of every transition taken
+    fn explore(vm: VM<'static>) -> (Vec<VM<'static>>, Vec<(String, Vec<GlobalAction>)>) {
+        let mut seen = HashSet::new();
+        let mut states = vec![vm];
+        let mut transitions = Vec::new();
+        let mut i = 0;
+        while i < states.len() {
+            for (name, _, _, actions, next) in states[i].next().unwrap() {
+                transitions.push((name, actions));
+                if seen.insert(next.clone()) {
+                    states.push(next);
+                }
+            }
+            i += 1;
+        }
+        (states, transitions)
+    }
+
+    #[test]
+    fn sync_send_and_receive_happen_in_a_single_step() {
+        let source = r#"
+shared {
+    let Received = 0;
+}
+program Receiver() {
+    await receive in (x) => {
+        Received = x;
+    }
+}
+main {
+    let r = run Receiver();
+    channel sync self.out (int)> r.in;
+    send out(42);
+}
+        "#;
+
+        let (states, transitions) = explore(compile_vm(source));
+
+        assert!(transitions
+            .iter()
+            .all(|(name, _)| !name.starts_with("__deliver__")));
+        // the receiver consumes the message in the step of the send
+        assert!(transitions.iter().any(|(name, actions)| name == "main"
+            && matches!(
+                actions.as_slice(),
+                [.., GlobalAction::Send(_), GlobalAction::Deliver(_)]
+            )));
+        assert!(states
+            .iter()
+            .filter(|vm| vm.is_finished())
+            .all(|vm| vm.globals.get("Received") == Some(&Literal::Int(42))));
+    }
+
+    #[test]
+    fn sync_send_blocks_until_the_receiver_is_ready() {
+        let source = r#"
+shared {
+    let Ready = false;
+}
+program Receiver() {
+    await Ready;
+    await receive in (x) => {
+        print(x);
+    }
+}
+main {
+    let r = run Receiver();
+    channel sync self.out (int)> r.in;
+    send out(42);
+    Ready = true;
+}
+        "#;
+
+        // the receiver only listens once main has passed the send: nobody can progress
+        let (states, _) = explore(compile_vm(source));
+        assert!(states
+            .iter()
+            .all(|vm| vm.globals.get("Ready") == Some(&Literal::Bool(false))));
+
+        // the same program with a buffered channel terminates
+        let (states, _) = explore(compile_vm(&source.replace("channel sync", "channel")));
+        assert!(states
+            .iter()
+            .any(|vm| vm.globals.get("Ready") == Some(&Literal::Bool(true))));
+    }
+
+    #[test]
+    fn step_back_restores_recorded_states() {
+        let source = r#"
+shared {
+    let X = 0;
+}
+main {
+    X = 1;
+    X = 2;
+    X = 3;
+}
+        "#;
+        let mut vm = compile_vm(source);
+        assert!(!vm.step_back());
+
+        let step = |vm: &mut VM<'static>| {
+            let (_, _, _, _, next) = vm.next().unwrap().pop().unwrap();
+            vm.step_forward_recorded(next);
+        };
+        let mut states = vec![vm.clone_light()];
+        while !vm.is_finished() {
+            step(&mut vm);
+            states.push(vm.clone_light());
+        }
+        assert_eq!(vm.globals.get("X"), Some(&Literal::Int(3)));
+        for state in states.iter().rev().skip(1) {
+            assert!(vm.step_back());
+            assert!(vm == *state);
+        }
+        assert!(!vm.step_back());
+
+        // only the most recent states are kept
+        vm.set_history_depth(1);
+        step(&mut vm);
+        step(&mut vm);
+        assert!(vm.step_back());
+        assert!(vm == states[1]);
+        assert!(!vm.step_back());
+    }
+
+    #[test]
+    fn forked_states_share_the_processes_that_did_not_step() {
+        let source = r#"
+shared {
+    let X = 0;
+    let Y = 0;
+}
+program A() {
+    X = 1;
+}
+program B() {
+    Y = 1;
+}
+main {
+    run A();
+    run B();
+}
+        "#;
+        let mut vm = compile_vm(source);
+        while vm.running_programs.len() < 3 || vm.executable_programs.contains(&0) {
+            let (_, _, _, _, next) = vm.next().unwrap().remove(0);
+            vm = next;
+        }
+
+        let forks = vm.fork_states().unwrap();
+        assert_eq!(forks.len(), 2);
+        for ForkedState { step, vm: fork } in forks.iter() {
+            for (program, forked) in vm.running_programs.iter().zip(&fork.running_programs) {
+                assert_eq!(Rc::ptr_eq(program, forked), program.id != step.prog_id);
+            }
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -1051,26 +2263,33 @@ impl<'a> Serialize for VM<'a> {
     where
         S: Serializer,
     {
+        // the maps keyed by channels are serialized as lists of [key, value] entries sorted by
+        // key, as JSON only allows string keys and the order must not depend on hashing
+        fn entries<K: Ord, V>(map: impl IntoIterator<Item = (K, V)>) -> Vec<(K, V)> {
+            let mut entries: Vec<(K, V)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            entries
+        }
+
         let (globals, channels, _locals) = self.current_state();
 
         // Number of fields in the serialized VM struct
         let mut s = serializer.serialize_struct("VM_JS", 6)?; // Using "VM_JS" for clarity
 
         s.serialize_field("globals", globals)?;
-        s.serialize_field("channels", channels)?;
+        s.serialize_field("channels", &entries(channels))?;
 
         let serializable_program_states: Vec<SerializableRunningProgramStateForJs> = self
             .running_programs // Iterate over all currently running programs
             .iter()
             .map(|prog_state| {
                 let (memory, instruction_pointer, clock) = prog_state.current_state();
-                let line = self
-                    .programs_code
-                    .get(&prog_state.name)
-                    .and_then(|code| code.instructions.get(instruction_pointer))
+                let line = prog_state
+                    .code()
+                    .instructions
+                    .get(instruction_pointer)
                     .and_then(|inst| inst.pos.as_ref())
-                    .map(|pos| pos.line)
-                    .unwrap_or(0);
+                    .map_or(0, |pos| pos.line);
 
                 SerializableRunningProgramStateForJs {
                     pid: prog_state.id,
@@ -1084,9 +2303,15 @@ impl<'a> Serialize for VM<'a> {
             .collect();
 
         s.serialize_field("locals", &serializable_program_states)?;
-        s.serialize_field("pending_deliveries", &self.channels.get_pending_deliveries())?;
-        s.serialize_field("waiting_send", &self.channels.get_waiting_send())?;
-        s.serialize_field("channel_connections", &self.channels.get_connections())?;
+        s.serialize_field(
+            "pending_deliveries",
+            &entries(self.channels.get_pending_deliveries()),
+        )?;
+        s.serialize_field("waiting_send", &entries(self.channels.get_waiting_send()))?;
+        s.serialize_field(
+            "channel_connections",
+            &entries(self.channels.get_connections()),
+        )?;
 
         s.end()
     }
@@ -0,0 +1,91 @@
+//! The values returned by the `rand(lo, hi)` builtin.
+//!
+//! When a program is run, the values are drawn from the random generator of the VM. When it is
+//! checked, `rand` is a non-deterministic choice: a step is replayed once for every sequence of
+//! values its draws can return, each replay giving a distinct successor (see `VM::next`).
+
+use fastrand::Rng;
+
+/// The maximal number of values a single `rand` call can return when the program is checked
+pub const MAX_RAND_CHOICES: i64 = 64;
+
+/// The values drawn during a step that is replayed by the checker
+#[derive(Debug, Clone, Default)]
+pub struct RandomReplay {
+    /// The values returned by the successive draws, the draws past the end returning their
+    /// lower bound
+    values: Vec<i64>,
+    /// The ranges of the draws performed so far
+    ranges: Vec<(i64, i64)>,
+}
+
+impl RandomReplay {
+    #[must_use]
+    pub fn new(values: Vec<i64>) -> Self {
+        Self {
+            values,
+            ranges: Vec::new(),
+        }
+    }
+
+    /// The sequences of values that differ from the replayed one on a single draw and return the
+    /// lower bound for the draws after it. Starting from the empty sequence and following these
+    /// alternatives enumerates every sequence exactly once.
+    #[must_use]
+    pub fn alternatives(&self) -> Vec<Vec<i64>> {
+        let mut alternatives = Vec::new();
+        for (index, (lo, hi)) in self.ranges.iter().enumerate().skip(self.values.len()) {
+            let prefix: Vec<i64> = self.ranges[..index]
+                .iter()
+                .enumerate()
+                .map(|(i, (lo, _))| self.values.get(i).copied().unwrap_or(*lo))
+                .collect();
+            for value in (lo + 1)..=*hi {
+                let mut values = prefix.clone();
+                values.push(value);
+                alternatives.push(values);
+            }
+        }
+        alternatives
+    }
+}
+
+/// Where the values returned by `rand` come from
+pub enum RandomSource<'r> {
+    /// Drawn from a random generator, when the program is run
+    Rng(&'r mut Rng),
+    /// Replayed from a given sequence, when the program is checked
+    Replay(&'r mut RandomReplay),
+    /// `rand` is not available (e.g. in the evaluation of a condition)
+    Unavailable,
+}
+
+impl RandomSource<'_> {
+    /// Returns an integer in `lo..=hi`
+    ///
+    /// # Errors
+    /// The reason why no value can be returned
+    pub fn draw(&mut self, lo: i64, hi: i64) -> Result<i64, String> {
+        if lo > hi {
+            return Err(format!("rand expects lo <= hi (got {lo} and {hi})"));
+        }
+        match self {
+            RandomSource::Rng(rng) => Ok(rng.i64(lo..=hi)),
+            RandomSource::Replay(replay) => {
+                if i128::from(hi) - i128::from(lo) >= i128::from(MAX_RAND_CHOICES) {
+                    return Err(format!(
+                        "rand({lo}, {hi}) has too many possible values to be checked (at most {MAX_RAND_CHOICES})"
+                    ));
+                }
+                let value = replay
+                    .values
+                    .get(replay.ranges.len())
+                    .copied()
+                    .unwrap_or(lo);
+                replay.ranges.push((lo, hi));
+                Ok(value)
+            }
+            RandomSource::Unavailable => Err("rand cannot be called here".to_string()),
+        }
+    }
+}
@@ -1,21 +1,102 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap},
     hash::{Hash, Hasher},
     rc::Rc,
 };
 
 use crate::{
     ast::token::{datatype::DataType, literal::Literal},
-    compiler::{stdlib::{invoke_interface_method, Stdlib}, FunctionDefinition},
+    compiler::{
+        stdlib::{
+            apply_list_function, invoke_function, invoke_interface_method, Stdlib,
+            LIST_FUNCTION_METHODS,
+        },
+        FunctionDefinition,
+    },
     error::{AlthreadError, AlthreadResult, ErrorType, Pos},
 };
 
 use super::{
     channels::Channels,
     instruction::{Instruction, InstructionType, ProgramCode},
+    int_abstraction::IntAbstraction,
+    random::RandomSource,
     str_to_expr_error, GlobalAction, GlobalActions, GlobalMemory, Memory,
 };
 
+/// Maximum number of steps a pure function may take when evaluated outside of a program step
+const PURE_FUNCTION_MAX_STEPS: usize = 100_000;
+
+/// Runs the user function `name` on the `args` tuple in a scratch program until it returns.
+/// Writes to the globals and channels are discarded, so this is only meant for `@pure` functions.
+pub(crate) fn run_pure_function(
+    user_functions: &HashMap<String, FunctionDefinition>,
+    stdlib: Rc<Stdlib>,
+    globals: &GlobalMemory,
+    next_pid: usize,
+    name: &str,
+    args: Literal,
+) -> Result<Literal, String> {
+    let code = ProgramCode {
+        name: name.to_string(),
+        instructions: vec![
+            Instruction {
+                pos: None,
+                control: InstructionType::Push(args),
+            },
+            Instruction {
+                pos: None,
+                control: InstructionType::FnCall {
+                    name: name.to_string(),
+                    unstack_len: 1,
+                    arguments: None,
+                },
+            },
+            Instruction {
+                pos: None,
+                control: InstructionType::EndProgram,
+            },
+        ],
+        labels: HashMap::new(),
+        argument_names: Vec::new(),
+    };
+    let mut scratch = RunningProgramState::new(
+        0,
+        name.to_string(),
+        &code,
+        user_functions,
+        Literal::Tuple(Vec::new()),
+        stdlib,
+    );
+
+    // a pure function cannot modify these, but the interpreter needs them
+    let mut globals = globals.clone();
+    let mut channels = Channels::new();
+    let mut next_pid = next_pid;
+
+    for _ in 0..PURE_FUNCTION_MAX_STEPS {
+        if scratch.has_terminated() && scratch.get_call_stack_info().len() == 1 {
+            let (memory, _, _) = scratch.current_state();
+            return memory
+                .last()
+                .cloned()
+                .ok_or_else(|| format!("function '{name}' did not return a value"));
+        }
+        scratch
+            .next_atomic(
+                &mut globals,
+                &mut channels,
+                &mut next_pid,
+                &BTreeSet::new(),
+                &mut RandomSource::Unavailable,
+            )
+            .map_err(|e| e.message)?;
+    }
+    Err(format!(
+        "function '{name}' did not return after {PURE_FUNCTION_MAX_STEPS} steps"
+    ))
+}
+
 #[derive(Debug, Clone)]
 struct StackFrame<'a> {
     return_ip: usize,               // the instruction pointer to return to
@@ -43,6 +124,13 @@ pub struct RunningProgramState<'a> {
     pub user_functions: &'a HashMap<String, FunctionDefinition>,
     call_stack: Vec<StackFrame<'a>>,
     frame_pointer: usize,
+
+    /// The range the integers stored by the program are clamped to, if any
+    pub int_abstraction: Option<&'a IntAbstraction>,
+
+    /// The maximum number of instructions executed in a single step (an atomic block or a
+    /// sequence of local instructions), to stop a block that never ends
+    pub instr_limit_per_step: Option<usize>,
 }
 
 impl PartialEq for RunningProgramState<'_> {
@@ -76,6 +164,49 @@ impl<'a> RunningProgramState<'a> {
             .map_err(|e| self.build_error_stack(e))
     }
 
+    /// Calls a list function method (`map` or `filter`), whose argument is the name of the user
+    /// function to apply to each element.
+    fn call_list_function(
+        &self,
+        name: &str,
+        receiver: &Literal,
+        args: Literal,
+        globals: &GlobalMemory,
+        next_pid: usize,
+        pos: Option<Pos>,
+    ) -> AlthreadResult<Literal> {
+        let args = args.into_tuple();
+        let Ok([Literal::String(function_name)]) = args.as_deref() else {
+            let e = AlthreadError::new(
+                ErrorType::RuntimeError,
+                pos,
+                format!(".{name}() expects the name of a user function"),
+            );
+            return Err(self.build_error_stack(e));
+        };
+        let func_def = self.user_functions.get(function_name).ok_or_else(|| {
+            self.build_error_stack(AlthreadError::new(
+                ErrorType::UndefinedFunction,
+                pos.clone(),
+                format!("undefined function {function_name}"),
+            ))
+        })?;
+
+        apply_list_function(name, func_def, receiver, |arg| {
+            run_pure_function(
+                self.user_functions,
+                self.stdlib.clone(),
+                globals,
+                next_pid,
+                function_name,
+                arg,
+            )
+        })
+        .map_err(|msg| {
+            self.build_error_stack(AlthreadError::new(ErrorType::RuntimeError, pos, msg))
+        })
+    }
+
     fn call_interface_method(
         &mut self,
         name: &String,
@@ -126,6 +257,16 @@ impl<'a> RunningProgramState<'a> {
             frame_pointer: 0,
             caller_program_id: None,
             call_site_pos: None,
+            int_abstraction: None,
+            instr_limit_per_step: None,
+        }
+    }
+
+    /// Clamps the integers of a value about to be stored when integers are abstracted
+    fn abstract_ints(&self, lit: Literal) -> Literal {
+        match self.int_abstraction {
+            Some(abstraction) => abstraction.clamp(lit),
+            None => lit,
         }
     }
 
@@ -153,6 +294,12 @@ impl<'a> RunningProgramState<'a> {
     pub fn current_state(&self) -> (&Memory, usize, usize) {
         (&self.memory, self.instruction_pointer, self.clock)
     }
+
+    /// The code the program runs, specialized on its `const` parameters if it has some
+    #[must_use]
+    pub fn code(&self) -> &'a ProgramCode {
+        self.code
+    }
     
     /// Get call stack information for debugging
     /// Returns a vector of (frame_pointer, instruction_pointer, source_position)
@@ -185,17 +332,23 @@ impl<'a> RunningProgramState<'a> {
         self.frame_pointer
     }
 
-    pub fn current_instruction(&self) -> AlthreadResult<&Instruction> {
+    /// The instruction the program is about to execute
+    ///
+    /// # Errors
+    /// If the instruction pointer is out of the code of the program
+    pub fn current_instruction(&self) -> AlthreadResult<&'a Instruction> {
         self.current_code
             .get(self.instruction_pointer)
-            .ok_or(AlthreadError::new(
-                ErrorType::InstructionNotAllowed,
-                None,
-                format!(
+            .ok_or_else(|| {
+                AlthreadError::new(
+                    ErrorType::InstructionNotAllowed,
+                    None,
+                    format!(
                 "the current instruction pointer points to no instruction (pointer:{}, program:{})",
                 self.instruction_pointer, self.name
             ),
-            ))
+                )
+            })
     }
 
     pub fn has_terminated(&self) -> bool {
@@ -211,19 +364,32 @@ impl<'a> RunningProgramState<'a> {
         globals: &mut GlobalMemory,
         channels: &mut Channels,
         next_pid: &mut usize,
+        terminated_programs: &BTreeSet<usize>,
+        random: &mut RandomSource,
     ) -> AlthreadResult<(GlobalActions, Vec<Instruction>)> {
         let mut instructions = Vec::new();
         let mut actions = Vec::new();
         let mut wait = false;
         let mut end = false;
         loop {
-            let (at_actions, at_instructions) = self.next_atomic(globals, channels, next_pid)?;
+            let (at_actions, at_instructions) = self.next_atomic(globals, channels, next_pid, terminated_programs, random)?;
+            let step_started = !instructions.is_empty() || !at_actions.actions.is_empty();
 
             actions.extend(at_actions.actions);
             instructions.extend(at_instructions);
 
             if at_actions.wait {
-                wait = true;
+                // a send blocked on a full channel once the step has started ends the step
+                // before it: the send is retried at the start of the next step
+                let blocked_send = matches!(
+                    self.current_instruction()?.control,
+                    InstructionType::Send { .. }
+                );
+                if blocked_send && step_started {
+                    instructions.pop();
+                } else {
+                    wait = true;
+                }
                 break;
             }
             if at_actions.end {
@@ -233,6 +399,7 @@ impl<'a> RunningProgramState<'a> {
             if self.is_next_instruction_global() {
                 break;
             }
+            self.check_instr_limit(instructions.len())?;
         }
         Ok((GlobalActions { actions, wait, end }, instructions))
     }
@@ -247,6 +414,8 @@ impl<'a> RunningProgramState<'a> {
         globals: &mut GlobalMemory,
         channels: &mut Channels,
         next_pid: &mut usize,
+        terminated_programs: &BTreeSet<usize>,
+        random: &mut RandomSource,
     ) -> AlthreadResult<(GlobalActions, Vec<Instruction>)> {
         let mut instructions = Vec::new();
 
@@ -258,7 +427,7 @@ impl<'a> RunningProgramState<'a> {
         // if the next instruction is not the start of an atomic block, we execute the next instruction
         if !self.current_instruction()?.is_atomic_start() {
             instructions.push(self.current_instruction()?.clone());
-            let action = self.next(globals, channels, next_pid)?;
+            let action = self.next(globals, channels, next_pid, terminated_programs, random)?;
             if let Some(action) = action {
                 if action == GlobalAction::Wait {
                     result.wait = true;
@@ -273,7 +442,7 @@ impl<'a> RunningProgramState<'a> {
         // else we execute all the instructions until the end of the atomic block
         loop {
             instructions.push(self.current_instruction()?.clone());
-            let action = self.next(globals, channels, next_pid)?;
+            let action = self.next(globals, channels, next_pid, terminated_programs, random)?;
             if let Some(action) = action {
                 if action == GlobalAction::Wait {
                     result.wait = true;
@@ -285,17 +454,34 @@ impl<'a> RunningProgramState<'a> {
             if self.current_instruction()?.is_atomic_end() {
                 break;
             }
+            self.check_instr_limit(instructions.len())?;
         }
         Ok((result, instructions))
     }
 
+    /// Fails when the current step has executed more instructions than `instr_limit_per_step`
+    fn check_instr_limit(&self, executed: usize) -> AlthreadResult<()> {
+        match self.instr_limit_per_step {
+            Some(limit) if executed > limit => Err(self.build_error_stack(AlthreadError::new(
+                ErrorType::RuntimeError,
+                self.current_instruction()?.pos.clone(),
+                format!(
+                    "atomic block exceeded instruction limit ({limit} instructions executed in a single step)"
+                ),
+            ))),
+            _ => Ok(()),
+        }
+    }
+
     fn next(
         &mut self,
         globals: &mut GlobalMemory,
         channels: &mut Channels,
         next_pid: &mut usize,
+        terminated_programs: &BTreeSet<usize>,
+        random: &mut RandomSource,
     ) -> AlthreadResult<Option<GlobalAction>> {
-        let cur_inst = self.current_instruction()?.clone();
+        let cur_inst = self.current_instruction()?;
 
         let mut action = None;
 
@@ -307,6 +493,7 @@ impl<'a> RunningProgramState<'a> {
             InstructionType::AtomicStart => 1,
             InstructionType::AtomicEnd => 1,
             InstructionType::Label { .. } => 1,
+            InstructionType::Yield => 1,
             InstructionType::Break {
                 unstack_len, jump, ..
             } => {
@@ -336,6 +523,7 @@ impl<'a> RunningProgramState<'a> {
                         AlthreadError::new(ErrorType::ExpressionError, cur_inst.pos.clone(), msg);
                     self.build_error_stack(e)
                 })?;
+                let lit = self.abstract_ints(lit);
                 self.memory.push(lit);
                 1
             }
@@ -353,6 +541,7 @@ impl<'a> RunningProgramState<'a> {
                     self.memory.pop();
                 }
                 // Push the final result of the expression.
+                let lit = self.abstract_ints(lit);
                 self.memory.push(lit);
                 1
             }
@@ -385,7 +574,8 @@ impl<'a> RunningProgramState<'a> {
                     }
                 }
 
-                self.memory.push(Literal::Tuple(evaluated_elements));
+                let tuple = self.abstract_ints(Literal::Tuple(evaluated_elements));
+                self.memory.push(tuple);
                 1
             }
             InstructionType::GlobalReads { variables, .. } => {
@@ -393,7 +583,7 @@ impl<'a> RunningProgramState<'a> {
                     self.memory.push(
                         globals
                             .get(var_name)
-                            .expect(format!("global variable '{}' not found", var_name).as_str())
+                            .unwrap_or_else(|| panic!("global variable '{var_name}' not found"))
                             .clone(),
                     );
                 }
@@ -417,10 +607,11 @@ impl<'a> RunningProgramState<'a> {
                     .apply(
                         &globals
                             .get(identifier)
-                            .expect(format!("global variable '{}' not found", identifier).as_str()),
+                            .unwrap_or_else(|| panic!("global variable '{identifier}' not found")),
                         &lit,
                     )
-                    .map_err(str_to_expr_error(cur_inst.pos))?;
+                    .map_err(str_to_expr_error(cur_inst.pos.as_ref()))?;
+                let lit = self.abstract_ints(lit);
 
                 globals.insert(identifier.clone(), lit);
                 action = Some(GlobalAction::Write(identifier.clone()));
@@ -442,9 +633,10 @@ impl<'a> RunningProgramState<'a> {
 
                 let len = self.memory.len();
 
-                self.memory[len - 1 - index] = operator
+                let lit = operator
                     .apply(&self.memory[len - 1 - *index], &lit)
-                    .map_err(str_to_expr_error(cur_inst.pos))?;
+                    .map_err(str_to_expr_error(cur_inst.pos.as_ref()))?;
+                self.memory[len - 1 - index] = self.abstract_ints(lit);
                 1
             }
             InstructionType::Unstack { unstack_len } => {
@@ -465,7 +657,11 @@ impl<'a> RunningProgramState<'a> {
                 self.memory.push(lit);
                 1
             }
-            InstructionType::RunCall { name, unstack_len } => {
+            InstructionType::RunCall {
+                name,
+                unstack_len,
+                specialization,
+            } => {
                 let args = self
                     .memory
                     .last()
@@ -476,7 +672,7 @@ impl<'a> RunningProgramState<'a> {
                 }
                 self.memory.push(Literal::Process(name.clone(), *next_pid));
                 action = Some(GlobalAction::StartProgram(
-                    name.clone(),
+                    specialization.as_ref().unwrap_or(name).clone(),
                     *next_pid,
                     args,
                     Some(self.id),
@@ -500,7 +696,7 @@ impl<'a> RunningProgramState<'a> {
                     0
                 }
             }
-            InstructionType::Return { has_value } => {
+            InstructionType::Return { has_value, .. } => {
                 let return_value = if *has_value {
                     self.memory
                         .pop()
@@ -583,19 +779,77 @@ impl<'a> RunningProgramState<'a> {
                             .into_tuple()
                             .expect("assert expects a tuple as argument");
                         let condition = &args[0];
-                        let message = &args[1];
 
                         if !condition.is_true() {
+                            let message = match args.get(1) {
+                                Some(message) => format!("Assertion failed: {message}"),
+                                None => "Assertion failed".to_string(),
+                            };
                             let e = AlthreadError::new(
                                 ErrorType::AssertionFailed,
                                 cur_inst.pos.clone(),
-                                format!("Assertion failed: {}", message),
+                                message,
                             );
                             return Err(self.build_error_stack(e));
                         }
                         self.memory.push(Literal::Null);
                         1
                     }
+                    "assume" => {
+                        let lit = self
+                            .memory
+                            .last()
+                            .expect("Panic: stack is empty, cannot perform function call.")
+                            .clone();
+
+                        for _ in 0..*unstack_len {
+                            self.memory.pop();
+                        }
+
+                        let args = lit
+                            .into_tuple()
+                            .expect("assume expects a tuple as argument");
+
+                        // the caller discards the execution path (see `VM::next`)
+                        if !args[0].is_true() {
+                            let e = AlthreadError::new(
+                                ErrorType::AssumptionViolated,
+                                cur_inst.pos.clone(),
+                                "Assumption does not hold".to_string(),
+                            );
+                            return Err(self.build_error_stack(e));
+                        }
+                        self.memory.push(Literal::Null);
+                        1
+                    }
+                    "rand" => {
+                        let lit = self
+                            .memory
+                            .last()
+                            .expect("Panic: stack is empty, cannot perform function call.")
+                            .clone();
+
+                        for _ in 0..*unstack_len {
+                            self.memory.pop();
+                        }
+
+                        let args = lit
+                            .into_tuple()
+                            .expect("rand expects a tuple as argument");
+                        let (Literal::Int(lo), Literal::Int(hi)) = (&args[0], &args[1]) else {
+                            unreachable!("rand expects two integers");
+                        };
+
+                        let value = random.draw(*lo, *hi).map_err(|message| {
+                            self.build_error_stack(AlthreadError::new(
+                                ErrorType::RuntimeError,
+                                cur_inst.pos.clone(),
+                                message,
+                            ))
+                        })?;
+                        self.memory.push(Literal::Int(value));
+                        1
+                    }
                     _ => {
                         if let Some(func_def) = self.user_functions.get(name) {
                             let args_tuple_lit = self.memory.pop().unwrap();
@@ -606,7 +860,7 @@ impl<'a> RunningProgramState<'a> {
                                     // should never happen
                                     return Err(AlthreadError::new(
                                         ErrorType::RuntimeError,
-                                        cur_inst.pos,
+                                        cur_inst.pos.clone(),
                                         format!("function {} expects a tuple as argument", name),
                                     ));
                                 }
@@ -631,6 +885,26 @@ impl<'a> RunningProgramState<'a> {
                             self.instruction_pointer = 0;
 
                             0
+                        } else if self.stdlib.is_function(name) {
+                            let mut args = self
+                                .memory
+                                .last()
+                                .expect("Panic: stack is empty, cannot perform function call.")
+                                .clone();
+
+                            for _ in 0..*unstack_len {
+                                self.memory.pop();
+                            }
+
+                            let ret = invoke_function(
+                                self.stdlib.as_ref(),
+                                name,
+                                &mut args,
+                                cur_inst.pos.clone(),
+                            )
+                            .map_err(|e| self.build_error_stack(e))?;
+                            self.memory.push(ret);
+                            1
                         } else {
                             let e = AlthreadError::new(
                                 ErrorType::UndefinedFunction,
@@ -666,10 +940,25 @@ impl<'a> RunningProgramState<'a> {
                         Literal::Tuple(args)
                     }
                 };
-                let ret = if let Some(global_name) = global_receiver {
+                let ret = if LIST_FUNCTION_METHODS.contains(&name.as_str()) {
+                    let receiver = match global_receiver {
+                        Some(global_name) => globals
+                            .get(global_name)
+                            .unwrap_or_else(|| panic!("global variable '{global_name}' not found")),
+                        None => &self.memory[self.memory.len() - 1 - *receiver_idx],
+                    };
+                    self.call_list_function(
+                        name,
+                        receiver,
+                        args,
+                        globals,
+                        *next_pid,
+                        cur_inst.pos.clone(),
+                    )?
+                } else if let Some(global_name) = global_receiver {
                     let mut receiver = globals
                         .get(global_name)
-                        .expect(format!("global variable '{}' not found", global_name).as_str())
+                        .unwrap_or_else(|| panic!("global variable '{global_name}' not found"))
                         .clone();
 
                     let (ret, mutates_receiver) = self.call_interface_method_on_literal(
@@ -744,30 +1033,36 @@ impl<'a> RunningProgramState<'a> {
                 channel_name,
                 unstack_len,
             } => {
-                let value = self
-                    .memory
-                    .last()
-                    .expect("Panic: stack is empty, cannot send")
-                    .clone();
+                // a full bounded channel blocks the send until the receiver pops a message
+                if channels.is_full(self.id, channel_name) {
+                    action = Some(GlobalAction::Wait);
+                    0
+                } else {
+                    let value = self
+                        .memory
+                        .last()
+                        .expect("Panic: stack is empty, cannot send")
+                        .clone();
 
-                for _ in 0..*unstack_len {
-                    self.memory.pop();
+                    for _ in 0..*unstack_len {
+                        self.memory.pop();
+                    }
+                    self.clock += 1;
+                    let _receiver =
+                        channels.send(self.id, channel_name.clone(), value.clone(), self.clock);
+                    action = Some(GlobalAction::Send(crate::vm::SendInfo {
+                        from: crate::vm::ProcessInfo {
+                            process_id: self.id,
+                            process_name: self.name.clone(),
+                        },
+                        to: crate::vm::ChannelInfo {
+                            channel_name: channel_name.clone(),
+                        },
+                        message: value,
+                        n_msg: self.clock,
+                    }));
+                    1
                 }
-                self.clock += 1;
-                let _receiver =
-                    channels.send(self.id, channel_name.clone(), value.clone(), self.clock);
-                action = Some(GlobalAction::Send(crate::vm::SendInfo {
-                    from: crate::vm::ProcessInfo {
-                        process_id: self.id,
-                        process_name: self.name.clone(),
-                    },
-                    to: crate::vm::ChannelInfo {
-                        channel_name: channel_name.clone(),
-                    },
-                    message: value,
-                    n_msg: self.clock,
-                }));
-                1
             }
             InstructionType::Broadcast {
                 channel_name: pattern,
@@ -843,11 +1138,27 @@ impl<'a> RunningProgramState<'a> {
                 let _ = channels.pop(self.id, channel_name.clone());
                 1
             }
+            InstructionType::HasTerminated => {
+                let handle = self.memory.pop().expect("Panic: stack is empty");
+                let Literal::Process(_, pid) = handle else {
+                    let e = AlthreadError::new(
+                        ErrorType::ProcessError,
+                        cur_inst.pos.clone(),
+                        format!("join expects a process handle, found {handle}"),
+                    );
+                    return Err(self.build_error_stack(e));
+                };
+                self.memory
+                    .push(Literal::Bool(terminated_programs.contains(&pid)));
+                1
+            }
             InstructionType::Connect {
                 sender_pid,
                 sender_channel,
                 receiver_pid,
                 receiver_channel,
+                sync,
+                capacity,
             } => {
                 let sender_pid = match *sender_pid {
                     None => self.id,
@@ -877,15 +1188,20 @@ impl<'a> RunningProgramState<'a> {
                         sender_channel.clone(),
                         receiver_pid,
                         receiver_channel.clone(),
+                        *sync,
                     )
                     .map_err(|msg| {
                         AlthreadError::new(
                             ErrorType::RuntimeError,
-                            cur_inst.pos,
+                            cur_inst.pos.clone(),
                             format!("Failed to connect channels: {}", msg),
                         )
                     })?;
 
+                if let Some(capacity) = capacity {
+                    channels.set_capacity(receiver_pid, receiver_channel.clone(), *capacity);
+                }
+
                 // Notify globally that the connection exists so any sender waiting for this connection can resume.
                 action = Some(GlobalAction::Connect(sender_pid, sender_channel.clone()));
                 1
@@ -910,6 +1226,19 @@ impl<'a> RunningProgramState<'a> {
                 self.memory.push(list);
                 1
             }
+            InstructionType::CheckRepeatCount => {
+                if let Some(Literal::Int(count)) = self.memory.last() {
+                    if *count < 0 {
+                        let e = AlthreadError::new(
+                            ErrorType::RuntimeError,
+                            cur_inst.pos.clone(),
+                            format!("The repeat count must not be negative ({count} is given)"),
+                        );
+                        return Err(self.build_error_stack(e));
+                    }
+                }
+                1
+            }
             InstructionType::ConvertEmptyListType { to_element_type } => {
                 // Convert the type of an empty list on top of stack
                 let list = self
@@ -935,6 +1264,36 @@ impl<'a> RunningProgramState<'a> {
                 }
                 1
             }
+            InstructionType::CreateMapFromStack {
+                entry_count,
+                key_type,
+                value_type,
+            } => {
+                let values = self.memory.split_off(self.memory.len() - 2 * entry_count);
+                let mut entries = BTreeMap::new();
+                let mut values = values.into_iter();
+                while let (Some(key), Some(value)) = (values.next(), values.next()) {
+                    // a key given twice keeps its last value
+                    entries.insert(key.map_key(), (key, value));
+                }
+                self.memory
+                    .push(Literal::Map(key_type.clone(), value_type.clone(), entries));
+                1
+            }
+            InstructionType::ConvertListToArray { element_type } => {
+                let list = self
+                    .memory
+                    .pop()
+                    .expect("Panic: stack is empty, cannot convert list to array");
+                match list {
+                    Literal::List(_, elements) => {
+                        self.memory
+                            .push(Literal::Array(element_type.clone(), elements));
+                    }
+                    _ => panic!("Expected list on stack for array conversion, but found: {list:?}"),
+                }
+                1
+            }
             _ => panic!("Instruction '{:?}' not implemented", cur_inst.control),
         };
         let new_pos = (self.instruction_pointer as i64) + pos_inc;
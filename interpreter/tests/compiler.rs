@@ -14,9 +14,14 @@ use althread::{
         },
         Ast,
     },
+    checker::CheckOptions,
     error::Pos,
     module_resolver::StandardFileSystem,
-    vm::{instruction::{Instruction, InstructionType}, GlobalAction, VM},
+    vm::{
+        instruction::{Instruction, InstructionType},
+        int_abstraction::IntAbstraction,
+        GlobalAction, RunOptions, VM,
+    },
 };
 
 // A simple test to verify that the compiler can compile a simple program
@@ -200,7 +205,7 @@ main {
         .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
         .unwrap();
 
-    let mut vm = VM::new(&compiled_project);
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
     vm.start(0);
 
     let mut actions = Vec::new();
@@ -424,7 +429,7 @@ main {
         .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
         .unwrap();
 
-    let mut initial_vm = VM::new(&compiled_project);
+    let mut initial_vm = VM::new(&compiled_project, RunOptions::default());
     initial_vm.start(0);
 
     let mut frontier = vec![(initial_vm, Vec::<String>::new())];
@@ -475,7 +480,7 @@ main {
         .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
         .unwrap();
 
-    let mut vm = VM::new(&compiled_project);
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
     vm.start(0);
 
     let mut actions = Vec::new();
@@ -538,7 +543,7 @@ main {
         .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
         .unwrap();
 
-    let mut initial_vm = VM::new(&compiled_project);
+    let mut initial_vm = VM::new(&compiled_project, RunOptions::default());
     initial_vm.start(0);
 
     let mut frontier = vec![initial_vm];
@@ -661,10 +666,212 @@ main {
 
     assert_eq!(compiled_project.always_conditions.len(), 1);
 
-    let mut vm = VM::new(&compiled_project);
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+
+    assert_eq!(vm.check_invariants().unwrap(), 1);
+}
+
+#[test]
+fn test_always_condition_calls_pure_function() {
+    let input = r"
+shared {
+    let X = 0;
+}
+
+@pure fn in_range(v: int, max: int) -> bool {
+    return v >= 0 && v <= max;
+}
+
+always {
+    in_range(X, 2);
+}
+
+main {
+    atomic { X = X + 3; }
+}
+";
+
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
     vm.start(0);
 
     assert_eq!(vm.check_invariants().unwrap(), 1);
+    vm.next_step_pid(0).unwrap();
+    assert!(vm.check_invariants().is_err());
+}
+
+#[test]
+fn test_always_condition_rejects_impure_functions() {
+    let not_marked = r"
+shared {
+    let X = 0;
+}
+
+fn positive(v: int) -> bool {
+    return v >= 0;
+}
+
+always {
+    positive(X);
+}
+
+main {
+}
+";
+    let writes_shared = r"
+shared {
+    let X = 0;
+}
+
+@pure fn positive(v: int) -> bool {
+    X = v;
+    return v >= 0;
+}
+
+main {
+}
+";
+
+    for input in [not_marked, writes_shared] {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+
+        let err = ast
+            .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+            .unwrap_err();
+        assert!(err.message.contains("@pure"), "{}", err.message);
+    }
+}
+
+#[test]
+fn test_list_map_and_filter_apply_user_functions() {
+    let input = r"
+shared {
+    let Values = [1, 2, 3, 4];
+    let Doubled: list(int);
+    let Even: list(int);
+    let Labels: list(bool);
+}
+
+@pure fn double(v: int) -> int {
+    return v * 2;
+}
+
+@pure fn is_even(v: int) -> bool {
+    return v % 2 == 0;
+}
+
+main {
+    let l = [1, 2, 3];
+    Doubled = l.map(double);
+    Even = Values.filter(is_even);
+    Labels = Values.map(is_even);
+}
+";
+
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    loop {
+        let next_states = vm.next().unwrap();
+        if next_states.is_empty() {
+            break;
+        }
+        vm = next_states.into_iter().next().unwrap().4;
+    }
+
+    let ints = |values: &[i64]| {
+        Literal::List(
+            DataType::Integer,
+            values.iter().map(|v| Literal::Int(*v)).collect(),
+        )
+    };
+    assert_eq!(vm.globals.get("Doubled"), Some(&ints(&[2, 4, 6])));
+    assert_eq!(vm.globals.get("Even"), Some(&ints(&[2, 4])));
+    assert_eq!(
+        vm.globals.get("Labels"),
+        Some(&Literal::List(
+            DataType::Boolean,
+            [false, true, false, true].map(Literal::Bool).to_vec()
+        ))
+    );
+}
+
+#[test]
+fn test_list_map_and_filter_check_function_signature() {
+    let cases = [
+        // argument type does not match the element type
+        r"
+@pure fn negate(b: bool) -> bool {
+    return !b;
+}
+
+main {
+    let l = [1, 2];
+    let m = l.map(negate);
+}
+",
+        // the predicate of filter must return a boolean
+        r"
+@pure fn double(v: int) -> int {
+    return v * 2;
+}
+
+main {
+    let l = [1, 2];
+    let m = l.filter(double);
+}
+",
+        // the function would be run outside of the program steps
+        r"
+fn double(v: int) -> int {
+    return v * 2;
+}
+
+main {
+    let l = [1, 2];
+    let m = l.map(double);
+}
+",
+    ];
+
+    for input in cases {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+
+        let err = ast
+            .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+            .unwrap_err();
+        assert!(
+            err.message.contains("given to .") || err.message.contains("must be marked @pure"),
+            "{}",
+            err.message
+        );
+    }
 }
 
 #[test]
@@ -703,7 +910,7 @@ main {
         .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
         .unwrap();
 
-    let mut vm = VM::new(&compiled_project);
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
     vm.start(0);
 
     let (_, _, _, _, after_main) = vm.next().unwrap().into_iter().next().unwrap();
@@ -769,7 +976,7 @@ main {
         .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
         .unwrap();
 
-    let mut vm = VM::new(&compiled_project);
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
     vm.start(0);
 
     let (_, _, _, _, after_main) = vm.next().unwrap().into_iter().next().unwrap();
@@ -825,7 +1032,7 @@ main {
         .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
         .unwrap();
 
-    let mut vm = VM::new(&compiled_project);
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
     vm.start(0);
 
     let mut actions = Vec::new();
@@ -921,7 +1128,7 @@ main {
         .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
         .unwrap();
 
-    let mut vm = VM::new(&compiled_project);
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
     vm.start(0);
 
     let mut actions = Vec::new();
@@ -1643,4 +1850,3389 @@ main {
             .instructions,
         expected
     );
-}
\ No newline at end of file
+}
+#[test]
+fn test_connected_reasons_about_channel_topology() {
+    let program = |invariant: &str| {
+        format!(
+            r"
+shared {{
+    let S: proc(Sender);
+    let R: proc(Receiver);
+    let Linked = false;
+}}
+
+program Sender() {{
+    send out(1);
+}}
+
+program Receiver() {{
+    await receive in(x) => {{}}
+}}
+
+always {{
+    {invariant}
+}}
+
+main {{
+    let s = run Sender();
+    let r = run Receiver();
+    atomic {{
+        S = s;
+        R = r;
+    }}
+    channel s.out (int)> r.in;
+    Linked = true;
+}}
+"
+        )
+    };
+
+    for (invariant, holds) in [
+        ("!Linked || connected(S, R);", true),
+        ("!connected(R, S);", true),
+        ("!connected(S, R);", false),
+    ] {
+        let input = program(invariant);
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.clone());
+
+        let pairs = althread::parser::parse(&input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        let compiled_project = ast
+            .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+            .unwrap();
+
+        let (violation, _) =
+            althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+        assert_eq!(violation.is_empty(), holds, "{invariant}");
+    }
+}
+
+#[test]
+fn test_shared_namespace_qualifies_variables() {
+    let input = r"
+shared {
+    let Count = 0;
+}
+
+shared mutex {
+    let Locked = false;
+    let Holders: list(int);
+}
+
+main {
+    mutex.Locked = true;
+    mutex.Holders.push(Count);
+}
+";
+
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    assert!(!compiled_project.global_memory.contains_key("Locked"));
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+
+    assert_eq!(vm.globals.get("mutex.Locked"), Some(&Literal::Bool(true)));
+    assert_eq!(
+        vm.globals.get("mutex.Holders"),
+        Some(&Literal::List(DataType::Integer, vec![Literal::Int(0)]))
+    );
+}
+
+#[test]
+fn test_disassemble_numbers_instructions_with_source_lines() {
+    let input = r"
+main {
+    let x = 5;
+}
+";
+
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let listing = compiled_project.programs_code["main"].disassemble(&input_map);
+    let lines: Vec<&str> = listing.lines().collect();
+    assert_eq!(lines[0], "main");
+    assert!(lines[1].starts_with("0  eval 5"), "{}", lines[1]);
+    assert!(lines[1].ends_with("// 3: let x = 5;"), "{}", lines[1]);
+}
+
+#[test]
+fn test_bool_list_all_and_any() {
+    let input = r"
+shared {
+    let Acks = [false, false];
+    let AllAcked = false;
+    let AnyAcked = false;
+}
+
+always {
+    !AllAcked || Acks.all();
+}
+
+main {
+    Acks.set(0, true);
+    AnyAcked = Acks.any();
+    AllAcked = Acks.all();
+}
+";
+
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        let info = vm.next_random().unwrap();
+        assert!(info.invariant_error.is_ok());
+    }
+    assert_eq!(vm.globals.get("AnyAcked"), Some(&Literal::Bool(true)));
+    assert_eq!(vm.globals.get("AllAcked"), Some(&Literal::Bool(false)));
+
+    let not_bool = r"
+main {
+    let l = [1, 2];
+    let a = l.all();
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), not_bool.to_string());
+    let pairs = althread::parser::parse(not_bool, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    assert!(ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .is_err());
+}
+
+#[test]
+fn test_join_waits_for_process_termination() {
+    let program = |joins: &str| {
+        format!(
+            r"
+shared {{
+    let Done = 0;
+    let Joined = false;
+}}
+
+program Worker() {{
+    atomic {{ Done = Done + 1; }}
+}}
+
+always {{
+    !Joined || Done == 2;
+}}
+
+main {{
+    let a = run Worker();
+    let b = run Worker();
+    {joins}
+    Joined = true;
+}}
+"
+        )
+    };
+
+    for (joins, holds) in [
+        ("join(a); join(b);", true),
+        ("await first { join(b) => join(a); }", true),
+        ("join(a);", false),
+    ] {
+        let input = program(joins);
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.clone());
+
+        let pairs = althread::parser::parse(&input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        let compiled_project = ast
+            .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+            .unwrap();
+
+        let (violation, _) =
+            althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+        assert_eq!(violation.is_empty(), holds, "{joins}");
+    }
+
+    let not_a_process = r"
+main {
+    let x = 1;
+    join(x);
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), not_a_process.to_string());
+    let pairs = althread::parser::parse(not_a_process, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    assert!(ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .is_err());
+}
+
+#[test]
+fn test_equals_compares_values_structurally() {
+    let input = r"
+shared {
+    let SameLists = false;
+    let SameProcesses = false;
+    let DifferentProcesses = true;
+}
+
+program P() {}
+
+main {
+    let a = [[1, 2], [3]];
+    let first = [1, 2];
+    let second = [3];
+    let b: list(list(int));
+    b.push(first);
+    b.push(second);
+    SameLists = a.equals(b);
+    let p = run P();
+    let q = run P();
+    SameProcesses = p.equals(p);
+    DifferentProcesses = p.equals(q);
+}
+";
+
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("SameLists"), Some(&Literal::Bool(true)));
+    assert_eq!(vm.globals.get("SameProcesses"), Some(&Literal::Bool(true)));
+    assert_eq!(vm.globals.get("DifferentProcesses"), Some(&Literal::Bool(false)));
+}
+
+#[test]
+fn test_state_cache_bounds_stored_states() {
+    let program = |invariant: &str| {
+        format!(
+            r"
+shared {{
+    let X = 0;
+}}
+
+program Toggle() {{
+    loop {{
+        atomic {{ X = X + 1; }}
+        atomic {{ X = X - 1; }}
+    }}
+}}
+
+always {{
+    {invariant}
+}}
+
+main {{
+    run Toggle();
+    run Toggle();
+}}
+"
+        )
+    };
+
+    for (invariant, holds) in [("X <= 2;", true), ("X <= 1;", false)] {
+        let input = program(invariant);
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.clone());
+
+        let pairs = althread::parser::parse(&input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        let compiled_project = ast
+            .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+            .unwrap();
+
+        let options = CheckOptions {
+            max_states: Some(200),
+            state_cache: Some(3),
+            ..Default::default()
+        };
+        let (violation, graph) =
+            althread::checker::check_program(&compiled_project, &options).unwrap();
+        assert_eq!(violation.is_empty(), holds, "{invariant}");
+        assert!(graph.is_approximate());
+        assert!(graph.states.iter().flatten().count() < graph.nodes.len());
+        if !holds {
+            let last = violation.last().unwrap().to;
+            assert!(graph.vm(last).check_invariants().is_err());
+        }
+    }
+}
+
+#[test]
+fn test_output_in_state_distinguishes_print_orders() {
+    let input = r#"
+program A() {
+    print("a");
+}
+
+program B() {
+    print("b");
+}
+
+main {
+    run A();
+    run B();
+}
+"#;
+
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let (_, graph) =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+    let states = graph.nodes.len();
+
+    let options = CheckOptions {
+        run: RunOptions {
+            output_in_state: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let (_, graph) = althread::checker::check_program(&compiled_project, &options).unwrap();
+    // the final state is reached once after "a b" and once after "b a"
+    assert_eq!(graph.nodes.len(), states + 1);
+}
+
+#[test]
+fn test_jumps_out_of_atomic_blocks_end_the_atomic_region() {
+    let input = r"
+shared {
+    let X = 0;
+    let Y = 0;
+}
+
+fn set_x() -> int {
+    atomic {
+        X = 1;
+        return 2;
+    }
+}
+
+main {
+    let v = set_x();
+    Y = v;
+    loop {
+        atomic {
+            X = X + 1;
+            if X == 3 {
+                break;
+            }
+        }
+    }
+    Y = X;
+}
+";
+
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let writes = |instructions: &[Instruction], name: &str| {
+        instructions.iter().any(|i| {
+            matches!(&i.control, InstructionType::GlobalAssignment { identifier, .. } if identifier == name)
+        })
+    };
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        let info = vm.next_random().unwrap();
+        // the atomic region ends with the jump, the code after it is a separate step
+        assert!(
+            !(writes(&info.instructions, "X") && writes(&info.instructions, "Y")),
+            "{:?}",
+            info.instructions
+        );
+    }
+    assert_eq!(vm.globals.get("Y"), Some(&Literal::Int(3)));
+}
+
+#[test]
+fn test_global_valuations_are_distinct() {
+    let input = r"
+shared {
+    let X = 0;
+}
+
+program Inc() {
+    atomic {
+        X = X + 1;
+    }
+}
+
+main {
+    run Inc();
+    run Inc();
+}
+";
+
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let (_, graph) =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+    let values: Vec<&Literal> = graph
+        .global_valuations()
+        .iter()
+        .map(|globals| globals.get("X").unwrap())
+        .collect();
+    assert!(graph.nodes.len() > 3);
+    assert_eq!(
+        values,
+        vec![&Literal::Int(0), &Literal::Int(1), &Literal::Int(2)]
+    );
+}
+
+#[test]
+fn test_assume_prunes_checked_paths() {
+    let program = |guard: &str| {
+        format!(
+            r"
+shared {{
+    let X = 0;
+    let Seen = 0;
+}}
+
+program Inc() {{
+    atomic {{ X = X + 1; }}
+}}
+
+always {{
+    Seen < 2;
+}}
+
+main {{
+    run Inc();
+    run Inc();
+    atomic {{
+        {guard}
+        Seen = X;
+    }}
+}}
+"
+        )
+    };
+
+    for (guard, holds) in [("", false), ("assume(X < 2);", true)] {
+        let input = program(guard);
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.clone());
+
+        let pairs = althread::parser::parse(&input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        let compiled_project = ast
+            .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+            .unwrap();
+
+        let (violation, _) =
+            althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+        assert_eq!(violation.is_empty(), holds, "{guard}");
+    }
+
+    let input = r"
+main {
+    assume(false);
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    let err = vm.next_random().unwrap_err();
+    assert!(matches!(
+        err.error_type,
+        althread::error::ErrorType::AssumptionViolated
+    ));
+}
+
+#[test]
+fn test_declaration_infers_type_from_initializer() {
+    let compile = |body: &str| {
+        let input = format!("main {{\n{body}\n}}\n");
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.clone());
+
+        let pairs = althread::parser::parse(&input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    assert!(compile("let x = 5; let y: int = x;").is_ok());
+    assert!(compile("let l: list(int) = []; let m = l; let n: list(int) = m;").is_ok());
+
+    for body in [
+        "let x = 5; let y: float = x;",
+        "let x: bool = 5;",
+        "let l = [];",
+    ] {
+        let err = compile(body).unwrap_err();
+        assert!(
+            matches!(err.error_type, althread::error::ErrorType::TypeError),
+            "{}",
+            body
+        );
+    }
+}
+
+#[test]
+fn test_explain_trace_narrates_counterexample() {
+    let input = r"
+shared {
+    let X = 0;
+}
+
+program Inc() {
+    atomic { X = X + 1; }
+}
+
+always {
+    X < 2;
+}
+
+main {
+    run Inc();
+    run Inc();
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let (path, graph) =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+    let sentences = althread::checker::explain::explain_trace(&graph, &path, input);
+    assert_eq!(sentences.len(), path.len() + 1);
+    assert!(sentences[0].starts_with("main#0 starts Inc#1"));
+    assert!(sentences.iter().any(|s| s.contains("sets X=2")));
+    assert_eq!(
+        sentences.last().unwrap(),
+        "The invariant `X < 2` (line 11) is violated because X=2."
+    );
+}
+
+#[test]
+fn test_std_modules_are_bundled() {
+    let input = r"
+import [std/lock, std/queue]
+
+shared {
+    let In = 0;
+    let Got = 0;
+}
+
+program P() {
+    lock.acquire();
+    In = In + 1;
+    In = In - 1;
+    lock.release();
+    queue.enqueue(1);
+}
+
+program C() {
+    let a = queue.dequeue();
+    let b = queue.dequeue();
+    atomic { Got = a + b; }
+}
+
+always {
+    In < 2;
+}
+
+main {
+    run P();
+    run P();
+    run C();
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    assert!(
+        althread::analysis::wait_dependencies::find_unsatisfiable_waits(&compiled_project)
+            .is_empty()
+    );
+
+    let (violation, graph) =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+    assert!(violation.is_empty());
+    assert!(graph
+        .global_valuations()
+        .iter()
+        .any(|globals| globals.get("Got") == Some(&Literal::Int(2))));
+
+    let input = "import [std/unknown]\n\nmain {\n}\n";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let err = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap_err();
+    assert!(matches!(
+        err.error_type,
+        althread::error::ErrorType::ModuleNotFound
+    ));
+}
+
+#[test]
+fn test_all_violations_are_gathered() {
+    let input = r"
+shared {
+    let X = 0;
+    let Y = 0;
+}
+
+program Inc() {
+    atomic { X = X + 1; }
+    Y = Y + 1;
+}
+
+always {
+    X < 2;
+    Y < 1;
+    X >= 0;
+}
+
+main {
+    run Inc();
+    run Inc();
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let (violations, _) = althread::checker::check_program_all_violations(
+        &compiled_project,
+        &CheckOptions::default(),
+    )
+    .unwrap();
+    let lines: Vec<usize> = violations
+        .iter()
+        .map(|v| v.error.pos.as_ref().unwrap().line)
+        .collect();
+    assert_eq!(lines, vec![13, 14]);
+
+    // each violation comes with the shortest path reaching it
+    let (first, _) =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+    assert_eq!(violations[0].path.len(), first.len());
+    assert!(violations[1].path.len() >= violations[0].path.len());
+}
+
+#[test]
+fn test_all_violations_paths_are_shortest_in_any_order() {
+    let input = r"
+shared {
+    let X = 0;
+    let Y = 0;
+}
+
+program Slow() {
+    Y = 1;
+    Y = 2;
+    Y = 3;
+    X = 1;
+}
+
+program Fast() {
+    X = 2;
+}
+
+always {
+    X == 0;
+}
+
+main {
+    run Fast();
+    run Slow();
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    // a depth-first search reaches the violation through Slow first, the paths are still the
+    // ones of a breadth-first search
+    let options = CheckOptions {
+        explore_order: althread::checker::ExploreOrder::Dfs,
+        ..Default::default()
+    };
+    let (violations, _) =
+        althread::checker::check_program_all_violations(&compiled_project, &options).unwrap();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].path.len(), 3);
+}
+
+#[test]
+fn test_starvation_of_enabled_process_is_detected() {
+    let program = |spinner: &str| {
+        format!(
+            r"
+shared {{
+    let Done = false;
+}}
+
+program Spinner() {{
+    {spinner}
+}}
+
+program Worker() {{
+    Done = true;
+}}
+
+main {{
+    run Spinner();
+    run Worker();
+}}
+"
+        )
+    };
+
+    for (spinner, starves) in [
+        ("while !Done { print(\"spin\"); }", true),
+        ("await Done;", false),
+    ] {
+        let input = program(spinner);
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.clone());
+
+        let pairs = althread::parser::parse(&input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        let compiled_project = ast
+            .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+            .unwrap();
+
+        let (_, graph) =
+            althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+        let starvation = althread::checker::starvation::find_starvation(&graph, 3);
+        assert_eq!(starvation.is_some(), starves, "{spinner}");
+        if let Some(starving) = starvation {
+            assert_eq!(starving.name, "Worker");
+            assert_eq!(
+                starving
+                    .path
+                    .iter()
+                    .rev()
+                    .take(3)
+                    .filter(|link| link.name == "Spinner")
+                    .count(),
+                3
+            );
+        }
+    }
+}
+
+#[test]
+fn test_repeat_runs_block_count_times() {
+    let run = |body: &str| {
+        let input = format!(
+            "shared {{\n    let Total = 0;\n}}\n\nmain {{\n    let total = 0;\n{body}\n    Total = total;\n}}\n"
+        );
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.clone());
+
+        let pairs = althread::parser::parse(&input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        let compiled_project = ast
+            .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+            .map_err(|e| e.error_type)?;
+        let mut vm = VM::new(&compiled_project, RunOptions::default());
+        vm.start(0);
+        while !vm.is_finished() {
+            vm.next_random().map_err(|e| e.error_type)?;
+        }
+        Ok::<_, althread::error::ErrorType>(vm.globals.get("Total").cloned())
+    };
+
+    let total = |body: &str| run(body).unwrap().unwrap();
+    assert_eq!(total("let n = 2; repeat n + 1 { total += 1; }"), Literal::Int(3));
+    assert_eq!(total("repeat 0 { total += 1; }"), Literal::Int(0));
+    assert_eq!(
+        total("repeat 4 { total += 1; if total == 1 { continue; } total += 10; }"),
+        Literal::Int(34)
+    );
+    assert_eq!(
+        total("repeat 5 { total += 1; if total == 2 { break; } }"),
+        Literal::Int(2)
+    );
+    // the count is evaluated once
+    assert_eq!(
+        total("let n = 3; repeat n { n += 1; total += 1; }"),
+        Literal::Int(3)
+    );
+
+    assert!(matches!(
+        run("repeat true { total += 1; }"),
+        Err(althread::error::ErrorType::TypeError)
+    ));
+    assert!(matches!(
+        run("let n = 1; repeat n - 2 { total += 1; }"),
+        Err(althread::error::ErrorType::RuntimeError)
+    ));
+}
+
+#[test]
+fn test_rand_is_enumerated_by_the_checker() {
+    let program = |invariant: &str| {
+        format!(
+            r"
+shared {{
+    let X = 0;
+}}
+
+always {{
+    {invariant}
+}}
+
+main {{
+    let a = rand(1, 3);
+    let b = rand(0, 1);
+    X = a * 2 + b;
+}}
+"
+        )
+    };
+
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    // every combination of the draws is explored
+    for (invariant, holds) in [("X != 7;", false), ("X != 2;", false), ("X < 8;", true)] {
+        let compiled_project = compile(&program(invariant)).unwrap();
+        let (violation, _) =
+            althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+        assert_eq!(violation.is_empty(), holds, "{invariant}");
+    }
+
+    // when the program is run, the values are drawn from the VM generator
+    let compiled_project = compile(&program("X < 8;")).unwrap();
+    for seed in 0..20 {
+        let mut vm = VM::new(&compiled_project, RunOptions::default());
+        vm.start(seed);
+        while !vm.is_finished() {
+            vm.next_random().unwrap();
+        }
+        let x = vm.globals.get("X").unwrap().clone();
+        assert!(matches!(x, Literal::Int(2..=7)), "{}", x);
+    }
+
+    let compiled_project = compile(
+        r"
+main {
+    let a = rand(0, 1000);
+}
+",
+    )
+    .unwrap();
+    let err =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap_err();
+    assert!(matches!(
+        err.error_type,
+        althread::error::ErrorType::RuntimeError
+    ));
+
+    let err = compile(
+        r"
+main {
+    let a = rand(0, true);
+}
+",
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err.error_type,
+        althread::error::ErrorType::FunctionArgumentTypeMismatch
+    ));
+}
+
+#[test]
+fn test_min_max_abs_builtins() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    let compiled_project = compile(
+        r"
+shared {
+    let X = 0;
+    let Y = 0;
+}
+
+main {
+    let a = 3;
+    let b = -7;
+    X = max(a, b) + 1;
+    Y = min(a, b) * 2 - abs(b);
+    let l = [abs(b), max(a, 10)];
+    assert(l.at(0) == 7 && l.at(1) == 10);
+}
+",
+    )
+    .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("X"), Some(&Literal::Int(4)));
+    assert_eq!(vm.globals.get("Y"), Some(&Literal::Int(-21)));
+
+    let err = compile("main { let x = max(1); }").unwrap_err();
+    assert!(matches!(
+        err.error_type,
+        althread::error::ErrorType::FunctionArgumentCountError
+    ));
+    let err = compile("main { let x = abs(true) + 1; }").unwrap_err();
+    assert!(matches!(
+        err.error_type,
+        althread::error::ErrorType::FunctionArgumentTypeMismatch
+    ));
+}
+
+#[test]
+fn test_calls_in_a_tuple_are_made_from_left_to_right() {
+    let mut input_map = HashMap::new();
+    let input = r"
+fn id(a: int) -> int {
+    return a;
+}
+
+fn digits(a: int, b: int, c: int, d: int) -> int {
+    return a * 1000 + b * 100 + c * 10 + d;
+}
+
+main {
+    let x = 5;
+    let n = digits(x, id(1), id(x) + id(2), x);
+    assert(n == 5175);
+}
+";
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+}
+
+#[test]
+fn test_registered_host_function_is_callable() {
+    let stdlib = std::rc::Rc::new(althread::compiler::stdlib::Stdlib::new());
+    stdlib.register_function(
+        "oracle",
+        vec![DataType::Integer],
+        DataType::Integer,
+        |args, _pos| match args {
+            [Literal::Int(x)] => Ok(Literal::Int(x * 10)),
+            _ => unreachable!("the argument types are checked at compile time"),
+        },
+    );
+
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile_with_stdlib(
+            std::path::Path::new(""),
+            StandardFileSystem,
+            stdlib.clone(),
+            &mut input_map,
+        )
+    };
+
+    let compiled_project = compile(
+        r"
+shared {
+    let X = 0;
+}
+
+always {
+    oracle(X) <= 40;
+}
+
+main {
+    let y = oracle(2);
+    X = y + 1;
+    X = X + 1;
+}
+",
+    )
+    .unwrap();
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("X"), Some(&Literal::Int(22)));
+
+    // the invariant calls the host function too: oracle(21) and oracle(22) exceed 40
+    let (violation, _) =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+    assert!(!violation.is_empty());
+
+    let err = compile(
+        r"
+main {
+    let x = oracle(true);
+}
+",
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err.error_type,
+        althread::error::ErrorType::FunctionArgumentTypeMismatch
+    ));
+
+    // without registration, the function does not exist
+    let input = "main { let x = oracle(1); }";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let err = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap_err();
+    assert!(matches!(
+        err.error_type,
+        althread::error::ErrorType::UndefinedFunction
+    ));
+}
+
+#[test]
+fn test_interleavings_of_region_are_counted() {
+    let program = |body: &str| {
+        format!(
+            r"
+shared {{
+    let X = 0;
+}}
+
+program A() {{
+    {body}
+}}
+
+main {{
+    run A();
+    run A();
+}}
+"
+        )
+    };
+
+    let count = |input: &str, lines| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        let compiled_project = ast
+            .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+            .unwrap();
+        let (_, graph) =
+            althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+        althread::checker::interleavings::count_interleavings(&graph, &lines)
+    };
+
+    // the two steps of each process interleave in 6 ways, reduced to 2 by an atomic block
+    let split = count(&program("let t = X;\n    X = t + 1;"), 7..=8);
+    assert_eq!(split.paths, Some(6));
+    let atomic = count(&program("atomic { let t = X; X = t + 1; }"), 7..=7);
+    assert_eq!(atomic.paths, Some(2));
+    assert!(atomic.transitions < split.transitions);
+
+    let outside = count(&program("X = X + 1;"), 100..=200);
+    assert_eq!(outside.transitions, 0);
+    assert_eq!(outside.paths, Some(0));
+
+    let looping = count(&program("loop { X = 1 - X; }"), 7..=7);
+    assert_eq!(looping.paths, None);
+}
+
+#[test]
+fn test_to_string_is_available_on_every_type() {
+    let input = r#"
+shared {
+    let Msg = "";
+    let N = 7;
+}
+
+main {
+    let l = [1, 2];
+    let b = true;
+    let f = 1.5;
+    let n = N.to_string();
+    Msg = n + " " + l.to_string() + " " + b.to_string() + " " + f.to_string();
+}
+"#;
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    let expected = format!(
+        "7 {} true {}",
+        Literal::List(DataType::Integer, vec![Literal::Int(1), Literal::Int(2)]),
+        Literal::Float(1.5.into())
+    );
+    assert_eq!(vm.globals.get("Msg"), Some(&Literal::String(expected)));
+}
+
+#[test]
+fn test_rand_in_shared_block_gives_several_initial_states() {
+    let program = |invariant: &str| {
+        format!(
+            r"
+shared {{
+    let X = rand(1, 3);
+    let Y = 0;
+}}
+
+always {{
+    {invariant}
+}}
+
+main {{
+    Y = X;
+}}
+"
+        )
+    };
+
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    for (invariant, holds) in [("X != 3;", false), ("Y != 2;", false), ("X < 4;", true)] {
+        let compiled_project = compile(&program(invariant)).unwrap();
+        let (violation, graph) =
+            althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+        assert_eq!(violation.is_empty(), holds, "{invariant}");
+        if holds {
+            assert_eq!(graph.initial_states.len(), 3);
+        }
+    }
+
+    // when the program is run, the initial value is drawn from the VM generator
+    let compiled_project = compile(&program("X < 4;")).unwrap();
+    for seed in 0..20 {
+        let mut vm = VM::new(&compiled_project, RunOptions::default());
+        vm.start(seed);
+        let x = vm.globals.get("X").unwrap().clone();
+        assert!(matches!(x, Literal::Int(1..=3)), "{}", x);
+    }
+
+    let err = compile(
+        r"
+shared {
+    let X = rand(3, 1);
+}
+main {}
+",
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err.error_type,
+        althread::error::ErrorType::ExpressionError
+    ));
+}
+
+#[test]
+fn test_yield_is_a_scheduling_point() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+    let states = |body: &str| {
+        let compiled_project = compile(&format!("main {{ {body} }}")).unwrap();
+        let (_, graph) =
+            althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+        graph.nodes.len()
+    };
+
+    // the local instructions are executed in a single step, unless a yield splits them
+    assert_eq!(
+        states("let a = 1; yield; let b = a + 1;"),
+        states("let a = 1; let b = a + 1;") + 1
+    );
+
+    let err = compile("main { atomic { yield; } }").unwrap_err();
+    assert!(matches!(
+        err.error_type,
+        althread::error::ErrorType::InstructionNotAllowed
+    ));
+}
+
+#[test]
+fn test_equivalent_transitions_are_pruned() {
+    let input = r"
+shared {
+    let X = 0;
+}
+
+main {
+    let a = rand(0, 2);
+    a = 0;
+    X = 1;
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let links = |graph: &althread::checker::StateGraph| {
+        graph.nodes.iter().map(|node| node.successors.len()).sum::<usize>()
+    };
+
+    let (_, graph) =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+    let (states, all_links) = (graph.nodes.len(), links(&graph));
+    assert_eq!(graph.pruned_links, 0);
+
+    let options = CheckOptions {
+        prune_equivalent_sends: true,
+        ..Default::default()
+    };
+    let (_, graph) = althread::checker::check_program(&compiled_project, &options).unwrap();
+    assert_eq!(graph.nodes.len(), states);
+    assert_eq!(graph.pruned_links, 2);
+    assert_eq!(links(&graph), all_links - 2);
+}
+
+#[test]
+fn test_tuple_len_and_append() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    let compiled_project = compile(
+        r"
+shared {
+    let N = 0;
+}
+
+main {
+    let t: tuple(int, int);
+    let u = t.append(3);
+    let v = u.append(4);
+    N = v.len();
+    let w: tuple(int, int, int, int) = v;
+    print(w);
+}
+",
+    )
+    .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("N"), Some(&Literal::Int(4)));
+
+    // the type of the appended element is only known for homogeneous tuples
+    assert!(compile(
+        r"
+main {
+    let t: tuple(int, string);
+    let u = t.append(3);
+}
+",
+    )
+    .is_err());
+}
+
+#[test]
+fn test_counterexample_schedule_replays_the_violation() {
+    let input = r"
+shared {
+    let X = 0;
+    let Y = rand(0, 1);
+}
+
+always {
+    X < 3;
+}
+
+program A() {
+    let d = rand(1, 2);
+    X = X + d;
+}
+
+main {
+    run A();
+    run A();
+    X = X + Y;
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let (path, graph) =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+    assert!(!path.is_empty());
+    let schedule = althread::checker::schedule::Schedule::from_path(
+        &compiled_project,
+        RunOptions::default(),
+        &graph,
+        &path,
+    )
+    .unwrap();
+    assert_eq!(schedule.steps.len(), path.len());
+
+    // the schedule survives its textual form
+    let parsed: althread::checker::schedule::Schedule = schedule.to_string().parse().unwrap();
+    assert_eq!(parsed, schedule);
+
+    let mut vm = parsed
+        .initial_vm(&compiled_project, RunOptions::default())
+        .unwrap();
+    assert!(vm.check_invariants().is_ok());
+    for (i, step) in parsed.steps.iter().enumerate() {
+        let (link, next) = step.replay(&vm).unwrap();
+        assert_eq!((link.pid, &link.name), (path[i].pid, &path[i].name));
+        vm = next;
+        assert_eq!(vm.check_invariants().is_err(), i == parsed.steps.len() - 1);
+    }
+    assert!(&vm == graph.vm(path.last().unwrap().to).as_ref());
+
+    assert!("0 0 main".parse::<althread::checker::schedule::Schedule>().is_err());
+}
+
+#[test]
+fn test_process_handles_are_comparable() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    let compiled_project = compile(
+        r"
+shared {
+    let Owner: proc(A);
+    let Unset = true;
+    let Same = false;
+    let Different = false;
+    let OtherProgram = true;
+}
+
+program A() {}
+program B() {}
+
+main {
+    let a = run A();
+    let b = run A();
+    let c = run B();
+    Unset = Owner != a;
+    Owner = a;
+    Same = Owner == a;
+    Different = Owner != b;
+    OtherProgram = a == c;
+}
+",
+    )
+    .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    for (name, value) in [
+        ("Unset", true),
+        ("Same", true),
+        ("Different", true),
+        ("OtherProgram", false),
+    ] {
+        assert_eq!(vm.globals.get(name), Some(&Literal::Bool(value)), "{name}");
+    }
+
+    // a handle is still not comparable with a value of another type
+    assert!(compile(
+        r"
+program A() {}
+main {
+    let a = run A();
+    let same = a == 1;
+}
+",
+    )
+    .is_err());
+}
+
+#[test]
+fn test_max_memory_stops_the_search() {
+    let input = r"
+shared {
+    let X = 0;
+}
+
+program A() {
+    loop {
+        X = X + 1;
+    }
+}
+
+main {
+    run A();
+    run A();
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut options = CheckOptions {
+        max_states: Some(2000),
+        ..Default::default()
+    };
+    let (_, graph) = althread::checker::check_program(&compiled_project, &options).unwrap();
+    assert!(!graph.memory_limit_reached);
+    let states = graph.nodes.len();
+
+    options.max_memory = Some(graph.estimated_memory / 4);
+    let (_, graph) = althread::checker::check_program(&compiled_project, &options).unwrap();
+    assert!(graph.memory_limit_reached);
+    assert!(!graph.exhaustive);
+    assert!(graph.nodes.len() < states);
+}
+
+#[test]
+fn test_timeout_stops_the_search() {
+    let input = r"
+shared {
+    let X = 0;
+}
+
+main {
+    loop {
+        X = X + 1;
+    }
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    // the search would only stop at the state limit, the partial graph is returned
+    let options = CheckOptions {
+        timeout: Some(std::time::Duration::from_millis(50)),
+        ..Default::default()
+    };
+    let (path, graph) = althread::checker::check_program(&compiled_project, &options).unwrap();
+    assert!(path.is_empty());
+    assert!(graph.time_limit_reached);
+    assert!(!graph.exhaustive);
+    assert!(!graph.nodes.is_empty());
+}
+
+#[test]
+fn test_list_dedup_and_count() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    let compiled_project = compile(
+        r"
+shared {
+    let L = [1, 1, 2, 2, 2, 1];
+    let Ones = 0;
+    let Twos = 0;
+}
+
+main {
+    L.dedup();
+    Ones = L.count(1);
+    Twos = L.count(2);
+}
+",
+    )
+    .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(
+        vm.globals.get("L"),
+        Some(&Literal::List(
+            DataType::Integer,
+            vec![Literal::Int(1), Literal::Int(2), Literal::Int(1)]
+        ))
+    );
+    assert_eq!(vm.globals.get("Ones"), Some(&Literal::Int(2)));
+    assert_eq!(vm.globals.get("Twos"), Some(&Literal::Int(1)));
+}
+
+#[test]
+fn test_custom_invariant_hooks() {
+    let input = r"
+shared {
+    let X = 0;
+}
+
+program A() {
+    X = X + 1;
+}
+
+main {
+    run A();
+    run A();
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let at_most = |max: i64| -> althread::checker::InvariantHook {
+        Box::new(move |vm: &VM| match vm.globals.get("X") {
+            Some(Literal::Int(x)) if *x > max => Err(format!("X exceeds {max}")),
+            _ => Ok(()),
+        })
+    };
+
+    let (path, _) = althread::checker::check_program_with_hooks(
+        &compiled_project,
+        &CheckOptions::default(),
+        &[at_most(2)],
+    )
+    .unwrap();
+    assert!(path.is_empty());
+
+    let (path, graph) = althread::checker::check_program_with_hooks(
+        &compiled_project,
+        &CheckOptions::default(),
+        &[at_most(2), at_most(1)],
+    )
+    .unwrap();
+    assert!(!path.is_empty());
+    let last = graph.vm(path.last().unwrap().to);
+    assert_eq!(last.globals.get("X"), Some(&Literal::Int(2)));
+}
+
+#[test]
+fn test_and_or_short_circuit_calls() {
+    let input = r"
+shared {
+    let Guarded = false;
+    let Fallback = false;
+    let Nested = false;
+}
+
+main {
+    let l = [1];
+    let i = 3;
+    Guarded = i < l.len() && 0 < l.at(i);
+    Fallback = i >= l.len() || 0 < l.at(i);
+    Nested = 0 < l.len() && (i > 2 || 0 < l.at(i));
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    // the out of bounds accesses on the right of the operators are never made
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Guarded"), Some(&Literal::Bool(false)));
+    assert_eq!(vm.globals.get("Fallback"), Some(&Literal::Bool(true)));
+    assert_eq!(vm.globals.get("Nested"), Some(&Literal::Bool(true)));
+}
+
+#[test]
+fn test_instruction_limit_per_step() {
+    let input = r"
+shared {
+    let X = 0;
+}
+
+main {
+    atomic {
+        while true {
+            X = X + 1;
+        }
+    }
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    let run = RunOptions {
+        instr_limit_per_step: Some(1000),
+        ..Default::default()
+    };
+
+    let mut vm = VM::new(&compiled_project, run);
+    vm.start(0);
+    let err = loop {
+        if let Err(err) = vm.next_random() {
+            break err;
+        }
+    };
+    assert!(matches!(
+        err.error_type,
+        althread::error::ErrorType::RuntimeError
+    ));
+    assert!(err.message.contains("instruction limit"));
+
+    let options = CheckOptions {
+        run,
+        ..Default::default()
+    };
+    assert!(althread::checker::check_program(&compiled_project, &options).is_err());
+}
+
+#[test]
+fn test_trailing_commas() {
+    let input = r"
+shared {
+    let L = [1, 2,];
+    let S = 0;
+}
+
+fn add(
+    a: int,
+    b: int,
+) -> int {
+    return a + b;
+}
+
+main {
+    S = add(
+        1,
+        2,
+    );
+    L.push(S,);
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("S"), Some(&Literal::Int(3)));
+    assert_eq!(
+        vm.globals.get("L"),
+        Some(&Literal::List(
+            DataType::Integer,
+            vec![Literal::Int(1), Literal::Int(2), Literal::Int(3)]
+        ))
+    );
+
+    // a comma alone is not an empty list
+    assert!(althread::parser::parse("main { let l = [,]; }", "").is_err());
+}
+
+#[test]
+fn test_huge_range_is_an_error() {
+    let input = r"
+main {
+    let l = [0..1000000000];
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    let err = loop {
+        if let Err(err) = vm.next_random() {
+            break err;
+        }
+    };
+    assert!(matches!(
+        err.error_type,
+        althread::error::ErrorType::ExpressionError
+    ));
+    assert_eq!(err.pos.unwrap().line, 3);
+}
+
+#[test]
+fn test_ignored_conditions_are_not_checked() {
+    let input = r"
+shared {
+    let X = 0;
+}
+
+always {
+    @ignore X < 1;
+    X >= 0;
+}
+
+check {
+    @ignore always (X == 0);
+}
+
+main {
+    X = 1;
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    assert_eq!(compiled_project.ignored_conditions.len(), 2);
+    assert_eq!(compiled_project.ignored_conditions[0].line, 7);
+    assert!(compiled_project.ltl_formulas.is_empty());
+
+    let (path, _) =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+    assert!(path.is_empty());
+}
+
+#[test]
+fn test_invariants_checked_after_process_start() {
+    let input = r#"
+program A() {
+    print("started");
+}
+
+always {
+    1 > $.procs.A.len();
+}
+
+main {
+    run A();
+}
+"#;
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    for every_step in [false, true] {
+        let run = RunOptions {
+            check_invariants_every_step: every_step,
+            ..Default::default()
+        };
+        let mut vm = VM::new(&compiled_project, run);
+        vm.start(0);
+        let mut violated = false;
+        while !vm.is_finished() {
+            if vm.next_random().unwrap().invariant_error.is_err() {
+                violated = true;
+                break;
+            }
+        }
+        assert!(violated);
+    }
+}
+
+#[test]
+fn test_implies_operator() {
+    let input = r"
+shared {
+    let Locked = false;
+    let Owner = 0;
+    let A = false => false;
+    let B = true => false;
+    let C = false => true => false;
+}
+
+always {
+    Locked => 0 < Owner;
+}
+
+main {
+    Owner = 1;
+    Locked = true;
+    await first {
+        (Locked => 0 < Owner) => Owner = 2;
+    }
+    Owner = 0;
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    assert_eq!(vm.globals.get("A"), Some(&Literal::Bool(true)));
+    assert_eq!(vm.globals.get("B"), Some(&Literal::Bool(false)));
+    // `=>` is right-associative: `false => (true => false)`
+    assert_eq!(vm.globals.get("C"), Some(&Literal::Bool(true)));
+
+    // the invariant is violated once the owner is reset while still locked
+    let (path, _) =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+    assert!(!path.is_empty());
+
+    let input = r"
+main {
+    let x = 1 => true;
+}
+";
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    assert!(ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .is_err());
+}
+
+#[test]
+fn test_fixed_size_arrays() {
+    let input = r"
+shared {
+    let R: [int; 4];
+    let Flags: [bool; 2] = [true, false];
+    let Sum = 0;
+}
+
+main {
+    let local: [int; 3] = [1, 2, 3];
+    local.set(2, 7);
+    for x in local {
+        Sum = Sum + x;
+    }
+    R.set(3, local.len());
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    assert_eq!(
+        compiled_project.global_table.get("R").unwrap().datatype,
+        DataType::Array(Box::new(DataType::Integer), 4)
+    );
+    assert_eq!(
+        compiled_project.global_memory.get("Flags"),
+        Some(&Literal::Array(
+            DataType::Boolean,
+            vec![Literal::Bool(true), Literal::Bool(false)]
+        ))
+    );
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Sum"), Some(&Literal::Int(10)));
+    assert_eq!(
+        vm.globals.get("R"),
+        Some(&Literal::Array(
+            DataType::Integer,
+            vec![Literal::Int(0), Literal::Int(0), Literal::Int(0), Literal::Int(3)]
+        ))
+    );
+
+    // constant indexes and initial values are checked at compile time
+    for input in [
+        "main { let a: [int; 2]; a.set(2, 1); }",
+        "main { let a: [int; 2]; let v = a.at(-1); }",
+        "main { let a: [int; 2] = [1, 2, 3]; }",
+    ] {
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        assert!(ast
+            .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+            .is_err());
+    }
+}
+
+#[test]
+fn test_never_condition_violation() {
+    let input = r"
+shared {
+    let X = 0;
+}
+
+never {
+    X > 1;
+}
+
+main {
+    X = 1;
+    X = 2;
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    assert_eq!(compiled_project.never_conditions.len(), 1);
+    assert!(compiled_project.always_conditions.is_empty());
+
+    let (path, _) =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+    assert!(!path.is_empty());
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    vm.globals.insert("X".to_string(), Literal::Int(2));
+    let error = vm.check_invariants().unwrap_err();
+    assert!(matches!(
+        error.error_type,
+        althread::error::ErrorType::InvariantError
+    ));
+    assert_eq!(error.pos.unwrap().line, 7);
+    assert_eq!(error.message, "The 'never' condition was satisfied");
+}
+
+#[test]
+fn test_bounded_integer_arithmetic() {
+    let input = r"
+shared {
+    let Max = 9223372036854775807;
+    let Min = 0;
+    let Wrapped = 0;
+    let Saturated = 0;
+    let Product = 0;
+    let Difference = 0;
+}
+
+main {
+    let min = 0 - Max - 1;
+    Min = min;
+    Wrapped = Max.add_wrapping(1);
+    Saturated = Max.add_saturating(1);
+    Product = Max.mul_saturating(2);
+    Difference = Min.sub_saturating(1);
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Wrapped"), Some(&Literal::Int(i64::MIN)));
+    assert_eq!(vm.globals.get("Saturated"), Some(&Literal::Int(i64::MAX)));
+    assert_eq!(vm.globals.get("Product"), Some(&Literal::Int(i64::MAX)));
+    assert_eq!(vm.globals.get("Difference"), Some(&Literal::Int(i64::MIN)));
+
+    let input = "main { let x = 1; let y = x.add_wrapping(true); }";
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    let error = vm.next_random().unwrap_err();
+    assert!(error.message.contains(".add_wrapping() expects one integer argument"));
+}
+
+#[test]
+fn test_string_methods() {
+    let input = r#"
+shared {
+    let Upper = "";
+    let Length = 0;
+    let Found = false;
+    let Parts = 0;
+    let Second = "";
+}
+
+main {
+    let name = "Hello World";
+    Upper = name.to_upper();
+    Length = name.len();
+    Found = name.contains("lo W");
+    let parts = name.split(" ");
+    Parts = parts.len();
+    Second = name.char_at(1);
+}
+"#;
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(
+        vm.globals.get("Upper"),
+        Some(&Literal::String("HELLO WORLD".to_string()))
+    );
+    assert_eq!(vm.globals.get("Length"), Some(&Literal::Int(11)));
+    assert_eq!(vm.globals.get("Found"), Some(&Literal::Bool(true)));
+    assert_eq!(vm.globals.get("Parts"), Some(&Literal::Int(2)));
+    assert_eq!(
+        vm.globals.get("Second"),
+        Some(&Literal::String("e".to_string()))
+    );
+
+    let input = r#"main { let s = "abc"; let c = s.char_at(3); }"#;
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    let error = vm.next_random().unwrap_err();
+    assert!(error.message.contains("Index out of bounds: 3"));
+}
+
+#[test]
+fn test_list_contains_and_index_of() {
+    let input = r"
+shared {
+    let Found = false;
+    let Missing = true;
+    let Index = 0;
+    let Absent = 0;
+}
+
+main {
+    let l = [4, 3, 3];
+    Found = l.contains(3);
+    Missing = l.contains(5);
+    Index = l.index_of(3);
+    Absent = l.index_of(5);
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Found"), Some(&Literal::Bool(true)));
+    assert_eq!(vm.globals.get("Missing"), Some(&Literal::Bool(false)));
+    assert_eq!(vm.globals.get("Index"), Some(&Literal::Int(1)));
+    assert_eq!(vm.globals.get("Absent"), Some(&Literal::Int(-1)));
+
+    let input = "main { let l = [1, 2]; let b = l.contains(true); }";
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    let error = vm.next_random().unwrap_err();
+    assert!(matches!(
+        error.error_type,
+        althread::error::ErrorType::RuntimeError
+    ));
+}
+
+#[test]
+fn test_guarded_receive() {
+    let input = r"
+shared {
+    let Got = 0;
+    let Min = 10;
+}
+
+program Sender() {
+    send out(5);
+    Min = 0;
+}
+
+program Receiver() {
+    await receive in (v) if v > Min => {
+        Got = v;
+    }
+}
+
+main {
+    let s = run Sender();
+    let r = run Receiver();
+    channel s.out (int)> r.in;
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    // the message is only received once Min is lowered
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Got"), Some(&Literal::Int(5)));
+
+    // the message never matches, the receiver stays blocked
+    let input = input.replace("Min = 0;", "Min = 7;");
+    let pairs = althread::parser::parse(&input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    let error = loop {
+        match vm.next_random() {
+            Ok(_) => {}
+            Err(error) => break error,
+        }
+    };
+    assert!(error.message.contains("deadlock"));
+    assert_eq!(vm.globals.get("Got"), Some(&Literal::Int(0)));
+}
+
+#[test]
+fn test_explore_orders() {
+    let input = r"
+shared {
+    let X = 0;
+}
+
+program Up() {
+    loop atomic {
+        X = (X + 1) % 6;
+    }
+}
+
+program Down() {
+    loop atomic {
+        X = (X + 5) % 6;
+    }
+}
+
+main {
+    run Up();
+    run Down();
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut results = Vec::new();
+    for order in [
+        althread::checker::ExploreOrder::Bfs,
+        althread::checker::ExploreOrder::Dfs,
+        althread::checker::ExploreOrder::Random,
+    ] {
+        let options = CheckOptions {
+            explore_order: order,
+            ..Default::default()
+        };
+        let (path, graph) = althread::checker::check_program(&compiled_project, &options).unwrap();
+        assert!(path.is_empty());
+        let depth = graph.nodes.iter().map(|node| node.level).max().unwrap();
+        results.push((graph.nodes.len(), depth));
+    }
+
+    // every order explores the same states, breadth-first reaches them by the shortest paths
+    assert!(results.iter().all(|(states, _)| *states == results[0].0));
+    assert!(results.iter().all(|(_, depth)| *depth >= results[0].1));
+    assert!(results[1].1 > results[0].1);
+}
+
+#[test]
+fn test_max_depth_bounds_infinite_exploration() {
+    let input = r"
+shared {
+    let X = 0;
+}
+
+main {
+    loop {
+        X = X + 1;
+    }
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    let options = CheckOptions {
+        max_depth: Some(5),
+        ..Default::default()
+    };
+
+    let (path, graph) = althread::checker::check_program(&compiled_project, &options).unwrap();
+    assert!(path.is_empty());
+    assert!(!graph.exhaustive);
+    assert!(graph.depth_limit_reached);
+    assert_eq!(graph.nodes.iter().map(|node| node.level).max(), Some(5));
+    // the states at the bound are kept, but not expanded
+    assert!(graph
+        .nodes
+        .iter()
+        .all(|node| node.expanded == (node.level < 5)));
+}
+
+#[test]
+fn test_check_reports_deadlock() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+            .unwrap()
+    };
+
+    let input = r"
+shared {
+    let A = false;
+    let B = false;
+}
+
+program P() {
+    await A;
+    B = true;
+}
+
+main {
+    run P();
+    await B;
+    A = true;
+}
+";
+    let compiled_project = compile(input);
+    let (path, graph) =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+    assert_eq!(path.len(), 1);
+    let deadlock = graph.deadlock.expect("the processes wait for each other");
+    assert!(matches!(
+        deadlock.error_type,
+        althread::error::ErrorType::DeadlockError
+    ));
+    assert!(deadlock
+        .message
+        .contains("-main#0 at line 14: waiting for a change of B"));
+    assert!(deadlock
+        .message
+        .contains("-P#1 at line 8: waiting for a change of A"));
+
+    // the states without successors because of an assumption are not deadlocks
+    let input = r"
+shared {
+    let A = false;
+}
+
+program P() {
+    await A;
+}
+
+main {
+    run P();
+    assume(false);
+}
+";
+    let compiled_project = compile(input);
+    let (path, graph) =
+        althread::checker::check_program(&compiled_project, &CheckOptions::default()).unwrap();
+    assert!(path.is_empty());
+    assert!(graph.deadlock.is_none());
+}
+
+#[test]
+fn test_bounded_channel_blocks_sender() {
+    let source = |capacity: &str| {
+        format!(
+            r"
+shared {{
+    let Sent = 0;
+    let Received = 0;
+}}
+
+program Producer() {{
+    repeat 3 {{
+        send out(Sent);
+        Sent = Sent + 1;
+    }}
+}}
+
+main {{
+    let p = run Producer();
+    channel p.out (int){capacity} self.in;
+    repeat 3 {{
+        await receive in(x) => {{
+            Received = Received + 1;
+        }}
+    }}
+}}
+
+always {{
+    Sent - Received <= 2;
+}}
+"
+        )
+    };
+
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+            .unwrap()
+    };
+
+    // with a capacity of 1, the producer is at most one message ahead of the consumer
+    let bounded = compile(&source("[1]"));
+    let (path, _) = althread::checker::check_program(&bounded, &CheckOptions::default()).unwrap();
+    assert!(path.is_empty());
+
+    let mut vm = VM::new(&bounded, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Received"), Some(&Literal::Int(3)));
+
+    let unbounded = compile(&source(""));
+    let (path, _) = althread::checker::check_program(&unbounded, &CheckOptions::default()).unwrap();
+    assert!(!path.is_empty());
+}
+
+#[test]
+fn test_select_over_racing_channels() {
+    let input = r"
+shared {
+    let First = 0;
+    let Second = 0;
+}
+
+program Sender(value: int) {
+    send out(value);
+}
+
+main {
+    let a = run Sender(1);
+    let b = run Sender(2);
+    channel a.out (int)> self.from_a;
+    channel b.out (int)> self.from_b;
+
+    select {
+        receive from_a(x) => {
+            First = x;
+        }
+        receive from_b(x) => {
+            First = x;
+        }
+    }
+    select {
+        receive from_a(x) => {
+            Second = x;
+        }
+        receive from_b(x) => {
+            Second = x;
+        }
+    }
+}
+";
+
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut initial_vm = VM::new(&compiled_project, RunOptions::default());
+    initial_vm.start(0);
+
+    // either sender can win the race, and the other message is received by the second select
+    let mut frontier = vec![initial_vm];
+    let mut winners = std::collections::BTreeSet::new();
+    while let Some(vm) = frontier.pop() {
+        let next_states = vm.next().unwrap();
+        if next_states.is_empty() {
+            assert!(vm.is_finished());
+            let first = vm.globals.get("First").cloned();
+            let second = vm.globals.get("Second").cloned();
+            assert_ne!(first, second);
+            winners.insert(format!("{}", first.unwrap()));
+            continue;
+        }
+        frontier.extend(next_states.into_iter().map(|(.., next_vm)| next_vm));
+    }
+    assert_eq!(winners, std::collections::BTreeSet::from(["1".to_string(), "2".to_string()]));
+}
+
+#[test]
+fn test_receive_with_literal_patterns() {
+    let input = r#"
+shared {
+    let Got = 0;
+    let Put = 0;
+}
+
+program Client() {
+    send out("get", 1);
+    send out("put", 2);
+}
+
+main {
+    let c = run Client();
+    channel c.out (string, int)> self.in;
+    repeat 2 {
+        await first {
+            receive in("get", key) => {
+                Got = key;
+            }
+            receive in("put", value) => {
+                Put = value;
+            }
+        }
+    }
+}
+"#;
+
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Got"), Some(&Literal::Int(1)));
+    assert_eq!(vm.globals.get("Put"), Some(&Literal::Int(2)));
+
+    // the literal must have the type of the component it is matched with
+    let input = input.replace(r#"in("get", key)"#, "in(1, key)");
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.clone());
+    let pairs = althread::parser::parse(&input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let error = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap_err();
+    assert!(matches!(error.error_type, althread::error::ErrorType::TypeError));
+}
+
+#[test]
+fn test_const_parameters_specialize_programs() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    let input = r"
+shared {
+    let Total = 0;
+}
+
+program Ring(const SIZE: int, id: int) {
+    let x = SIZE * 10 + id;
+    atomic {
+        Total = Total + x;
+    }
+}
+
+main {
+    run Ring(4, 1);
+    run Ring(2, 2);
+    run Ring(4, 3);
+}
+";
+    let compiled_project = compile(input).unwrap();
+    // one copy of the program for each value of its const parameter
+    let mut code_names: Vec<&String> = compiled_project.programs_code.keys().collect();
+    code_names.sort();
+    assert_eq!(code_names, vec!["Ring(2)", "Ring(4)", "main"]);
+    assert_eq!(compiled_project.programs_code["Ring(4)"].name, "Ring");
+    assert_eq!(
+        compiled_project.programs_code["Ring(4)"].argument_names,
+        vec!["id".to_string()]
+    );
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Total"), Some(&Literal::Int(41 + 22 + 43)));
+
+    // the value of a const parameter must be known at compile time
+    let input = input.replace("run Ring(2, 2);", "let n = 2;\n    run Ring(n, 2);");
+    let error = compile(&input).unwrap_err();
+    assert!(matches!(
+        error.error_type,
+        althread::error::ErrorType::ExpressionError
+    ));
+
+    // only the parameters of a program can be const
+    let input = "fn f(const x: int) -> int { return x; }\nmain {}\n";
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let error = Ast::build(pairs, "").unwrap_err();
+    assert!(matches!(
+        error.error_type,
+        althread::error::ErrorType::SyntaxError
+    ));
+}
+
+#[test]
+fn test_map_literal_and_interfaces() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    let input = r#"
+shared {
+    let Counts: map(string, int) = {};
+    let Same = false;
+    let Len = 0;
+}
+
+program Count(name: string) {
+    atomic {
+        if Counts.contains_key(name) {
+            let c = Counts.get(name);
+            Counts.insert(name, c + 1);
+        } else {
+            Counts.insert(name, 1);
+        }
+    }
+}
+
+main {
+    let a = {"x": 1, "y": 2};
+    let b: map(string, int) = {};
+    b.insert("y", 2);
+    b.insert("z", 3);
+    b.insert("x", 1);
+    b.remove("z");
+    b.remove("missing");
+    Same = a.equals(b);
+
+    run Count("p");
+    run Count("q");
+    run Count("p");
+    await Counts.len() == 2;
+    Len = b.len();
+}
+"#;
+    let compiled_project = compile(input).unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    // the entries do not depend on the insertion order
+    assert_eq!(vm.globals.get("Same"), Some(&Literal::Bool(true)));
+    assert_eq!(vm.globals.get("Len"), Some(&Literal::Int(2)));
+    match vm.globals.get("Counts") {
+        Some(Literal::Map(_, _, entries)) => {
+            let counts: Vec<&Literal> = entries.values().map(|(_, value)| value).collect();
+            assert_eq!(counts, vec![&Literal::Int(2), &Literal::Int(1)]);
+        }
+        other => panic!("expected a map, got {other:?}"),
+    }
+
+    // every key of a map literal has the same type
+    let error = compile("main {\n    let m = {\"a\": 1, 2: 3};\n}\n").unwrap_err();
+    assert!(matches!(
+        error.error_type,
+        althread::error::ErrorType::TypeError
+    ));
+}
+
+#[test]
+fn test_string_escapes_and_multiline_strings() {
+    let input = r#"
+shared {
+    let Escaped = "a\tb\n\"c\" \\";
+    let Multiline = """
+first "line"
+second""";
+}
+
+main {}
+"#;
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    assert_eq!(
+        vm.globals.get("Escaped"),
+        Some(&Literal::String("a\tb\n\"c\" \\".to_string()))
+    );
+    // the line break after the opening quotes is not part of the string
+    assert_eq!(
+        vm.globals.get("Multiline"),
+        Some(&Literal::String("first \"line\"\nsecond".to_string()))
+    );
+
+    // an unknown escape sequence is a syntax error
+    assert!(althread::parser::parse("main { print(\"\\q\"); }", "").is_err());
+}
+
+#[test]
+fn test_run_where_bindings() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    let input = r"
+shared {
+    let NextId = 5;
+    let Sum = 0;
+}
+
+fn next_id() -> int {
+    return 7;
+}
+
+program Worker(id: int, prio: int) {
+    atomic {
+        Sum = Sum + id * 10 + prio;
+    }
+}
+
+main {
+    let base = 2;
+    run Worker(id, prio) where id = next_id(), prio = base + 1;
+    let p = run Worker(i, 0) where i = NextId;
+    atomic {
+        run Worker(1, b) where b = base;
+    }
+}
+";
+    let compiled_project = compile(input).unwrap();
+    // the bindings are computed in the atomic step spawning the process
+    let main = &compiled_project.programs_code["main"].instructions;
+    let run_index = main
+        .iter()
+        .position(|inst| matches!(inst.control, InstructionType::RunCall { .. }))
+        .unwrap();
+    assert!(main[..run_index]
+        .iter()
+        .any(|inst| matches!(inst.control, InstructionType::AtomicStart)));
+    assert!(matches!(
+        main[run_index + 1].control,
+        InstructionType::AtomicEnd
+    ));
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Sum"), Some(&Literal::Int(73 + 50 + 12)));
+
+    // the bindings are only visible in the arguments of the call
+    let error = compile(&input.replace(
+        "    let p = run Worker(i, 0) where i = NextId;",
+        "    let p = run Worker(id, 0);",
+    ))
+    .unwrap_err();
+    assert!(matches!(
+        error.error_type,
+        althread::error::ErrorType::VariableError
+    ));
+}
+
+#[test]
+fn test_assert_with_optional_message() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+    let run = |input: &str| {
+        let compiled_project = compile(input).unwrap();
+        let mut vm = VM::new(&compiled_project, RunOptions::default());
+        vm.start(0);
+        while !vm.is_finished() {
+            vm.next_random()?;
+        }
+        Ok::<(), althread::error::AlthreadError>(())
+    };
+
+    run("main { let x = 1; assert(x == 1); assert(x > 0, \"positive\"); }").unwrap();
+
+    let error = run("main { let x = 1; assert(x == 2); }").unwrap_err();
+    assert!(matches!(
+        error.error_type,
+        althread::error::ErrorType::AssertionFailed
+    ));
+    assert_eq!(error.message, "Assertion failed");
+    assert_eq!(error.pos.unwrap().line, 1);
+
+    let error = run("main { assert(false, \"x should be 2\"); }").unwrap_err();
+    assert_eq!(error.message, "Assertion failed: x should be 2");
+
+    let error = compile("main { assert(); }").unwrap_err();
+    assert!(matches!(
+        error.error_type,
+        althread::error::ErrorType::FunctionArgumentCountError
+    ));
+}
+
+#[test]
+fn test_on_exit_block() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    let input = r"
+shared {
+    let Locked = false;
+    let Released = 0;
+}
+
+program Worker(id: int) {
+    await first {
+        Locked == false => { Locked = true; }
+    }
+    on_exit {
+        Locked = false;
+        Released = Released + id;
+    }
+    let done = id;
+}
+
+main {
+    run Worker(1);
+    run Worker(2);
+}
+";
+    let compiled_project = compile(input).unwrap();
+    // the finalizer is the last atomic block, only the locals are popped after it
+    let worker = &compiled_project.programs_code["Worker"].instructions;
+    let atomic_end = worker
+        .iter()
+        .rposition(|inst| matches!(inst.control, InstructionType::AtomicEnd))
+        .unwrap();
+    assert!(worker[atomic_end + 1..].iter().all(|inst| matches!(
+        inst.control,
+        InstructionType::Unstack { .. } | InstructionType::EndProgram
+    )));
+
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Locked"), Some(&Literal::Bool(false)));
+    assert_eq!(vm.globals.get("Released"), Some(&Literal::Int(3)));
+
+    // a finalizer can only be at the top level of a program
+    let error = compile("main { if true { on_exit { print(1); } } }").unwrap_err();
+    assert!(matches!(
+        error.error_type,
+        althread::error::ErrorType::InstructionNotAllowed
+    ));
+    let error = compile("main { on_exit { print(1); } on_exit { print(2); } }").unwrap_err();
+    assert!(matches!(
+        error.error_type,
+        althread::error::ErrorType::InstructionNotAllowed
+    ));
+}
+
+#[test]
+fn test_modulo_and_bitwise_operators() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    let compiled_project = compile(
+        r"
+shared {
+    let Mod = 0;
+    let Xor = 0;
+    let Mixed = 0;
+}
+
+main {
+    let a = 1;
+    let b = 7;
+    let c = 3;
+    Mod = a + b % c;
+    Xor = b ^ c;
+    Mixed = (b & 6) | (c ^ 1);
+}
+",
+    )
+    .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    // `%` binds tighter than `+`
+    assert_eq!(vm.globals.get("Mod"), Some(&Literal::Int(2)));
+    assert_eq!(vm.globals.get("Xor"), Some(&Literal::Int(4)));
+    assert_eq!(vm.globals.get("Mixed"), Some(&Literal::Int(6)));
+
+    // the operands of `%` and of the bitwise operators are integers
+    for expression in ["1.5 % 2", "5 % 2.0", "true ^ false", "1.0 & 2.0"] {
+        let error = compile(&format!("main {{ let x = {expression}; }}")).unwrap_err();
+        assert!(
+            matches!(error.error_type, althread::error::ErrorType::ExpressionError),
+            "{}",
+            expression
+        );
+    }
+}
+
+#[test]
+fn test_list_contains_all_and_is_subset() {
+    let input = r"
+shared {
+    let All = [1, 2, 3];
+    let Acquired = [3, 1, 3];
+    let Subset = false;
+    let ContainsAll = false;
+    let EmptySubset = false;
+    let NotSubset = true;
+}
+
+main {
+    let empty: list(int) = [];
+    let other = [2, 4];
+    Subset = Acquired.is_subset(All);
+    ContainsAll = All.contains_all(Acquired);
+    EmptySubset = empty.is_subset(All);
+    NotSubset = other.is_subset(All);
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Subset"), Some(&Literal::Bool(true)));
+    assert_eq!(vm.globals.get("ContainsAll"), Some(&Literal::Bool(true)));
+    assert_eq!(vm.globals.get("EmptySubset"), Some(&Literal::Bool(true)));
+    assert_eq!(vm.globals.get("NotSubset"), Some(&Literal::Bool(false)));
+}
+
+#[test]
+fn test_list_pop_and_insert() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+            .unwrap()
+    };
+
+    let compiled_project = compile(
+        r"
+shared {
+    let L = [1, 2, 3];
+    let Last = 0;
+}
+
+main {
+    Last = L.pop();
+    L.insert(0, 0);
+    L.insert(3, 4);
+    L.insert(1, 9);
+}
+",
+    );
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Last"), Some(&Literal::Int(3)));
+    assert_eq!(
+        vm.globals.get("L"),
+        Some(&Literal::List(
+            DataType::Integer,
+            vec![
+                Literal::Int(0),
+                Literal::Int(9),
+                Literal::Int(1),
+                Literal::Int(2),
+                Literal::Int(4),
+            ]
+        ))
+    );
+
+    // popping an empty list and inserting past the end are runtime errors
+    for body in ["let l: list(int) = []; let x = l.pop();", "let l = [1]; l.insert(2, 5);"] {
+        let compiled_project = compile(&format!("main {{\n    {body}\n}}\n"));
+        let mut vm = VM::new(&compiled_project, RunOptions::default());
+        vm.start(0);
+        let err = loop {
+            if let Err(err) = vm.next_random() {
+                break err;
+            }
+            assert!(!vm.is_finished());
+        };
+        assert!(matches!(
+            err.error_type,
+            althread::error::ErrorType::RuntimeError
+        ));
+    }
+}
+
+#[test]
+fn test_send_payload_checked_against_channel_types() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+    let receiver = r"
+program Receiver() {
+    await receive in (x) => {
+        print(x);
+    }
+}
+";
+
+    // the channel is declared before the send, or after it in the main block or in another
+    // program
+    for main in [
+        "main {\n    let r = run Receiver();\n    channel self.out (string)> r.in;\n    send out(42);\n}\n",
+        "main {\n    let r = run Receiver();\n    send out(42);\n    channel self.out (string)> r.in;\n}\n",
+        "program Sender() {\n    send out(42);\n}\n\nmain {\n    let r = run Receiver();\n    let s = run Sender();\n    channel s.out (string)> r.in;\n}\n",
+    ] {
+        let input = format!("{receiver}\n{main}");
+        let declaration_line = input
+            .lines()
+            .position(|line| line.contains("channel"))
+            .unwrap()
+            + 1;
+        let err = compile(&input).unwrap_err();
+        assert!(
+            matches!(err.error_type, althread::error::ErrorType::TypeError),
+            "{}",
+            main
+        );
+        assert_eq!(
+            err.message,
+            format!(
+                "Channel out, bound at line {declaration_line}, expects values of types (string), but (int) were given"
+            )
+        );
+        assert!(compile(&input.replace("42", "\"42\"")).is_ok());
+    }
+}
+
+#[test]
+fn test_for_in_list() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+        let pairs = althread::parser::parse(input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    let compiled_project = compile(
+        r"
+shared {
+    let Sum = 0;
+    let Iterations = 0;
+}
+
+main {
+    let values = [1, 2, 3];
+    for x in values {
+        let y: int = x;
+        Sum = Sum + y;
+    }
+    let empty: list(int) = [];
+    for x in empty {
+        Iterations = Iterations + 1;
+    }
+}
+",
+    )
+    .unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Sum"), Some(&Literal::Int(6)));
+    assert_eq!(vm.globals.get("Iterations"), Some(&Literal::Int(0)));
+
+    // the loop variable has the type of the elements
+    let err = compile(
+        "main {\n    let l = [1, 2];\n    for x in l {\n        let s: string = x;\n    }\n}\n",
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err.error_type,
+        althread::error::ErrorType::TypeError
+    ));
+}
+
+#[test]
+fn test_mutex_expands_to_pairwise_conditions() {
+    let compile = |input: &str| {
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.to_string());
+
+        let pairs = althread::parser::parse(input, "").unwrap();
+        Ast::build(pairs, "")?.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+    let model = |conditions: &str| {
+        format!(
+            "shared {{\n    let A = false;\n    let B = false;\n    let C = false;\n    let N = 0;\n}}\n{conditions}\nmain {{\n    atomic {{ A = true; B = true; }}\n}}\n"
+        )
+    };
+
+    let sugar = compile(&model("always {\n    mutex(A, B, C);\n}")).unwrap();
+    let written = compile(&model("always {\n    !(A && B);\n    !(A && C);\n    !(B && C);\n}")).unwrap();
+    assert_eq!(sugar.always_conditions.len(), 3);
+    for (expanded, by_hand) in sugar.always_conditions.iter().zip(&written.always_conditions) {
+        assert_eq!(expanded.0, by_hand.0);
+        assert_eq!(expanded.1, by_hand.1);
+        assert_eq!(expanded.2, by_hand.2);
+    }
+
+    let mut vm = VM::new(&sugar, RunOptions::default());
+    vm.start(0);
+    assert!(vm.check_invariants().is_ok());
+    vm.next_step_pid(0).unwrap();
+    assert!(vm.check_invariants().is_err());
+
+    for conditions in [
+        "always {\n    mutex(A, N);\n}",
+        "always {\n    mutex(A, Missing);\n}",
+        "never {\n    mutex(A, B);\n}",
+    ] {
+        assert!(compile(&model(conditions)).is_err(), "{}", conditions);
+    }
+
+    for (conditions, message) in [
+        ("always {\n    mutex(A);\n}", "at least two variables"),
+        ("always {\n    mutex(A, B, A);\n}", "'A' is listed twice"),
+        (
+            "always {\n    mutex(A, B) && true;\n}",
+            "must be a whole condition",
+        ),
+    ] {
+        let error = compile(&model(conditions)).unwrap_err();
+        assert!(error.message.contains(message), "{}", error.message);
+    }
+}
+
+#[test]
+fn test_run_call_checks_the_program_arguments() {
+    let compile = |call: &str| {
+        let input = format!(
+            r"
+shared {{
+    let Total = 0;
+}}
+program Worker(n: int, add: bool) {{
+    if add {{
+        Total = Total + n;
+    }}
+}}
+main {{
+    {call}
+}}
+"
+        );
+        let mut input_map = HashMap::new();
+        input_map.insert(String::new(), input.clone());
+
+        let pairs = althread::parser::parse(&input, "").unwrap();
+        let ast = Ast::build(pairs, "").unwrap();
+        ast.compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+    };
+
+    let compiled_project = compile("run Worker(2, true); run Worker(3, false);").unwrap();
+    let mut vm = VM::new(&compiled_project, RunOptions::default());
+    vm.start(0);
+    while !vm.is_finished() {
+        vm.next_random().unwrap();
+    }
+    assert_eq!(vm.globals.get("Total").unwrap(), &Literal::Int(2));
+
+    for call in ["run Worker(2);", "run Worker(2, true, 1);", "run Worker(true, 2);"] {
+        let err = compile(call).unwrap_err();
+        assert!(
+            matches!(err.error_type, althread::error::ErrorType::ExpressionError),
+            "{}",
+            call
+        );
+        assert_eq!(err.pos.unwrap().line, 11, "{call}");
+    }
+}
+
+#[test]
+fn test_int_abstraction_clamps_initial_globals() {
+    let input = r"
+shared {
+    let X = 100;
+}
+
+always {
+    X <= 5;
+}
+
+main {
+    print(X);
+}
+";
+    let mut input_map = HashMap::new();
+    input_map.insert(String::new(), input.to_string());
+    let pairs = althread::parser::parse(input, "").unwrap();
+    let ast = Ast::build(pairs, "").unwrap();
+    let compiled_project = ast
+        .compile(std::path::Path::new(""), StandardFileSystem, &mut input_map)
+        .unwrap();
+    let abstraction: IntAbstraction = "0..5".parse().unwrap();
+    let options = CheckOptions {
+        run: RunOptions {
+            int_abstraction: Some(&abstraction),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let vm = VM::new(&compiled_project, options.run);
+    assert_eq!(vm.globals.get("X"), Some(&Literal::Int(5)));
+
+    let (path, _) = althread::checker::check_program(&compiled_project, &options).unwrap();
+    assert!(path.is_empty());
+    assert_eq!(abstraction.clamped_count(), 2);
+}
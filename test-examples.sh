@@ -40,6 +40,10 @@ get_expected_result() {
         ltl-deadlock-freedom.alt) echo pass ;;
         ltl-implications.alt) echo pass ;;
         ltl-multiple-properties.alt) echo pass ;;
+        # the two non-atomic increments of Done can lose an update, main then waits forever
+        test-atomic.alt) echo fail ;;
+        # once the workers stop exchanging messages, main's `loop await first` waits forever
+        TP2-communication.alt) echo fail ;;
         *) echo pass ;;
     esac
 }
@@ -12,6 +12,7 @@ export type {
 	ChannelEndpoint,
 	ChannelState,
 	CheckResult,
+	CheckStats,
 	ExecutedStepInfo,
 	GraphNode,
 	InteractiveSessionState,
@@ -7,7 +7,7 @@ use wasm_bindgen::prelude::*;
 
 use althread::ast::token::literal::Literal;
 use althread::module_resolver::VirtualFileSystem;
-use althread::vm::VM;
+use althread::vm::{RunOptions, VM};
 use althread::{ast::Ast, checker, error::AlthreadError, vm::GlobalAction};
 use console_error_panic_hook;
 
@@ -89,9 +89,16 @@ fn value_to_literal(value: &althread::ast::token::literal::Literal) -> types::Li
         VmLiteral::Float(f) => types::Literal::Float(f.into_inner()),
         VmLiteral::String(s) => types::Literal::String(s.clone()),
         VmLiteral::Bool(b) => types::Literal::Bool(*b),
-        VmLiteral::List(_, items) => types::Literal::List(items.iter().map(value_to_literal).collect()),
+        VmLiteral::List(_, items) | VmLiteral::Array(_, items) => types::Literal::List(items.iter().map(value_to_literal).collect()),
         VmLiteral::Tuple(items) => types::Literal::Tuple(items.iter().map(value_to_literal).collect()),
         VmLiteral::Process(name, id) => types::Literal::Process(name.clone(), *id),
+        // the entries of a map are shown as a list of (key, value) pairs
+        VmLiteral::Map(_, _, entries) => types::Literal::List(
+            entries
+                .values()
+                .map(|(key, value)| types::Literal::Tuple(vec![value_to_literal(key), value_to_literal(value)]))
+                .collect(),
+        ),
     }
 }
 
@@ -148,8 +155,8 @@ fn create_vm_state(vm: &althread::vm::VM) -> VMState {
                 .map(|p| p.name.clone())
                 .unwrap_or_else(|| format!("PID_{}", index));
             
-            let line = vm.programs_code.get(&prog_name)
-                .and_then(|code| code.instructions.get(*instruction_pointer))
+            let line = vm.running_programs.get(index)
+                .and_then(|p| p.code().instructions.get(*instruction_pointer))
                 .and_then(|inst| inst.pos.as_ref())
                 .map(|pos| pos.line);
             
@@ -177,7 +184,7 @@ fn create_vm_state(vm: &althread::vm::VM) -> VMState {
                                     var_info.name.clone(),
                                     VariableInfo {
                                         value: value_to_literal(&memory[var_info.stack_index]),
-                                        var_type: format!("{:?}", var_info.datatype),
+                                        var_type: var_info.datatype.to_string(),
                                     }
                                 );
                             }
@@ -273,7 +280,7 @@ pub fn run(source: &str, filepath: &str, virtual_fs: JsValue) -> Result<JsValue,
         .map_err(error_to_js)?;
 
     // Rest of the function stays exactly the same
-    let mut vm = althread::vm::VM::new(&compiled_project);
+    let mut vm = althread::vm::VM::new(&compiled_project, RunOptions::default());
 
     vm.start(fastrand::u64(0..(1 << 32)));
 
@@ -470,7 +477,12 @@ pub fn check(
         .compile(Path::new(filepath), virtual_filesystem, &mut input_map)
         .map_err(error_to_js)?;
 
-    let (path, state_graph) = checker::check_program(&compiled_project, max_states).map_err(error_to_js)?;
+    let options = checker::CheckOptions {
+        max_states,
+        ..Default::default()
+    };
+    let (path, state_graph) =
+        checker::check_program(&compiled_project, &options).map_err(error_to_js)?;
     let omit_transition_details = state_graph.nodes.len() > WEB_GRAPH_DETAILS_THRESHOLD;
     
     // Convert path to GraphNode structure
@@ -495,8 +507,8 @@ pub fn check(
                 Successor {
                     to_index: succ.to,
                     lines: succ.lines.clone(),
-                    instructions: succ.instructions.iter().map(|i| format!("{:?}", i)).collect(),
-                    actions: succ.actions.iter().map(|a| format!("{:?}", a)).collect(),
+                    instructions: succ.instructions.iter().map(|i| i.to_string()).collect(),
+                    actions: succ.actions.iter().map(|a| a.to_string()).collect(),
                     pid: succ.pid,
                     name: succ.name.clone(),
                 }
@@ -514,10 +526,18 @@ pub fn check(
         }
     }).collect();
     
+    let stats = state_graph.stats();
     let result = CheckResult {
         path: path_nodes,
         nodes: graph_nodes,
         exhaustive: state_graph.exhaustive,
+        stats: CheckStats {
+            states: stats.states,
+            transitions: stats.transitions,
+            max_depth: stats.max_depth,
+            eventually_states: stats.eventually_states,
+        },
+        deadlock: state_graph.deadlock.map(runtime_error_info),
     };
 
     Ok(to_js(&result))
@@ -651,7 +671,7 @@ pub fn start_interactive_session(
         .compile(Path::new(filepath), virtual_filesystem, &mut input_map)
         .map_err(error_to_js)?;
 
-    let mut vm = althread::vm::VM::new(&compiled_project);
+    let mut vm = althread::vm::VM::new(&compiled_project, RunOptions::default());
     vm.start(0); // Use deterministic seed for interactive mode
 
     // Get initial next states
@@ -730,20 +750,12 @@ pub fn get_next_interactive_states(
         .compile(Path::new(filepath), virtual_filesystem, &mut input_map)
         .map_err(error_to_js)?;
 
-    let mut vm = althread::vm::VM::new(&compiled_project);
+    let mut vm = althread::vm::VM::new(&compiled_project, RunOptions::default());
     vm.start(0); // Use deterministic seed for interactive mode
 
     // Replay execution history
     for &selected_index in &history {
-        let next_states = vm.next().map_err(error_to_js)?;
-        if selected_index >= next_states.len() {
-            return Err(JsValue::from_str(&format!(
-                "Invalid selection index {} in history",
-                selected_index
-            )));
-        }
-        let (_, _, _, _, new_vm) = next_states.into_iter().nth(selected_index).unwrap();
-        vm = new_vm;
+        vm.next_with_choice(selected_index).map_err(error_to_js)?;
     }
 
     // Get next possible states - web-safe error handling
@@ -776,30 +788,8 @@ pub fn get_next_interactive_states(
         })
         .collect();
 
-    let current_state = vm.current_state();
-
     // Generate state display information similar to run_interactive
-    let mut state_display_info = Vec::new();
-    state_display_info.push(format!("global: {:?}", current_state.0));
-    for ((channel_pid, cname), state) in current_state.1.iter() {
-        state_display_info.push(format!("channel {},{}", channel_pid, cname));
-        for v in state.iter() {
-            state_display_info.push(format!("  * {}", v));
-        }
-    }
-    for (local_pid, local_state) in current_state.2.iter().enumerate() {
-        state_display_info.push(format!(
-            "{} ({}): {:?}",
-            local_pid,
-            local_state.1,
-            local_state
-                .0
-                .iter()
-                .map(|v| format!("{}", v))
-                .collect::<Vec<String>>()
-                .join(", ")
-        ));
-    }
+    let state_display_info = vm.state_lines();
 
     let result = InteractiveSessionState {
         next_states: next_state_options,
@@ -842,23 +832,12 @@ pub fn execute_interactive_step(
         .compile(Path::new(filepath), virtual_filesystem, &mut input_map)
         .map_err(error_to_js)?;
 
-    let mut vm = althread::vm::VM::new(&compiled_project);
+    let mut vm = althread::vm::VM::new(&compiled_project, RunOptions::default());
     vm.start(0); // Use deterministic seed for interactive mode
 
     // Replay execution history
     for &step_index in &history {
-        let next_states = vm.next().map_err(error_to_js)?;
-        if next_states.is_empty() {
-            return Err(JsValue::from_str("No next state during replay"));
-        }
-        if step_index >= next_states.len() {
-            return Err(JsValue::from_str(&format!(
-                "Invalid selection index {} in history",
-                step_index
-            )));
-        }
-        let (_, _, _, _, new_vm) = next_states.into_iter().nth(step_index).unwrap();
-        vm = new_vm;
+        vm.next_with_choice(step_index).map_err(error_to_js)?;
     }
 
     // Get next possible states for this step - web-safe error handling
@@ -958,28 +937,7 @@ pub fn execute_interactive_step(
     };
 
     // Generate state display information
-    let current_state = execution_vm.current_state();
-    let mut state_display_info = Vec::new();
-    state_display_info.push(format!("global: {:?}", current_state.0));
-    for ((channel_pid, cname), state) in current_state.1.iter() {
-        state_display_info.push(format!("channel {},{}", channel_pid, cname));
-        for v in state.iter() {
-            state_display_info.push(format!("  * {}", v));
-        }
-    }
-    for (local_pid, local_state) in current_state.2.iter().enumerate() {
-        state_display_info.push(format!(
-            "{} ({}): {:?}",
-            local_pid,
-            local_state.1,
-            local_state
-                .0
-                .iter()
-                .map(|v| format!("{}", v))
-                .collect::<Vec<String>>()
-                .join(", ")
-        ));
-    }
+    let state_display_info = execution_vm.state_lines();
 
     let result = InteractiveStepResult {
         executed_step: ExecutedStepInfo {
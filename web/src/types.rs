@@ -201,6 +201,15 @@ pub struct RunResult {
     pub runtime_error: Option<RuntimeErrorInfo>,
 }
 
+/// Size of the state graph explored by a check
+#[derive(Serialize, Tsify)]
+pub struct CheckStats {
+    pub states: usize,
+    pub transitions: usize,
+    pub max_depth: usize,
+    pub eventually_states: usize,
+}
+
 /// Result from checking a program
 #[derive(Serialize, Tsify)]
 #[tsify(into_wasm_abi)]
@@ -208,6 +217,10 @@ pub struct CheckResult {
     pub path: Vec<GraphNode>,
     pub nodes: Vec<GraphNode>,
     pub exhaustive: bool,
+    pub stats: CheckStats,
+    /// Set when `path` leads to a deadlock rather than to a violated invariant
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadlock: Option<RuntimeErrorInfo>,
 }
 
 /// Result from an interactive step execution